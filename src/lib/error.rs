@@ -0,0 +1,178 @@
+//! Errors of the server.
+//!
+//! `Error` covers what can go wrong with a connection, and ends it. Errors
+//! that are replied to the client, which keeps the connection open unless
+//! the protocol was broken, are described by `ReplyError`.
+
+use crate::server::frame::{self, Frame};
+
+use core::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The client sent bytes that are not valid RESP
+    Protocol(frame::Error),
+
+    /// The client closed the connection in the middle of a frame
+    ConnectionReset,
+
+    /// Reading from or writing to the socket failed
+    Io(io::Error),
+}
+
+/// Errors replied to the client as `Frame::Error`.
+///
+/// Every variant maps to the error code Redis starts the reply with, so
+/// clients can match on it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplyError {
+    /// The client does not speak RESP properly, the connection is closed
+    /// after the reply
+    Protocol(String),
+
+    /// The command does not exist
+    UnknownCommand { name: String, args: Vec<String> },
+
+    /// The command was called with too few or too many arguments
+    WrongArity(String),
+
+    /// The arguments do not follow the syntax of the command
+    Syntax,
+
+    /// An argument is not an integer, or does not fit in 64 bits
+    NotInteger,
+
+    /// An argument is not a valid floating point number
+    NotFloat,
+
+    /// An argument is outside of the range accepted by the command
+    OutOfRange(String),
+
+    /// The key holds a value of a type the command does not operate on
+    WrongType,
+
+    /// The client must authenticate before running commands
+    NoAuth,
+
+    /// The transaction was discarded because a command could not be queued
+    ExecAbort,
+
+    /// The protocol version asked with `HELLO` is not supported
+    NoProto,
+
+    /// The stream or its consumer group does not exist, with the message
+    /// naming them
+    NoGroup(String),
+
+    /// A consumer group with that name already exists on the stream
+    BusyGroup,
+
+    /// The key holds a string that is not a HyperLogLog
+    InvalidHyperLogLog,
+
+    /// The hash slot of the shard channel is not served by this node
+    SlotNotServed,
+
+    /// Any other error, with its message
+    Other(String),
+}
+
+impl ReplyError {
+    /// Returns the error code the reply starts with.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ReplyError::WrongType | ReplyError::InvalidHyperLogLog => "WRONGTYPE",
+            ReplyError::NoAuth => "NOAUTH",
+            ReplyError::ExecAbort => "EXECABORT",
+            ReplyError::NoProto => "NOPROTO",
+            ReplyError::NoGroup(_) => "NOGROUP",
+            ReplyError::BusyGroup => "BUSYGROUP",
+            ReplyError::SlotNotServed => "CLUSTERDOWN",
+            _ => "ERR",
+        }
+    }
+}
+
+impl From<ReplyError> for Frame {
+    fn from(err: ReplyError) -> Frame {
+        Frame::Error(format!("{} {}", err.code(), err))
+    }
+}
+
+impl From<frame::Error> for Error {
+    fn from(err: frame::Error) -> Error {
+        Error::Protocol(err)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Protocol(err) => write!(fmt, "protocol error: {}", err),
+            Error::ConnectionReset => "connection reset by peer".fmt(fmt),
+            Error::Io(err) => err.fmt(fmt),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Protocol(err) => Some(err),
+            Error::ConnectionReset => None,
+            Error::Io(err) => Some(err),
+        }
+    }
+}
+
+/// Displays the message of the reply, without its error code.
+impl fmt::Display for ReplyError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplyError::Protocol(msg) => write!(fmt, "Protocol error: {}", msg),
+            ReplyError::UnknownCommand { name, args } => {
+                let args: Vec<String> = args.iter().map(|arg| format!("'{}'", arg)).collect();
+                write!(
+                    fmt,
+                    "unknown command '{}', with args beginning with: {}",
+                    name,
+                    args.join(" ")
+                )
+            }
+            ReplyError::WrongArity(name) => {
+                write!(fmt, "wrong number of arguments for '{}' command", name)
+            }
+            ReplyError::Syntax => "syntax error".fmt(fmt),
+            ReplyError::NotInteger => "value is not an integer or out of range".fmt(fmt),
+            ReplyError::NotFloat => "value is not a valid float".fmt(fmt),
+            ReplyError::OutOfRange(msg) => msg.fmt(fmt),
+            ReplyError::WrongType => {
+                "Operation against a key holding the wrong kind of value".fmt(fmt)
+            }
+            ReplyError::NoAuth => "Authentication required.".fmt(fmt),
+            ReplyError::ExecAbort => "Transaction discarded because of previous errors.".fmt(fmt),
+            ReplyError::NoProto => "unsupported protocol version".fmt(fmt),
+            ReplyError::NoGroup(msg) => msg.fmt(fmt),
+            ReplyError::BusyGroup => "Consumer Group name already exists".fmt(fmt),
+            ReplyError::InvalidHyperLogLog => {
+                "Key is not a valid HyperLogLog string value.".fmt(fmt)
+            }
+            ReplyError::SlotNotServed => "Hash slot not served".fmt(fmt),
+            ReplyError::Other(msg) => msg.fmt(fmt),
+        }
+    }
+}
+
+impl std::error::Error for ReplyError {}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/error_test.rs"]
+mod error_test;
@@ -1,5 +1,6 @@
 pub mod server;
 
-pub const BUFFER_SIZE: usize = 4096;
+pub mod error;
+pub use error::Error;
 
-pub type Error = Box<dyn std::error::Error + Send + Sync>;
\ No newline at end of file
+pub const BUFFER_SIZE: usize = 4096;
@@ -1,20 +1,46 @@
-use std::io::Cursor;
-
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpListener;
 
-use crate::{Error, BUFFER_SIZE};
+use crate::error::ReplyError;
+use crate::Error;
 
 pub mod frame;
-use frame::Frame;
+use frame::{Frame, Protocol};
 
 pub mod parser;
 
+mod inline;
+
 pub mod connection;
 use connection::Connection;
 
+mod db;
+use db::Db;
+
+mod cmd;
+use cmd::Command;
+
+mod geo;
+
+mod glob;
+
+mod hyperloglog;
+
+mod pubsub;
+
+mod set;
+
+mod slot;
+
+mod sorted_set;
+
+mod stream;
+
 pub struct RedisServer {
     binding_socket: TcpListener,
+
+    /// Keyspace shared by every connection
+    db: Db,
 }
 
 impl RedisServer {
@@ -24,20 +50,134 @@ impl RedisServer {
 
         Ok(RedisServer {
             binding_socket: listener,
+            db: Db::new(),
         })
     }
 
     pub async fn run(&self) -> Result<(), Error> {
         loop {
-            let (mut inbound_stream, _) = self.binding_socket.accept().await?;
-            let mut connection = Connection::new(inbound_stream);
+            let (inbound_stream, address) = self.binding_socket.accept().await?;
+            let connection = Connection::new(inbound_stream);
+            let db = self.db.clone();
             tokio::spawn(async move {
-                loop {
-                    if let Err(err) = connection.read_frame().await {
-                        println!("connection error");
-                    }
+                if let Err(err) = handle_connection(db, connection).await {
+                    spdlog::warn!("connection error with {}: {}", address, err);
                 }
             });
         }
     }
 }
+
+/// Serves a client until it disconnects, then drops its subscriptions and
+/// watched keys.
+async fn handle_connection<S>(db: Db, mut connection: Connection<S>) -> Result<(), Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let result = serve(&db, &mut connection).await;
+
+    let id = connection.id();
+    db.with_broker(|broker| connection.subscriptions().clear(broker, id));
+    cmd::unwatch(&db, std::mem::take(connection.watched()));
+
+    result
+}
+
+/// Executes the commands sent by a client until it disconnects or quits.
+///
+/// A frame breaking the protocol is answered with a `Protocol error` reply,
+/// then the connection is closed since the rest of the stream can not be
+/// trusted anymore.
+async fn serve<S>(db: &Db, connection: &mut Connection<S>) -> Result<(), Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    loop {
+        let frame = match connection.read_frame().await {
+            Ok(Some(frame)) => frame,
+            // The client closed the connection
+            Ok(None) => return Ok(()),
+            Err(Error::Protocol(err)) => {
+                let response = ReplyError::Protocol(err.to_string()).into();
+                connection.write_frame(&response).await?;
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+
+        // Like Redis, an empty array is silently ignored
+        if frame == Frame::new() {
+            continue;
+        }
+
+        // A subscribed RESP2 client can only manage its subscriptions, as
+        // any reply could be mistaken for a message
+        if connection.is_subscribed() && connection.protocol() == Protocol::Resp2 {
+            if let Some(name) = forbidden_when_subscribed(&frame) {
+                let err = ReplyError::Other(format!(
+                    "Can't execute '{}': only {} are allowed in this context",
+                    name, ALLOWED_WHEN_SUBSCRIBED_NAMES
+                ));
+                connection.write_frame(&err.into()).await?;
+                continue;
+            }
+        }
+
+        match Command::from_frame(frame).map_err(ReplyError::from) {
+            Ok(Command::Quit(cmd)) => {
+                connection.write_frame(&cmd.execute()).await?;
+                return Ok(());
+            }
+            Ok(command) => command.apply(db, connection).await?,
+            Err(err @ ReplyError::Protocol(_)) => {
+                connection.write_frame(&err.into()).await?;
+                return Ok(());
+            }
+            Err(err) => {
+                // A command that can not be parsed can not be queued
+                if let Some(transaction) = connection.transaction() {
+                    transaction.abort();
+                }
+                connection.write_frame(&err.into()).await?
+            }
+        }
+    }
+}
+
+/// Commands a subscribed RESP2 client can run.
+const ALLOWED_WHEN_SUBSCRIBED: [&str; 9] = [
+    "subscribe",
+    "psubscribe",
+    "ssubscribe",
+    "unsubscribe",
+    "punsubscribe",
+    "sunsubscribe",
+    "ping",
+    "quit",
+    "reset",
+];
+
+/// The commands of `ALLOWED_WHEN_SUBSCRIBED`, as Redis lists them in the
+/// error replied to the other commands.
+const ALLOWED_WHEN_SUBSCRIBED_NAMES: &str =
+    "(P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING / QUIT / RESET";
+
+/// Returns the name of the command sent as `frame` if a subscribed RESP2
+/// client can not run it.
+fn forbidden_when_subscribed(frame: &Frame) -> Option<String> {
+    let Frame::Array(frames) = frame else {
+        return None;
+    };
+    let name = match frames.first()? {
+        Frame::Bulk(name) => String::from_utf8_lossy(name).to_lowercase(),
+        Frame::Simple(name) => name.to_lowercase(),
+        _ => return None,
+    };
+
+    (!ALLOWED_WHEN_SUBSCRIBED.contains(&name.as_str())).then_some(name)
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "server/test/server_test.rs"]
+mod server_test;
@@ -0,0 +1,680 @@
+use crate::error::ReplyError;
+use crate::server::connection::Connection;
+use crate::server::db::{BlockingCommand, Db, State};
+use crate::server::frame::{Frame, Protocol};
+use crate::server::parser::{Parser, ParserError};
+use crate::server::pubsub::{Broker, Kind};
+use crate::Error;
+
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+mod bitmap;
+pub(crate) use bitmap::{BitCount, BitField, BitOp, BitPos, GetBit, SetBit};
+
+mod connection;
+pub(crate) use connection::{Echo, Hello, Ping, Quit, Reset};
+
+mod geo;
+pub(crate) use geo::{GeoAdd, GeoDist, GeoHash, GeoPos, GeoSearch};
+
+mod hash;
+pub(crate) use hash::{
+    HDel, HExists, HGet, HGetAll, HIncrBy, HIncrByFloat, HLen, HMGet, HRandField, HScan, HSet,
+    HSetNx, HStrLen, HashPart,
+};
+
+mod hyperloglog;
+pub(crate) use hyperloglog::{PfAdd, PfCount, PfMerge};
+
+mod keys;
+pub(crate) use keys::{Del, Exists, Expire, Flush, Persist, TimeUnit, Ttl};
+
+mod list;
+pub(crate) use list::{
+    BPop, End, LIndex, LInsert, LLen, LRange, LRem, LSet, LTrim, Move, Pop, Push,
+};
+
+mod pubsub;
+pub(crate) use pubsub::{PubSub, Publish, SPublish, Subscribe, Unsubscribe};
+
+mod set;
+pub(crate) use set::{
+    SAdd, SCard, SInterCard, SIsMember, SMIsMember, SMembers, SMove, SPop, SRandMember, SRem,
+    SetOp, SetOperation,
+};
+
+mod sorted_set;
+pub(crate) use sorted_set::{
+    ZAdd, ZCard, ZCount, ZIncrBy, ZPop, ZRange, ZRank, ZRem, ZScore, ZStore,
+};
+
+mod stream;
+pub(crate) use stream::{
+    XAck, XAdd, XAutoClaim, XClaim, XDel, XGroup, XLen, XPending, XRange, XRead, XReadGroup, XTrim,
+};
+
+mod string;
+pub(crate) use string::{
+    Append, Get, GetDel, GetEx, GetRange, IncrBy, IncrByFloat, MGet, MSet, Set, SetRange, StrLen,
+};
+
+mod transaction;
+pub(crate) use transaction::{unwatch, Discard, Exec, Multi, Transaction, Unwatch, Watch};
+
+/// Commands understood by the server.
+///
+/// Every incoming `Frame::Array` is mapped to one of these variants by
+/// `Command::from_frame`, then executed by `Command::apply`.
+#[derive(Debug)]
+pub(crate) enum Command {
+    Ping(Ping),
+    Echo(Echo),
+    Hello(Hello),
+    Quit(Quit),
+    Reset(Reset),
+    Subscribe(Subscribe),
+    Unsubscribe(Unsubscribe),
+    Publish(Publish),
+    SPublish(SPublish),
+    PubSub(PubSub),
+    Multi(Multi),
+    Exec(Exec),
+    Discard(Discard),
+    Watch(Watch),
+    Unwatch(Unwatch),
+    Get(Get),
+    Set(Set),
+    IncrBy(IncrBy),
+    IncrByFloat(IncrByFloat),
+    Append(Append),
+    StrLen(StrLen),
+    GetRange(GetRange),
+    SetRange(SetRange),
+    GetDel(GetDel),
+    GetEx(GetEx),
+    MGet(MGet),
+    MSet(MSet),
+    SetBit(SetBit),
+    GetBit(GetBit),
+    BitCount(BitCount),
+    BitPos(BitPos),
+    BitOp(BitOp),
+    BitField(BitField),
+    PfAdd(PfAdd),
+    PfCount(PfCount),
+    PfMerge(PfMerge),
+    Del(Del),
+    Exists(Exists),
+    Expire(Expire),
+    Ttl(Ttl),
+    Persist(Persist),
+    Flush(Flush),
+    Push(Push),
+    Pop(Pop),
+    BPop(BPop),
+    Move(Move),
+    LRange(LRange),
+    LLen(LLen),
+    LIndex(LIndex),
+    LSet(LSet),
+    LTrim(LTrim),
+    LRem(LRem),
+    LInsert(LInsert),
+    HSet(HSet),
+    HSetNx(HSetNx),
+    HGet(HGet),
+    HMGet(HMGet),
+    HDel(HDel),
+    HExists(HExists),
+    HLen(HLen),
+    HGetAll(HGetAll),
+    HIncrBy(HIncrBy),
+    HIncrByFloat(HIncrByFloat),
+    HStrLen(HStrLen),
+    HRandField(HRandField),
+    HScan(HScan),
+    SAdd(SAdd),
+    SRem(SRem),
+    SMembers(SMembers),
+    SIsMember(SIsMember),
+    SMIsMember(SMIsMember),
+    SCard(SCard),
+    SMove(SMove),
+    SetOp(SetOp),
+    SInterCard(SInterCard),
+    SPop(SPop),
+    SRandMember(SRandMember),
+    ZAdd(ZAdd),
+    ZIncrBy(ZIncrBy),
+    ZRange(ZRange),
+    ZRank(ZRank),
+    ZScore(ZScore),
+    ZRem(ZRem),
+    ZCard(ZCard),
+    ZCount(ZCount),
+    ZPop(ZPop),
+    ZStore(ZStore),
+    GeoAdd(GeoAdd),
+    GeoPos(GeoPos),
+    GeoDist(GeoDist),
+    GeoHash(GeoHash),
+    GeoSearch(GeoSearch),
+    XAdd(XAdd),
+    XRange(XRange),
+    XLen(XLen),
+    XDel(XDel),
+    XTrim(XTrim),
+    XRead(XRead),
+    XGroup(XGroup),
+    XReadGroup(XReadGroup),
+    XAck(XAck),
+    XPending(XPending),
+    XClaim(XClaim),
+    XAutoClaim(XAutoClaim),
+    Unknown(Unknown),
+}
+
+/// A command the server does not know about.
+///
+/// This is not an error on the protocol level: the client simply receives an
+/// `-ERR unknown command` reply and the connection stays usable.
+#[derive(Debug)]
+pub(crate) struct Unknown {
+    name: String,
+    args: Vec<String>,
+}
+
+impl Command {
+    /// Parses a command from a received frame.
+    ///
+    /// The frame must be an array whose first entry is the command name.
+    /// Missing or superfluous arguments are reported as a
+    /// `wrong number of arguments` error.
+    pub(crate) fn from_frame(frame: Frame) -> Result<Command, ParserError> {
+        let mut parser = Parser::new(frame)?;
+
+        let command_name = parser.next_string()?;
+        let name = command_name.to_lowercase();
+
+        let command = match &name[..] {
+            "ping" => Ping::parse_frames(&mut parser).map(Command::Ping),
+            "echo" => Echo::parse_frames(&mut parser).map(Command::Echo),
+            "hello" => Hello::parse_frames(&mut parser).map(Command::Hello),
+            "quit" => Quit::parse_frames(&mut parser).map(Command::Quit),
+            "reset" => Reset::parse_frames(&mut parser).map(Command::Reset),
+            "subscribe" => {
+                Subscribe::parse_frames(&mut parser, Kind::Channel).map(Command::Subscribe)
+            }
+            "psubscribe" => {
+                Subscribe::parse_frames(&mut parser, Kind::Pattern).map(Command::Subscribe)
+            }
+            "unsubscribe" => {
+                Unsubscribe::parse_frames(&mut parser, Kind::Channel).map(Command::Unsubscribe)
+            }
+            "punsubscribe" => {
+                Unsubscribe::parse_frames(&mut parser, Kind::Pattern).map(Command::Unsubscribe)
+            }
+            "ssubscribe" => {
+                Subscribe::parse_frames(&mut parser, Kind::Shard).map(Command::Subscribe)
+            }
+            "sunsubscribe" => {
+                Unsubscribe::parse_frames(&mut parser, Kind::Shard).map(Command::Unsubscribe)
+            }
+            "publish" => Publish::parse_frames(&mut parser).map(Command::Publish),
+            "spublish" => SPublish::parse_frames(&mut parser).map(Command::SPublish),
+            "pubsub" => PubSub::parse_frames(&mut parser).map(Command::PubSub),
+            "multi" => Multi::parse_frames(&mut parser).map(Command::Multi),
+            "exec" => Exec::parse_frames(&mut parser).map(Command::Exec),
+            "discard" => Discard::parse_frames(&mut parser).map(Command::Discard),
+            "watch" => Watch::parse_frames(&mut parser).map(Command::Watch),
+            "unwatch" => Unwatch::parse_frames(&mut parser).map(Command::Unwatch),
+            "get" => Get::parse_frames(&mut parser).map(Command::Get),
+            "set" => Set::parse_frames(&mut parser).map(Command::Set),
+            "incr" => IncrBy::parse_frames(&mut parser, 1, false).map(Command::IncrBy),
+            "decr" => IncrBy::parse_frames(&mut parser, -1, false).map(Command::IncrBy),
+            "incrby" => IncrBy::parse_frames(&mut parser, 1, true).map(Command::IncrBy),
+            "decrby" => IncrBy::parse_frames(&mut parser, -1, true).map(Command::IncrBy),
+            "incrbyfloat" => IncrByFloat::parse_frames(&mut parser).map(Command::IncrByFloat),
+            "append" => Append::parse_frames(&mut parser).map(Command::Append),
+            "strlen" => StrLen::parse_frames(&mut parser).map(Command::StrLen),
+            "getrange" => GetRange::parse_frames(&mut parser).map(Command::GetRange),
+            "setrange" => SetRange::parse_frames(&mut parser).map(Command::SetRange),
+            "getdel" => GetDel::parse_frames(&mut parser).map(Command::GetDel),
+            "getex" => GetEx::parse_frames(&mut parser).map(Command::GetEx),
+            "mget" => MGet::parse_frames(&mut parser).map(Command::MGet),
+            "mset" => MSet::parse_frames(&mut parser, false).map(Command::MSet),
+            "msetnx" => MSet::parse_frames(&mut parser, true).map(Command::MSet),
+            "setbit" => SetBit::parse_frames(&mut parser).map(Command::SetBit),
+            "getbit" => GetBit::parse_frames(&mut parser).map(Command::GetBit),
+            "bitcount" => BitCount::parse_frames(&mut parser).map(Command::BitCount),
+            "bitpos" => BitPos::parse_frames(&mut parser).map(Command::BitPos),
+            "bitop" => BitOp::parse_frames(&mut parser).map(Command::BitOp),
+            "bitfield" => BitField::parse_frames(&mut parser, false).map(Command::BitField),
+            "bitfield_ro" => BitField::parse_frames(&mut parser, true).map(Command::BitField),
+            "pfadd" => PfAdd::parse_frames(&mut parser).map(Command::PfAdd),
+            "pfcount" => PfCount::parse_frames(&mut parser).map(Command::PfCount),
+            "pfmerge" => PfMerge::parse_frames(&mut parser).map(Command::PfMerge),
+            "del" => Del::parse_frames(&mut parser).map(Command::Del),
+            "exists" => Exists::parse_frames(&mut parser).map(Command::Exists),
+            "expire" => Expire::parse_frames(&mut parser, "expire", TimeUnit::Seconds, false)
+                .map(Command::Expire),
+            "pexpire" => {
+                Expire::parse_frames(&mut parser, "pexpire", TimeUnit::Milliseconds, false)
+                    .map(Command::Expire)
+            }
+            "expireat" => Expire::parse_frames(&mut parser, "expireat", TimeUnit::Seconds, true)
+                .map(Command::Expire),
+            "pexpireat" => {
+                Expire::parse_frames(&mut parser, "pexpireat", TimeUnit::Milliseconds, true)
+                    .map(Command::Expire)
+            }
+            "ttl" => Ttl::parse_frames(&mut parser, TimeUnit::Seconds).map(Command::Ttl),
+            "pttl" => Ttl::parse_frames(&mut parser, TimeUnit::Milliseconds).map(Command::Ttl),
+            "persist" => Persist::parse_frames(&mut parser).map(Command::Persist),
+            "flushdb" | "flushall" => Flush::parse_frames(&mut parser).map(Command::Flush),
+            "lpush" => Push::parse_frames(&mut parser, End::Left).map(Command::Push),
+            "rpush" => Push::parse_frames(&mut parser, End::Right).map(Command::Push),
+            "lpop" => Pop::parse_frames(&mut parser, End::Left).map(Command::Pop),
+            "rpop" => Pop::parse_frames(&mut parser, End::Right).map(Command::Pop),
+            "blpop" => BPop::parse_frames(&mut parser, End::Left).map(Command::BPop),
+            "brpop" => BPop::parse_frames(&mut parser, End::Right).map(Command::BPop),
+            "lmove" => Move::parse_frames(&mut parser, false).map(Command::Move),
+            "blmove" => Move::parse_frames(&mut parser, true).map(Command::Move),
+            "lrange" => LRange::parse_frames(&mut parser).map(Command::LRange),
+            "llen" => LLen::parse_frames(&mut parser).map(Command::LLen),
+            "lindex" => LIndex::parse_frames(&mut parser).map(Command::LIndex),
+            "lset" => LSet::parse_frames(&mut parser).map(Command::LSet),
+            "ltrim" => LTrim::parse_frames(&mut parser).map(Command::LTrim),
+            "lrem" => LRem::parse_frames(&mut parser).map(Command::LRem),
+            "linsert" => LInsert::parse_frames(&mut parser).map(Command::LInsert),
+            "hset" => HSet::parse_frames(&mut parser).map(Command::HSet),
+            "hsetnx" => HSetNx::parse_frames(&mut parser).map(Command::HSetNx),
+            "hget" => HGet::parse_frames(&mut parser).map(Command::HGet),
+            "hmget" => HMGet::parse_frames(&mut parser).map(Command::HMGet),
+            "hdel" => HDel::parse_frames(&mut parser).map(Command::HDel),
+            "hexists" => HExists::parse_frames(&mut parser).map(Command::HExists),
+            "hlen" => HLen::parse_frames(&mut parser).map(Command::HLen),
+            "hkeys" => HGetAll::parse_frames(&mut parser, HashPart::Fields).map(Command::HGetAll),
+            "hvals" => HGetAll::parse_frames(&mut parser, HashPart::Values).map(Command::HGetAll),
+            "hgetall" => {
+                HGetAll::parse_frames(&mut parser, HashPart::Entries).map(Command::HGetAll)
+            }
+            "hincrby" => HIncrBy::parse_frames(&mut parser).map(Command::HIncrBy),
+            "hincrbyfloat" => HIncrByFloat::parse_frames(&mut parser).map(Command::HIncrByFloat),
+            "hstrlen" => HStrLen::parse_frames(&mut parser).map(Command::HStrLen),
+            "hrandfield" => HRandField::parse_frames(&mut parser).map(Command::HRandField),
+            "hscan" => HScan::parse_frames(&mut parser).map(Command::HScan),
+            "sadd" => SAdd::parse_frames(&mut parser).map(Command::SAdd),
+            "srem" => SRem::parse_frames(&mut parser).map(Command::SRem),
+            "smembers" => SMembers::parse_frames(&mut parser).map(Command::SMembers),
+            "sismember" => SIsMember::parse_frames(&mut parser).map(Command::SIsMember),
+            "smismember" => SMIsMember::parse_frames(&mut parser).map(Command::SMIsMember),
+            "scard" => SCard::parse_frames(&mut parser).map(Command::SCard),
+            "smove" => SMove::parse_frames(&mut parser).map(Command::SMove),
+            "sinter" => {
+                SetOp::parse_frames(&mut parser, SetOperation::Inter, false).map(Command::SetOp)
+            }
+            "sunion" => {
+                SetOp::parse_frames(&mut parser, SetOperation::Union, false).map(Command::SetOp)
+            }
+            "sdiff" => {
+                SetOp::parse_frames(&mut parser, SetOperation::Diff, false).map(Command::SetOp)
+            }
+            "sinterstore" => {
+                SetOp::parse_frames(&mut parser, SetOperation::Inter, true).map(Command::SetOp)
+            }
+            "sunionstore" => {
+                SetOp::parse_frames(&mut parser, SetOperation::Union, true).map(Command::SetOp)
+            }
+            "sdiffstore" => {
+                SetOp::parse_frames(&mut parser, SetOperation::Diff, true).map(Command::SetOp)
+            }
+            "sintercard" => SInterCard::parse_frames(&mut parser).map(Command::SInterCard),
+            "spop" => SPop::parse_frames(&mut parser).map(Command::SPop),
+            "srandmember" => SRandMember::parse_frames(&mut parser).map(Command::SRandMember),
+            "zadd" => ZAdd::parse_frames(&mut parser).map(Command::ZAdd),
+            "zincrby" => ZIncrBy::parse_frames(&mut parser).map(Command::ZIncrBy),
+            "zrange" => ZRange::parse_frames(&mut parser).map(Command::ZRange),
+            "zrank" => ZRank::parse_frames(&mut parser, false).map(Command::ZRank),
+            "zrevrank" => ZRank::parse_frames(&mut parser, true).map(Command::ZRank),
+            "zscore" => ZScore::parse_frames(&mut parser).map(Command::ZScore),
+            "zrem" => ZRem::parse_frames(&mut parser).map(Command::ZRem),
+            "zcard" => ZCard::parse_frames(&mut parser).map(Command::ZCard),
+            "zcount" => ZCount::parse_frames(&mut parser).map(Command::ZCount),
+            "zpopmin" => ZPop::parse_frames(&mut parser, false).map(Command::ZPop),
+            "zpopmax" => ZPop::parse_frames(&mut parser, true).map(Command::ZPop),
+            "zunionstore" => {
+                ZStore::parse_frames(&mut parser, "zunionstore", true).map(Command::ZStore)
+            }
+            "zinterstore" => {
+                ZStore::parse_frames(&mut parser, "zinterstore", false).map(Command::ZStore)
+            }
+            "geoadd" => GeoAdd::parse_frames(&mut parser).map(Command::GeoAdd),
+            "geopos" => GeoPos::parse_frames(&mut parser).map(Command::GeoPos),
+            "geodist" => GeoDist::parse_frames(&mut parser).map(Command::GeoDist),
+            "geohash" => GeoHash::parse_frames(&mut parser).map(Command::GeoHash),
+            "geosearch" => GeoSearch::parse_frames(&mut parser, false).map(Command::GeoSearch),
+            "geosearchstore" => GeoSearch::parse_frames(&mut parser, true).map(Command::GeoSearch),
+            "xadd" => XAdd::parse_frames(&mut parser).map(Command::XAdd),
+            "xrange" => XRange::parse_frames(&mut parser, false).map(Command::XRange),
+            "xrevrange" => XRange::parse_frames(&mut parser, true).map(Command::XRange),
+            "xlen" => XLen::parse_frames(&mut parser).map(Command::XLen),
+            "xdel" => XDel::parse_frames(&mut parser).map(Command::XDel),
+            "xtrim" => XTrim::parse_frames(&mut parser).map(Command::XTrim),
+            "xread" => XRead::parse_frames(&mut parser).map(Command::XRead),
+            "xgroup" => XGroup::parse_frames(&mut parser).map(Command::XGroup),
+            "xreadgroup" => XReadGroup::parse_frames(&mut parser).map(Command::XReadGroup),
+            "xack" => XAck::parse_frames(&mut parser).map(Command::XAck),
+            "xpending" => XPending::parse_frames(&mut parser).map(Command::XPending),
+            "xclaim" => XClaim::parse_frames(&mut parser).map(Command::XClaim),
+            "xautoclaim" => XAutoClaim::parse_frames(&mut parser).map(Command::XAutoClaim),
+            _ => {
+                return Ok(Command::Unknown(Unknown::parse_frames(
+                    command_name,
+                    &mut parser,
+                )))
+            }
+        };
+
+        // Make sure every argument has been consumed, otherwise the command
+        // was called with too many arguments.
+        match command.and_then(|command| parser.finish().map(|_| command)) {
+            Err(ParserError::NoMoreFrame) | Err(ParserError::TrailingFrame) => {
+                Err(ReplyError::WrongArity(name).into())
+            }
+            result => result,
+        }
+    }
+
+    /// Executes the command against the keyspace and returns the reply to be
+    /// sent to the client.
+    ///
+    /// Most replies are encoded for `protocol` when written, the few
+    /// commands whose reply has a different shape depending on it build it
+    /// themselves.
+    pub(crate) fn execute(self, state: &mut State, protocol: Protocol) -> Frame {
+        match self {
+            Command::Ping(cmd) => cmd.execute(),
+            Command::Echo(cmd) => cmd.execute(),
+            // Only meaningful on a connection, see `Command::apply`
+            Command::Hello(_) => ReplyError::Other("HELLO can not be used here".to_string()).into(),
+            Command::Quit(cmd) => cmd.execute(),
+            Command::Reset(_) => ReplyError::Other("RESET can not be used here".to_string()).into(),
+            Command::Subscribe(_) => {
+                ReplyError::Other("SUBSCRIBE can not be used here".to_string()).into()
+            }
+            Command::Unsubscribe(_) => {
+                ReplyError::Other("UNSUBSCRIBE can not be used here".to_string()).into()
+            }
+            Command::Publish(_) => {
+                ReplyError::Other("PUBLISH can not be used here".to_string()).into()
+            }
+            Command::SPublish(_) => {
+                ReplyError::Other("SPUBLISH can not be used here".to_string()).into()
+            }
+            Command::PubSub(_) => {
+                ReplyError::Other("PUBSUB can not be used here".to_string()).into()
+            }
+            Command::Multi(_) => ReplyError::Other("MULTI can not be used here".to_string()).into(),
+            Command::Exec(_) => ReplyError::Other("EXEC can not be used here".to_string()).into(),
+            Command::Discard(_) => {
+                ReplyError::Other("DISCARD can not be used here".to_string()).into()
+            }
+            Command::Watch(_) => ReplyError::Other("WATCH can not be used here".to_string()).into(),
+            Command::Unwatch(_) => {
+                ReplyError::Other("UNWATCH can not be used here".to_string()).into()
+            }
+            Command::Get(cmd) => cmd.execute(state),
+            Command::Set(cmd) => cmd.execute(state),
+            Command::IncrBy(cmd) => cmd.execute(state),
+            Command::IncrByFloat(cmd) => cmd.execute(state),
+            Command::Append(cmd) => cmd.execute(state),
+            Command::StrLen(cmd) => cmd.execute(state),
+            Command::GetRange(cmd) => cmd.execute(state),
+            Command::SetRange(cmd) => cmd.execute(state),
+            Command::GetDel(cmd) => cmd.execute(state),
+            Command::GetEx(cmd) => cmd.execute(state),
+            Command::MGet(cmd) => cmd.execute(state),
+            Command::MSet(cmd) => cmd.execute(state),
+            Command::SetBit(cmd) => cmd.execute(state),
+            Command::GetBit(cmd) => cmd.execute(state),
+            Command::BitCount(cmd) => cmd.execute(state),
+            Command::BitPos(cmd) => cmd.execute(state),
+            Command::BitOp(cmd) => cmd.execute(state),
+            Command::BitField(cmd) => cmd.execute(state),
+            Command::PfAdd(cmd) => cmd.execute(state),
+            Command::PfCount(cmd) => cmd.execute(state),
+            Command::PfMerge(cmd) => cmd.execute(state),
+            Command::Del(cmd) => cmd.execute(state),
+            Command::Exists(cmd) => cmd.execute(state),
+            Command::Expire(cmd) => cmd.execute(state),
+            Command::Ttl(cmd) => cmd.execute(state),
+            Command::Persist(cmd) => cmd.execute(state),
+            Command::Flush(cmd) => cmd.execute(state),
+            Command::Push(cmd) => cmd.execute(state),
+            Command::Pop(cmd) => cmd.execute(state),
+            Command::BPop(cmd) => cmd.execute(state),
+            Command::Move(cmd) => cmd.execute(state),
+            Command::LRange(cmd) => cmd.execute(state),
+            Command::LLen(cmd) => cmd.execute(state),
+            Command::LIndex(cmd) => cmd.execute(state),
+            Command::LSet(cmd) => cmd.execute(state),
+            Command::LTrim(cmd) => cmd.execute(state),
+            Command::LRem(cmd) => cmd.execute(state),
+            Command::LInsert(cmd) => cmd.execute(state),
+            Command::HSet(cmd) => cmd.execute(state),
+            Command::HSetNx(cmd) => cmd.execute(state),
+            Command::HGet(cmd) => cmd.execute(state),
+            Command::HMGet(cmd) => cmd.execute(state),
+            Command::HDel(cmd) => cmd.execute(state),
+            Command::HExists(cmd) => cmd.execute(state),
+            Command::HLen(cmd) => cmd.execute(state),
+            Command::HGetAll(cmd) => cmd.execute(state),
+            Command::HIncrBy(cmd) => cmd.execute(state),
+            Command::HIncrByFloat(cmd) => cmd.execute(state),
+            Command::HStrLen(cmd) => cmd.execute(state),
+            Command::HRandField(cmd) => cmd.execute(state, protocol),
+            Command::HScan(cmd) => cmd.execute(state),
+            Command::SAdd(cmd) => cmd.execute(state),
+            Command::SRem(cmd) => cmd.execute(state),
+            Command::SMembers(cmd) => cmd.execute(state),
+            Command::SIsMember(cmd) => cmd.execute(state),
+            Command::SMIsMember(cmd) => cmd.execute(state),
+            Command::SCard(cmd) => cmd.execute(state),
+            Command::SMove(cmd) => cmd.execute(state),
+            Command::SetOp(cmd) => cmd.execute(state),
+            Command::SInterCard(cmd) => cmd.execute(state),
+            Command::SPop(cmd) => cmd.execute(state),
+            Command::SRandMember(cmd) => cmd.execute(state),
+            Command::ZAdd(cmd) => cmd.execute(state),
+            Command::ZIncrBy(cmd) => cmd.execute(state),
+            Command::ZRange(cmd) => cmd.execute(state, protocol),
+            Command::ZRank(cmd) => cmd.execute(state),
+            Command::ZScore(cmd) => cmd.execute(state),
+            Command::ZRem(cmd) => cmd.execute(state),
+            Command::ZCard(cmd) => cmd.execute(state),
+            Command::ZCount(cmd) => cmd.execute(state),
+            Command::ZPop(cmd) => cmd.execute(state, protocol),
+            Command::ZStore(cmd) => cmd.execute(state),
+            Command::GeoAdd(cmd) => cmd.execute(state),
+            Command::GeoPos(cmd) => cmd.execute(state),
+            Command::GeoDist(cmd) => cmd.execute(state),
+            Command::GeoHash(cmd) => cmd.execute(state),
+            Command::GeoSearch(cmd) => cmd.execute(state),
+            Command::XAdd(cmd) => cmd.execute(state),
+            Command::XRange(cmd) => cmd.execute(state),
+            Command::XLen(cmd) => cmd.execute(state),
+            Command::XDel(cmd) => cmd.execute(state),
+            Command::XTrim(cmd) => cmd.execute(state),
+            Command::XRead(cmd) => cmd.execute(state, protocol),
+            Command::XGroup(cmd) => cmd.execute(state),
+            Command::XReadGroup(cmd) => cmd.execute(state, protocol),
+            Command::XAck(cmd) => cmd.execute(state),
+            Command::XPending(cmd) => cmd.execute(state),
+            Command::XClaim(cmd) => cmd.execute(state),
+            Command::XAutoClaim(cmd) => cmd.execute(state),
+            Command::Unknown(cmd) => cmd.execute(),
+        }
+    }
+
+    /// Executes a command queued by `MULTI`, while `EXEC` holds the
+    /// keyspace and the broker. Blocking commands do not block.
+    fn execute_queued(self, state: &mut State, broker: &mut Broker, protocol: Protocol) -> Frame {
+        match self {
+            Command::Publish(cmd) => cmd.execute(broker),
+            Command::SPublish(cmd) => cmd.execute(broker),
+            Command::PubSub(cmd) => cmd.execute(broker),
+            // `EXEC` already unwatched every key
+            Command::Unwatch(_) => Frame::Simple("OK".to_string()),
+            cmd => cmd.execute(state, protocol),
+        }
+    }
+
+    /// Executes the command and writes the reply to the connection.
+    ///
+    /// Within a transaction, the command is queued instead, unless it
+    /// controls the transaction.
+    pub(crate) async fn apply<S>(self, db: &Db, dst: &mut Connection<S>) -> Result<(), Error>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let queued = !matches!(
+            self,
+            Command::Multi(_)
+                | Command::Exec(_)
+                | Command::Discard(_)
+                | Command::Watch(_)
+                | Command::Reset(_)
+        );
+        if let Some(transaction) = dst.transaction().as_mut().filter(|_| queued) {
+            let response = transaction.queue(self);
+            dst.write_frame(&response).await?;
+            return Ok(());
+        }
+
+        let response = match self {
+            Command::Hello(cmd) => cmd.apply(dst),
+            Command::Reset(cmd) => cmd.apply(db, dst),
+            Command::Multi(cmd) => cmd.apply(dst),
+            Command::Exec(cmd) => cmd.apply(db, dst),
+            Command::Discard(cmd) => cmd.apply(db, dst),
+            Command::Watch(cmd) => cmd.apply(db, dst),
+            Command::Unwatch(cmd) => cmd.apply(db, dst),
+            Command::Subscribe(cmd) => {
+                for response in cmd.apply(db, dst) {
+                    dst.write_frame(&response).await?;
+                }
+                return Ok(());
+            }
+            Command::Unsubscribe(cmd) => {
+                for response in cmd.apply(db, dst) {
+                    dst.write_frame(&response).await?;
+                }
+                return Ok(());
+            }
+            Command::Publish(cmd) => db.with_broker(|broker| cmd.execute(broker)),
+            Command::SPublish(cmd) => db.with_broker(|broker| cmd.execute(broker)),
+            Command::PubSub(cmd) => db.with_broker(|broker| cmd.execute(broker)),
+            // Subscribed RESP2 clients only expect messages
+            Command::Ping(cmd) if dst.is_subscribed() && dst.protocol() == Protocol::Resp2 => {
+                cmd.execute_subscribed()
+            }
+            Command::BPop(cmd) => {
+                let timeout = cmd.timeout();
+                match block(cmd, timeout, db, dst).await {
+                    Some(response) => response,
+                    None => return Ok(()),
+                }
+            }
+            Command::Move(cmd) if cmd.is_blocking() => {
+                let timeout = cmd.timeout();
+                match block(cmd, timeout, db, dst).await {
+                    Some(response) => response,
+                    None => return Ok(()),
+                }
+            }
+            Command::XRead(cmd) if cmd.is_blocking() => {
+                let timeout = cmd.timeout();
+                // `$` is the last ID when the command is received, not when
+                // it is served
+                let cmd = cmd.with_protocol(dst.protocol());
+                let cmd = db.with_state(|state| cmd.resolve_ids(state));
+                match block(cmd, timeout, db, dst).await {
+                    Some(response) => response,
+                    None => return Ok(()),
+                }
+            }
+            Command::XReadGroup(cmd) if cmd.is_blocking() => {
+                let timeout = cmd.timeout();
+                let cmd = cmd.with_protocol(dst.protocol());
+                match block(cmd, timeout, db, dst).await {
+                    Some(response) => response,
+                    None => return Ok(()),
+                }
+            }
+            cmd => {
+                let protocol = dst.protocol();
+                db.with_state(|state| cmd.execute(state, protocol))
+            }
+        };
+        dst.write_frame(&response).await?;
+
+        Ok(())
+    }
+}
+
+/// Executes a blocking command, waiting up to `timeout` for another client
+/// to make it possible if it can not be served right away. Replies with the
+/// command's `timeout_reply` on timeout.
+///
+/// Returns `None` if the client disconnected while waiting.
+async fn block<C, S>(
+    command: C,
+    timeout: Option<Duration>,
+    db: &Db,
+    dst: &mut Connection<S>,
+) -> Option<Frame>
+where
+    C: BlockingCommand + 'static,
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let timeout_reply = command.timeout_reply();
+    let blocked = match db.execute_or_block(command) {
+        Ok(response) => return Some(response),
+        Err(blocked) => blocked,
+    };
+
+    tokio::select! {
+        response = blocked.wait(timeout) => Some(response.unwrap_or(timeout_reply)),
+        _ = dst.closed() => None,
+    }
+}
+
+impl Unknown {
+    fn parse_frames(name: String, parser: &mut Parser) -> Unknown {
+        let mut args = vec![];
+        while let Ok(arg) = parser.next_string() {
+            args.push(arg);
+        }
+
+        Unknown { name, args }
+    }
+
+    fn execute(self) -> Frame {
+        ReplyError::UnknownCommand {
+            name: self.name,
+            args: self.args,
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+#[path = "test/helper.rs"]
+pub(crate) mod helper;
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/cmd_test.rs"]
+mod cmd_test;
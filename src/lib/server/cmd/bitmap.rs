@@ -0,0 +1,619 @@
+use crate::error::ReplyError;
+use crate::server::cmd::list::normalize_range;
+use crate::server::cmd::string::{get_string, update_string};
+use crate::server::db::{State, Value};
+use crate::server::frame::Frame;
+use crate::server::parser::{parse_int, Parser, ParserError};
+
+use bytes::Bytes;
+
+/// Sets or clears the bit at an offset of the string at a key, growing the
+/// string with zero bytes as needed. Bit 0 is the most significant bit of
+/// the first byte.
+///
+/// Returns the previous value of the bit.
+#[derive(Debug)]
+pub(crate) struct SetBit {
+    key: Bytes,
+    offset: u64,
+    value: bool,
+}
+
+/// Returns the bit at an offset of the string at a key, 0 past its end.
+#[derive(Debug)]
+pub(crate) struct GetBit {
+    key: Bytes,
+    offset: u64,
+}
+
+/// Counts the bits set in the string at a key, optionally within a range of
+/// bytes, or of bits with `BIT`.
+#[derive(Debug)]
+pub(crate) struct BitCount {
+    key: Bytes,
+    range: Option<BitRange>,
+}
+
+/// Returns the position of the first bit set to 0 or 1 in the string at a
+/// key, optionally within a range of bytes, or of bits with `BIT`.
+///
+/// Returns -1 if there is none, except when looking for a 0 without giving
+/// the end of the range: the string is then considered padded with zeros.
+#[derive(Debug)]
+pub(crate) struct BitPos {
+    key: Bytes,
+    bit: bool,
+    start: i64,
+
+    /// End of the range, the end of the string if `None`
+    end: Option<i64>,
+    unit: BitUnit,
+}
+
+/// Applies a bitwise operation between strings and stores the result at
+/// `destination`, the shorter strings being padded with zero bytes.
+///
+/// Returns the length of the stored string.
+#[derive(Debug)]
+pub(crate) struct BitOp {
+    operation: BitOperation,
+    destination: Bytes,
+    keys: Vec<Bytes>,
+}
+
+/// Reads and writes integers of arbitrary width at any bit offset of the
+/// string at a key. Registered as `BITFIELD`, and `BITFIELD_RO` which only
+/// accepts `GET`.
+///
+/// Returns the result of each operation: the value read by `GET`, the
+/// previous value for `SET` and the new one for `INCRBY`. `OVERFLOW` changes
+/// how the following writes handle overflows, `Frame::Null` being returned
+/// for the ones that fail with `FAIL`.
+#[derive(Debug)]
+pub(crate) struct BitField {
+    key: Bytes,
+    operations: Vec<FieldOperation>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BitOperation {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+/// Unit of the indexes of a range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BitUnit {
+    Byte,
+    Bit,
+}
+
+#[derive(Debug)]
+struct BitRange {
+    start: i64,
+    end: i64,
+    unit: BitUnit,
+}
+
+/// Integer type of a bit field, such as `i8` or `u16`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FieldType {
+    signed: bool,
+    bits: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Overflow {
+    /// Wrap around, as integers do in most languages
+    Wrap,
+    /// Saturate to the minimum or maximum value
+    Sat,
+    /// Do not write, and reply `Frame::Null`
+    Fail,
+}
+
+#[derive(Debug)]
+enum FieldOperation {
+    Get(FieldType, u64),
+    Set(FieldType, u64, i64, Overflow),
+    IncrBy(FieldType, u64, i64, Overflow),
+}
+
+/// Largest bit offset, as strings are limited to 512MB
+const MAX_BIT_OFFSET: u64 = 512 * 1024 * 1024 * 8 - 1;
+
+fn invalid_offset() -> ReplyError {
+    ReplyError::OutOfRange("bit offset is not an integer or out of range".into())
+}
+
+/// Parses a bit offset. With a `width`, `#n` stands for the offset of the
+/// `n`th field of that width.
+fn parse_offset(src: &[u8], width: Option<u32>) -> Result<u64, ReplyError> {
+    let offset = match (src, width) {
+        ([b'#', index @ ..], Some(width)) => parse_int(index)
+            .ok()
+            .and_then(|index| u64::try_from(index).ok())
+            .and_then(|index| index.checked_mul(width as u64)),
+        (offset, _) => parse_int(offset)
+            .ok()
+            .and_then(|offset| u64::try_from(offset).ok()),
+    };
+
+    offset
+        .filter(|offset| *offset <= MAX_BIT_OFFSET)
+        .ok_or_else(invalid_offset)
+}
+
+/// Parses the unit following a range, bytes by default.
+fn parse_unit(parser: &mut Parser) -> Result<BitUnit, ParserError> {
+    if parser.remaining() == 0 {
+        return Ok(BitUnit::Byte);
+    }
+
+    match &parser.next_string()?.to_uppercase()[..] {
+        "BYTE" => Ok(BitUnit::Byte),
+        "BIT" => Ok(BitUnit::Bit),
+        _ => Err(ReplyError::Syntax.into()),
+    }
+}
+
+/// Converts a range of `unit`, whose indexes count from the end when
+/// negative, into an inclusive range of bits of a string of `len` bytes.
+/// Returns `None` if the range is empty.
+fn bit_range(start: i64, end: i64, unit: BitUnit, len: usize) -> Option<(u64, u64)> {
+    match unit {
+        BitUnit::Byte => normalize_range(start, end, len)
+            .map(|(start, end)| (start as u64 * 8, end as u64 * 8 + 7)),
+        BitUnit::Bit => {
+            normalize_range(start, end, len * 8).map(|(start, end)| (start as u64, end as u64))
+        }
+    }
+}
+
+fn get_bit(bytes: &[u8], offset: u64) -> bool {
+    let byte = bytes.get((offset / 8) as usize).copied().unwrap_or(0);
+    byte & (0x80 >> (offset % 8)) != 0
+}
+
+/// Counts the bits set between the inclusive offsets `start` and `end`, the
+/// whole bytes at once and the partial bytes at each edge bit by bit.
+fn count_bits(bytes: &[u8], start: u64, end: u64) -> u64 {
+    // Bits set between `start` and `end` excluded
+    let count = |start, end| (start..end).filter(|bit| get_bit(bytes, *bit)).count() as u64;
+
+    // Whole bytes of the range, `last` excluded
+    let first = start.div_ceil(8);
+    let last = (end + 1) / 8;
+    if first >= last {
+        return count(start, end + 1);
+    }
+
+    let whole: u64 = bytes[first as usize..last as usize]
+        .iter()
+        .map(|byte| byte.count_ones() as u64)
+        .sum();
+    whole + count(start, first * 8) + count(last * 8, end + 1)
+}
+
+/// Sets the bit at `offset`, which must be within `bytes`.
+fn set_bit(bytes: &mut [u8], offset: u64, value: bool) {
+    let mask = 0x80 >> (offset % 8);
+    let byte = &mut bytes[(offset / 8) as usize];
+    if value {
+        *byte |= mask;
+    } else {
+        *byte &= !mask;
+    }
+}
+
+impl SetBit {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<SetBit, ParserError> {
+        let key = parser.next_bytes()?;
+        let offset = parse_offset(&parser.next_bytes()?, None)?;
+        let value = match &parser.next_bytes()?[..] {
+            b"0" => false,
+            b"1" => true,
+            _ => {
+                return Err(
+                    ReplyError::OutOfRange("bit is not an integer or out of range".into()).into(),
+                )
+            }
+        };
+
+        Ok(SetBit { key, offset, value })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let previous = match update_string(state, self.key.clone(), |bytes| {
+            let len = (self.offset / 8 + 1) as usize;
+            if bytes.len() < len {
+                bytes.resize(len, 0);
+            }
+
+            let previous = get_bit(bytes, self.offset);
+            set_bit(bytes, self.offset, self.value);
+            previous
+        }) {
+            Ok(previous) => previous,
+            Err(err) => return err.into(),
+        };
+        state.touch(&self.key);
+
+        Frame::Integer(previous as i64)
+    }
+}
+
+impl GetBit {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<GetBit, ParserError> {
+        Ok(GetBit {
+            key: parser.next_bytes()?,
+            offset: parse_offset(&parser.next_bytes()?, None)?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match get_string(state, &self.key) {
+            Ok(value) => {
+                let bit = value.is_some_and(|value| get_bit(value, self.offset));
+                Frame::Integer(bit as i64)
+            }
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl BitCount {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<BitCount, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let range = match parser.remaining() {
+            0 => None,
+            1 => return Err(ReplyError::Syntax.into()),
+            _ => Some(BitRange {
+                start: parser.next_int()?,
+                end: parser.next_int()?,
+                unit: parse_unit(parser)?,
+            }),
+        };
+
+        Ok(BitCount { key, range })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let value = match get_string(state, &self.key) {
+            Ok(Some(value)) => value,
+            Ok(None) => return Frame::Integer(0),
+            Err(err) => return err.into(),
+        };
+
+        let count = match self.range {
+            None => value.iter().map(|byte| byte.count_ones() as u64).sum(),
+            Some(range) => match bit_range(range.start, range.end, range.unit, value.len()) {
+                Some((start, end)) => count_bits(value, start, end),
+                None => 0,
+            },
+        };
+
+        Frame::Integer(count as i64)
+    }
+}
+
+impl BitPos {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<BitPos, ParserError> {
+        let key = parser.next_bytes()?;
+        let bit = match parser.next_int()? {
+            0 => false,
+            1 => true,
+            _ => {
+                return Err(
+                    ReplyError::OutOfRange("The bit argument must be 1 or 0.".into()).into(),
+                )
+            }
+        };
+
+        let start = match parser.remaining() {
+            0 => 0,
+            _ => parser.next_int()?,
+        };
+        let end = match parser.remaining() {
+            0 => None,
+            _ => Some(parser.next_int()?),
+        };
+        let unit = parse_unit(parser)?;
+
+        Ok(BitPos {
+            key,
+            bit,
+            start,
+            end,
+            unit,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let value = match get_string(state, &self.key) {
+            Ok(Some(value)) => value,
+            // A missing key is an empty string, padded with zeros
+            Ok(None) => return Frame::Integer(if self.bit { -1 } else { 0 }),
+            Err(err) => return err.into(),
+        };
+
+        let Some((start, end)) =
+            bit_range(self.start, self.end.unwrap_or(-1), self.unit, value.len())
+        else {
+            return Frame::Integer(-1);
+        };
+
+        match (start..=end).find(|bit| get_bit(value, *bit) == self.bit) {
+            Some(position) => Frame::Integer(position as i64),
+            None if !self.bit && self.end.is_none() => Frame::Integer(end as i64 + 1),
+            None => Frame::Integer(-1),
+        }
+    }
+}
+
+impl BitOp {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<BitOp, ParserError> {
+        let operation = match &parser.next_string()?.to_uppercase()[..] {
+            "AND" => BitOperation::And,
+            "OR" => BitOperation::Or,
+            "XOR" => BitOperation::Xor,
+            "NOT" => BitOperation::Not,
+            _ => return Err(ReplyError::Syntax.into()),
+        };
+        let destination = parser.next_bytes()?;
+
+        let mut keys = vec![parser.next_bytes()?];
+        while parser.remaining() > 0 {
+            keys.push(parser.next_bytes()?);
+        }
+
+        if operation == BitOperation::Not && keys.len() != 1 {
+            return Err(ReplyError::Other(
+                "BITOP NOT must be called with a single source key.".into(),
+            )
+            .into());
+        }
+
+        Ok(BitOp {
+            operation,
+            destination,
+            keys,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let mut sources = Vec::with_capacity(self.keys.len());
+        for key in &self.keys {
+            match get_string(state, key) {
+                Ok(value) => sources.push(value.cloned().unwrap_or_default()),
+                Err(err) => return err.into(),
+            }
+        }
+
+        let len = sources.iter().map(|source| source.len()).max().unwrap_or(0);
+        let byte = |source: &Bytes, i: usize| source.get(i).copied().unwrap_or(0);
+        let result: Vec<u8> = (0..len)
+            .map(|i| {
+                let mut bytes = sources.iter().map(|source| byte(source, i));
+                let first = bytes.next().unwrap_or(0);
+                match self.operation {
+                    BitOperation::And => bytes.fold(first, |acc, byte| acc & byte),
+                    BitOperation::Or => bytes.fold(first, |acc, byte| acc | byte),
+                    BitOperation::Xor => bytes.fold(first, |acc, byte| acc ^ byte),
+                    BitOperation::Not => !first,
+                }
+            })
+            .collect();
+
+        if result.is_empty() {
+            state.remove(&self.destination);
+        } else {
+            state.insert(self.destination, Value::String(Bytes::from(result)), None);
+        }
+
+        Frame::Integer(len as i64)
+    }
+}
+
+impl FieldType {
+    fn parse(src: &[u8]) -> Result<FieldType, ReplyError> {
+        let invalid = || {
+            ReplyError::Other(
+                "Invalid bitfield type. Use something like i16 u8. Note that u64 is not \
+                 supported but i64 is."
+                    .into(),
+            )
+        };
+
+        let (signed, bits) = match src {
+            [b'i' | b'I', bits @ ..] => (true, bits),
+            [b'u' | b'U', bits @ ..] => (false, bits),
+            _ => return Err(invalid()),
+        };
+        let max_bits = if signed { 64 } else { 63 };
+        let bits = parse_int(bits)
+            .ok()
+            .filter(|bits| (1..=max_bits).contains(bits))
+            .ok_or_else(invalid)?;
+
+        Ok(FieldType {
+            signed,
+            bits: bits as u32,
+        })
+    }
+
+    fn min(self) -> i128 {
+        if self.signed {
+            -(1 << (self.bits - 1))
+        } else {
+            0
+        }
+    }
+
+    fn max(self) -> i128 {
+        if self.signed {
+            (1 << (self.bits - 1)) - 1
+        } else {
+            (1 << self.bits) - 1
+        }
+    }
+
+    /// Reads a field of this type at `offset`.
+    fn read(self, bytes: &[u8], offset: u64) -> i64 {
+        let raw = (0..self.bits as u64).fold(0u64, |raw, i| {
+            (raw << 1) | get_bit(bytes, offset + i) as u64
+        });
+
+        // Sign extend negative values
+        if self.signed && self.bits < 64 && raw & (1 << (self.bits - 1)) != 0 {
+            (raw | (u64::MAX << self.bits)) as i64
+        } else {
+            raw as i64
+        }
+    }
+
+    /// Writes `value`, which must fit in this type, at `offset`. `bytes`
+    /// must be long enough.
+    fn write(self, bytes: &mut [u8], offset: u64, value: i64) {
+        let raw = value as u64;
+        for i in 0..self.bits as u64 {
+            let bit = (raw >> (self.bits as u64 - 1 - i)) & 1 != 0;
+            set_bit(bytes, offset + i, bit);
+        }
+    }
+
+    /// Brings `value` within the range of this type as `overflow` says,
+    /// returning `None` if it fails.
+    fn fit(self, value: i128, overflow: Overflow) -> Option<i64> {
+        let (min, max) = (self.min(), self.max());
+        if (min..=max).contains(&value) {
+            return Some(value as i64);
+        }
+
+        match overflow {
+            Overflow::Wrap => Some(((value - min).rem_euclid(1 << self.bits) + min) as i64),
+            Overflow::Sat => Some(value.clamp(min, max) as i64),
+            Overflow::Fail => None,
+        }
+    }
+}
+
+impl BitField {
+    pub(crate) fn parse_frames(
+        parser: &mut Parser,
+        read_only: bool,
+    ) -> Result<BitField, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let mut operations = vec![];
+        let mut overflow = Overflow::Wrap;
+        while parser.remaining() > 0 {
+            let subcommand = parser.next_string()?.to_uppercase();
+            if read_only && subcommand != "GET" {
+                return Err(ReplyError::Other(
+                    "BITFIELD_RO only supports the GET subcommand".into(),
+                )
+                .into());
+            }
+
+            if subcommand == "OVERFLOW" {
+                overflow = match &parser.next_string()?.to_uppercase()[..] {
+                    "WRAP" => Overflow::Wrap,
+                    "SAT" => Overflow::Sat,
+                    "FAIL" => Overflow::Fail,
+                    _ => {
+                        return Err(
+                            ReplyError::Other("Invalid OVERFLOW type specified".into()).into()
+                        )
+                    }
+                };
+                continue;
+            }
+
+            let field = FieldType::parse(&parser.next_bytes()?)?;
+            let offset = parse_offset(&parser.next_bytes()?, Some(field.bits))?;
+            operations.push(match &subcommand[..] {
+                "GET" => FieldOperation::Get(field, offset),
+                "SET" => FieldOperation::Set(field, offset, parser.next_int()?, overflow),
+                "INCRBY" => FieldOperation::IncrBy(field, offset, parser.next_int()?, overflow),
+                _ => return Err(ReplyError::Syntax.into()),
+            });
+        }
+
+        Ok(BitField { key, operations })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        if let Err(err) = get_string(state, &self.key) {
+            return err.into();
+        }
+
+        let mut written = false;
+        let mut replies = Vec::with_capacity(self.operations.len());
+        for operation in self.operations {
+            match operation.apply(state, &self.key) {
+                Ok((reply, write)) => {
+                    written |= write;
+                    replies.push(reply);
+                }
+                Err(err) => return err.into(),
+            }
+        }
+
+        if written {
+            state.touch(&self.key);
+        }
+
+        Frame::Array(replies)
+    }
+}
+
+impl FieldOperation {
+    /// Applies the operation to the string at `key`, changed in place and
+    /// only created by a write. Returns the reply and whether it wrote.
+    fn apply(self, state: &mut State, key: &Bytes) -> Result<(Frame, bool), ReplyError> {
+        let read = |state: &mut State, field: FieldType, offset| {
+            get_string(state, key)
+                .map(|value| field.read(value.map_or(&[][..], |value| &value[..]), offset))
+        };
+
+        let (field, offset, value, is_set) = match self {
+            FieldOperation::Get(field, offset) => {
+                return Ok((Frame::Integer(read(state, field, offset)?), false));
+            }
+            FieldOperation::Set(field, offset, value, overflow) => {
+                match field.fit(value as i128, overflow) {
+                    Some(value) => (field, offset, value, true),
+                    None => return Ok((Frame::Null, false)),
+                }
+            }
+            FieldOperation::IncrBy(field, offset, increment, overflow) => {
+                let value = read(state, field, offset)? as i128 + increment as i128;
+                match field.fit(value, overflow) {
+                    Some(value) => (field, offset, value, false),
+                    None => return Ok((Frame::Null, false)),
+                }
+            }
+        };
+
+        let previous = update_string(state, key.clone(), |bytes| {
+            let len = (offset + field.bits as u64).div_ceil(8) as usize;
+            if bytes.len() < len {
+                bytes.resize(len, 0);
+            }
+            let previous = field.read(bytes, offset);
+            field.write(bytes, offset, value);
+            previous
+        })?;
+
+        Ok((Frame::Integer(if is_set { previous } else { value }), true))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/bitmap_test.rs"]
+mod bitmap_test;
@@ -0,0 +1,206 @@
+use crate::error::ReplyError;
+use crate::server::cmd::unwatch;
+use crate::server::connection::Connection;
+use crate::server::db::Db;
+use crate::server::frame::{Frame, Protocol};
+use crate::server::parser::{Parser, ParserError};
+
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// Returns `PONG` if no argument is provided, otherwise returns a copy of the
+/// argument as a bulk.
+#[derive(Debug, Default)]
+pub(crate) struct Ping {
+    /// Optional message to be returned
+    msg: Option<Bytes>,
+}
+
+/// Returns the given message as a bulk.
+#[derive(Debug)]
+pub(crate) struct Echo {
+    /// Message to be returned
+    msg: Bytes,
+}
+
+/// Switches the protocol spoken on the connection and returns information
+/// about the server.
+///
+/// `AUTH` and `SETNAME` are accepted for compatibility with clients sending
+/// them, but there is no authentication nor client naming yet.
+#[derive(Debug, Default)]
+pub(crate) struct Hello {
+    /// Requested protocol version, the current one is kept if missing
+    version: Option<i64>,
+}
+
+/// Replies `OK`, then closes the connection.
+#[derive(Debug, Default)]
+pub(crate) struct Quit;
+
+/// Resets the connection to the state of a new one: subscriptions, the
+/// transaction and the watched keys are dropped and the protocol is back to
+/// RESP2.
+#[derive(Debug, Default)]
+pub(crate) struct Reset;
+
+impl Ping {
+    pub(crate) fn new(msg: Option<Bytes>) -> Ping {
+        Ping { msg }
+    }
+
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<Ping, ParserError> {
+        match parser.next_bytes() {
+            Ok(msg) => Ok(Ping::new(Some(msg))),
+            Err(ParserError::NoMoreFrame) => Ok(Ping::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub(crate) fn execute(self) -> Frame {
+        match self.msg {
+            None => Frame::Simple("PONG".to_string()),
+            Some(msg) => Frame::Bulk(msg),
+        }
+    }
+
+    /// Replies on a RESP2 connection in subscriber mode, where replies must
+    /// look like messages: a `pong` array with the message, empty if there
+    /// is none.
+    pub(crate) fn execute_subscribed(self) -> Frame {
+        Frame::Array(vec![
+            bulk("pong"),
+            Frame::Bulk(self.msg.unwrap_or_default()),
+        ])
+    }
+}
+
+impl Quit {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<Quit, ParserError> {
+        // Arguments are ignored, as Redis does
+        while parser.remaining() > 0 {
+            parser.next_bytes()?;
+        }
+        Ok(Quit)
+    }
+
+    pub(crate) fn execute(self) -> Frame {
+        Frame::Simple("OK".to_string())
+    }
+}
+
+impl Reset {
+    pub(crate) fn parse_frames(_parser: &mut Parser) -> Result<Reset, ParserError> {
+        Ok(Reset)
+    }
+
+    /// Resets `dst` and returns the reply.
+    pub(crate) fn apply<S: AsyncRead + AsyncWrite + Unpin>(
+        self,
+        db: &Db,
+        dst: &mut Connection<S>,
+    ) -> Frame {
+        let id = dst.id();
+        db.with_broker(|broker| dst.subscriptions().clear(broker, id));
+        *dst.transaction() = None;
+        unwatch(db, std::mem::take(dst.watched()));
+        dst.set_protocol(Protocol::Resp2);
+
+        Frame::Simple("RESET".to_string())
+    }
+}
+
+impl Echo {
+    pub(crate) fn new(msg: Bytes) -> Echo {
+        Echo { msg }
+    }
+
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<Echo, ParserError> {
+        Ok(Echo::new(parser.next_bytes()?))
+    }
+
+    pub(crate) fn execute(self) -> Frame {
+        Frame::Bulk(self.msg)
+    }
+}
+
+impl Hello {
+    pub(crate) fn new(version: Option<i64>) -> Hello {
+        Hello { version }
+    }
+
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<Hello, ParserError> {
+        if parser.remaining() == 0 {
+            return Ok(Hello::default());
+        }
+
+        let version = parser.next_int().map_err(|_| {
+            ReplyError::OutOfRange("Protocol version is not an integer or out of range".to_string())
+        })?;
+
+        while parser.remaining() > 0 {
+            let option = parser.next_string()?;
+            match &option.to_uppercase()[..] {
+                "AUTH" if parser.remaining() >= 2 => {
+                    parser.next_bytes()?;
+                    parser.next_bytes()?;
+                }
+                "SETNAME" if parser.remaining() >= 1 => {
+                    parser.next_bytes()?;
+                }
+                _ => {
+                    let msg = format!("Syntax error in HELLO option '{}'", option);
+                    return Err(ReplyError::Other(msg).into());
+                }
+            }
+        }
+
+        Ok(Hello::new(Some(version)))
+    }
+
+    /// Switches the protocol of `dst` and returns the reply.
+    pub(crate) fn apply<S: AsyncRead + AsyncWrite + Unpin>(self, dst: &mut Connection<S>) -> Frame {
+        let (protocol, response) = self.execute(dst.id(), dst.protocol());
+        dst.set_protocol(protocol);
+        response
+    }
+
+    /// Returns the protocol the connection should use from now on, together
+    /// with the reply. `id` and `protocol` describe the connection.
+    pub(crate) fn execute(self, id: u64, protocol: Protocol) -> (Protocol, Frame) {
+        let protocol = match self.version {
+            None => protocol,
+            Some(2) => Protocol::Resp2,
+            Some(3) => Protocol::Resp3,
+            Some(_) => {
+                return (protocol, ReplyError::NoProto.into());
+            }
+        };
+
+        let version = match protocol {
+            Protocol::Resp2 => 2,
+            Protocol::Resp3 => 3,
+        };
+
+        let response = Frame::Map(vec![
+            (bulk("server"), bulk("redis")),
+            (bulk("version"), bulk(env!("CARGO_PKG_VERSION"))),
+            (bulk("proto"), Frame::Integer(version)),
+            (bulk("id"), Frame::Integer(id as i64)),
+            (bulk("mode"), bulk("standalone")),
+            (bulk("role"), bulk("master")),
+            (bulk("modules"), Frame::Array(vec![])),
+        ]);
+
+        (protocol, response)
+    }
+}
+
+fn bulk(value: &'static str) -> Frame {
+    Frame::Bulk(Bytes::from_static(value.as_bytes()))
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/connection_test.rs"]
+mod connection_test;
@@ -0,0 +1,587 @@
+use crate::error::ReplyError;
+use crate::server::cmd::sorted_set::{add, get_sorted_set, ZAddCondition};
+use crate::server::db::{State, Value};
+use crate::server::frame::Frame;
+use crate::server::geo::{self, Shape};
+use crate::server::parser::{parse_double, parse_int, Parser, ParserError};
+use crate::server::sorted_set::SortedSet;
+
+use bytes::Bytes;
+use std::ops::Bound;
+
+/// Adds members to a sorted set at their position, creating the sorted set
+/// if the key does not exist. Positions are given as longitude, then
+/// latitude, and stored as the geohash score of the member.
+///
+/// `NX` only adds new members and `XX` only updates existing ones. Returns
+/// the number of added members, plus the moved ones with `CH`.
+#[derive(Debug)]
+pub(crate) struct GeoAdd {
+    key: Bytes,
+    elements: Vec<(f64, Bytes)>,
+    condition: Option<ZAddCondition>,
+    changed: bool,
+}
+
+/// Returns the position of members, `Frame::NullArray` for the missing ones.
+#[derive(Debug)]
+pub(crate) struct GeoPos {
+    key: Bytes,
+    members: Vec<Bytes>,
+}
+
+/// Returns the distance between two members, in meters or in the given
+/// unit, `Frame::Null` if one of them is missing.
+#[derive(Debug)]
+pub(crate) struct GeoDist {
+    key: Bytes,
+    from: Bytes,
+    to: Bytes,
+    unit: Unit,
+}
+
+/// Returns the standard 11 characters geohash of members, `Frame::Null` for
+/// the missing ones.
+#[derive(Debug)]
+pub(crate) struct GeoHash {
+    key: Bytes,
+    members: Vec<Bytes>,
+}
+
+/// Returns the members within a radius or a box around a member or a
+/// position. Registered as `GEOSEARCH` and `GEOSEARCHSTORE`.
+///
+/// `ASC` and `DESC` sort the members by distance, `COUNT` returns the
+/// closest ones only, or the first ones found with `ANY`. Members are
+/// returned along with their distance, geohash and position with
+/// `WITHDIST`, `WITHHASH` and `WITHCOORD`.
+///
+/// `GEOSEARCHSTORE` stores the members in a sorted set instead, with their
+/// geohash as score, or their distance with `STOREDIST`, and returns their
+/// number.
+#[derive(Debug)]
+pub(crate) struct GeoSearch {
+    key: Bytes,
+    center: Center,
+    shape: Shape,
+    unit: Unit,
+    sort: Option<Sort>,
+
+    /// Maximum number of members, and whether any matching member can be
+    /// returned rather than the closest ones
+    count: Option<(usize, bool)>,
+    with_coord: bool,
+    with_dist: bool,
+    with_hash: bool,
+
+    /// Destination of `GEOSEARCHSTORE`, and whether distances are stored
+    /// rather than geohashes
+    store: Option<(Bytes, bool)>,
+}
+
+/// Unit of a distance, as its length in meters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Unit(f64);
+
+/// Where a search is centered.
+#[derive(Debug, Clone, PartialEq)]
+enum Center {
+    Member(Bytes),
+    Position(f64, f64),
+}
+
+/// Order of the results of a search.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sort {
+    Asc,
+    Desc,
+}
+
+/// Member found by a search.
+struct Found {
+    member: Bytes,
+    distance: f64,
+    hash: u64,
+}
+
+impl Unit {
+    fn parse(src: &[u8]) -> Result<Unit, ReplyError> {
+        match &src.to_ascii_lowercase()[..] {
+            b"m" => Ok(Unit(1.0)),
+            b"km" => Ok(Unit(1000.0)),
+            b"ft" => Ok(Unit(0.3048)),
+            b"mi" => Ok(Unit(1609.34)),
+            _ => Err(ReplyError::Other(
+                "unsupported unit provided. please use M, KM, FT, MI".into(),
+            )),
+        }
+    }
+
+    /// Replies with a distance in meters converted to the unit.
+    fn reply(self, distance: f64) -> Frame {
+        Frame::Bulk(Bytes::from(format!("{:.4}", distance / self.0)))
+    }
+}
+
+/// Parses a longitude and a latitude, which must be storable in a geohash.
+fn parse_position(longitude: &[u8], latitude: &[u8]) -> Result<(f64, f64), ParserError> {
+    let longitude = parse_double(longitude)?;
+    let latitude = parse_double(latitude)?;
+    if !geo::is_valid(longitude, latitude) {
+        return Err(ReplyError::Other(format!(
+            "invalid longitude,latitude pair {:.6},{:.6}",
+            longitude, latitude
+        ))
+        .into());
+    }
+
+    Ok((longitude, latitude))
+}
+
+/// Parses a distance that must not be negative, replying with `invalid` if
+/// it is not a number.
+fn parse_distance(src: &[u8], invalid: &str, negative: &str) -> Result<f64, ReplyError> {
+    let distance = parse_double(src).map_err(|_| ReplyError::Other(invalid.into()))?;
+    if distance < 0.0 {
+        return Err(ReplyError::Other(negative.into()));
+    }
+    Ok(distance)
+}
+
+/// Replies with a coordinate the way Redis prints it, with up to 17
+/// decimals.
+fn coordinate(value: f64) -> Frame {
+    let value = format!("{:.17}", value);
+    let value = value.trim_end_matches('0').trim_end_matches('.');
+    Frame::Bulk(Bytes::from(value.to_string()))
+}
+
+/// Replies with the position stored as `score`.
+fn position(score: f64) -> Frame {
+    let (longitude, latitude) = geo::decode(score as u64);
+    Frame::Array(vec![coordinate(longitude), coordinate(latitude)])
+}
+
+impl GeoAdd {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<GeoAdd, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let mut args = vec![];
+        while parser.remaining() > 0 {
+            args.push(parser.next_bytes()?);
+        }
+        let mut args = args.into_iter().peekable();
+
+        let (mut nx, mut xx, mut changed) = (false, false, false);
+        while let Some(arg) = args.peek() {
+            match &arg.to_ascii_uppercase()[..] {
+                b"NX" => nx = true,
+                b"XX" => xx = true,
+                b"CH" => changed = true,
+                _ => break,
+            }
+            args.next();
+        }
+
+        let args: Vec<Bytes> = args.collect();
+        if args.is_empty() || !args.len().is_multiple_of(3) || (nx && xx) {
+            return Err(ReplyError::Other(
+                "syntax error. Try GEOADD key [x1] [y1] [name1] [x2] [y2] [name2] ... ".into(),
+            )
+            .into());
+        }
+
+        let elements = args
+            .chunks(3)
+            .map(|triple| {
+                let (longitude, latitude) = parse_position(&triple[0], &triple[1])?;
+                let score = geo::encode(longitude, latitude) as f64;
+                Ok((score, triple[2].clone()))
+            })
+            .collect::<Result<_, ParserError>>()?;
+
+        let condition = match (nx, xx) {
+            (true, _) => Some(ZAddCondition::Nx),
+            (_, true) => Some(ZAddCondition::Xx),
+            _ => None,
+        };
+
+        Ok(GeoAdd {
+            key,
+            elements,
+            condition,
+            changed,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        // `XX` never adds to a missing key, which is not created
+        if self.condition == Some(ZAddCondition::Xx) && !state.contains(&self.key) {
+            return Frame::Integer(0);
+        }
+
+        let value =
+            state.get_or_insert_with(self.key.clone(), || Value::SortedSet(SortedSet::new()));
+        let Value::SortedSet(set) = value else {
+            return ReplyError::WrongType.into();
+        };
+
+        let mut added = 0;
+        let mut changed = 0;
+        for (score, member) in self.elements {
+            // Geohashes are never NaN, so adding cannot fail
+            if let Ok(outcome) = add(set, member, score, false, self.condition, None) {
+                added += outcome.added as i64;
+                changed += outcome.changed as i64;
+            }
+        }
+
+        if added + changed > 0 {
+            state.touch(&self.key);
+        }
+
+        if self.changed {
+            Frame::Integer(added + changed)
+        } else {
+            Frame::Integer(added)
+        }
+    }
+}
+
+impl GeoPos {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<GeoPos, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let mut members = vec![];
+        while parser.remaining() > 0 {
+            members.push(parser.next_bytes()?);
+        }
+
+        Ok(GeoPos { key, members })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let set = match get_sorted_set(state, &self.key) {
+            Ok(set) => set,
+            Err(err) => return err.into(),
+        };
+
+        let positions = self
+            .members
+            .iter()
+            .map(
+                |member| match set.as_ref().and_then(|set| set.score(member)) {
+                    Some(score) => position(score),
+                    None => Frame::NullArray,
+                },
+            )
+            .collect();
+
+        Frame::Array(positions)
+    }
+}
+
+impl GeoDist {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<GeoDist, ParserError> {
+        let key = parser.next_bytes()?;
+        let from = parser.next_bytes()?;
+        let to = parser.next_bytes()?;
+        let unit = match parser.remaining() {
+            0 => Unit(1.0),
+            _ => Unit::parse(&parser.next_bytes()?)?,
+        };
+
+        Ok(GeoDist {
+            key,
+            from,
+            to,
+            unit,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let set = match get_sorted_set(state, &self.key) {
+            Ok(Some(set)) => set,
+            Ok(None) => return Frame::Null,
+            Err(err) => return err.into(),
+        };
+
+        let (Some(from), Some(to)) = (set.score(&self.from), set.score(&self.to)) else {
+            return Frame::Null;
+        };
+
+        let (lon1, lat1) = geo::decode(from as u64);
+        let (lon2, lat2) = geo::decode(to as u64);
+        self.unit.reply(geo::distance(lon1, lat1, lon2, lat2))
+    }
+}
+
+impl GeoHash {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<GeoHash, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let mut members = vec![];
+        while parser.remaining() > 0 {
+            members.push(parser.next_bytes()?);
+        }
+
+        Ok(GeoHash { key, members })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let set = match get_sorted_set(state, &self.key) {
+            Ok(set) => set,
+            Err(err) => return err.into(),
+        };
+
+        let hashes = self
+            .members
+            .iter()
+            .map(
+                |member| match set.as_ref().and_then(|set| set.score(member)) {
+                    Some(score) => Frame::Bulk(Bytes::from(geo::to_base32(score as u64))),
+                    None => Frame::Null,
+                },
+            )
+            .collect();
+
+        Frame::Array(hashes)
+    }
+}
+
+impl GeoSearch {
+    pub(crate) fn parse_frames(parser: &mut Parser, store: bool) -> Result<GeoSearch, ParserError> {
+        let command = if store { "GEOSEARCHSTORE" } else { "GEOSEARCH" };
+        let destination = if store {
+            Some(parser.next_bytes()?)
+        } else {
+            None
+        };
+        let key = parser.next_bytes()?;
+
+        let mut center = None;
+        let mut shape = None;
+        let mut sort = None;
+        let mut count = None;
+        let mut any = false;
+        let (mut with_coord, mut with_dist, mut with_hash) = (false, false, false);
+        let mut store_dist = false;
+
+        let one_center = || {
+            ReplyError::Other(format!(
+                "exactly one of FROMMEMBER or FROMLONLAT can be specified for {}",
+                command
+            ))
+        };
+        let one_shape = || {
+            ReplyError::Other(format!(
+                "exactly one of BYRADIUS and BYBOX can be specified for {}",
+                command
+            ))
+        };
+
+        while parser.remaining() > 0 {
+            match &parser.next_string()?.to_uppercase()[..] {
+                "FROMMEMBER" if center.is_none() => {
+                    center = Some(Center::Member(parser.next_bytes()?));
+                }
+                "FROMLONLAT" if center.is_none() => {
+                    let longitude = parser.next_bytes()?;
+                    let latitude = parser.next_bytes()?;
+                    let (longitude, latitude) = parse_position(&longitude, &latitude)?;
+                    center = Some(Center::Position(longitude, latitude));
+                }
+                "FROMMEMBER" | "FROMLONLAT" => return Err(one_center().into()),
+                "BYRADIUS" if shape.is_none() => {
+                    let radius = parse_distance(
+                        &parser.next_bytes()?,
+                        "need numeric radius",
+                        "radius cannot be negative",
+                    )?;
+                    let unit = Unit::parse(&parser.next_bytes()?)?;
+                    shape = Some((Shape::Radius(radius * unit.0), unit));
+                }
+                "BYBOX" if shape.is_none() => {
+                    let negative = "height or width cannot be negative";
+                    let width =
+                        parse_distance(&parser.next_bytes()?, "need numeric width", negative)?;
+                    let height =
+                        parse_distance(&parser.next_bytes()?, "need numeric height", negative)?;
+                    let unit = Unit::parse(&parser.next_bytes()?)?;
+                    let shape_box = Shape::Box {
+                        width: width * unit.0,
+                        height: height * unit.0,
+                    };
+                    shape = Some((shape_box, unit));
+                }
+                "BYRADIUS" | "BYBOX" => return Err(one_shape().into()),
+                "ASC" => sort = Some(Sort::Asc),
+                "DESC" => sort = Some(Sort::Desc),
+                "COUNT" => {
+                    let err = || ReplyError::Other("COUNT must be > 0".into());
+                    let value = parse_int(&parser.next_bytes()?).map_err(|_| err())?;
+                    count = Some(
+                        usize::try_from(value)
+                            .ok()
+                            .filter(|count| *count > 0)
+                            .ok_or_else(err)?,
+                    );
+                }
+                "ANY" => any = true,
+                "WITHCOORD" => with_coord = true,
+                "WITHDIST" => with_dist = true,
+                "WITHHASH" => with_hash = true,
+                "STOREDIST" if store => store_dist = true,
+                _ => return Err(ReplyError::Syntax.into()),
+            }
+        }
+
+        let center = center.ok_or_else(one_center)?;
+        let (shape, unit) = shape.ok_or_else(one_shape)?;
+        if any && count.is_none() {
+            return Err(
+                ReplyError::Other("the ANY argument requires COUNT argument".into()).into(),
+            );
+        }
+        if store && (with_coord || with_dist || with_hash) {
+            return Err(ReplyError::Other(format!(
+                "{} is not compatible with WITHDIST, WITHHASH and WITHCOORD options",
+                command
+            ))
+            .into());
+        }
+
+        Ok(GeoSearch {
+            key,
+            center,
+            shape,
+            unit,
+            sort,
+            count: count.map(|count| (count, any)),
+            with_coord,
+            with_dist,
+            with_hash,
+            store: destination.map(|destination| (destination, store_dist)),
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let found = match self.search(state) {
+            Ok(found) => found,
+            Err(err) => return err.into(),
+        };
+
+        if let Some((destination, store_dist)) = self.store {
+            let mut set = SortedSet::new();
+            for found in found {
+                let score = if store_dist {
+                    found.distance / self.unit.0
+                } else {
+                    found.hash as f64
+                };
+                set.insert(found.member, score);
+            }
+
+            let len = set.len();
+            if set.is_empty() {
+                state.remove(&destination);
+            } else {
+                state.insert(destination, Value::SortedSet(set), None);
+            }
+            return Frame::Integer(len as i64);
+        }
+
+        let with_any = self.with_coord || self.with_dist || self.with_hash;
+        let frames = found
+            .into_iter()
+            .map(|found| {
+                if !with_any {
+                    return Frame::Bulk(found.member);
+                }
+
+                let mut frames = vec![Frame::Bulk(found.member)];
+                if self.with_dist {
+                    frames.push(self.unit.reply(found.distance));
+                }
+                if self.with_hash {
+                    frames.push(Frame::Integer(found.hash as i64));
+                }
+                if self.with_coord {
+                    frames.push(position(found.hash as f64));
+                }
+                Frame::Array(frames)
+            })
+            .collect();
+
+        Frame::Array(frames)
+    }
+
+    /// Returns the members within the shape, sorted and counted as asked.
+    fn search(&self, state: &mut State) -> Result<Vec<Found>, ReplyError> {
+        let Some(set) = get_sorted_set(state, &self.key)? else {
+            return Ok(vec![]);
+        };
+
+        let center = match &self.center {
+            Center::Position(longitude, latitude) => (*longitude, *latitude),
+            Center::Member(member) => {
+                let score = set.score(member).ok_or_else(|| {
+                    ReplyError::Other("could not decode requested zset member".into())
+                })?;
+                geo::decode(score as u64)
+            }
+        };
+
+        // Members can be returned as soon as enough are found with `ANY`
+        let limit = match self.count {
+            Some((count, true)) => count,
+            _ => usize::MAX,
+        };
+
+        // Only the cells around the center are read
+        let mut found: Vec<Found> = self
+            .shape
+            .search_ranges(center)
+            .into_iter()
+            .flat_map(|range| {
+                let ranks = set.ranks_by_score(
+                    Bound::Included(range.start as f64),
+                    Bound::Excluded(range.end as f64),
+                );
+                set.iter_from(ranks.start, false).take(ranks.len())
+            })
+            .filter_map(|(member, score)| {
+                let hash = score as u64;
+                let distance = self.shape.distance_within(center, geo::decode(hash))?;
+                Some(Found {
+                    member: member.clone(),
+                    distance,
+                    hash,
+                })
+            })
+            .take(limit)
+            .collect();
+
+        // The closest members are returned by `COUNT` without `ANY`
+        let sort = match (self.sort, self.count) {
+            (None, Some((_, false))) => Some(Sort::Asc),
+            (sort, _) => sort,
+        };
+        match sort {
+            Some(Sort::Asc) => found.sort_by(|a, b| a.distance.total_cmp(&b.distance)),
+            Some(Sort::Desc) => found.sort_by(|a, b| b.distance.total_cmp(&a.distance)),
+            None => {}
+        }
+
+        if let Some((count, _)) = self.count {
+            found.truncate(count);
+        }
+
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/geo_test.rs"]
+mod geo_test;
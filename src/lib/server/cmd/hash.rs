@@ -0,0 +1,651 @@
+use crate::error::ReplyError;
+use crate::server::db::{State, Value};
+use crate::server::frame::{Frame, Protocol};
+use crate::server::glob;
+use crate::server::parser::{parse_double, parse_int, Parser, ParserError};
+
+use bytes::Bytes;
+use rand::seq::{IndexedRandom, IteratorRandom};
+use std::collections::HashMap;
+
+/// Sets fields of a hash, creating the hash if the key does not exist.
+///
+/// Returns the number of fields that were added, not counting the ones whose
+/// value was updated.
+#[derive(Debug)]
+pub(crate) struct HSet {
+    key: Bytes,
+    fields: Vec<(Bytes, Bytes)>,
+}
+
+/// Sets a field of a hash only if it does not exist yet.
+///
+/// Returns 1 if the field was set, 0 otherwise.
+#[derive(Debug)]
+pub(crate) struct HSetNx {
+    key: Bytes,
+    field: Bytes,
+    value: Bytes,
+}
+
+/// Returns the value of a field, `Frame::Null` if the field or the key does
+/// not exist.
+#[derive(Debug)]
+pub(crate) struct HGet {
+    key: Bytes,
+    field: Bytes,
+}
+
+/// Returns the values of several fields, with `Frame::Null` in place of the
+/// missing ones.
+#[derive(Debug)]
+pub(crate) struct HMGet {
+    key: Bytes,
+    fields: Vec<Bytes>,
+}
+
+/// Removes fields from a hash, deleting the hash once it is empty.
+///
+/// Returns the number of fields that were removed.
+#[derive(Debug)]
+pub(crate) struct HDel {
+    key: Bytes,
+    fields: Vec<Bytes>,
+}
+
+/// Returns 1 if a field exists in a hash, 0 otherwise.
+#[derive(Debug)]
+pub(crate) struct HExists {
+    key: Bytes,
+    field: Bytes,
+}
+
+/// Returns the number of fields of a hash, 0 if the key does not exist.
+#[derive(Debug)]
+pub(crate) struct HLen {
+    key: Bytes,
+}
+
+/// Returns the whole content of a hash. Registered as `HKEYS`, `HVALS` and
+/// `HGETALL`, which only differ in the part of the entries they return.
+///
+/// `HGETALL` replies with a map, flattened to an array of fields and values
+/// on RESP2 connections.
+#[derive(Debug)]
+pub(crate) struct HGetAll {
+    key: Bytes,
+    part: HashPart,
+}
+
+/// Increments the integer stored in a field, which is set to 0 first if it
+/// does not exist.
+///
+/// Returns the value of the field after the increment.
+#[derive(Debug)]
+pub(crate) struct HIncrBy {
+    key: Bytes,
+    field: Bytes,
+    increment: i64,
+}
+
+/// Increments the floating point number stored in a field, which is set to
+/// 0 first if it does not exist.
+///
+/// Returns the value of the field after the increment, as a bulk.
+#[derive(Debug)]
+pub(crate) struct HIncrByFloat {
+    key: Bytes,
+    field: Bytes,
+    increment: f64,
+}
+
+/// Returns the length of the value of a field, 0 if the field or the key
+/// does not exist.
+#[derive(Debug)]
+pub(crate) struct HStrLen {
+    key: Bytes,
+    field: Bytes,
+}
+
+/// Returns random fields of a hash.
+///
+/// Without a count a single field is returned as a bulk. A positive count
+/// returns up to that many distinct fields, a negative one returns exactly
+/// that many fields, possibly repeated, up to `MAX_REPEATED_PICKS`. With
+/// `WITHVALUES` every field is followed by its value, or paired with it on
+/// RESP3 connections.
+#[derive(Debug)]
+pub(crate) struct HRandField {
+    key: Bytes,
+    count: Option<i64>,
+    with_values: bool,
+}
+
+/// Iterates over the fields of a hash.
+///
+/// Unlike Redis, `HSCAN` is not incremental: the fields are kept in a
+/// `HashMap`, whose order changes as it grows, so a cursor could not resume
+/// an iteration. The call made with cursor 0 returns every matching field at
+/// once, along with the cursor 0 ending the iteration, and a call made with
+/// any other cursor returns nothing. `COUNT` is validated but ignored.
+#[derive(Debug)]
+pub(crate) struct HScan {
+    key: Bytes,
+    cursor: u64,
+
+    /// Only return the fields matching this glob-style pattern
+    pattern: Option<Bytes>,
+
+    /// Only return the fields, not their values
+    no_values: bool,
+}
+
+/// Largest number of fields or members a negative count of `HRANDFIELD` or
+/// `SRANDMEMBER` can ask for, as they may repeat and the whole reply is built
+/// while the keyspace is locked
+pub(crate) const MAX_REPEATED_PICKS: i64 = 1 << 20;
+
+/// Part of the entries of a hash returned by `HGetAll`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum HashPart {
+    /// The fields, as `HKEYS`
+    Fields,
+    /// The values, as `HVALS`
+    Values,
+    /// Both, as `HGETALL`
+    Entries,
+}
+
+/// Returns the hash stored at `key`, `None` if the key does not exist.
+fn get_hash<'a>(
+    state: &'a mut State,
+    key: &[u8],
+) -> Result<Option<&'a HashMap<Bytes, Bytes>>, ReplyError> {
+    match state.get(key) {
+        None => Ok(None),
+        Some(Value::Hash(hash)) => Ok(Some(hash)),
+        Some(_) => Err(ReplyError::WrongType),
+    }
+}
+
+/// Like `get_hash`, for commands modifying the hash.
+fn get_hash_mut<'a>(
+    state: &'a mut State,
+    key: &[u8],
+) -> Result<Option<&'a mut HashMap<Bytes, Bytes>>, ReplyError> {
+    match state.get_mut(key) {
+        None => Ok(None),
+        Some(Value::Hash(hash)) => Ok(Some(hash)),
+        Some(_) => Err(ReplyError::WrongType),
+    }
+}
+
+/// Returns the hash stored at `key`, creating an empty one if the key does
+/// not exist.
+fn get_or_create_hash(
+    state: &mut State,
+    key: Bytes,
+) -> Result<&mut HashMap<Bytes, Bytes>, ReplyError> {
+    match state.get_or_insert_with(key, || Value::Hash(HashMap::new())) {
+        Value::Hash(hash) => Ok(hash),
+        _ => Err(ReplyError::WrongType),
+    }
+}
+
+/// Replies with fields and their values, as pairs on RESP3 connections and
+/// as a flat array on RESP2 ones.
+fn entries<'a>(
+    entries: impl IntoIterator<Item = (&'a Bytes, &'a Bytes)>,
+    protocol: Protocol,
+) -> Frame {
+    let entries = entries.into_iter();
+    match protocol {
+        Protocol::Resp2 => Frame::Array(
+            entries
+                .flat_map(|(field, value)| [Frame::Bulk(field.clone()), Frame::Bulk(value.clone())])
+                .collect(),
+        ),
+        Protocol::Resp3 => Frame::Array(
+            entries
+                .map(|(field, value)| {
+                    Frame::Array(vec![Frame::Bulk(field.clone()), Frame::Bulk(value.clone())])
+                })
+                .collect(),
+        ),
+    }
+}
+
+impl HSet {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<HSet, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let mut fields = vec![(parser.next_bytes()?, parser.next_bytes()?)];
+        while parser.remaining() > 0 {
+            fields.push((parser.next_bytes()?, parser.next_bytes()?));
+        }
+
+        Ok(HSet { key, fields })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let hash = match get_or_create_hash(state, self.key.clone()) {
+            Ok(hash) => hash,
+            Err(err) => return err.into(),
+        };
+
+        let mut added = 0;
+        for (field, value) in self.fields {
+            if hash.insert(field, value).is_none() {
+                added += 1;
+            }
+        }
+        state.touch(&self.key);
+
+        Frame::Integer(added)
+    }
+}
+
+impl HSetNx {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<HSetNx, ParserError> {
+        Ok(HSetNx {
+            key: parser.next_bytes()?,
+            field: parser.next_bytes()?,
+            value: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let hash = match get_or_create_hash(state, self.key.clone()) {
+            Ok(hash) => hash,
+            Err(err) => return err.into(),
+        };
+
+        if hash.contains_key(&self.field) {
+            return Frame::Integer(0);
+        }
+        hash.insert(self.field, self.value);
+        state.touch(&self.key);
+
+        Frame::Integer(1)
+    }
+}
+
+impl HGet {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<HGet, ParserError> {
+        Ok(HGet {
+            key: parser.next_bytes()?,
+            field: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match get_hash(state, &self.key) {
+            Ok(hash) => hash
+                .and_then(|hash| hash.get(&self.field))
+                .map_or(Frame::Null, |value| Frame::Bulk(value.clone())),
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl HMGet {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<HMGet, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let mut fields = vec![parser.next_bytes()?];
+        while parser.remaining() > 0 {
+            fields.push(parser.next_bytes()?);
+        }
+
+        Ok(HMGet { key, fields })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let hash = match get_hash(state, &self.key) {
+            Ok(hash) => hash,
+            Err(err) => return err.into(),
+        };
+
+        let values = self
+            .fields
+            .iter()
+            .map(|field| {
+                hash.as_ref()
+                    .and_then(|hash| hash.get(field))
+                    .map_or(Frame::Null, |value| Frame::Bulk(value.clone()))
+            })
+            .collect();
+
+        Frame::Array(values)
+    }
+}
+
+impl HDel {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<HDel, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let mut fields = vec![parser.next_bytes()?];
+        while parser.remaining() > 0 {
+            fields.push(parser.next_bytes()?);
+        }
+
+        Ok(HDel { key, fields })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let hash = match get_hash_mut(state, &self.key) {
+            Ok(Some(hash)) => hash,
+            Ok(None) => return Frame::Integer(0),
+            Err(err) => return err.into(),
+        };
+
+        let removed = self
+            .fields
+            .iter()
+            .filter(|field| hash.remove(*field).is_some())
+            .count();
+
+        if hash.is_empty() {
+            state.remove(&self.key);
+        } else if removed > 0 {
+            state.touch(&self.key);
+        }
+
+        Frame::Integer(removed as i64)
+    }
+}
+
+impl HExists {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<HExists, ParserError> {
+        Ok(HExists {
+            key: parser.next_bytes()?,
+            field: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match get_hash(state, &self.key) {
+            Ok(hash) => {
+                let exists = hash.is_some_and(|hash| hash.contains_key(&self.field));
+                Frame::Integer(exists as i64)
+            }
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl HLen {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<HLen, ParserError> {
+        Ok(HLen {
+            key: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match get_hash(state, &self.key) {
+            Ok(hash) => Frame::Integer(hash.map_or(0, |hash| hash.len() as i64)),
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl HGetAll {
+    pub(crate) fn parse_frames(
+        parser: &mut Parser,
+        part: HashPart,
+    ) -> Result<HGetAll, ParserError> {
+        Ok(HGetAll {
+            key: parser.next_bytes()?,
+            part,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let hash = match get_hash(state, &self.key) {
+            Ok(Some(hash)) => hash,
+            Ok(None) if self.part == HashPart::Entries => return Frame::Map(vec![]),
+            Ok(None) => return Frame::Array(vec![]),
+            Err(err) => return err.into(),
+        };
+
+        match self.part {
+            HashPart::Fields => Frame::Array(hash.keys().cloned().map(Frame::Bulk).collect()),
+            HashPart::Values => Frame::Array(hash.values().cloned().map(Frame::Bulk).collect()),
+            HashPart::Entries => Frame::Map(
+                hash.iter()
+                    .map(|(field, value)| (Frame::Bulk(field.clone()), Frame::Bulk(value.clone())))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl HIncrBy {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<HIncrBy, ParserError> {
+        Ok(HIncrBy {
+            key: parser.next_bytes()?,
+            field: parser.next_bytes()?,
+            increment: parser.next_int()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let hash = match get_or_create_hash(state, self.key.clone()) {
+            Ok(hash) => hash,
+            Err(err) => return err.into(),
+        };
+
+        let current = match hash.get(&self.field) {
+            Some(value) => match parse_int(value) {
+                Ok(current) => current,
+                Err(_) => return ReplyError::Other("hash value is not an integer".into()).into(),
+            },
+            None => 0,
+        };
+
+        let Some(value) = current.checked_add(self.increment) else {
+            return ReplyError::OutOfRange("increment or decrement would overflow".into()).into();
+        };
+        hash.insert(self.field, Bytes::from(value.to_string()));
+        state.touch(&self.key);
+
+        Frame::Integer(value)
+    }
+}
+
+impl HIncrByFloat {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<HIncrByFloat, ParserError> {
+        let key = parser.next_bytes()?;
+        let field = parser.next_bytes()?;
+        let increment = parser.next_double()?;
+        if increment.is_infinite() {
+            return Err(ReplyError::Other("value is NaN or Infinity".into()).into());
+        }
+
+        Ok(HIncrByFloat {
+            key,
+            field,
+            increment,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let hash = match get_or_create_hash(state, self.key.clone()) {
+            Ok(hash) => hash,
+            Err(err) => return err.into(),
+        };
+
+        let current = match hash.get(&self.field) {
+            Some(value) => match parse_double(value) {
+                Ok(current) => current,
+                Err(_) => return ReplyError::Other("hash value is not a float".into()).into(),
+            },
+            None => 0.0,
+        };
+
+        let value = current + self.increment;
+        if !value.is_finite() {
+            return ReplyError::Other("increment would produce NaN or Infinity".into()).into();
+        }
+
+        let value = Bytes::from(value.to_string());
+        hash.insert(self.field, value.clone());
+        state.touch(&self.key);
+
+        Frame::Bulk(value)
+    }
+}
+
+impl HStrLen {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<HStrLen, ParserError> {
+        Ok(HStrLen {
+            key: parser.next_bytes()?,
+            field: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match get_hash(state, &self.key) {
+            Ok(hash) => {
+                let len = hash
+                    .and_then(|hash| hash.get(&self.field))
+                    .map_or(0, |value| value.len());
+                Frame::Integer(len as i64)
+            }
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl HRandField {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<HRandField, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let count = if parser.remaining() > 0 {
+            Some(parser.next_int()?)
+        } else {
+            None
+        };
+
+        let with_values = if parser.remaining() > 0 {
+            if parser.next_string()?.to_uppercase() != "WITHVALUES" {
+                return Err(ReplyError::Syntax.into());
+            }
+            true
+        } else {
+            false
+        };
+
+        if count.is_some_and(|count| count < -MAX_REPEATED_PICKS) {
+            return Err(ReplyError::OutOfRange("value is out of range".into()).into());
+        }
+
+        Ok(HRandField {
+            key,
+            count,
+            with_values,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State, protocol: Protocol) -> Frame {
+        let hash = match get_hash(state, &self.key) {
+            Ok(Some(hash)) => hash,
+            Ok(None) if self.count.is_some() => return Frame::Array(vec![]),
+            Ok(None) => return Frame::Null,
+            Err(err) => return err.into(),
+        };
+
+        let mut rng = rand::rng();
+        let Some(count) = self.count else {
+            return hash
+                .keys()
+                .choose(&mut rng)
+                .map_or(Frame::Null, |field| Frame::Bulk(field.clone()));
+        };
+
+        let picked: Vec<(&Bytes, &Bytes)> = if count >= 0 {
+            let count = usize::try_from(count).unwrap_or(usize::MAX);
+            hash.iter().sample(&mut rng, count)
+        } else {
+            // Fields may be repeated, so pick each of them independently
+            let all: Vec<(&Bytes, &Bytes)> = hash.iter().collect();
+            (0..count.unsigned_abs())
+                .filter_map(|_| all.choose(&mut rng).copied())
+                .collect()
+        };
+
+        if self.with_values {
+            entries(picked, protocol)
+        } else {
+            Frame::Array(
+                picked
+                    .into_iter()
+                    .map(|(field, _)| Frame::Bulk(field.clone()))
+                    .collect(),
+            )
+        }
+    }
+}
+
+impl HScan {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<HScan, ParserError> {
+        let key = parser.next_bytes()?;
+        let cursor = parser
+            .next_string()?
+            .parse()
+            .map_err(|_| ReplyError::Other("invalid cursor".into()))?;
+
+        let mut pattern = None;
+        let mut no_values = false;
+        while parser.remaining() > 0 {
+            match &parser.next_string()?.to_uppercase()[..] {
+                "MATCH" => pattern = Some(parser.next_bytes()?),
+                "COUNT" => {
+                    if parser.next_int()? < 1 {
+                        return Err(ReplyError::Syntax.into());
+                    }
+                }
+                "NOVALUES" => no_values = true,
+                _ => return Err(ReplyError::Syntax.into()),
+            }
+        }
+
+        Ok(HScan {
+            key,
+            cursor,
+            pattern,
+            no_values,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let hash = match get_hash(state, &self.key) {
+            Ok(hash) => hash,
+            Err(err) => return err.into(),
+        };
+
+        // Everything was returned by the call made with cursor 0
+        let hash = hash.filter(|_| self.cursor == 0);
+
+        let mut found = vec![];
+        for (field, value) in hash.iter().flat_map(|hash| hash.iter()) {
+            if let Some(pattern) = &self.pattern {
+                if !glob::matches(pattern, field) {
+                    continue;
+                }
+            }
+
+            found.push(Frame::Bulk(field.clone()));
+            if !self.no_values {
+                found.push(Frame::Bulk(value.clone()));
+            }
+        }
+
+        Frame::Array(vec![Frame::Bulk(Bytes::from("0")), Frame::Array(found)])
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/hash_test.rs"]
+mod hash_test;
@@ -0,0 +1,184 @@
+use crate::error::ReplyError;
+use crate::server::cmd::string::{get_string, set_keeping_ttl, update_string};
+use crate::server::db::State;
+use crate::server::frame::Frame;
+use crate::server::hyperloglog::{self, HyperLogLog};
+use crate::server::parser::{Parser, ParserError};
+
+use bytes::Bytes;
+
+/// Adds elements to the HyperLogLog at a key, creating it if needed.
+///
+/// Returns 1 if the estimated cardinality may have changed, 0 otherwise.
+#[derive(Debug)]
+pub(crate) struct PfAdd {
+    key: Bytes,
+    elements: Vec<Bytes>,
+}
+
+/// Returns the estimated cardinality of the HyperLogLog at a key, or of the
+/// union of the HyperLogLogs at several keys. Missing keys count as empty.
+#[derive(Debug)]
+pub(crate) struct PfCount {
+    keys: Vec<Bytes>,
+}
+
+/// Stores at a key the union of the HyperLogLogs at the source keys and the
+/// destination key itself.
+#[derive(Debug)]
+pub(crate) struct PfMerge {
+    destination: Bytes,
+    keys: Vec<Bytes>,
+}
+
+impl PfAdd {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<PfAdd, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let mut elements = vec![];
+        while parser.remaining() > 0 {
+            elements.push(parser.next_bytes()?);
+        }
+
+        Ok(PfAdd { key, elements })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let created = match get_string(state, &self.key) {
+            Ok(Some(value)) if !hyperloglog::is_valid(value) => {
+                return ReplyError::InvalidHyperLogLog.into()
+            }
+            Ok(value) => value.is_none(),
+            Err(err) => return err.into(),
+        };
+
+        // The registers are changed in place, creating the key counts as a
+        // change even without elements
+        let changed = update_string(state, self.key.clone(), |value| {
+            if created {
+                value.extend_from_slice(&HyperLogLog::new().encode());
+            }
+            self.elements.iter().fold(created, |changed, element| {
+                hyperloglog::add_encoded(value, element) || changed
+            })
+        });
+
+        match changed {
+            Ok(changed) => {
+                if changed {
+                    state.touch(&self.key);
+                }
+                Frame::Integer(changed as i64)
+            }
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl PfCount {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<PfCount, ParserError> {
+        let mut keys = vec![parser.next_bytes()?];
+        while parser.remaining() > 0 {
+            keys.push(parser.next_bytes()?);
+        }
+
+        Ok(PfCount { keys })
+    }
+
+    pub(crate) fn execute(mut self, state: &mut State) -> Frame {
+        if self.keys.len() == 1 {
+            let key = self.keys.remove(0);
+            return match count_one(state, key) {
+                Ok(count) => Frame::Integer(count as i64),
+                Err(err) => err.into(),
+            };
+        }
+
+        match union(state, &self.keys, HyperLogLog::new()) {
+            Ok(mut hll) => Frame::Integer(hll.count() as i64),
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl PfMerge {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<PfMerge, ParserError> {
+        let destination = parser.next_bytes()?;
+
+        let mut keys = vec![];
+        while parser.remaining() > 0 {
+            keys.push(parser.next_bytes()?);
+        }
+
+        Ok(PfMerge { destination, keys })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let destination = match get_hyperloglog(state, &self.destination) {
+            Ok(hll) => hll.unwrap_or_default(),
+            Err(err) => return err.into(),
+        };
+
+        match union(state, &self.keys, destination) {
+            Ok(mut hll) => {
+                hll.make_dense();
+                set_keeping_ttl(state, self.destination, hll.encode());
+                Frame::Simple("OK".into())
+            }
+            Err(err) => err.into(),
+        }
+    }
+}
+
+/// Returns the cardinality of the HyperLogLog at `key`, from its header if
+/// it is cached there. Otherwise it is computed and cached for the next
+/// time.
+fn count_one(state: &mut State, key: Bytes) -> Result<u64, ReplyError> {
+    let value = match get_string(state, &key)? {
+        Some(value) if hyperloglog::is_valid(value) => value,
+        Some(_) => return Err(ReplyError::InvalidHyperLogLog),
+        None => return Ok(0),
+    };
+    if let Some(count) = hyperloglog::cached_count(value) {
+        return Ok(count);
+    }
+
+    let count = HyperLogLog::decode(value)
+        .ok_or(ReplyError::InvalidHyperLogLog)?
+        .count();
+    update_string(state, key.clone(), |value| {
+        hyperloglog::cache_count(value, count)
+    })?;
+    state.touch(&key);
+
+    Ok(count)
+}
+
+/// Merges the HyperLogLogs at `keys` into `hll`.
+fn union(
+    state: &mut State,
+    keys: &[Bytes],
+    mut hll: HyperLogLog,
+) -> Result<HyperLogLog, ReplyError> {
+    for key in keys {
+        if let Some(other) = get_hyperloglog(state, key)? {
+            hll.merge(&other);
+        }
+    }
+    Ok(hll)
+}
+
+/// Returns the HyperLogLog stored at `key`, decoded.
+fn get_hyperloglog(state: &mut State, key: &[u8]) -> Result<Option<HyperLogLog>, ReplyError> {
+    match get_string(state, key)? {
+        Some(value) => HyperLogLog::decode(value)
+            .map(Some)
+            .ok_or(ReplyError::InvalidHyperLogLog),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/hyperloglog_test.rs"]
+mod hyperloglog_test;
@@ -0,0 +1,290 @@
+use crate::error::ReplyError;
+use crate::server::db::{now_ms, State};
+use crate::server::frame::Frame;
+use crate::server::parser::{Parser, ParserError};
+
+use bytes::Bytes;
+
+/// Removes the given keys, ignoring the ones that do not exist.
+///
+/// Returns the number of keys that were removed.
+#[derive(Debug)]
+pub(crate) struct Del {
+    keys: Vec<Bytes>,
+}
+
+/// Returns how many of the given keys exist. A key mentioned several times is
+/// counted several times.
+#[derive(Debug)]
+pub(crate) struct Exists {
+    keys: Vec<Bytes>,
+}
+
+/// Sets a time to live on a key. Registered as `EXPIRE`, `PEXPIRE`,
+/// `EXPIREAT` and `PEXPIREAT`, which only differ in how the time is given.
+///
+/// Returns 1 if the time to live was set, 0 if the key does not exist or the
+/// `NX`, `XX`, `GT` or `LT` condition was not met.
+#[derive(Debug)]
+pub(crate) struct Expire {
+    /// Name of the command, for error messages
+    name: &'static str,
+    key: Bytes,
+    time: i64,
+    unit: TimeUnit,
+
+    /// `time` is a unix timestamp rather than a delay
+    absolute: bool,
+    condition: Option<ExpireCondition>,
+}
+
+/// Returns the remaining time to live of a key, in seconds for `TTL` and in
+/// milliseconds for `PTTL`.
+///
+/// Returns -2 if the key does not exist and -1 if it has no time to live.
+#[derive(Debug)]
+pub(crate) struct Ttl {
+    key: Bytes,
+    unit: TimeUnit,
+}
+
+/// Removes the time to live of a key.
+///
+/// Returns 1 if a time to live was removed, 0 otherwise.
+#[derive(Debug)]
+pub(crate) struct Persist {
+    key: Bytes,
+}
+
+/// Removes every key. Registered as `FLUSHDB` and `FLUSHALL`, which are
+/// the same with a single database.
+///
+/// `ASYNC` and `SYNC` are accepted, the keys always being removed right
+/// away.
+#[derive(Debug)]
+pub(crate) struct Flush;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TimeUnit {
+    Seconds,
+    Milliseconds,
+}
+
+/// Conditions of the `EXPIRE` family.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExpireCondition {
+    /// Only when the key has no time to live
+    Nx,
+    /// Only when the key already has a time to live
+    Xx,
+    /// Only when the new deadline is later than the current one
+    Gt,
+    /// Only when the new deadline is earlier than the current one
+    Lt,
+}
+
+/// Parses one or more keys until the end of the frame.
+fn parse_keys(parser: &mut Parser) -> Result<Vec<Bytes>, ParserError> {
+    let mut keys = vec![parser.next_bytes()?];
+    while parser.remaining() > 0 {
+        keys.push(parser.next_bytes()?);
+    }
+
+    Ok(keys)
+}
+
+impl Del {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<Del, ParserError> {
+        Ok(Del {
+            keys: parse_keys(parser)?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let removed = self
+            .keys
+            .iter()
+            .filter(|key| state.remove(key).is_some())
+            .count();
+
+        Frame::Integer(removed as i64)
+    }
+}
+
+impl Exists {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<Exists, ParserError> {
+        Ok(Exists {
+            keys: parse_keys(parser)?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let found = self.keys.iter().filter(|key| state.contains(key)).count();
+
+        Frame::Integer(found as i64)
+    }
+}
+
+impl Expire {
+    pub(crate) fn parse_frames(
+        parser: &mut Parser,
+        name: &'static str,
+        unit: TimeUnit,
+        absolute: bool,
+    ) -> Result<Expire, ParserError> {
+        let key = parser.next_bytes()?;
+        let time = parser.next_int().map_err(|err| match err {
+            ParserError::NoMoreFrame => err,
+            _ => ReplyError::NotInteger.into(),
+        })?;
+
+        let mut condition = None;
+        while parser.remaining() > 0 {
+            let option = match &parser.next_string()?.to_uppercase()[..] {
+                "NX" => ExpireCondition::Nx,
+                "XX" => ExpireCondition::Xx,
+                "GT" => ExpireCondition::Gt,
+                "LT" => ExpireCondition::Lt,
+                option => {
+                    let msg = format!("Unsupported option {}", option);
+                    return Err(ReplyError::Other(msg).into());
+                }
+            };
+
+            condition = match (condition, option) {
+                (None, option) => Some(option),
+                (Some(current), option) if current == option => Some(option),
+                (Some(ExpireCondition::Gt), ExpireCondition::Lt)
+                | (Some(ExpireCondition::Lt), ExpireCondition::Gt) => {
+                    let msg = "GT and LT options at the same time are not compatible";
+                    return Err(ReplyError::Other(msg.to_string()).into());
+                }
+                _ => {
+                    let msg = "NX and XX, GT or LT options at the same time are not compatible";
+                    return Err(ReplyError::Other(msg.to_string()).into());
+                }
+            };
+        }
+
+        Ok(Expire {
+            name,
+            key,
+            time,
+            unit,
+            absolute,
+            condition,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let Some(deadline) = self.deadline() else {
+            let msg = format!("invalid expire time in '{}' command", self.name);
+            return ReplyError::OutOfRange(msg).into();
+        };
+
+        let Some(current) = state.expires_at(&self.key) else {
+            return Frame::Integer(0);
+        };
+
+        let allowed = match (self.condition, current) {
+            (None, _) => true,
+            (Some(ExpireCondition::Nx), current) => current.is_none(),
+            (Some(ExpireCondition::Xx), current) => current.is_some(),
+            // A key without a time to live is considered to live forever
+            (Some(ExpireCondition::Gt), None) => false,
+            (Some(ExpireCondition::Gt), Some(current)) => deadline > current,
+            (Some(ExpireCondition::Lt), None) => true,
+            (Some(ExpireCondition::Lt), Some(current)) => deadline < current,
+        };
+
+        if !allowed {
+            return Frame::Integer(0);
+        }
+
+        state.set_expires_at(&self.key, Some(deadline));
+        Frame::Integer(1)
+    }
+
+    /// Converts the given time into a unix time in milliseconds, or `None`
+    /// if it overflows. Negative times are clamped to 0, which is in the past
+    /// and deletes the key.
+    fn deadline(&self) -> Option<u64> {
+        let millis = match self.unit {
+            TimeUnit::Seconds => self.time.checked_mul(1000)?,
+            TimeUnit::Milliseconds => self.time,
+        };
+
+        let deadline = if self.absolute {
+            millis
+        } else {
+            millis.checked_add(now_ms() as i64)?
+        };
+
+        Some(deadline.max(0) as u64)
+    }
+}
+
+impl Ttl {
+    pub(crate) fn parse_frames(parser: &mut Parser, unit: TimeUnit) -> Result<Ttl, ParserError> {
+        Ok(Ttl {
+            key: parser.next_bytes()?,
+            unit,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match state.expires_at(&self.key) {
+            None => Frame::Integer(-2),
+            Some(None) => Frame::Integer(-1),
+            Some(Some(when)) => {
+                let remaining = when.saturating_sub(now_ms()) as i64;
+                match self.unit {
+                    // Round to the closest second, like Redis does
+                    TimeUnit::Seconds => Frame::Integer((remaining + 500) / 1000),
+                    TimeUnit::Milliseconds => Frame::Integer(remaining),
+                }
+            }
+        }
+    }
+}
+
+impl Persist {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<Persist, ParserError> {
+        Ok(Persist {
+            key: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match state.expires_at(&self.key) {
+            Some(Some(_)) => {
+                state.set_expires_at(&self.key, None);
+                Frame::Integer(1)
+            }
+            _ => Frame::Integer(0),
+        }
+    }
+}
+
+impl Flush {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<Flush, ParserError> {
+        if parser.remaining() > 0 {
+            match &parser.next_string()?.to_uppercase()[..] {
+                "ASYNC" | "SYNC" => {}
+                _ => return Err(ReplyError::Syntax.into()),
+            }
+        }
+
+        Ok(Flush)
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        state.clear();
+        Frame::Simple("OK".to_string())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/keys_test.rs"]
+mod keys_test;
@@ -0,0 +1,681 @@
+use crate::error::ReplyError;
+use crate::server::db::{BlockingCommand, State, Value};
+use crate::server::frame::Frame;
+use crate::server::parser::{Parser, ParserError};
+
+use bytes::Bytes;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Inserts values at the head or the tail of a list, creating the list if
+/// the key does not exist. Registered as `LPUSH` and `RPUSH`.
+///
+/// Values are inserted one after the other, so `LPUSH key a b c` leaves `c`
+/// at the head. Returns the length of the list after the push.
+#[derive(Debug)]
+pub(crate) struct Push {
+    key: Bytes,
+    values: Vec<Bytes>,
+    end: End,
+}
+
+/// Removes and returns elements from the head or the tail of a list.
+/// Registered as `LPOP` and `RPOP`.
+///
+/// Without a count a single element is returned as a bulk, or `Frame::Null`
+/// if the key does not exist. With a count, up to that many elements are
+/// returned as an array, or a null array if the key does not exist.
+#[derive(Debug)]
+pub(crate) struct Pop {
+    key: Bytes,
+    end: End,
+    count: Option<usize>,
+}
+
+/// Pops an element from the first non empty list among the given keys,
+/// blocking until another client pushes to one of them if they are all
+/// empty. Registered as `BLPOP` and `BRPOP`.
+///
+/// Returns the key and the element as an array, or `Frame::Null` if the
+/// timeout elapsed first.
+#[derive(Debug)]
+pub(crate) struct BPop {
+    keys: Vec<Bytes>,
+    end: End,
+
+    /// How long to wait, forever if `None`
+    timeout: Option<Duration>,
+}
+
+/// Atomically pops an element from a list and pushes it to another one.
+/// Registered as `LMOVE` and `BLMOVE`, which blocks until the source list is
+/// pushed to if it is empty.
+///
+/// Returns the moved element, or `Frame::Null` if the source list is empty
+/// or the timeout elapsed.
+#[derive(Debug)]
+pub(crate) struct Move {
+    /// Holds the source, then the destination
+    keys: [Bytes; 2],
+    from: End,
+    to: End,
+
+    /// Wait for the source to be pushed to, as `BLMOVE` does
+    blocking: bool,
+
+    /// How long to wait, forever if `None`
+    timeout: Option<Duration>,
+}
+
+/// Returns the elements of a list between two inclusive indexes. Negative
+/// indexes count from the tail, -1 being the last element.
+#[derive(Debug)]
+pub(crate) struct LRange {
+    key: Bytes,
+    start: i64,
+    stop: i64,
+}
+
+/// Returns the length of a list, 0 if the key does not exist.
+#[derive(Debug)]
+pub(crate) struct LLen {
+    key: Bytes,
+}
+
+/// Returns the element at an index of a list, `Frame::Null` if the index is
+/// out of range or the key does not exist.
+#[derive(Debug)]
+pub(crate) struct LIndex {
+    key: Bytes,
+    index: i64,
+}
+
+/// Replaces the element at an index of a list.
+#[derive(Debug)]
+pub(crate) struct LSet {
+    key: Bytes,
+    index: i64,
+    value: Bytes,
+}
+
+/// Keeps only the elements of a list between two inclusive indexes, which
+/// follow the same rules as `LRANGE`.
+#[derive(Debug)]
+pub(crate) struct LTrim {
+    key: Bytes,
+    start: i64,
+    stop: i64,
+}
+
+/// Removes the elements equal to a value. A positive count removes that many
+/// elements starting from the head, a negative one from the tail, and 0
+/// removes them all.
+///
+/// Returns the number of removed elements.
+#[derive(Debug)]
+pub(crate) struct LRem {
+    key: Bytes,
+    count: i64,
+    value: Bytes,
+}
+
+/// Inserts a value before or after the first element equal to a pivot.
+///
+/// Returns the length of the list after the insertion, -1 if the pivot was
+/// not found and 0 if the key does not exist.
+#[derive(Debug)]
+pub(crate) struct LInsert {
+    key: Bytes,
+    before: bool,
+    pivot: Bytes,
+    value: Bytes,
+}
+
+/// End of a list an element is pushed to or popped from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum End {
+    /// The head of the list
+    Left,
+    /// The tail of the list
+    Right,
+}
+
+/// Returns the list stored at `key`, `None` if the key does not exist.
+fn get_list<'a>(
+    state: &'a mut State,
+    key: &[u8],
+) -> Result<Option<&'a VecDeque<Bytes>>, ReplyError> {
+    match state.get(key) {
+        None => Ok(None),
+        Some(Value::List(list)) => Ok(Some(list)),
+        Some(_) => Err(ReplyError::WrongType),
+    }
+}
+
+/// Like `get_list`, for commands modifying the list.
+fn get_list_mut<'a>(
+    state: &'a mut State,
+    key: &[u8],
+) -> Result<Option<&'a mut VecDeque<Bytes>>, ReplyError> {
+    match state.get_mut(key) {
+        None => Ok(None),
+        Some(Value::List(list)) => Ok(Some(list)),
+        Some(_) => Err(ReplyError::WrongType),
+    }
+}
+
+/// Converts the inclusive `start` and `stop` indexes, which count from the
+/// tail when negative, into the bounds of a range of a sequence of `len`
+/// elements. Returns `None` if the range is empty.
+pub(crate) fn normalize_range(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 { start + len } else { start }.max(0);
+    let stop = if stop < 0 { stop + len } else { stop }.min(len - 1);
+
+    if start > stop || start >= len {
+        return None;
+    }
+
+    Some((start as usize, stop as usize))
+}
+
+/// Converts an index, which counts from the tail when negative, into a
+/// position in a sequence of `len` elements.
+fn normalize_index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { index + len as i64 } else { index };
+
+    if index < 0 || index >= len as i64 {
+        return None;
+    }
+
+    Some(index as usize)
+}
+
+/// Pushes `values` one after the other to the list at `key`, creating it
+/// if needed. Returns the length of the list.
+fn push(
+    state: &mut State,
+    key: Bytes,
+    end: End,
+    values: impl IntoIterator<Item = Bytes>,
+) -> Result<usize, ReplyError> {
+    let value = state.get_or_insert_with(key.clone(), || Value::List(VecDeque::new()));
+    let Value::List(list) = value else {
+        return Err(ReplyError::WrongType);
+    };
+
+    let before = list.len();
+    for value in values {
+        end.push(list, value);
+    }
+    let len = list.len();
+    if len > before {
+        state.touch(&key);
+    }
+
+    // Serve the clients blocked on the list
+    state.signal_ready(&key);
+
+    Ok(len)
+}
+
+/// Pops an element from the list at `key`, deleting the list if it becomes
+/// empty.
+fn pop(state: &mut State, key: &[u8], end: End) -> Result<Option<Bytes>, ReplyError> {
+    let Some(list) = get_list_mut(state, key)? else {
+        return Ok(None);
+    };
+
+    let value = end.pop(list);
+    if list.is_empty() {
+        state.remove(key);
+    } else if value.is_some() {
+        state.touch(key);
+    }
+
+    Ok(value)
+}
+
+/// Parses the timeout of a blocking command, given in seconds as a float.
+/// A timeout of 0 blocks forever.
+fn parse_timeout(parser: &mut Parser) -> Result<Option<Duration>, ParserError> {
+    let timeout = parser.next_string()?;
+    let timeout: f64 = timeout
+        .parse()
+        .ok()
+        .filter(|timeout: &f64| timeout.is_finite())
+        .ok_or_else(|| ReplyError::OutOfRange("timeout is not a float or out of range".into()))?;
+
+    if timeout < 0.0 {
+        return Err(ReplyError::OutOfRange("timeout is negative".into()).into());
+    }
+
+    if timeout == 0.0 {
+        return Ok(None);
+    }
+
+    Duration::try_from_secs_f64(timeout)
+        .map(Some)
+        .map_err(|_| ReplyError::OutOfRange("timeout is out of range".into()).into())
+}
+
+fn bulks<'a>(values: impl IntoIterator<Item = &'a Bytes>) -> Frame {
+    Frame::Array(values.into_iter().cloned().map(Frame::Bulk).collect())
+}
+
+impl End {
+    /// Parses `LEFT` or `RIGHT`.
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<End, ParserError> {
+        match &parser.next_string()?.to_uppercase()[..] {
+            "LEFT" => Ok(End::Left),
+            "RIGHT" => Ok(End::Right),
+            _ => Err(ReplyError::Syntax.into()),
+        }
+    }
+
+    fn push(self, list: &mut VecDeque<Bytes>, value: Bytes) {
+        match self {
+            End::Left => list.push_front(value),
+            End::Right => list.push_back(value),
+        }
+    }
+
+    fn pop(self, list: &mut VecDeque<Bytes>) -> Option<Bytes> {
+        match self {
+            End::Left => list.pop_front(),
+            End::Right => list.pop_back(),
+        }
+    }
+}
+
+impl Push {
+    pub(crate) fn parse_frames(parser: &mut Parser, end: End) -> Result<Push, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let mut values = vec![parser.next_bytes()?];
+        while parser.remaining() > 0 {
+            values.push(parser.next_bytes()?);
+        }
+
+        Ok(Push { key, values, end })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match push(state, self.key, self.end, self.values) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl Pop {
+    pub(crate) fn parse_frames(parser: &mut Parser, end: End) -> Result<Pop, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let count = if parser.remaining() > 0 {
+            let count = parser.next_int().map_err(|_| ReplyError::NotInteger)?;
+            let count = usize::try_from(count).map_err(|_| {
+                ReplyError::OutOfRange("value is out of range, must be positive".to_string())
+            })?;
+            Some(count)
+        } else {
+            None
+        };
+
+        Ok(Pop { key, end, count })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let list = match get_list_mut(state, &self.key) {
+            Ok(Some(list)) => list,
+            Ok(None) if self.count.is_some() => return Frame::NullArray,
+            Ok(None) => return Frame::Null,
+            Err(err) => return err.into(),
+        };
+
+        let len = list.len();
+        let response = match self.count {
+            None => self.end.pop(list).map_or(Frame::Null, Frame::Bulk),
+            Some(count) => {
+                let popped: Vec<Frame> = (0..count)
+                    .map_while(|_| self.end.pop(list))
+                    .map(Frame::Bulk)
+                    .collect();
+                Frame::Array(popped)
+            }
+        };
+
+        if list.is_empty() {
+            state.remove(&self.key);
+        } else if list.len() < len {
+            state.touch(&self.key);
+        }
+
+        response
+    }
+}
+
+impl BPop {
+    pub(crate) fn parse_frames(parser: &mut Parser, end: End) -> Result<BPop, ParserError> {
+        let mut keys = vec![parser.next_bytes()?];
+
+        // The timeout comes after the keys
+        while parser.remaining() > 1 {
+            keys.push(parser.next_bytes()?);
+        }
+        let timeout = parse_timeout(parser)?;
+
+        Ok(BPop { keys, end, timeout })
+    }
+
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Executes the command without blocking, as done within a transaction.
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        self.try_execute(state).unwrap_or(Frame::Null)
+    }
+}
+
+impl BlockingCommand for BPop {
+    fn keys(&self) -> &[Bytes] {
+        &self.keys
+    }
+
+    fn try_execute(&self, state: &mut State) -> Option<Frame> {
+        for key in &self.keys {
+            match pop(state, key, self.end) {
+                Ok(Some(value)) => {
+                    return Some(Frame::Array(vec![
+                        Frame::Bulk(key.clone()),
+                        Frame::Bulk(value),
+                    ]))
+                }
+                Ok(None) => {}
+                Err(err) => return Some(err.into()),
+            }
+        }
+
+        None
+    }
+}
+
+impl Move {
+    pub(crate) fn parse_frames(parser: &mut Parser, blocking: bool) -> Result<Move, ParserError> {
+        let source = parser.next_bytes()?;
+        let destination = parser.next_bytes()?;
+        let from = End::parse_frames(parser)?;
+        let to = End::parse_frames(parser)?;
+        let timeout = if blocking {
+            parse_timeout(parser)?
+        } else {
+            None
+        };
+
+        Ok(Move {
+            keys: [source, destination],
+            from,
+            to,
+            blocking,
+            timeout,
+        })
+    }
+
+    /// Returns `true` for `BLMOVE`.
+    pub(crate) fn is_blocking(&self) -> bool {
+        self.blocking
+    }
+
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        self.try_execute(state).unwrap_or(Frame::Null)
+    }
+}
+
+impl BlockingCommand for Move {
+    /// Only the source is waited on
+    fn keys(&self) -> &[Bytes] {
+        &self.keys[..1]
+    }
+
+    fn try_execute(&self, state: &mut State) -> Option<Frame> {
+        let [source, destination] = &self.keys;
+
+        // Check both types first so the element is not lost on error
+        match get_list(state, source) {
+            Ok(Some(_)) => {}
+            Ok(None) => return None,
+            Err(err) => return Some(err.into()),
+        }
+        if let Err(err) = get_list(state, destination) {
+            return Some(err.into());
+        }
+
+        let value = pop(state, source, self.from).ok()??;
+        if let Err(err) = push(state, destination.clone(), self.to, [value.clone()]) {
+            return Some(err.into());
+        }
+
+        Some(Frame::Bulk(value))
+    }
+}
+
+impl LRange {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<LRange, ParserError> {
+        Ok(LRange {
+            key: parser.next_bytes()?,
+            start: parser.next_int()?,
+            stop: parser.next_int()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let list = match get_list(state, &self.key) {
+            Ok(Some(list)) => list,
+            Ok(None) => return Frame::Array(vec![]),
+            Err(err) => return err.into(),
+        };
+
+        match normalize_range(self.start, self.stop, list.len()) {
+            Some((start, stop)) => bulks(list.range(start..=stop)),
+            None => Frame::Array(vec![]),
+        }
+    }
+}
+
+impl LLen {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<LLen, ParserError> {
+        Ok(LLen {
+            key: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match get_list(state, &self.key) {
+            Ok(list) => Frame::Integer(list.map_or(0, |list| list.len() as i64)),
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl LIndex {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<LIndex, ParserError> {
+        Ok(LIndex {
+            key: parser.next_bytes()?,
+            index: parser.next_int()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let list = match get_list(state, &self.key) {
+            Ok(Some(list)) => list,
+            Ok(None) => return Frame::Null,
+            Err(err) => return err.into(),
+        };
+
+        normalize_index(self.index, list.len())
+            .map_or(Frame::Null, |index| Frame::Bulk(list[index].clone()))
+    }
+}
+
+impl LSet {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<LSet, ParserError> {
+        Ok(LSet {
+            key: parser.next_bytes()?,
+            index: parser.next_int()?,
+            value: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let list = match get_list_mut(state, &self.key) {
+            Ok(Some(list)) => list,
+            Ok(None) => return ReplyError::Other("no such key".to_string()).into(),
+            Err(err) => return err.into(),
+        };
+
+        match normalize_index(self.index, list.len()) {
+            Some(index) => {
+                list[index] = self.value;
+                state.touch(&self.key);
+                Frame::Simple("OK".to_string())
+            }
+            None => ReplyError::OutOfRange("index out of range".to_string()).into(),
+        }
+    }
+}
+
+impl LTrim {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<LTrim, ParserError> {
+        Ok(LTrim {
+            key: parser.next_bytes()?,
+            start: parser.next_int()?,
+            stop: parser.next_int()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let list = match get_list_mut(state, &self.key) {
+            Ok(Some(list)) => list,
+            Ok(None) => return Frame::Simple("OK".to_string()),
+            Err(err) => return err.into(),
+        };
+
+        let len = list.len();
+        match normalize_range(self.start, self.stop, len) {
+            Some((start, stop)) => {
+                list.truncate(stop + 1);
+                list.drain(..start);
+            }
+            None => list.clear(),
+        }
+
+        if list.is_empty() {
+            state.remove(&self.key);
+        } else if list.len() < len {
+            state.touch(&self.key);
+        }
+
+        Frame::Simple("OK".to_string())
+    }
+}
+
+impl LRem {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<LRem, ParserError> {
+        Ok(LRem {
+            key: parser.next_bytes()?,
+            count: parser.next_int()?,
+            value: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let list = match get_list_mut(state, &self.key) {
+            Ok(Some(list)) => list,
+            Ok(None) => return Frame::Integer(0),
+            Err(err) => return err.into(),
+        };
+
+        let limit = match self.count {
+            0 => usize::MAX,
+            count => count.unsigned_abs().try_into().unwrap_or(usize::MAX),
+        };
+
+        // Counting from the tail, the matches before the last `limit` ones
+        // are kept
+        let mut skipped = match self.count {
+            count if count < 0 => {
+                let matches = list.iter().filter(|value| **value == self.value).count();
+                matches.saturating_sub(limit)
+            }
+            _ => 0,
+        };
+
+        let mut removed = 0;
+        list.retain(|value| {
+            if *value != self.value || removed == limit {
+                return true;
+            }
+            if skipped > 0 {
+                skipped -= 1;
+                return true;
+            }
+            removed += 1;
+            false
+        });
+
+        if list.is_empty() {
+            state.remove(&self.key);
+        } else if removed > 0 {
+            state.touch(&self.key);
+        }
+
+        Frame::Integer(removed as i64)
+    }
+}
+
+impl LInsert {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<LInsert, ParserError> {
+        let key = parser.next_bytes()?;
+        let before = match &parser.next_string()?.to_uppercase()[..] {
+            "BEFORE" => true,
+            "AFTER" => false,
+            _ => return Err(ReplyError::Syntax.into()),
+        };
+
+        Ok(LInsert {
+            key,
+            before,
+            pivot: parser.next_bytes()?,
+            value: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let list = match get_list_mut(state, &self.key) {
+            Ok(Some(list)) => list,
+            Ok(None) => return Frame::Integer(0),
+            Err(err) => return err.into(),
+        };
+
+        let Some(index) = list.iter().position(|value| *value == self.pivot) else {
+            return Frame::Integer(-1);
+        };
+
+        let index = if self.before { index } else { index + 1 };
+        list.insert(index, self.value);
+        let len = list.len();
+        state.touch(&self.key);
+
+        Frame::Integer(len as i64)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/list_test.rs"]
+mod list_test;
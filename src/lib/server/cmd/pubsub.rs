@@ -0,0 +1,288 @@
+use crate::error::ReplyError;
+use crate::server::connection::Connection;
+use crate::server::db::Db;
+use crate::server::frame::Frame;
+use crate::server::parser::{Parser, ParserError};
+use crate::server::pubsub::{Broker, Kind};
+
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// Subscribes the client to channels, to patterns for `PSUBSCRIBE` or to
+/// shard channels for `SSUBSCRIBE`.
+///
+/// Each subscription is confirmed with a `subscribe` push holding the
+/// number of subscriptions of the client, shard channels being counted
+/// apart. Once subscribed, a RESP2 client
+/// can only (un)subscribe, `PING`, `QUIT` or `RESET`.
+#[derive(Debug)]
+pub(crate) struct Subscribe {
+    kind: Kind,
+    names: Vec<Bytes>,
+}
+
+/// Unsubscribes the client from channels, from patterns for `PUNSUBSCRIBE`
+/// or from shard channels for `SUNSUBSCRIBE`, from all of them if none is
+/// given.
+#[derive(Debug)]
+pub(crate) struct Unsubscribe {
+    kind: Kind,
+    names: Vec<Bytes>,
+}
+
+/// Sends a message to the subscribers of a channel and of the patterns
+/// matching it. Returns the number of clients that received it.
+#[derive(Debug)]
+pub(crate) struct Publish {
+    channel: Bytes,
+    message: Bytes,
+}
+
+/// Sends a message to the subscribers of a shard channel, provided its hash
+/// slot is served by this node. Returns the number of clients that received
+/// it.
+#[derive(Debug)]
+pub(crate) struct SPublish {
+    channel: Bytes,
+    message: Bytes,
+}
+
+/// Reports on the subscriptions of all the clients.
+#[derive(Debug)]
+pub(crate) enum PubSub {
+    /// Channels with at least one subscriber, optionally matching a pattern
+    Channels(Option<Bytes>),
+    /// Number of subscribers of each channel, patterns excluded
+    NumSub(Vec<Bytes>),
+    /// Number of patterns subscribed to
+    NumPat,
+    /// Shard channels with at least one subscriber, optionally matching a
+    /// pattern
+    ShardChannels(Option<Bytes>),
+    /// Number of subscribers of each shard channel
+    ShardNumSub(Vec<Bytes>),
+}
+
+/// Name of the push confirming a subscription, or an unsubscription.
+fn confirmation(kind: Kind, subscribe: bool) -> Frame {
+    let name: &'static [u8] = match (kind, subscribe) {
+        (Kind::Channel, true) => b"subscribe",
+        (Kind::Channel, false) => b"unsubscribe",
+        (Kind::Pattern, true) => b"psubscribe",
+        (Kind::Pattern, false) => b"punsubscribe",
+        (Kind::Shard, true) => b"ssubscribe",
+        (Kind::Shard, false) => b"sunsubscribe",
+    };
+    Frame::Bulk(Bytes::from_static(name))
+}
+
+impl Subscribe {
+    pub(crate) fn parse_frames(parser: &mut Parser, kind: Kind) -> Result<Subscribe, ParserError> {
+        let mut names = vec![parser.next_bytes()?];
+        while parser.remaining() > 0 {
+            names.push(parser.next_bytes()?);
+        }
+
+        Ok(Subscribe { kind, names })
+    }
+
+    /// Subscribes `dst` and returns the confirmations.
+    pub(crate) fn apply<S: AsyncRead + AsyncWrite + Unpin>(
+        self,
+        db: &Db,
+        dst: &mut Connection<S>,
+    ) -> Vec<Frame> {
+        let id = dst.id();
+        db.with_broker(|broker| {
+            self.names
+                .into_iter()
+                .map(|name| {
+                    let count = dst
+                        .subscriptions()
+                        .subscribe(broker, id, self.kind, name.clone());
+                    Frame::Push(vec![
+                        confirmation(self.kind, true),
+                        Frame::Bulk(name),
+                        Frame::Integer(count as i64),
+                    ])
+                })
+                .collect()
+        })
+    }
+}
+
+impl Unsubscribe {
+    pub(crate) fn parse_frames(
+        parser: &mut Parser,
+        kind: Kind,
+    ) -> Result<Unsubscribe, ParserError> {
+        let mut names = vec![];
+        while parser.remaining() > 0 {
+            names.push(parser.next_bytes()?);
+        }
+
+        Ok(Unsubscribe { kind, names })
+    }
+
+    /// Unsubscribes `dst` and returns the confirmations.
+    pub(crate) fn apply<S: AsyncRead + AsyncWrite + Unpin>(
+        self,
+        db: &Db,
+        dst: &mut Connection<S>,
+    ) -> Vec<Frame> {
+        let id = dst.id();
+        let names = match self.names.is_empty() {
+            true => dst.subscriptions().list(self.kind),
+            false => self.names,
+        };
+
+        // There is still a confirmation when there was nothing to
+        // unsubscribe from
+        if names.is_empty() {
+            let count = dst.subscriptions().count(self.kind);
+            return vec![Frame::Push(vec![
+                confirmation(self.kind, false),
+                Frame::Null,
+                Frame::Integer(count as i64),
+            ])];
+        }
+
+        db.with_broker(|broker| {
+            names
+                .into_iter()
+                .map(|name| {
+                    let count = dst
+                        .subscriptions()
+                        .unsubscribe(broker, id, self.kind, &name);
+                    Frame::Push(vec![
+                        confirmation(self.kind, false),
+                        Frame::Bulk(name),
+                        Frame::Integer(count as i64),
+                    ])
+                })
+                .collect()
+        })
+    }
+}
+
+impl Publish {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<Publish, ParserError> {
+        Ok(Publish {
+            channel: parser.next_bytes()?,
+            message: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, broker: &Broker) -> Frame {
+        Frame::Integer(broker.publish(&self.channel, &self.message) as i64)
+    }
+}
+
+impl SPublish {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<SPublish, ParserError> {
+        Ok(SPublish {
+            channel: parser.next_bytes()?,
+            message: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, broker: &Broker) -> Frame {
+        if !broker.serves(&self.channel) {
+            return ReplyError::SlotNotServed.into();
+        }
+
+        Frame::Integer(broker.publish_shard(&self.channel, &self.message) as i64)
+    }
+}
+
+impl PubSub {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<PubSub, ParserError> {
+        let subcommand = parser.next_string()?;
+
+        let pubsub = match &subcommand.to_uppercase()[..] {
+            "CHANNELS" => PubSub::Channels(pattern(parser, &subcommand)?),
+            "SHARDCHANNELS" => PubSub::ShardChannels(pattern(parser, &subcommand)?),
+            "NUMSUB" => PubSub::NumSub(channels(parser)?),
+            "SHARDNUMSUB" => PubSub::ShardNumSub(channels(parser)?),
+            "NUMPAT" if parser.remaining() == 0 => PubSub::NumPat,
+            "NUMPAT" => return Err(wrong_arity(&subcommand)),
+            _ => {
+                return Err(ReplyError::Other(format!(
+                    "unknown subcommand '{}'. Try PUBSUB HELP.",
+                    subcommand
+                ))
+                .into())
+            }
+        };
+
+        Ok(pubsub)
+    }
+
+    pub(crate) fn execute(self, broker: &Broker) -> Frame {
+        match self {
+            PubSub::Channels(pattern) => Frame::Array(
+                broker
+                    .channels(pattern.as_deref())
+                    .into_iter()
+                    .map(Frame::Bulk)
+                    .collect(),
+            ),
+            PubSub::NumSub(channels) => counts(channels, |channel| broker.subscribers(channel)),
+            PubSub::NumPat => Frame::Integer(broker.patterns() as i64),
+            PubSub::ShardChannels(pattern) => Frame::Array(
+                broker
+                    .shard_channels(pattern.as_deref())
+                    .into_iter()
+                    .map(Frame::Bulk)
+                    .collect(),
+            ),
+            PubSub::ShardNumSub(channels) => {
+                counts(channels, |channel| broker.shard_subscribers(channel))
+            }
+        }
+    }
+}
+
+/// Parses the optional pattern of `subcommand`.
+fn pattern(parser: &mut Parser, subcommand: &str) -> Result<Option<Bytes>, ParserError> {
+    match parser.remaining() {
+        0 => Ok(None),
+        1 => Ok(Some(parser.next_bytes()?)),
+        _ => Err(wrong_arity(subcommand)),
+    }
+}
+
+fn wrong_arity(subcommand: &str) -> ParserError {
+    ReplyError::Other(format!(
+        "unknown subcommand or wrong number of arguments for '{}'. Try PUBSUB HELP.",
+        subcommand
+    ))
+    .into()
+}
+
+/// Parses the remaining arguments as channel names.
+fn channels(parser: &mut Parser) -> Result<Vec<Bytes>, ParserError> {
+    let mut channels = vec![];
+    while parser.remaining() > 0 {
+        channels.push(parser.next_bytes()?);
+    }
+    Ok(channels)
+}
+
+/// Maps each of `channels` to its number of subscribers.
+fn counts(channels: Vec<Bytes>, subscribers: impl Fn(&[u8]) -> usize) -> Frame {
+    Frame::Map(
+        channels
+            .into_iter()
+            .map(|channel| {
+                let count = subscribers(&channel);
+                (Frame::Bulk(channel), Frame::Integer(count as i64))
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/pubsub_test.rs"]
+mod pubsub_test;
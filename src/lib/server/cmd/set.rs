@@ -0,0 +1,577 @@
+use crate::error::ReplyError;
+use crate::server::cmd::hash::MAX_REPEATED_PICKS;
+use crate::server::db::{State, Value};
+use crate::server::frame::Frame;
+use crate::server::parser::{Parser, ParserError};
+use crate::server::set::Set;
+
+use bytes::Bytes;
+use rand::seq::IndexedRandom;
+
+/// Adds members to a set, creating the set if the key does not exist.
+///
+/// Returns the number of members that were added, not counting the ones
+/// already in the set.
+#[derive(Debug)]
+pub(crate) struct SAdd {
+    key: Bytes,
+    members: Vec<Bytes>,
+}
+
+/// Removes members from a set, deleting the set once it is empty.
+///
+/// Returns the number of members that were removed.
+#[derive(Debug)]
+pub(crate) struct SRem {
+    key: Bytes,
+    members: Vec<Bytes>,
+}
+
+/// Returns every member of a set.
+#[derive(Debug)]
+pub(crate) struct SMembers {
+    key: Bytes,
+}
+
+/// Returns 1 if a value is a member of a set, 0 otherwise.
+#[derive(Debug)]
+pub(crate) struct SIsMember {
+    key: Bytes,
+    member: Bytes,
+}
+
+/// Returns, for each of the given values, 1 if it is a member of a set and
+/// 0 otherwise.
+#[derive(Debug)]
+pub(crate) struct SMIsMember {
+    key: Bytes,
+    members: Vec<Bytes>,
+}
+
+/// Returns the number of members of a set, 0 if the key does not exist.
+#[derive(Debug)]
+pub(crate) struct SCard {
+    key: Bytes,
+}
+
+/// Moves a member from a set to another one.
+///
+/// Returns 1 if the member was moved, 0 if it is not a member of the source.
+#[derive(Debug)]
+pub(crate) struct SMove {
+    source: Bytes,
+    destination: Bytes,
+    member: Bytes,
+}
+
+/// Computes the intersection, union or difference of sets. Registered as
+/// `SINTER`, `SUNION` and `SDIFF`, and as `SINTERSTORE`, `SUNIONSTORE` and
+/// `SDIFFSTORE` which store the result instead of returning it.
+///
+/// Missing keys are considered empty sets. The store variants replace the
+/// destination whatever its type, delete it if the result is empty, and
+/// return the number of members of the result.
+#[derive(Debug)]
+pub(crate) struct SetOp {
+    operation: SetOperation,
+    keys: Vec<Bytes>,
+
+    /// Where to store the result, for the store variants
+    destination: Option<Bytes>,
+}
+
+/// Returns the number of members of the intersection of sets, counting up
+/// to `LIMIT` members if given.
+#[derive(Debug)]
+pub(crate) struct SInterCard {
+    keys: Vec<Bytes>,
+
+    /// Stop counting after that many members, 0 meaning no limit
+    limit: usize,
+}
+
+/// Removes and returns random members of a set.
+///
+/// Without a count a single member is returned as a bulk, or `Frame::Null`
+/// if the key does not exist. With a count, up to that many members are
+/// returned as a set.
+#[derive(Debug)]
+pub(crate) struct SPop {
+    key: Bytes,
+    count: Option<usize>,
+}
+
+/// Returns random members of a set without removing them.
+///
+/// Without a count a single member is returned as a bulk. A positive count
+/// returns up to that many distinct members, a negative one returns exactly
+/// that many members, possibly repeated, up to `MAX_REPEATED_PICKS`.
+#[derive(Debug)]
+pub(crate) struct SRandMember {
+    key: Bytes,
+    count: Option<i64>,
+}
+
+/// Operations of `SetOp`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SetOperation {
+    /// Members of every set
+    Inter,
+    /// Members of any set
+    Union,
+    /// Members of the first set that are in none of the others
+    Diff,
+}
+
+/// Returns the set stored at `key`, `None` if the key does not exist.
+fn get_set<'a>(state: &'a mut State, key: &[u8]) -> Result<Option<&'a Set>, ReplyError> {
+    match state.get(key) {
+        None => Ok(None),
+        Some(Value::Set(set)) => Ok(Some(set)),
+        Some(_) => Err(ReplyError::WrongType),
+    }
+}
+
+/// Like `get_set`, for commands modifying the set.
+fn get_set_mut<'a>(state: &'a mut State, key: &[u8]) -> Result<Option<&'a mut Set>, ReplyError> {
+    match state.get_mut(key) {
+        None => Ok(None),
+        Some(Value::Set(set)) => Ok(Some(set)),
+        Some(_) => Err(ReplyError::WrongType),
+    }
+}
+
+/// Returns the set stored at `key`, creating an empty one if the key does
+/// not exist.
+fn get_or_create_set(state: &mut State, key: Bytes) -> Result<&mut Set, ReplyError> {
+    match state.get_or_insert_with(key, || Value::Set(Set::new())) {
+        Value::Set(set) => Ok(set),
+        _ => Err(ReplyError::WrongType),
+    }
+}
+
+/// Returns the sets stored at `keys`, `None` for the keys that do not
+/// exist.
+fn get_sets<'a>(state: &'a mut State, keys: &[Bytes]) -> Result<Vec<Option<&'a Set>>, ReplyError> {
+    state
+        .get_all(keys)
+        .into_iter()
+        .map(|value| match value {
+            None => Ok(None),
+            Some(Value::Set(set)) => Ok(Some(set)),
+            Some(_) => Err(ReplyError::WrongType),
+        })
+        .collect()
+}
+
+/// Iterates over the members of the smallest of `sets` that are in all of
+/// them.
+fn intersection<'a, 'b>(sets: &'b [Option<&'a Set>]) -> impl Iterator<Item = &'a Bytes> + 'b {
+    let smallest = sets
+        .iter()
+        .min_by_key(|set| set.map_or(0, Set::len))
+        .copied()
+        .flatten();
+
+    smallest.into_iter().flatten().filter(|member| {
+        sets.iter()
+            .all(|set| set.is_some_and(|set| set.contains(member)))
+    })
+}
+
+/// Parses a list of at least one key or member, up to the end of the
+/// command.
+fn parse_list(parser: &mut Parser) -> Result<Vec<Bytes>, ParserError> {
+    let mut values = vec![parser.next_bytes()?];
+    while parser.remaining() > 0 {
+        values.push(parser.next_bytes()?);
+    }
+
+    Ok(values)
+}
+
+fn members<'a>(members: impl IntoIterator<Item = &'a Bytes>) -> Frame {
+    Frame::Set(members.into_iter().cloned().map(Frame::Bulk).collect())
+}
+
+impl SAdd {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<SAdd, ParserError> {
+        Ok(SAdd {
+            key: parser.next_bytes()?,
+            members: parse_list(parser)?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let set = match get_or_create_set(state, self.key.clone()) {
+            Ok(set) => set,
+            Err(err) => return err.into(),
+        };
+
+        let mut added = 0;
+        for member in self.members {
+            if set.insert(member) {
+                added += 1;
+            }
+        }
+        if added > 0 {
+            state.touch(&self.key);
+        }
+
+        Frame::Integer(added)
+    }
+}
+
+impl SRem {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<SRem, ParserError> {
+        Ok(SRem {
+            key: parser.next_bytes()?,
+            members: parse_list(parser)?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let set = match get_set_mut(state, &self.key) {
+            Ok(Some(set)) => set,
+            Ok(None) => return Frame::Integer(0),
+            Err(err) => return err.into(),
+        };
+
+        let removed = self
+            .members
+            .iter()
+            .filter(|member| set.remove(member))
+            .count();
+
+        if set.is_empty() {
+            state.remove(&self.key);
+        } else if removed > 0 {
+            state.touch(&self.key);
+        }
+
+        Frame::Integer(removed as i64)
+    }
+}
+
+impl SMembers {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<SMembers, ParserError> {
+        Ok(SMembers {
+            key: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match get_set(state, &self.key) {
+            Ok(set) => members(set.iter().flat_map(|set| set.iter())),
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl SIsMember {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<SIsMember, ParserError> {
+        Ok(SIsMember {
+            key: parser.next_bytes()?,
+            member: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match get_set(state, &self.key) {
+            Ok(set) => Frame::Integer(set.is_some_and(|set| set.contains(&self.member)) as i64),
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl SMIsMember {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<SMIsMember, ParserError> {
+        Ok(SMIsMember {
+            key: parser.next_bytes()?,
+            members: parse_list(parser)?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let set = match get_set(state, &self.key) {
+            Ok(set) => set,
+            Err(err) => return err.into(),
+        };
+
+        let found = self
+            .members
+            .iter()
+            .map(|member| {
+                let found = set.as_ref().is_some_and(|set| set.contains(member));
+                Frame::Integer(found as i64)
+            })
+            .collect();
+
+        Frame::Array(found)
+    }
+}
+
+impl SCard {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<SCard, ParserError> {
+        Ok(SCard {
+            key: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match get_set(state, &self.key) {
+            Ok(set) => Frame::Integer(set.map_or(0, |set| set.len() as i64)),
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl SMove {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<SMove, ParserError> {
+        Ok(SMove {
+            source: parser.next_bytes()?,
+            destination: parser.next_bytes()?,
+            member: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        // Check both types first so the member is not lost on error
+        if let Err(err) = get_set(state, &self.destination) {
+            return err.into();
+        }
+        let source = match get_set_mut(state, &self.source) {
+            Ok(Some(source)) => source,
+            Ok(None) => return Frame::Integer(0),
+            Err(err) => return err.into(),
+        };
+
+        // Moving a member within the same set changes nothing
+        if self.source == self.destination {
+            return Frame::Integer(source.contains(&self.member) as i64);
+        }
+
+        if !source.remove(&self.member) {
+            return Frame::Integer(0);
+        }
+        if source.is_empty() {
+            state.remove(&self.source);
+        } else {
+            state.touch(&self.source);
+        }
+
+        match get_or_create_set(state, self.destination.clone()) {
+            Ok(destination) => {
+                if destination.insert(self.member) {
+                    state.touch(&self.destination);
+                }
+                Frame::Integer(1)
+            }
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl SetOp {
+    pub(crate) fn parse_frames(
+        parser: &mut Parser,
+        operation: SetOperation,
+        store: bool,
+    ) -> Result<SetOp, ParserError> {
+        let destination = if store {
+            Some(parser.next_bytes()?)
+        } else {
+            None
+        };
+
+        Ok(SetOp {
+            operation,
+            keys: parse_list(parser)?,
+            destination,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let sets = match get_sets(state, &self.keys) {
+            Ok(sets) => sets,
+            Err(err) => return err.into(),
+        };
+
+        let result: Set = match self.operation {
+            SetOperation::Inter => intersection(&sets).cloned().collect(),
+            SetOperation::Union => sets
+                .iter()
+                .flatten()
+                .flat_map(|set| set.iter())
+                .cloned()
+                .collect(),
+            SetOperation::Diff => {
+                // Commands name at least one key
+                let (first, others) = sets.split_first().unwrap();
+                first
+                    .iter()
+                    .flat_map(|set| set.iter())
+                    .filter(|member| !others.iter().flatten().any(|set| set.contains(member)))
+                    .cloned()
+                    .collect()
+            }
+        };
+
+        let Some(destination) = self.destination else {
+            return members(&result);
+        };
+
+        let len = result.len();
+        if result.is_empty() {
+            state.remove(&destination);
+        } else {
+            state.insert(destination, Value::Set(result), None);
+        }
+
+        Frame::Integer(len as i64)
+    }
+}
+
+impl SInterCard {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<SInterCard, ParserError> {
+        let numkeys = parser.next_int()?;
+        if numkeys <= 0 {
+            return Err(ReplyError::OutOfRange("numkeys should be greater than 0".into()).into());
+        }
+        if numkeys as usize > parser.remaining() {
+            return Err(ReplyError::Other(
+                "Number of keys can't be greater than number of args".into(),
+            )
+            .into());
+        }
+
+        let keys = (0..numkeys)
+            .map(|_| parser.next_bytes())
+            .collect::<Result<_, _>>()?;
+
+        let mut limit = 0;
+        while parser.remaining() > 0 {
+            match &parser.next_string()?.to_uppercase()[..] {
+                "LIMIT" => {
+                    limit = usize::try_from(parser.next_int()?)
+                        .map_err(|_| ReplyError::OutOfRange("LIMIT can't be negative".into()))?;
+                }
+                _ => return Err(ReplyError::Syntax.into()),
+            }
+        }
+
+        Ok(SInterCard { keys, limit })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let sets = match get_sets(state, &self.keys) {
+            Ok(sets) => sets,
+            Err(err) => return err.into(),
+        };
+
+        let limit = if self.limit == 0 {
+            usize::MAX
+        } else {
+            self.limit
+        };
+
+        let count = intersection(&sets).take(limit).count();
+
+        Frame::Integer(count as i64)
+    }
+}
+
+impl SPop {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<SPop, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let count = if parser.remaining() > 0 {
+            let count = parser.next_int()?;
+            let count = usize::try_from(count).map_err(|_| {
+                ReplyError::OutOfRange("value is out of range, must be positive".to_string())
+            })?;
+            Some(count)
+        } else {
+            None
+        };
+
+        Ok(SPop { key, count })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let set = match get_set_mut(state, &self.key) {
+            Ok(Some(set)) => set,
+            Ok(None) if self.count.is_some() => return Frame::Set(vec![]),
+            Ok(None) => return Frame::Null,
+            Err(err) => return err.into(),
+        };
+
+        let mut rng = rand::rng();
+        let popped: Vec<Bytes> = (0..self.count.unwrap_or(1))
+            .map_while(|_| set.pop(&mut rng))
+            .collect();
+
+        if set.is_empty() {
+            state.remove(&self.key);
+        } else if !popped.is_empty() {
+            state.touch(&self.key);
+        }
+
+        match self.count {
+            Some(_) => members(&popped),
+            None => popped.into_iter().next().map_or(Frame::Null, Frame::Bulk),
+        }
+    }
+}
+
+impl SRandMember {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<SRandMember, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let count = if parser.remaining() > 0 {
+            Some(parser.next_int()?)
+        } else {
+            None
+        };
+
+        if count.is_some_and(|count| count < -MAX_REPEATED_PICKS) {
+            return Err(ReplyError::OutOfRange("value is out of range".into()).into());
+        }
+
+        Ok(SRandMember { key, count })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let set = match get_set(state, &self.key) {
+            Ok(Some(set)) => set,
+            Ok(None) if self.count.is_some() => return Frame::Array(vec![]),
+            Ok(None) => return Frame::Null,
+            Err(err) => return err.into(),
+        };
+
+        let mut rng = rand::rng();
+        let Some(count) = self.count else {
+            return set
+                .members()
+                .choose(&mut rng)
+                .map_or(Frame::Null, |member| Frame::Bulk(member.clone()));
+        };
+
+        let picked: Vec<&Bytes> = if count >= 0 {
+            let count = usize::try_from(count).unwrap_or(usize::MAX);
+            set.members().sample(&mut rng, count).collect()
+        } else {
+            // Members may be repeated, so pick each of them independently
+            (0..count.unsigned_abs())
+                .filter_map(|_| set.members().choose(&mut rng))
+                .collect()
+        };
+
+        Frame::Array(picked.into_iter().cloned().map(Frame::Bulk).collect())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/set_test.rs"]
+mod set_test;
@@ -0,0 +1,916 @@
+use crate::error::ReplyError;
+use crate::server::cmd::list::normalize_range;
+use crate::server::db::{State, Value};
+use crate::server::frame::{Frame, Protocol};
+use crate::server::parser::{parse_double, parse_int, Parser, ParserError};
+use crate::server::sorted_set::SortedSet;
+
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::ops::Bound;
+
+/// Adds members to a sorted set or updates their score, creating the
+/// sorted set if the key does not exist.
+///
+/// `NX` only adds new members and `XX` only updates existing ones, `GT` and
+/// `LT` only update a score if the new one is greater or lower. Returns the
+/// number of added members, plus the updated ones with `CH`. With `INCR`
+/// the score is incremented like `ZINCRBY` does, and the new score is
+/// returned, or `Frame::Null` if a condition prevented the update.
+#[derive(Debug)]
+pub(crate) struct ZAdd {
+    key: Bytes,
+    elements: Vec<(f64, Bytes)>,
+    condition: Option<ZAddCondition>,
+    comparison: Option<ZAddComparison>,
+    changed: bool,
+    incr: bool,
+}
+
+/// Increments the score of a member, adding it with the increment as score
+/// if needed. Returns the new score.
+#[derive(Debug)]
+pub(crate) struct ZIncrBy {
+    key: Bytes,
+    increment: f64,
+    member: Bytes,
+}
+
+/// Returns the members of a sorted set within a range of positions, of
+/// scores with `BYSCORE`, or of members with `BYLEX`.
+///
+/// `REV` walks the sorted set from the highest score, in which case the
+/// score and lexicographical ranges are given from their maximum. `LIMIT`
+/// skips and caps the returned members, and `WITHSCORES` returns every
+/// member along with its score.
+#[derive(Debug)]
+pub(crate) struct ZRange {
+    key: Bytes,
+    range: RangeBy,
+    rev: bool,
+
+    /// Number of members to skip, then maximum number of members to return
+    /// if positive
+    limit: Option<(i64, i64)>,
+    with_scores: bool,
+}
+
+/// Returns the position of a member in a sorted set, from the lowest score,
+/// or the highest one for `ZREVRANK`. With `WITHSCORE` the score of the
+/// member is returned as well.
+#[derive(Debug)]
+pub(crate) struct ZRank {
+    key: Bytes,
+    member: Bytes,
+    rev: bool,
+    with_score: bool,
+}
+
+/// Returns the score of a member, `Frame::Null` if the member or the key
+/// does not exist.
+#[derive(Debug)]
+pub(crate) struct ZScore {
+    key: Bytes,
+    member: Bytes,
+}
+
+/// Removes members from a sorted set, deleting it once it is empty.
+///
+/// Returns the number of members that were removed.
+#[derive(Debug)]
+pub(crate) struct ZRem {
+    key: Bytes,
+    members: Vec<Bytes>,
+}
+
+/// Returns the number of members of a sorted set, 0 if the key does not
+/// exist.
+#[derive(Debug)]
+pub(crate) struct ZCard {
+    key: Bytes,
+}
+
+/// Returns the number of members whose score is within a range.
+#[derive(Debug)]
+pub(crate) struct ZCount {
+    key: Bytes,
+    min: Bound<f64>,
+    max: Bound<f64>,
+}
+
+/// Removes and returns the members with the lowest scores, or the highest
+/// ones. Registered as `ZPOPMIN` and `ZPOPMAX`.
+///
+/// Without a count a single member is returned along with its score.
+#[derive(Debug)]
+pub(crate) struct ZPop {
+    key: Bytes,
+    max: bool,
+    count: Option<usize>,
+}
+
+/// Stores the union or the intersection of sorted sets. Registered as
+/// `ZUNIONSTORE` and `ZINTERSTORE`.
+///
+/// The score of each member is the aggregate of its scores in the inputs,
+/// multiplied by the weight of each input. Sets can be used as inputs, all
+/// their members having a score of 1. Returns the number of members of the
+/// result.
+#[derive(Debug)]
+pub(crate) struct ZStore {
+    destination: Bytes,
+    keys: Vec<Bytes>,
+    weights: Vec<f64>,
+    aggregate: Aggregate,
+
+    /// Union if set, intersection otherwise
+    union: bool,
+}
+
+/// Existence conditions of `ZADD`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ZAddCondition {
+    /// Only add new members
+    Nx,
+    /// Only update existing members
+    Xx,
+}
+
+/// Score conditions of `ZADD`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ZAddComparison {
+    /// Only update a score if the new one is greater
+    Gt,
+    /// Only update a score if the new one is lower
+    Lt,
+}
+
+/// What `add` did to a member.
+#[derive(Debug, Default)]
+pub(crate) struct Added {
+    /// The member was added
+    pub(crate) added: bool,
+    /// The score of an existing member changed
+    pub(crate) changed: bool,
+    /// The new score, `None` if a condition was not met
+    pub(crate) score: Option<f64>,
+}
+
+/// What a `ZRANGE` range applies to.
+#[derive(Debug, Clone, PartialEq)]
+enum RangeBy {
+    /// Inclusive positions, counting from the end when negative
+    Rank(i64, i64),
+    /// Minimum and maximum scores
+    Score(Bound<f64>, Bound<f64>),
+    /// Minimum and maximum members, only meaningful when all the scores
+    /// are equal
+    Lex(LexBound, LexBound),
+}
+
+/// Bound of a lexicographical range.
+#[derive(Debug, Clone, PartialEq)]
+enum LexBound {
+    /// `-`, lower than any member
+    Lowest,
+    /// `+`, greater than any member
+    Highest,
+    Included(Bytes),
+    Excluded(Bytes),
+}
+
+/// How the scores of a member are combined by `ZStore`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Aggregate {
+    Sum,
+    Min,
+    Max,
+}
+
+/// Returns the sorted set stored at `key`, `None` if the key does not
+/// exist.
+pub(crate) fn get_sorted_set<'a>(
+    state: &'a mut State,
+    key: &[u8],
+) -> Result<Option<&'a SortedSet>, ReplyError> {
+    match state.get(key) {
+        None => Ok(None),
+        Some(Value::SortedSet(set)) => Ok(Some(set)),
+        Some(_) => Err(ReplyError::WrongType),
+    }
+}
+
+/// Like `get_sorted_set`, for commands modifying the sorted set.
+pub(crate) fn get_sorted_set_mut<'a>(
+    state: &'a mut State,
+    key: &[u8],
+) -> Result<Option<&'a mut SortedSet>, ReplyError> {
+    match state.get_mut(key) {
+        None => Ok(None),
+        Some(Value::SortedSet(set)) => Ok(Some(set)),
+        Some(_) => Err(ReplyError::WrongType),
+    }
+}
+
+/// Sets the score of `member`, or adds `score` to it if `incr` is set,
+/// subject to the conditions of `ZADD`.
+pub(crate) fn add(
+    set: &mut SortedSet,
+    member: Bytes,
+    score: f64,
+    incr: bool,
+    condition: Option<ZAddCondition>,
+    comparison: Option<ZAddComparison>,
+) -> Result<Added, ReplyError> {
+    let Some(current) = set.score(&member) else {
+        if condition == Some(ZAddCondition::Xx) {
+            return Ok(Added::default());
+        }
+        set.insert(member, score);
+        return Ok(Added {
+            added: true,
+            changed: false,
+            score: Some(score),
+        });
+    };
+
+    if condition == Some(ZAddCondition::Nx) {
+        return Ok(Added::default());
+    }
+
+    let score = if incr { current + score } else { score };
+    if score.is_nan() {
+        return Err(ReplyError::Other(
+            "resulting score is not a number (NaN)".into(),
+        ));
+    }
+
+    match comparison {
+        Some(ZAddComparison::Gt) if score <= current => return Ok(Added::default()),
+        Some(ZAddComparison::Lt) if score >= current => return Ok(Added::default()),
+        _ => {}
+    }
+
+    let changed = score != current;
+    if changed {
+        set.insert(member, score);
+    }
+
+    Ok(Added {
+        added: false,
+        changed,
+        score: Some(score),
+    })
+}
+
+/// Parses a score bound, exclusive when prefixed with `(`.
+fn parse_score_bound(src: &[u8]) -> Result<Bound<f64>, ReplyError> {
+    let err = || ReplyError::Other("min or max is not a float".into());
+    match src.strip_prefix(b"(") {
+        Some(src) => Ok(Bound::Excluded(parse_double(src).map_err(|_| err())?)),
+        None => Ok(Bound::Included(parse_double(src).map_err(|_| err())?)),
+    }
+}
+
+/// Parses a lexicographical bound: `-`, `+`, or a member prefixed with `[`
+/// when inclusive or `(` when exclusive.
+fn parse_lex_bound(src: &Bytes) -> Result<LexBound, ReplyError> {
+    match src.first() {
+        Some(b'-') if src.len() == 1 => Ok(LexBound::Lowest),
+        Some(b'+') if src.len() == 1 => Ok(LexBound::Highest),
+        Some(b'[') => Ok(LexBound::Included(src.slice(1..))),
+        Some(b'(') => Ok(LexBound::Excluded(src.slice(1..))),
+        _ => Err(ReplyError::Other(
+            "min or max not valid string range item".into(),
+        )),
+    }
+}
+
+/// Returns `true` if `member` is above the lexicographical bound `min`.
+fn above_min(member: &[u8], min: &LexBound) -> bool {
+    match min {
+        LexBound::Lowest => true,
+        LexBound::Highest => false,
+        LexBound::Included(min) => member >= &min[..],
+        LexBound::Excluded(min) => member > &min[..],
+    }
+}
+
+/// Returns `true` if `member` is below the lexicographical bound `max`.
+fn below_max(member: &[u8], max: &LexBound) -> bool {
+    match max {
+        LexBound::Lowest => false,
+        LexBound::Highest => true,
+        LexBound::Included(max) => member <= &max[..],
+        LexBound::Excluded(max) => member < &max[..],
+    }
+}
+
+/// Parses an optional count, which must not be negative.
+fn parse_count(parser: &mut Parser) -> Result<Option<usize>, ParserError> {
+    if parser.remaining() == 0 {
+        return Ok(None);
+    }
+
+    let count = parser.next_int()?;
+    usize::try_from(count).map(Some).map_err(|_| {
+        ReplyError::OutOfRange("value is out of range, must be positive".to_string()).into()
+    })
+}
+
+/// Replies with members, followed by their score if `with_scores` is set.
+/// Scores are paired with their member on RESP3 connections.
+fn scored(entries: Vec<(Bytes, f64)>, with_scores: bool, protocol: Protocol) -> Frame {
+    let frames = entries.into_iter();
+    match (with_scores, protocol) {
+        (false, _) => Frame::Array(frames.map(|(member, _)| Frame::Bulk(member)).collect()),
+        (true, Protocol::Resp2) => Frame::Array(
+            frames
+                .flat_map(|(member, score)| [Frame::Bulk(member), Frame::Double(score)])
+                .collect(),
+        ),
+        (true, Protocol::Resp3) => Frame::Array(
+            frames
+                .map(|(member, score)| {
+                    Frame::Array(vec![Frame::Bulk(member), Frame::Double(score)])
+                })
+                .collect(),
+        ),
+    }
+}
+
+impl ZAdd {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<ZAdd, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let mut args = vec![parser.next_bytes()?, parser.next_bytes()?];
+        while parser.remaining() > 0 {
+            args.push(parser.next_bytes()?);
+        }
+        let mut args = args.into_iter().peekable();
+
+        let (mut nx, mut xx, mut gt, mut lt) = (false, false, false, false);
+        let mut changed = false;
+        let mut incr = false;
+        while let Some(arg) = args.peek() {
+            match &arg.to_ascii_uppercase()[..] {
+                b"NX" => nx = true,
+                b"XX" => xx = true,
+                b"GT" => gt = true,
+                b"LT" => lt = true,
+                b"CH" => changed = true,
+                b"INCR" => incr = true,
+                _ => break,
+            }
+            args.next();
+        }
+
+        if nx && xx {
+            return Err(ReplyError::Other(
+                "XX and NX options at the same time are not compatible".into(),
+            )
+            .into());
+        }
+        if (gt && lt) || (nx && (gt || lt)) {
+            return Err(ReplyError::Other(
+                "GT, LT, and/or NX options at the same time are not compatible".into(),
+            )
+            .into());
+        }
+
+        let condition = match (nx, xx) {
+            (true, _) => Some(ZAddCondition::Nx),
+            (_, true) => Some(ZAddCondition::Xx),
+            _ => None,
+        };
+        let comparison = match (gt, lt) {
+            (true, _) => Some(ZAddComparison::Gt),
+            (_, true) => Some(ZAddComparison::Lt),
+            _ => None,
+        };
+
+        let args: Vec<Bytes> = args.collect();
+        if args.is_empty() || !args.len().is_multiple_of(2) {
+            return Err(ReplyError::Syntax.into());
+        }
+        if incr && args.len() > 2 {
+            return Err(ReplyError::Other(
+                "INCR option supports a single increment-element pair".into(),
+            )
+            .into());
+        }
+
+        let elements = args
+            .chunks(2)
+            .map(|pair| Ok((parse_double(&pair[0])?, pair[1].clone())))
+            .collect::<Result<_, ParserError>>()?;
+
+        Ok(ZAdd {
+            key,
+            elements,
+            condition,
+            comparison,
+            changed,
+            incr,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        // `XX` never adds to a missing key, which is not created
+        if self.condition == Some(ZAddCondition::Xx) && !state.contains(&self.key) {
+            return match self.incr {
+                true => Frame::Null,
+                false => Frame::Integer(0),
+            };
+        }
+
+        let value =
+            state.get_or_insert_with(self.key.clone(), || Value::SortedSet(SortedSet::new()));
+        let Value::SortedSet(set) = value else {
+            return ReplyError::WrongType.into();
+        };
+
+        let mut added = 0;
+        let mut changed = 0;
+        let mut last_score = None;
+        let mut error = None;
+        for (score, member) in self.elements {
+            match add(
+                set,
+                member,
+                score,
+                self.incr,
+                self.condition,
+                self.comparison,
+            ) {
+                Ok(outcome) => {
+                    added += outcome.added as i64;
+                    changed += outcome.changed as i64;
+                    last_score = outcome.score;
+                }
+                Err(err) => {
+                    error = Some(err);
+                    break;
+                }
+            }
+        }
+
+        if added + changed > 0 {
+            state.touch(&self.key);
+        }
+
+        if let Some(err) = error {
+            return err.into();
+        }
+
+        if self.incr {
+            return last_score.map_or(Frame::Null, Frame::Double);
+        }
+
+        if self.changed {
+            Frame::Integer(added + changed)
+        } else {
+            Frame::Integer(added)
+        }
+    }
+}
+
+impl ZIncrBy {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<ZIncrBy, ParserError> {
+        Ok(ZIncrBy {
+            key: parser.next_bytes()?,
+            increment: parser.next_double()?,
+            member: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let value =
+            state.get_or_insert_with(self.key.clone(), || Value::SortedSet(SortedSet::new()));
+        let Value::SortedSet(set) = value else {
+            return ReplyError::WrongType.into();
+        };
+
+        match add(set, self.member, self.increment, true, None, None) {
+            Ok(outcome) => {
+                if outcome.added || outcome.changed {
+                    state.touch(&self.key);
+                }
+                outcome.score.map_or(Frame::Null, Frame::Double)
+            }
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl ZRange {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<ZRange, ParserError> {
+        let key = parser.next_bytes()?;
+        let start = parser.next_bytes()?;
+        let stop = parser.next_bytes()?;
+
+        let mut by_score = false;
+        let mut by_lex = false;
+        let mut rev = false;
+        let mut limit = None;
+        let mut with_scores = false;
+        while parser.remaining() > 0 {
+            match &parser.next_string()?.to_uppercase()[..] {
+                "BYSCORE" => by_score = true,
+                "BYLEX" => by_lex = true,
+                "REV" => rev = true,
+                "LIMIT" => limit = Some((parser.next_int()?, parser.next_int()?)),
+                "WITHSCORES" => with_scores = true,
+                _ => return Err(ReplyError::Syntax.into()),
+            }
+        }
+
+        if by_score && by_lex {
+            return Err(ReplyError::Syntax.into());
+        }
+        if limit.is_some() && !by_score && !by_lex {
+            return Err(ReplyError::Other(
+                "syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX"
+                    .into(),
+            )
+            .into());
+        }
+        if with_scores && by_lex {
+            return Err(ReplyError::Other(
+                "syntax error, WITHSCORES not supported in combination with BYLEX".into(),
+            )
+            .into());
+        }
+
+        // Score and lexicographical ranges start from their maximum when
+        // walking backwards
+        let (min, max) = if rev {
+            (&stop, &start)
+        } else {
+            (&start, &stop)
+        };
+        let range = if by_score {
+            RangeBy::Score(parse_score_bound(min)?, parse_score_bound(max)?)
+        } else if by_lex {
+            RangeBy::Lex(parse_lex_bound(min)?, parse_lex_bound(max)?)
+        } else {
+            RangeBy::Rank(parse_int(&start)?, parse_int(&stop)?)
+        };
+
+        Ok(ZRange {
+            key,
+            range,
+            rev,
+            limit,
+            with_scores,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State, protocol: Protocol) -> Frame {
+        let set = match get_sorted_set(state, &self.key) {
+            Ok(Some(set)) => set,
+            Ok(None) => return Frame::Array(vec![]),
+            Err(err) => return err.into(),
+        };
+
+        // Positions of the range, counting from the highest score with `REV`
+        let mut ranks = match &self.range {
+            RangeBy::Rank(start, stop) => match normalize_range(*start, *stop, set.len()) {
+                Some((start, stop)) => start..stop + 1,
+                None => return Frame::Array(vec![]),
+            },
+            RangeBy::Score(min, max) => set.ranks_by_score(*min, *max),
+            RangeBy::Lex(min, max) => {
+                let start = set.count_while(|member, _| !above_min(member, min));
+                let end = set.count_while(|member, _| below_max(member, max));
+                start..end.max(start)
+            }
+        };
+        if self.rev && !matches!(self.range, RangeBy::Rank(..)) {
+            ranks = set.len() - ranks.end..set.len() - ranks.start;
+        }
+
+        if let Some((offset, count)) = self.limit {
+            let Ok(offset) = usize::try_from(offset) else {
+                return Frame::Array(vec![]);
+            };
+            let count = usize::try_from(count).unwrap_or(usize::MAX);
+            let start = ranks.start.saturating_add(offset).min(ranks.end);
+            ranks = start..ranks.end.min(start.saturating_add(count));
+        }
+
+        let entries = set
+            .iter_from(ranks.start, self.rev)
+            .take(ranks.len())
+            .map(|(member, score)| (member.clone(), score))
+            .collect();
+        scored(entries, self.with_scores, protocol)
+    }
+}
+
+impl ZRank {
+    pub(crate) fn parse_frames(parser: &mut Parser, rev: bool) -> Result<ZRank, ParserError> {
+        let key = parser.next_bytes()?;
+        let member = parser.next_bytes()?;
+
+        let with_score = if parser.remaining() > 0 {
+            if parser.next_string()?.to_uppercase() != "WITHSCORE" {
+                return Err(ReplyError::Syntax.into());
+            }
+            true
+        } else {
+            false
+        };
+
+        Ok(ZRank {
+            key,
+            member,
+            rev,
+            with_score,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let set = match get_sorted_set(state, &self.key) {
+            Ok(set) => set,
+            Err(err) => return err.into(),
+        };
+
+        let found = set.and_then(|set| {
+            let rank = set.rank(&self.member, self.rev)?;
+            Some((rank, set.score(&self.member)?))
+        });
+
+        match (found, self.with_score) {
+            (Some((rank, score)), true) => {
+                Frame::Array(vec![Frame::Integer(rank as i64), Frame::Double(score)])
+            }
+            (Some((rank, _)), false) => Frame::Integer(rank as i64),
+            (None, true) => Frame::NullArray,
+            (None, false) => Frame::Null,
+        }
+    }
+}
+
+impl ZScore {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<ZScore, ParserError> {
+        Ok(ZScore {
+            key: parser.next_bytes()?,
+            member: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match get_sorted_set(state, &self.key) {
+            Ok(set) => set
+                .and_then(|set| set.score(&self.member))
+                .map_or(Frame::Null, Frame::Double),
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl ZRem {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<ZRem, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let mut members = vec![parser.next_bytes()?];
+        while parser.remaining() > 0 {
+            members.push(parser.next_bytes()?);
+        }
+
+        Ok(ZRem { key, members })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let set = match get_sorted_set_mut(state, &self.key) {
+            Ok(Some(set)) => set,
+            Ok(None) => return Frame::Integer(0),
+            Err(err) => return err.into(),
+        };
+
+        let removed = self
+            .members
+            .iter()
+            .filter(|member| set.remove(member))
+            .count();
+
+        if set.is_empty() {
+            state.remove(&self.key);
+        } else if removed > 0 {
+            state.touch(&self.key);
+        }
+
+        Frame::Integer(removed as i64)
+    }
+}
+
+impl ZCard {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<ZCard, ParserError> {
+        Ok(ZCard {
+            key: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match get_sorted_set(state, &self.key) {
+            Ok(set) => Frame::Integer(set.map_or(0, |set| set.len() as i64)),
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl ZCount {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<ZCount, ParserError> {
+        Ok(ZCount {
+            key: parser.next_bytes()?,
+            min: parse_score_bound(&parser.next_bytes()?)?,
+            max: parse_score_bound(&parser.next_bytes()?)?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match get_sorted_set(state, &self.key) {
+            Ok(set) => {
+                let count = set.map_or(0, |set| set.ranks_by_score(self.min, self.max).len());
+                Frame::Integer(count as i64)
+            }
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl ZPop {
+    pub(crate) fn parse_frames(parser: &mut Parser, max: bool) -> Result<ZPop, ParserError> {
+        Ok(ZPop {
+            key: parser.next_bytes()?,
+            max,
+            count: parse_count(parser)?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State, protocol: Protocol) -> Frame {
+        let set = match get_sorted_set_mut(state, &self.key) {
+            Ok(Some(set)) => set,
+            Ok(None) => return Frame::Array(vec![]),
+            Err(err) => return err.into(),
+        };
+
+        let popped: Vec<(Bytes, f64)> = (0..self.count.unwrap_or(1))
+            .map_while(|_| set.pop(self.max))
+            .collect();
+
+        if set.is_empty() {
+            state.remove(&self.key);
+        } else if !popped.is_empty() {
+            state.touch(&self.key);
+        }
+
+        match self.count {
+            Some(_) => scored(popped, true, protocol),
+            // A single member is not paired with its score, whatever the
+            // protocol
+            None => scored(popped, true, Protocol::Resp2),
+        }
+    }
+}
+
+impl ZStore {
+    pub(crate) fn parse_frames(
+        parser: &mut Parser,
+        name: &str,
+        union: bool,
+    ) -> Result<ZStore, ParserError> {
+        let destination = parser.next_bytes()?;
+
+        let numkeys = parser.next_int()?;
+        if numkeys < 1 {
+            return Err(ReplyError::Other(format!(
+                "at least 1 input key is needed for '{}' command",
+                name
+            ))
+            .into());
+        }
+        if numkeys as usize > parser.remaining() {
+            return Err(ReplyError::Syntax.into());
+        }
+
+        let keys: Vec<Bytes> = (0..numkeys)
+            .map(|_| parser.next_bytes())
+            .collect::<Result<_, _>>()?;
+
+        let mut weights = vec![1.0; keys.len()];
+        let mut aggregate = Aggregate::Sum;
+        while parser.remaining() > 0 {
+            match &parser.next_string()?.to_uppercase()[..] {
+                "WEIGHTS" if parser.remaining() >= keys.len() => {
+                    for weight in weights.iter_mut() {
+                        *weight = parser
+                            .next_double()
+                            .map_err(|_| ReplyError::Other("weight value is not a float".into()))?;
+                    }
+                }
+                "AGGREGATE" => {
+                    aggregate = match &parser.next_string()?.to_uppercase()[..] {
+                        "SUM" => Aggregate::Sum,
+                        "MIN" => Aggregate::Min,
+                        "MAX" => Aggregate::Max,
+                        _ => return Err(ReplyError::Syntax.into()),
+                    }
+                }
+                _ => return Err(ReplyError::Syntax.into()),
+            }
+        }
+
+        Ok(ZStore {
+            destination,
+            keys,
+            weights,
+            aggregate,
+            union,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let mut inputs: Vec<HashMap<Bytes, f64>> = Vec::with_capacity(self.keys.len());
+        for (key, weight) in self.keys.iter().zip(&self.weights) {
+            let scores: Vec<(Bytes, f64)> = match state.get(key) {
+                None => vec![],
+                Some(Value::SortedSet(set)) => set
+                    .iter()
+                    .map(|(member, score)| (member.clone(), score))
+                    .collect(),
+                Some(Value::Set(set)) => set.iter().map(|member| (member.clone(), 1.0)).collect(),
+                Some(_) => return ReplyError::WrongType.into(),
+            };
+
+            let weighted = scores
+                .into_iter()
+                .map(|(member, score)| (member, weighted(score, *weight)))
+                .collect();
+            inputs.push(weighted);
+        }
+
+        let mut result: HashMap<Bytes, f64> = HashMap::new();
+        if self.union {
+            for input in inputs {
+                for (member, score) in input {
+                    result
+                        .entry(member)
+                        .and_modify(|current| *current = self.aggregate.apply(*current, score))
+                        .or_insert(score);
+                }
+            }
+        } else if let Some((first, others)) = inputs.split_first() {
+            for (member, score) in first {
+                let score = others.iter().try_fold(*score, |score, input| {
+                    let other = input.get(member)?;
+                    Some(self.aggregate.apply(score, *other))
+                });
+                if let Some(score) = score {
+                    result.insert(member.clone(), score);
+                }
+            }
+        }
+
+        let mut set = SortedSet::new();
+        for (member, score) in result {
+            set.insert(member, score);
+        }
+
+        let len = set.len();
+        if set.is_empty() {
+            state.remove(&self.destination);
+        } else {
+            state.insert(self.destination, Value::SortedSet(set), None);
+        }
+
+        Frame::Integer(len as i64)
+    }
+}
+
+/// Multiplies `score` by `weight`, where infinity times 0 is 0.
+fn weighted(score: f64, weight: f64) -> f64 {
+    let score = score * weight;
+    if score.is_nan() {
+        0.0
+    } else {
+        score
+    }
+}
+
+impl Aggregate {
+    fn apply(self, a: f64, b: f64) -> f64 {
+        match self {
+            // Adding opposite infinities gives 0
+            Aggregate::Sum => Some(a + b).filter(|sum| !sum.is_nan()).unwrap_or(0.0),
+            Aggregate::Min => a.min(b),
+            Aggregate::Max => a.max(b),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/sorted_set_test.rs"]
+mod sorted_set_test;
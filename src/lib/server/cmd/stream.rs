@@ -0,0 +1,1421 @@
+use crate::error::ReplyError;
+use crate::server::db::{now_ms, BlockingCommand, State, Value};
+use crate::server::frame::{Frame, Protocol};
+use crate::server::parser::{parse_int, Parser, ParserError};
+use crate::server::stream::{Fields, Stream, StreamId, Trim};
+
+use bytes::Bytes;
+use std::time::Duration;
+
+/// Appends an entry to a stream, creating the stream if the key does not
+/// exist unless `NOMKSTREAM` is given.
+///
+/// The ID is generated from the current time with `*`, or from the given
+/// time with `ms-*`, and must be greater than the last one of the stream
+/// when given in full. `MAXLEN` and `MINID` trim the stream after the entry
+/// is added. Returns the ID of the entry, or `Frame::Null` if the key does
+/// not exist and `NOMKSTREAM` was given.
+#[derive(Debug)]
+pub(crate) struct XAdd {
+    key: Bytes,
+    id: NewId,
+    fields: Fields,
+    no_mkstream: bool,
+    trim: Option<Trim>,
+
+    /// Maximum number of entries removed by the trimming
+    limit: Option<usize>,
+}
+
+/// Returns the entries of a stream with an ID between two bounds. `-` and
+/// `+` are the lowest and greatest IDs, and a bound prefixed with `(` is
+/// exclusive. Registered as `XRANGE` and `XREVRANGE`, which returns the
+/// entries from the greatest ID and takes the bounds the other way around.
+#[derive(Debug)]
+pub(crate) struct XRange {
+    key: Bytes,
+    start: StreamId,
+    end: StreamId,
+    rev: bool,
+    count: Option<usize>,
+}
+
+/// Returns the number of entries of a stream, 0 if the key does not exist.
+#[derive(Debug)]
+pub(crate) struct XLen {
+    key: Bytes,
+}
+
+/// Removes entries from a stream.
+///
+/// Returns the number of entries that were removed.
+#[derive(Debug)]
+pub(crate) struct XDel {
+    key: Bytes,
+    ids: Vec<StreamId>,
+}
+
+/// Removes the oldest entries of a stream, keeping at most `MAXLEN` entries
+/// or the ones with an ID of at least `MINID`.
+///
+/// Returns the number of entries that were removed.
+#[derive(Debug)]
+pub(crate) struct XTrim {
+    key: Bytes,
+    trim: Trim,
+    limit: Option<usize>,
+}
+
+/// Returns the entries of streams with an ID greater than the given ones,
+/// `$` standing for the last ID of the stream.
+///
+/// With `BLOCK`, waits for another client to add entries if there are none
+/// yet. Returns the entries of each stream that has some, as a map on RESP3
+/// connections and as an array of pairs on RESP2 ones, or a null array if
+/// none has.
+#[derive(Debug)]
+pub(crate) struct XRead {
+    keys: Vec<Bytes>,
+
+    /// IDs to read after, `None` standing for `$` until resolved
+    ids: Vec<Option<StreamId>>,
+    count: Option<usize>,
+
+    /// Wait for entries to be added, as `BLOCK` does
+    blocking: bool,
+
+    /// How long to wait, forever if `None`
+    timeout: Option<Duration>,
+
+    /// Protocol of the connection, which the reply depends on
+    protocol: Protocol,
+}
+
+/// Manages the consumer groups of a stream, depending on the subcommand:
+///
+/// - `CREATE key group id|$ [MKSTREAM]` creates a group delivering the
+///   entries after `id`, creating the stream with `MKSTREAM`
+/// - `SETID key group id|$` changes the last ID delivered to the group
+/// - `DESTROY key group` removes a group and its pending entries
+/// - `CREATECONSUMER key group consumer` adds a consumer to a group
+/// - `DELCONSUMER key group consumer` removes a consumer along with its
+///   pending entries, returning how many it had
+#[derive(Debug)]
+pub(crate) struct XGroup {
+    key: Bytes,
+    group: Bytes,
+    action: GroupAction,
+}
+
+/// Reads entries from streams on behalf of a consumer of a group.
+///
+/// With `>`, reads the entries never delivered to the group, adding them to
+/// the pending entries list of the consumer unless `NOACK` is given, and
+/// blocks with `BLOCK` if there are none yet. With an ID, reads the entries
+/// pending for the consumer after that ID instead.
+#[derive(Debug)]
+pub(crate) struct XReadGroup {
+    group: Bytes,
+    consumer: Bytes,
+    keys: Vec<Bytes>,
+
+    /// IDs to read the pending entries after, `None` standing for `>`
+    ids: Vec<Option<StreamId>>,
+    count: Option<usize>,
+    no_ack: bool,
+    blocking: bool,
+    timeout: Option<Duration>,
+    protocol: Protocol,
+}
+
+/// Removes entries from the pending entries list of a group, once they are
+/// processed.
+///
+/// Returns the number of entries that were pending.
+#[derive(Debug)]
+pub(crate) struct XAck {
+    key: Bytes,
+    group: Bytes,
+    ids: Vec<StreamId>,
+}
+
+/// Inspects the pending entries list of a group.
+///
+/// Without a range, returns the number of pending entries, the lowest and
+/// greatest pending IDs and the number of entries pending for each consumer.
+/// With a range, returns the pending entries in it along with their
+/// consumer, idle time and delivery count, optionally only those idle for
+/// at least `IDLE` milliseconds or pending for a given consumer.
+#[derive(Debug)]
+pub(crate) struct XPending {
+    key: Bytes,
+    group: Bytes,
+    range: Option<PendingRange>,
+}
+
+/// Transfers pending entries idle for at least `min_idle` milliseconds to
+/// another consumer, as done when the consumer they were delivered to
+/// failed.
+///
+/// Returns the claimed entries, or only their ID with `JUSTID`.
+#[derive(Debug)]
+pub(crate) struct XClaim {
+    key: Bytes,
+    group: Bytes,
+    consumer: Bytes,
+    min_idle: u64,
+    ids: Vec<StreamId>,
+
+    /// Idle time the claimed entries are given, as `IDLE` does
+    idle: Option<u64>,
+
+    /// Unix time in milliseconds the claimed entries are delivered at, as
+    /// `TIME` does
+    time: Option<u64>,
+
+    /// Delivery count the claimed entries are given
+    retry_count: Option<u64>,
+
+    /// Claim the entries that are not pending yet, as long as they exist
+    force: bool,
+    just_id: bool,
+
+    /// Last ID delivered to the group, if greater than the current one
+    last_id: Option<StreamId>,
+}
+
+/// Claims the pending entries idle for at least `min_idle` milliseconds,
+/// scanning the pending entries list from `start`, like calling `XPENDING`
+/// then `XCLAIM`.
+///
+/// Returns the ID to resume the scan from, `0-0` once done, the claimed
+/// entries and the IDs of the pending entries that no longer exist in the
+/// stream, which are removed from the list.
+#[derive(Debug)]
+pub(crate) struct XAutoClaim {
+    key: Bytes,
+    group: Bytes,
+    consumer: Bytes,
+    min_idle: u64,
+    start: StreamId,
+    count: usize,
+    just_id: bool,
+}
+
+#[derive(Debug)]
+enum GroupAction {
+    /// Create the group, `None` standing for `$`
+    Create {
+        id: Option<StreamId>,
+        mkstream: bool,
+    },
+    /// Set the last delivered ID, `None` standing for `$`
+    SetId(Option<StreamId>),
+    Destroy,
+    CreateConsumer(Bytes),
+    DelConsumer(Bytes),
+}
+
+/// Range of pending entries requested from `XPENDING`.
+#[derive(Debug)]
+struct PendingRange {
+    min_idle: u64,
+    start: StreamId,
+    end: StreamId,
+    count: usize,
+    consumer: Option<Bytes>,
+}
+
+/// ID requested for a new entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NewId {
+    /// `*`, generated from the current time
+    Auto,
+    /// `ms-*`, the sequence number is generated
+    Time(u64),
+    /// `ms-seq`
+    Explicit(StreamId),
+}
+
+fn invalid_id() -> ReplyError {
+    ReplyError::Other("Invalid stream ID specified as stream command argument".into())
+}
+
+/// Returns the stream stored at `key`, `None` if the key does not exist.
+fn get_stream<'a>(state: &'a mut State, key: &[u8]) -> Result<Option<&'a Stream>, ReplyError> {
+    match state.get(key) {
+        None => Ok(None),
+        Some(Value::Stream(stream)) => Ok(Some(stream)),
+        Some(_) => Err(ReplyError::WrongType),
+    }
+}
+
+/// Like `get_stream`, for commands modifying the stream.
+fn get_stream_mut<'a>(
+    state: &'a mut State,
+    key: &[u8],
+) -> Result<Option<&'a mut Stream>, ReplyError> {
+    match state.get_mut(key) {
+        None => Ok(None),
+        Some(Value::Stream(stream)) => Ok(Some(stream)),
+        Some(_) => Err(ReplyError::WrongType),
+    }
+}
+
+/// Returns the stream stored at `key`, replying with a `NOGROUP` error
+/// naming `group` if it does not exist.
+fn get_group_stream<'a>(
+    state: &'a mut State,
+    key: &Bytes,
+    group: &Bytes,
+) -> Result<&'a mut Stream, ReplyError> {
+    get_stream_mut(state, key)?.ok_or_else(|| no_group(key, group))
+}
+
+fn no_group(key: &Bytes, group: &Bytes) -> ReplyError {
+    ReplyError::NoGroup(format!(
+        "No such key '{}' or consumer group '{}'",
+        String::from_utf8_lossy(key),
+        String::from_utf8_lossy(group)
+    ))
+}
+
+/// Parses a minimum idle time in milliseconds, a negative one standing for 0.
+fn parse_min_idle(parser: &mut Parser) -> Result<u64, ParserError> {
+    Ok(u64::try_from(parser.next_int()?).unwrap_or(0))
+}
+
+/// Parses a stream ID, `ms` alone standing for `ms-0`.
+fn parse_id(src: &[u8]) -> Result<StreamId, ReplyError> {
+    StreamId::parse(src, 0).ok_or_else(invalid_id)
+}
+
+/// Parses the trimming strategy following `MAXLEN` or `MINID`, with its
+/// optional `=` or `~` modifier. Returns it along with whether trimming
+/// was requested as approximate.
+fn parse_trim(parser: &mut Parser, strategy: &str) -> Result<(Trim, bool), ParserError> {
+    let mut threshold = parser.next_bytes()?;
+    let approximate = &threshold[..] == b"~";
+    if approximate || &threshold[..] == b"=" {
+        threshold = parser.next_bytes()?;
+    }
+
+    let trim = if strategy == "MAXLEN" {
+        let max_len = usize::try_from(parse_int(&threshold)?)
+            .map_err(|_| ReplyError::OutOfRange("The MAXLEN argument must be >= 0.".into()))?;
+        Trim::MaxLen(max_len)
+    } else {
+        Trim::MinId(parse_id(&threshold)?)
+    };
+
+    Ok((trim, approximate))
+}
+
+/// Parses the count following `LIMIT`.
+fn parse_limit(parser: &mut Parser) -> Result<usize, ParserError> {
+    usize::try_from(parser.next_int()?)
+        .map_err(|_| ReplyError::OutOfRange("The LIMIT argument must be >= 0.".into()).into())
+}
+
+/// Parses the start of an interval, `-` standing for the lowest ID and a
+/// `(` prefix making it exclusive. `ms` alone stands for `ms-0`.
+fn parse_start(src: &[u8]) -> Result<StreamId, ReplyError> {
+    match src {
+        b"-" => Ok(StreamId::MIN),
+        b"+" => Ok(StreamId::MAX),
+        [b'(', id @ ..] => parse_id(id)?
+            .next()
+            .ok_or_else(|| ReplyError::Other("invalid start ID for the interval".into())),
+        id => parse_id(id),
+    }
+}
+
+/// Parses the end of an interval, `+` standing for the greatest ID and a
+/// `(` prefix making it exclusive. `ms` alone stands for the last ID of
+/// that millisecond.
+fn parse_end(src: &[u8]) -> Result<StreamId, ReplyError> {
+    match src {
+        b"-" => Ok(StreamId::MIN),
+        b"+" => Ok(StreamId::MAX),
+        [b'(', id @ ..] => StreamId::parse(id, u64::MAX)
+            .ok_or_else(invalid_id)?
+            .prev()
+            .ok_or_else(|| ReplyError::Other("invalid end ID for the interval".into())),
+        id => StreamId::parse(id, u64::MAX).ok_or_else(invalid_id),
+    }
+}
+
+/// Options of `XREAD` and `XREADGROUP`, up to `STREAMS`.
+#[derive(Debug, Default)]
+struct ReadOptions {
+    count: Option<usize>,
+    blocking: bool,
+    timeout: Option<Duration>,
+
+    /// Do not add the entries to the pending entries list, only accepted
+    /// by `XREADGROUP`
+    no_ack: bool,
+}
+
+/// Parses the options of `XREAD` or `XREADGROUP` up to `STREAMS`, then the
+/// keys and the IDs that follow. The IDs are returned as is since each
+/// command gives a special meaning to some of them.
+fn parse_read(
+    parser: &mut Parser,
+    command: &str,
+    group: bool,
+) -> Result<(ReadOptions, Vec<Bytes>, Vec<Bytes>), ParserError> {
+    let mut options = ReadOptions::default();
+    loop {
+        match &parser.next_string()?.to_uppercase()[..] {
+            "COUNT" => {
+                // Like Redis, a count that is not positive means no limit
+                options.count = usize::try_from(parser.next_int()?)
+                    .ok()
+                    .filter(|count| *count > 0);
+            }
+            "BLOCK" => {
+                let ms = parser.next_int().map_err(|_| {
+                    ReplyError::OutOfRange("timeout is not an integer or out of range".into())
+                })?;
+                let ms = u64::try_from(ms)
+                    .map_err(|_| ReplyError::OutOfRange("timeout is negative".into()))?;
+                options.blocking = true;
+                options.timeout = (ms > 0).then(|| Duration::from_millis(ms));
+            }
+            "NOACK" if group => options.no_ack = true,
+            "STREAMS" => break,
+            _ => return Err(ReplyError::Syntax.into()),
+        }
+    }
+
+    let remaining = parser.remaining();
+    if remaining == 0 || !remaining.is_multiple_of(2) {
+        let special = if group { ">" } else { "$" };
+        return Err(ReplyError::Other(format!(
+            "Unbalanced '{}' list of streams: for each stream key an ID or '{}' must be specified.",
+            command, special
+        ))
+        .into());
+    }
+
+    let keys = (0..remaining / 2)
+        .map(|_| parser.next_bytes())
+        .collect::<Result<_, _>>()?;
+    let ids = (0..remaining / 2)
+        .map(|_| parser.next_bytes())
+        .collect::<Result<_, _>>()?;
+
+    Ok((options, keys, ids))
+}
+
+/// Replies with the entries read from each stream, as a map on RESP3
+/// connections and as an array of pairs on RESP2 ones.
+fn streams_reply(streams: Vec<(Frame, Frame)>, protocol: Protocol) -> Frame {
+    match protocol {
+        Protocol::Resp2 => Frame::Array(
+            streams
+                .into_iter()
+                .map(|(key, entries)| Frame::Array(vec![key, entries]))
+                .collect(),
+        ),
+        Protocol::Resp3 => Frame::Map(streams),
+    }
+}
+
+/// Replies with an entry as an array of its ID and its fields and values.
+fn entry(id: &StreamId, fields: &Fields) -> Frame {
+    let fields = fields
+        .iter()
+        .flat_map(|(field, value)| [Frame::Bulk(field.clone()), Frame::Bulk(value.clone())])
+        .collect();
+
+    Frame::Array(vec![
+        Frame::Bulk(Bytes::from(id.to_string())),
+        Frame::Array(fields),
+    ])
+}
+
+impl XAdd {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<XAdd, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let mut no_mkstream = false;
+        let mut trim = None;
+        let mut approximate = false;
+        let mut limit = None;
+        let id = loop {
+            let arg = parser.next_bytes()?;
+            match &arg.to_ascii_uppercase()[..] {
+                b"NOMKSTREAM" => no_mkstream = true,
+                b"MAXLEN" | b"MINID" => {
+                    let strategy = String::from_utf8_lossy(&arg).to_uppercase();
+                    let (strategy, is_approximate) = parse_trim(parser, &strategy)?;
+                    trim = Some(strategy);
+                    approximate = is_approximate;
+                }
+                b"LIMIT" => limit = Some(parse_limit(parser)?),
+                _ => break arg,
+            }
+        };
+
+        if limit.is_some() && !approximate {
+            return Err(ReplyError::Other(
+                "syntax error, LIMIT cannot be used without the special ~ option".into(),
+            )
+            .into());
+        }
+
+        let id = if &id[..] == b"*" {
+            NewId::Auto
+        } else if let Some(ms) = id.strip_suffix(b"-*") {
+            NewId::Time(StreamId::parse(ms, 0).ok_or_else(invalid_id)?.ms)
+        } else {
+            NewId::Explicit(parse_id(&id)?)
+        };
+
+        let mut fields = vec![(parser.next_bytes()?, parser.next_bytes()?)];
+        while parser.remaining() > 0 {
+            fields.push((parser.next_bytes()?, parser.next_bytes()?));
+        }
+
+        Ok(XAdd {
+            key,
+            id,
+            fields,
+            no_mkstream,
+            trim,
+            limit,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        if self.id == NewId::Explicit(StreamId::MIN) {
+            return ReplyError::Other("The ID specified in XADD must be greater than 0-0".into())
+                .into();
+        }
+
+        if self.no_mkstream && !state.contains(&self.key) {
+            return Frame::Null;
+        }
+        let stream =
+            match state.get_or_insert_with(self.key.clone(), || Value::Stream(Stream::new())) {
+                Value::Stream(stream) => stream,
+                _ => return ReplyError::WrongType.into(),
+            };
+
+        let id = match self.id {
+            NewId::Auto => stream.next_id(None, now_ms()),
+            NewId::Time(ms) => stream.next_id(Some(ms), now_ms()),
+            NewId::Explicit(id) => Some(id),
+        };
+        let Some(id) = id.filter(|id| stream.add(*id, self.fields)) else {
+            return ReplyError::Other(
+                "The ID specified in XADD is equal or smaller than the target stream top item"
+                    .into(),
+            )
+            .into();
+        };
+
+        if let Some(trim) = self.trim {
+            stream.trim(trim, self.limit);
+        }
+        state.touch(&self.key);
+
+        // Serve the clients blocked on the stream
+        state.signal_ready(&self.key);
+
+        Frame::Bulk(Bytes::from(id.to_string()))
+    }
+}
+
+impl XRange {
+    pub(crate) fn parse_frames(parser: &mut Parser, rev: bool) -> Result<XRange, ParserError> {
+        let key = parser.next_bytes()?;
+        let (start, end) = if rev {
+            let end = parser.next_bytes()?;
+            (parser.next_bytes()?, end)
+        } else {
+            (parser.next_bytes()?, parser.next_bytes()?)
+        };
+
+        let count = if parser.remaining() > 0 {
+            if parser.next_string()?.to_uppercase() != "COUNT" {
+                return Err(ReplyError::Syntax.into());
+            }
+            Some(usize::try_from(parser.next_int()?).unwrap_or(0))
+        } else {
+            None
+        };
+
+        let start = parse_start(&start)?;
+        let end = parse_end(&end)?;
+
+        Ok(XRange {
+            key,
+            start,
+            end,
+            rev,
+            count,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let stream = match get_stream(state, &self.key) {
+            Ok(Some(stream)) => stream,
+            Ok(None) => return Frame::Array(vec![]),
+            Err(err) => return err.into(),
+        };
+
+        let count = self.count.unwrap_or(usize::MAX);
+        let entries = stream.range(self.start, self.end);
+        let entries = if self.rev {
+            entries
+                .rev()
+                .take(count)
+                .map(|(id, fields)| entry(id, fields))
+                .collect()
+        } else {
+            entries
+                .take(count)
+                .map(|(id, fields)| entry(id, fields))
+                .collect()
+        };
+
+        Frame::Array(entries)
+    }
+}
+
+impl XLen {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<XLen, ParserError> {
+        Ok(XLen {
+            key: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match get_stream(state, &self.key) {
+            Ok(stream) => Frame::Integer(stream.map_or(0, |stream| stream.len() as i64)),
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl XDel {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<XDel, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let mut ids = vec![parse_id(&parser.next_bytes()?)?];
+        while parser.remaining() > 0 {
+            ids.push(parse_id(&parser.next_bytes()?)?);
+        }
+
+        Ok(XDel { key, ids })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let stream = match get_stream_mut(state, &self.key) {
+            Ok(Some(stream)) => stream,
+            Ok(None) => return Frame::Integer(0),
+            Err(err) => return err.into(),
+        };
+
+        let removed = self.ids.iter().filter(|id| stream.remove(id)).count();
+        if removed > 0 {
+            state.touch(&self.key);
+        }
+
+        Frame::Integer(removed as i64)
+    }
+}
+
+impl XTrim {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<XTrim, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let strategy = parser.next_string()?.to_uppercase();
+        if strategy != "MAXLEN" && strategy != "MINID" {
+            return Err(ReplyError::Syntax.into());
+        }
+        let (trim, approximate) = parse_trim(parser, &strategy)?;
+
+        let mut limit = None;
+        while parser.remaining() > 0 {
+            match &parser.next_string()?.to_uppercase()[..] {
+                "LIMIT" => limit = Some(parse_limit(parser)?),
+                _ => return Err(ReplyError::Syntax.into()),
+            }
+        }
+
+        if limit.is_some() && !approximate {
+            return Err(ReplyError::Other(
+                "syntax error, LIMIT cannot be used without the special ~ option".into(),
+            )
+            .into());
+        }
+
+        Ok(XTrim { key, trim, limit })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let trimmed = match get_stream_mut(state, &self.key) {
+            Ok(Some(stream)) => stream.trim(self.trim, self.limit),
+            Ok(None) => 0,
+            Err(err) => return err.into(),
+        };
+        if trimmed > 0 {
+            state.touch(&self.key);
+        }
+
+        Frame::Integer(trimmed as i64)
+    }
+}
+
+impl XRead {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<XRead, ParserError> {
+        let (options, keys, ids) = parse_read(parser, "xread", false)?;
+        let ids = ids
+            .iter()
+            .map(|id| match &id[..] {
+                b"$" => Ok(None),
+                id => Ok(Some(parse_id(id)?)),
+            })
+            .collect::<Result<_, ReplyError>>()?;
+
+        Ok(XRead {
+            keys,
+            ids,
+            count: options.count,
+            blocking: options.blocking,
+            timeout: options.timeout,
+            protocol: Protocol::default(),
+        })
+    }
+
+    /// Returns `true` if `BLOCK` was given.
+    pub(crate) fn is_blocking(&self) -> bool {
+        self.blocking
+    }
+
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Replies for a connection speaking `protocol`.
+    pub(crate) fn with_protocol(mut self, protocol: Protocol) -> XRead {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Replaces `$` by the last ID of each stream, so only the entries
+    /// added from now on are read.
+    pub(crate) fn resolve_ids(mut self, state: &mut State) -> XRead {
+        for (key, id) in self.keys.iter().zip(self.ids.iter_mut()) {
+            if id.is_none() {
+                let last_id = get_stream(state, key).ok().flatten().map(|s| s.last_id());
+                *id = Some(last_id.unwrap_or(StreamId::MIN));
+            }
+        }
+
+        self
+    }
+
+    /// Executes the command without blocking, as done within a transaction.
+    pub(crate) fn execute(self, state: &mut State, protocol: Protocol) -> Frame {
+        self.with_protocol(protocol)
+            .resolve_ids(state)
+            .try_execute(state)
+            .unwrap_or(Frame::NullArray)
+    }
+}
+
+impl BlockingCommand for XRead {
+    fn keys(&self) -> &[Bytes] {
+        &self.keys
+    }
+
+    fn try_execute(&self, state: &mut State) -> Option<Frame> {
+        let count = self.count.unwrap_or(usize::MAX);
+
+        let mut streams = vec![];
+        for (key, id) in self.keys.iter().zip(&self.ids) {
+            let stream = match get_stream(state, key) {
+                Ok(Some(stream)) => stream,
+                Ok(None) => continue,
+                Err(err) => return Some(err.into()),
+            };
+
+            let Some(start) = id.unwrap_or(stream.last_id()).next() else {
+                continue;
+            };
+            let entries: Vec<Frame> = stream
+                .range(start, StreamId::MAX)
+                .take(count)
+                .map(|(id, fields)| entry(id, fields))
+                .collect();
+
+            if !entries.is_empty() {
+                streams.push((Frame::Bulk(key.clone()), Frame::Array(entries)));
+            }
+        }
+
+        if streams.is_empty() {
+            return None;
+        }
+
+        Some(streams_reply(streams, self.protocol))
+    }
+
+    fn timeout_reply(&self) -> Frame {
+        Frame::NullArray
+    }
+}
+
+impl XGroup {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<XGroup, ParserError> {
+        let subcommand = parser.next_string()?;
+        let key = parser.next_bytes()?;
+        let group = parser.next_bytes()?;
+
+        let parse_last_id = |id: Bytes| -> Result<_, ReplyError> {
+            match &id[..] {
+                b"$" => Ok(None),
+                id => Ok(Some(parse_id(id)?)),
+            }
+        };
+
+        let action = match &subcommand.to_uppercase()[..] {
+            "CREATE" => {
+                let id = parse_last_id(parser.next_bytes()?)?;
+                let mut mkstream = false;
+                while parser.remaining() > 0 {
+                    match &parser.next_string()?.to_uppercase()[..] {
+                        "MKSTREAM" => mkstream = true,
+                        _ => return Err(ReplyError::Syntax.into()),
+                    }
+                }
+                GroupAction::Create { id, mkstream }
+            }
+            "SETID" => GroupAction::SetId(parse_last_id(parser.next_bytes()?)?),
+            "DESTROY" => GroupAction::Destroy,
+            "CREATECONSUMER" => GroupAction::CreateConsumer(parser.next_bytes()?),
+            "DELCONSUMER" => GroupAction::DelConsumer(parser.next_bytes()?),
+            _ => {
+                return Err(ReplyError::Other(format!(
+                    "unknown subcommand '{}'. Try XGROUP HELP.",
+                    subcommand
+                ))
+                .into())
+            }
+        };
+
+        Ok(XGroup { key, group, action })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        if let GroupAction::Create { mkstream: true, .. } = self.action {
+            if !state.contains(&self.key) {
+                state.insert(self.key.clone(), Value::Stream(Stream::new()), None);
+            }
+        }
+
+        let stream =
+            match get_stream_mut(state, &self.key) {
+                Ok(Some(stream)) => stream,
+                Ok(None) => return ReplyError::Other(
+                    "The XGROUP subcommand requires the key to exist. Note that for CREATE you \
+                     may want to use the MKSTREAM option to create an empty stream automatically."
+                        .into(),
+                )
+                .into(),
+                Err(err) => return err.into(),
+            };
+
+        let last_id = stream.last_id();
+        let no_such_group = || -> Frame {
+            ReplyError::NoGroup(format!(
+                "No such consumer group '{}' for key name '{}'",
+                String::from_utf8_lossy(&self.group),
+                String::from_utf8_lossy(&self.key)
+            ))
+            .into()
+        };
+
+        let (modified, reply) = match self.action {
+            GroupAction::Create { id, .. } => {
+                if !stream.create_group(self.group, id.unwrap_or(last_id)) {
+                    return ReplyError::BusyGroup.into();
+                }
+                (true, Frame::Simple("OK".into()))
+            }
+            GroupAction::Destroy => {
+                let destroyed = stream.destroy_group(&self.group);
+                (destroyed, Frame::Integer(destroyed as i64))
+            }
+            GroupAction::SetId(id) => {
+                let Some(group) = stream.group(&self.group) else {
+                    return no_such_group();
+                };
+                group.set_last_delivered(id.unwrap_or(last_id));
+                (true, Frame::Simple("OK".into()))
+            }
+            GroupAction::CreateConsumer(consumer) => {
+                let Some(group) = stream.group(&self.group) else {
+                    return no_such_group();
+                };
+                let created = group.create_consumer(&consumer);
+                (created, Frame::Integer(created as i64))
+            }
+            GroupAction::DelConsumer(consumer) => {
+                let Some(group) = stream.group(&self.group) else {
+                    return no_such_group();
+                };
+                match group.remove_consumer(&consumer) {
+                    Some(pending) => (true, Frame::Integer(pending as i64)),
+                    None => (false, Frame::Integer(0)),
+                }
+            }
+        };
+        if modified {
+            state.touch(&self.key);
+        }
+
+        reply
+    }
+}
+
+impl XReadGroup {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<XReadGroup, ParserError> {
+        if parser.next_string()?.to_uppercase() != "GROUP" {
+            return Err(ReplyError::Syntax.into());
+        }
+        let group = parser.next_bytes()?;
+        let consumer = parser.next_bytes()?;
+
+        let (options, keys, ids) = parse_read(parser, "xreadgroup", true)?;
+        let ids = ids
+            .iter()
+            .map(|id| match &id[..] {
+                b">" => Ok(None),
+                b"$" => Err(ReplyError::Other(
+                    "The $ ID is meaningless in the context of XREADGROUP: you want to read the \
+                     history of this consumer by specifying a proper ID, or use the > ID to get \
+                     new messages. The $ ID would just return an empty result set."
+                        .into(),
+                )),
+                id => Ok(Some(parse_id(id)?)),
+            })
+            .collect::<Result<_, ReplyError>>()?;
+
+        Ok(XReadGroup {
+            group,
+            consumer,
+            keys,
+            ids,
+            count: options.count,
+            no_ack: options.no_ack,
+            blocking: options.blocking,
+            timeout: options.timeout,
+            protocol: Protocol::default(),
+        })
+    }
+
+    /// Returns `true` if `BLOCK` was given.
+    pub(crate) fn is_blocking(&self) -> bool {
+        self.blocking
+    }
+
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Replies for a connection speaking `protocol`.
+    pub(crate) fn with_protocol(mut self, protocol: Protocol) -> XReadGroup {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Executes the command without blocking, as done within a transaction.
+    pub(crate) fn execute(self, state: &mut State, protocol: Protocol) -> Frame {
+        self.with_protocol(protocol)
+            .try_execute(state)
+            .unwrap_or(Frame::NullArray)
+    }
+}
+
+impl BlockingCommand for XReadGroup {
+    fn keys(&self) -> &[Bytes] {
+        &self.keys
+    }
+
+    fn try_execute(&self, state: &mut State) -> Option<Frame> {
+        // Check every group exists before delivering anything
+        for key in &self.keys {
+            let exists = match get_stream(state, key) {
+                Ok(stream) => stream.is_some_and(|stream| stream.has_group(&self.group)),
+                Err(err) => return Some(err.into()),
+            };
+            if !exists {
+                return Some(
+                    ReplyError::NoGroup(format!(
+                        "No such key '{}' or consumer group '{}' in XREADGROUP with GROUP option",
+                        String::from_utf8_lossy(key),
+                        String::from_utf8_lossy(&self.group)
+                    ))
+                    .into(),
+                );
+            }
+        }
+
+        let count = self.count.unwrap_or(usize::MAX);
+        let now = now_ms();
+
+        let mut streams = vec![];
+        for (key, id) in self.keys.iter().zip(&self.ids) {
+            let Ok(Some(stream)) = get_stream_mut(state, key) else {
+                continue;
+            };
+
+            let entries: Vec<Frame> = match id {
+                None => stream
+                    .read_group(&self.group, &self.consumer, count, self.no_ack, now)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(id, fields)| entry(id, fields))
+                    .collect(),
+                Some(id) => {
+                    // The history of the consumer, delivered once again
+                    let group = stream.group(&self.group)?;
+                    let ids = match id.next() {
+                        Some(start) => group.consumer_pending(&self.consumer, start, count),
+                        None => vec![],
+                    };
+                    for id in &ids {
+                        group.deliver(*id, &self.consumer, now, 1);
+                    }
+
+                    ids.iter()
+                        .map(|id| match stream.get(id) {
+                            Some(fields) => entry(id, fields),
+                            None => Frame::Array(vec![
+                                Frame::Bulk(Bytes::from(id.to_string())),
+                                Frame::NullArray,
+                            ]),
+                        })
+                        .collect()
+                }
+            };
+
+            // Delivering entries updates the group, even for the history
+            if !entries.is_empty() {
+                state.touch(key);
+            }
+
+            // The history is always replied, even if empty
+            if !entries.is_empty() || id.is_some() {
+                streams.push((Frame::Bulk(key.clone()), Frame::Array(entries)));
+            }
+        }
+
+        if streams.is_empty() {
+            return None;
+        }
+
+        Some(streams_reply(streams, self.protocol))
+    }
+
+    fn timeout_reply(&self) -> Frame {
+        Frame::NullArray
+    }
+}
+
+impl XAck {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<XAck, ParserError> {
+        let key = parser.next_bytes()?;
+        let group = parser.next_bytes()?;
+
+        let mut ids = vec![parse_id(&parser.next_bytes()?)?];
+        while parser.remaining() > 0 {
+            ids.push(parse_id(&parser.next_bytes()?)?);
+        }
+
+        Ok(XAck { key, group, ids })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let stream = match get_stream_mut(state, &self.key) {
+            Ok(Some(stream)) => stream,
+            Ok(None) => return Frame::Integer(0),
+            Err(err) => return err.into(),
+        };
+        let Some(group) = stream.group(&self.group) else {
+            return Frame::Integer(0);
+        };
+
+        let acked = self.ids.iter().filter(|id| group.ack(id)).count();
+        if acked > 0 {
+            state.touch(&self.key);
+        }
+
+        Frame::Integer(acked as i64)
+    }
+}
+
+impl XPending {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<XPending, ParserError> {
+        let key = parser.next_bytes()?;
+        let group = parser.next_bytes()?;
+
+        if parser.remaining() == 0 {
+            return Ok(XPending {
+                key,
+                group,
+                range: None,
+            });
+        }
+
+        let mut start = parser.next_bytes()?;
+        let mut min_idle = 0;
+        if start.eq_ignore_ascii_case(b"IDLE") {
+            min_idle = parse_min_idle(parser)?;
+            start = parser.next_bytes()?;
+        }
+        let start = parse_start(&start)?;
+        let end = parse_end(&parser.next_bytes()?)?;
+        let count = usize::try_from(parser.next_int()?).unwrap_or(0);
+        let consumer = match parser.remaining() {
+            0 => None,
+            _ => Some(parser.next_bytes()?),
+        };
+
+        Ok(XPending {
+            key,
+            group,
+            range: Some(PendingRange {
+                min_idle,
+                start,
+                end,
+                count,
+                consumer,
+            }),
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let stream = match get_group_stream(state, &self.key, &self.group) {
+            Ok(stream) => stream,
+            Err(err) => return err.into(),
+        };
+        let Some(group) = stream.group(&self.group) else {
+            return no_group(&self.key, &self.group).into();
+        };
+        let pending = group.pending();
+
+        let Some(range) = self.range else {
+            let (Some((first, _)), Some((last, _))) =
+                (pending.first_key_value(), pending.last_key_value())
+            else {
+                return Frame::Array(vec![
+                    Frame::Integer(0),
+                    Frame::Null,
+                    Frame::Null,
+                    Frame::NullArray,
+                ]);
+            };
+
+            let mut consumers: Vec<(&Bytes, usize)> =
+                group.consumers().filter(|(_, count)| *count > 0).collect();
+            consumers.sort();
+
+            return Frame::Array(vec![
+                Frame::Integer(pending.len() as i64),
+                Frame::Bulk(Bytes::from(first.to_string())),
+                Frame::Bulk(Bytes::from(last.to_string())),
+                Frame::Array(
+                    consumers
+                        .into_iter()
+                        .map(|(consumer, count)| {
+                            Frame::Array(vec![
+                                Frame::Bulk(consumer.clone()),
+                                Frame::Bulk(Bytes::from(count.to_string())),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ]);
+        };
+
+        if range.start > range.end {
+            return Frame::Array(vec![]);
+        }
+
+        let now = now_ms();
+        let entries = pending
+            .range(range.start..=range.end)
+            .filter(|(_, entry)| {
+                range
+                    .consumer
+                    .as_ref()
+                    .is_none_or(|consumer| *consumer == entry.consumer)
+            })
+            .filter(|(_, entry)| now.saturating_sub(entry.delivered_at) >= range.min_idle)
+            .take(range.count)
+            .map(|(id, entry)| {
+                Frame::Array(vec![
+                    Frame::Bulk(Bytes::from(id.to_string())),
+                    Frame::Bulk(entry.consumer.clone()),
+                    Frame::Integer(now.saturating_sub(entry.delivered_at) as i64),
+                    Frame::Integer(entry.deliveries as i64),
+                ])
+            })
+            .collect();
+
+        Frame::Array(entries)
+    }
+}
+
+impl XClaim {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<XClaim, ParserError> {
+        let key = parser.next_bytes()?;
+        let group = parser.next_bytes()?;
+        let consumer = parser.next_bytes()?;
+        let min_idle = parse_min_idle(parser)
+            .map_err(|_| ReplyError::Other("Invalid min-idle-time argument for XCLAIM".into()))?;
+
+        // IDs come first, the options start at the first argument that is
+        // not an ID
+        let mut ids = vec![parse_id(&parser.next_bytes()?)?];
+        let mut option = None;
+        while parser.remaining() > 0 {
+            let arg = parser.next_bytes()?;
+            match StreamId::parse(&arg, 0) {
+                Some(id) => ids.push(id),
+                None => {
+                    option = Some(arg);
+                    break;
+                }
+            }
+        }
+
+        let mut claim = XClaim {
+            key,
+            group,
+            consumer,
+            min_idle,
+            ids,
+            idle: None,
+            time: None,
+            retry_count: None,
+            force: false,
+            just_id: false,
+            last_id: None,
+        };
+
+        while let Some(arg) = option {
+            let non_negative = |value: i64| {
+                u64::try_from(value)
+                    .map_err(|_| ReplyError::OutOfRange("Invalid IDLE option argument".into()))
+            };
+            match &arg.to_ascii_uppercase()[..] {
+                b"IDLE" => claim.idle = Some(non_negative(parser.next_int()?)?),
+                b"TIME" => claim.time = Some(non_negative(parser.next_int()?)?),
+                b"RETRYCOUNT" => claim.retry_count = Some(non_negative(parser.next_int()?)?),
+                b"FORCE" => claim.force = true,
+                b"JUSTID" => claim.just_id = true,
+                b"LASTID" => claim.last_id = Some(parse_id(&parser.next_bytes()?)?),
+                _ => {
+                    return Err(ReplyError::Other(format!(
+                        "Unrecognized XCLAIM option '{}'",
+                        String::from_utf8_lossy(&arg)
+                    ))
+                    .into())
+                }
+            }
+
+            option = match parser.remaining() {
+                0 => None,
+                _ => Some(parser.next_bytes()?),
+            };
+        }
+
+        Ok(claim)
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let stream = match get_group_stream(state, &self.key, &self.group) {
+            Ok(stream) => stream,
+            Err(err) => return err.into(),
+        };
+
+        let exists: Vec<bool> = self.ids.iter().map(|id| stream.get(id).is_some()).collect();
+        let Some(group) = stream.group(&self.group) else {
+            return no_group(&self.key, &self.group).into();
+        };
+
+        let now = now_ms();
+        let delivered_at = self
+            .time
+            .or(self.idle.map(|idle| now.saturating_sub(idle)))
+            .unwrap_or(now);
+
+        group.create_consumer(&self.consumer);
+        let mut modified = false;
+        if let Some(last_id) = self.last_id {
+            if last_id > group.last_delivered() {
+                group.set_last_delivered(last_id);
+                modified = true;
+            }
+        }
+
+        let mut claimed = vec![];
+        for (id, exists) in self.ids.iter().zip(exists) {
+            match group.pending().get(id) {
+                // Entries deleted from the stream can not be claimed anymore
+                Some(_) if !exists => {
+                    group.ack(id);
+                    modified = true;
+                    continue;
+                }
+                Some(entry) if now.saturating_sub(entry.delivered_at) < self.min_idle => continue,
+                Some(_) => {}
+                None if self.force && exists => {}
+                None => continue,
+            }
+
+            group.deliver(*id, &self.consumer, delivered_at, (!self.just_id) as u64);
+            if let Some(retry_count) = self.retry_count {
+                group.set_deliveries(id, retry_count);
+            }
+            claimed.push(*id);
+        }
+
+        let claimed = claimed
+            .iter()
+            .filter_map(|id| match self.just_id {
+                true => Some(Frame::Bulk(Bytes::from(id.to_string()))),
+                false => stream.get(id).map(|fields| entry(id, fields)),
+            })
+            .collect::<Vec<_>>();
+        if modified || !claimed.is_empty() {
+            state.touch(&self.key);
+        }
+
+        Frame::Array(claimed)
+    }
+}
+
+impl XAutoClaim {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<XAutoClaim, ParserError> {
+        let key = parser.next_bytes()?;
+        let group = parser.next_bytes()?;
+        let consumer = parser.next_bytes()?;
+        let min_idle = parse_min_idle(parser).map_err(|_| {
+            ReplyError::Other("Invalid min-idle-time argument for XAUTOCLAIM".into())
+        })?;
+        let start = parse_start(&parser.next_bytes()?)?;
+
+        let mut count = 100;
+        let mut just_id = false;
+        while parser.remaining() > 0 {
+            match &parser.next_string()?.to_uppercase()[..] {
+                "COUNT" => {
+                    count = usize::try_from(parser.next_int()?)
+                        .ok()
+                        .filter(|count| *count > 0)
+                        .ok_or_else(|| ReplyError::OutOfRange("COUNT must be > 0".into()))?;
+                }
+                "JUSTID" => just_id = true,
+                _ => return Err(ReplyError::Syntax.into()),
+            }
+        }
+
+        Ok(XAutoClaim {
+            key,
+            group,
+            consumer,
+            min_idle,
+            start,
+            count,
+            just_id,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let stream = match get_group_stream(state, &self.key, &self.group) {
+            Ok(stream) => stream,
+            Err(err) => return err.into(),
+        };
+        let Some(group) = stream.group(&self.group) else {
+            return no_group(&self.key, &self.group).into();
+        };
+
+        // Like Redis, bound the work done in a single call by scanning at
+        // most ten entries per requested one
+        let scanned: Vec<(StreamId, u64)> = group
+            .pending()
+            .range(self.start..)
+            .take(self.count.saturating_mul(10))
+            .map(|(id, entry)| (*id, entry.delivered_at))
+            .collect();
+        let exists: Vec<bool> = scanned
+            .iter()
+            .map(|(id, _)| stream.get(id).is_some())
+            .collect();
+        let Some(group) = stream.group(&self.group) else {
+            return no_group(&self.key, &self.group).into();
+        };
+
+        let now = now_ms();
+        group.create_consumer(&self.consumer);
+
+        let mut claimed = vec![];
+        let mut deleted = vec![];
+        let mut last_scanned = None;
+        for ((id, delivered_at), exists) in scanned.into_iter().zip(exists) {
+            if claimed.len() == self.count {
+                break;
+            }
+            last_scanned = Some(id);
+
+            if !exists {
+                group.ack(&id);
+                deleted.push(id);
+            } else if now.saturating_sub(delivered_at) >= self.min_idle {
+                group.deliver(id, &self.consumer, now, (!self.just_id) as u64);
+                claimed.push(id);
+            }
+        }
+
+        // The next call resumes from the first entry that was not scanned
+        let cursor = last_scanned
+            .and_then(StreamId::next)
+            .and_then(|start| group.pending().range(start..).next().map(|(id, _)| *id))
+            .unwrap_or(StreamId::MIN);
+
+        let claimed = claimed
+            .iter()
+            .filter_map(|id| match self.just_id {
+                true => Some(Frame::Bulk(Bytes::from(id.to_string()))),
+                false => stream.get(id).map(|fields| entry(id, fields)),
+            })
+            .collect::<Vec<_>>();
+        let deleted = deleted
+            .iter()
+            .map(|id| Frame::Bulk(Bytes::from(id.to_string())))
+            .collect::<Vec<_>>();
+        if !claimed.is_empty() || !deleted.is_empty() {
+            state.touch(&self.key);
+        }
+
+        Frame::Array(vec![
+            Frame::Bulk(Bytes::from(cursor.to_string())),
+            Frame::Array(claimed),
+            Frame::Array(deleted),
+        ])
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/stream_test.rs"]
+mod stream_test;
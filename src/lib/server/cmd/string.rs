@@ -0,0 +1,632 @@
+use crate::error::ReplyError;
+use crate::server::cmd::list::normalize_range;
+use crate::server::db::{now_ms, State, Value};
+use crate::server::frame::Frame;
+use crate::server::parser::{parse_double, parse_int, Parser, ParserError};
+
+use bytes::{Bytes, BytesMut};
+
+/// Gets the value of a key.
+///
+/// Returns `Frame::Null` if the key does not exist.
+#[derive(Debug)]
+pub(crate) struct Get {
+    key: Bytes,
+}
+
+/// Sets a key to hold a string value, overwriting any previous value.
+///
+/// Supports the `EX`, `PX`, `EXAT`, `PXAT` and `KEEPTTL` options to control
+/// the time to live, `NX` and `XX` to only set the key if it does not already
+/// exist or if it already exists, and `GET` to return the previous value.
+#[derive(Debug)]
+pub(crate) struct Set {
+    key: Bytes,
+    value: Bytes,
+
+    /// How the time to live of the key is set
+    expiry: Option<Expiry>,
+
+    /// Condition the key must satisfy to be set
+    condition: Option<Condition>,
+
+    /// Return the previous value instead of `OK`
+    get: bool,
+}
+
+/// Adds an integer to the number stored at a key, created as 0 if missing.
+/// Registered as `INCR`, `DECR`, `INCRBY` and `DECRBY`.
+///
+/// Returns the value after the increment.
+#[derive(Debug)]
+pub(crate) struct IncrBy {
+    key: Bytes,
+    increment: i64,
+}
+
+/// Adds a floating point number to the number stored at a key, created as 0
+/// if missing.
+///
+/// Returns the value after the increment.
+#[derive(Debug)]
+pub(crate) struct IncrByFloat {
+    key: Bytes,
+    increment: f64,
+}
+
+/// Appends a value to the string at a key, created empty if missing.
+///
+/// Returns the length of the string after the append.
+#[derive(Debug)]
+pub(crate) struct Append {
+    key: Bytes,
+    value: Bytes,
+}
+
+/// Returns the length of the string at a key, 0 if the key does not exist.
+#[derive(Debug)]
+pub(crate) struct StrLen {
+    key: Bytes,
+}
+
+/// Returns the part of the string at a key between two inclusive offsets,
+/// which count from the end when negative.
+#[derive(Debug)]
+pub(crate) struct GetRange {
+    key: Bytes,
+    start: i64,
+    end: i64,
+}
+
+/// Overwrites the string at a key from an offset, padding it with zero
+/// bytes if it is shorter than the offset.
+///
+/// Returns the length of the string after the write.
+#[derive(Debug)]
+pub(crate) struct SetRange {
+    key: Bytes,
+    offset: usize,
+    value: Bytes,
+}
+
+/// Gets the value of a key and deletes it.
+///
+/// Returns `Frame::Null` if the key does not exist.
+#[derive(Debug)]
+pub(crate) struct GetDel {
+    key: Bytes,
+}
+
+/// Gets the value of a key and changes its time to live with the `EX`,
+/// `PX`, `EXAT` and `PXAT` options, or removes it with `PERSIST`.
+///
+/// Returns `Frame::Null` if the key does not exist.
+#[derive(Debug)]
+pub(crate) struct GetEx {
+    key: Bytes,
+
+    /// New time to live, `KeepTtl` leaving it unchanged
+    expiry: Expiry,
+
+    /// Remove the time to live
+    persist: bool,
+}
+
+/// Gets the values of several keys, `Frame::Null` standing for the keys
+/// that do not exist or do not hold a string.
+#[derive(Debug)]
+pub(crate) struct MGet {
+    keys: Vec<Bytes>,
+}
+
+/// Sets several keys at once. Registered as `MSET` and `MSETNX`, which sets
+/// none of the keys if any of them already exists.
+///
+/// `MSETNX` returns 1 if the keys were set, 0 otherwise.
+#[derive(Debug)]
+pub(crate) struct MSet {
+    entries: Vec<(Bytes, Bytes)>,
+    nx: bool,
+}
+
+/// Largest string `APPEND` and `SETRANGE` can produce, as Redis' default
+/// `proto-max-bulk-len`
+const MAX_STRING_LEN: usize = 512 * 1024 * 1024;
+
+/// Time to live options of `SET`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Expiry {
+    /// Expire after the given number of seconds
+    Ex(u64),
+    /// Expire after the given number of milliseconds
+    Px(u64),
+    /// Expire at the given unix time in seconds
+    ExAt(u64),
+    /// Expire at the given unix time in milliseconds
+    PxAt(u64),
+    /// Retain the time to live of the previous value
+    KeepTtl,
+}
+
+/// Existence conditions of `SET`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Condition {
+    /// Only set the key if it does not already exist
+    Nx,
+    /// Only set the key if it already exists
+    Xx,
+}
+
+impl Get {
+    pub(crate) fn new(key: Bytes) -> Get {
+        Get { key }
+    }
+
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<Get, ParserError> {
+        Ok(Get::new(parser.next_bytes()?))
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match state.get(&self.key) {
+            Some(Value::String(value)) => Frame::Bulk(value.clone()),
+            Some(_) => ReplyError::WrongType.into(),
+            None => Frame::Null,
+        }
+    }
+}
+
+impl Set {
+    pub(crate) fn new(key: Bytes, value: Bytes) -> Set {
+        Set {
+            key,
+            value,
+            expiry: None,
+            condition: None,
+            get: false,
+        }
+    }
+
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<Set, ParserError> {
+        let key = parser.next_bytes()?;
+        let value = parser.next_bytes()?;
+
+        let mut set = Set::new(key, value);
+
+        while parser.remaining() > 0 {
+            let option = parser.next_string()?.to_uppercase();
+            match &option[..] {
+                "NX" if set.condition.is_none() => set.condition = Some(Condition::Nx),
+                "XX" if set.condition.is_none() => set.condition = Some(Condition::Xx),
+                "GET" => set.get = true,
+                "KEEPTTL" if set.expiry.is_none() => set.expiry = Some(Expiry::KeepTtl),
+                "EX" | "PX" | "EXAT" | "PXAT" if set.expiry.is_none() => {
+                    set.expiry = Some(parse_expiry(parser, &option, "set")?);
+                }
+                _ => return Err(ReplyError::Syntax.into()),
+            }
+        }
+
+        Ok(set)
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        // The outer `Option` tells whether the key exists, the inner one holds
+        // the previous value if it is a string
+        let previous = match state.get(&self.key) {
+            None => None,
+            Some(Value::String(value)) => Some(Some(value.clone())),
+            // `GET` can only return a string, other values are overwritten
+            Some(_) if self.get => return ReplyError::WrongType.into(),
+            Some(_) => Some(None),
+        };
+
+        let ok = match self.condition {
+            Some(Condition::Nx) => previous.is_none(),
+            Some(Condition::Xx) => previous.is_some(),
+            None => true,
+        };
+
+        let reply = if self.get {
+            previous.flatten().map_or(Frame::Null, Frame::Bulk)
+        } else if ok {
+            Frame::Simple("OK".to_string())
+        } else {
+            Frame::Null
+        };
+
+        if !ok {
+            return reply;
+        }
+
+        let expires_at = match self.expiry {
+            None => None,
+            Some(Expiry::KeepTtl) => state.expires_at(&self.key).flatten(),
+            Some(expiry) => match expiry.deadline() {
+                Some(when) => Some(when),
+                None => return invalid_expire_time("set").into(),
+            },
+        };
+
+        state.insert(self.key, Value::String(self.value), expires_at);
+
+        reply
+    }
+}
+
+impl IncrBy {
+    pub(crate) fn parse_frames(
+        parser: &mut Parser,
+        sign: i64,
+        with_increment: bool,
+    ) -> Result<IncrBy, ParserError> {
+        let key = parser.next_bytes()?;
+        let increment = if with_increment {
+            parser.next_int()?
+        } else {
+            1
+        };
+
+        // -i64::MIN does not fit in an i64
+        let increment = increment
+            .checked_mul(sign)
+            .ok_or_else(|| ReplyError::OutOfRange("decrement would overflow".into()))?;
+
+        Ok(IncrBy { key, increment })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let current = match get_string(state, &self.key) {
+            Ok(Some(value)) => match parse_int(value) {
+                Ok(current) => current,
+                Err(_) => return ReplyError::NotInteger.into(),
+            },
+            Ok(None) => 0,
+            Err(err) => return err.into(),
+        };
+
+        let Some(new) = current.checked_add(self.increment) else {
+            return ReplyError::OutOfRange("increment or decrement would overflow".into()).into();
+        };
+        set_keeping_ttl(state, self.key, Bytes::from(new.to_string()));
+
+        Frame::Integer(new)
+    }
+}
+
+impl IncrByFloat {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<IncrByFloat, ParserError> {
+        Ok(IncrByFloat {
+            key: parser.next_bytes()?,
+            increment: parser.next_double()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let current = match get_string(state, &self.key) {
+            Ok(Some(value)) => match parse_double(value) {
+                Ok(current) => current,
+                Err(_) => return ReplyError::NotFloat.into(),
+            },
+            Ok(None) => 0.0,
+            Err(err) => return err.into(),
+        };
+
+        let new = current + self.increment;
+        if !new.is_finite() {
+            return ReplyError::Other("increment would produce NaN or Infinity".into()).into();
+        }
+        let new = Bytes::from(new.to_string());
+        set_keeping_ttl(state, self.key, new.clone());
+
+        Frame::Bulk(new)
+    }
+}
+
+impl Append {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<Append, ParserError> {
+        Ok(Append {
+            key: parser.next_bytes()?,
+            value: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let len = match get_string(state, &self.key) {
+            Ok(value) => value.map_or(0, |value| value.len()),
+            Err(err) => return err.into(),
+        };
+        if len + self.value.len() > MAX_STRING_LEN {
+            return string_too_long().into();
+        }
+
+        let len = match update_string(state, self.key.clone(), |value| {
+            value.extend_from_slice(&self.value);
+            value.len()
+        }) {
+            Ok(len) => len,
+            Err(err) => return err.into(),
+        };
+        state.touch(&self.key);
+
+        Frame::Integer(len as i64)
+    }
+}
+
+impl StrLen {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<StrLen, ParserError> {
+        Ok(StrLen {
+            key: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match get_string(state, &self.key) {
+            Ok(value) => Frame::Integer(value.map_or(0, |value| value.len() as i64)),
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl GetRange {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<GetRange, ParserError> {
+        Ok(GetRange {
+            key: parser.next_bytes()?,
+            start: parser.next_int()?,
+            end: parser.next_int()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let value = match get_string(state, &self.key) {
+            Ok(Some(value)) => value,
+            Ok(None) => return Frame::Bulk(Bytes::new()),
+            Err(err) => return err.into(),
+        };
+
+        match normalize_range(self.start, self.end, value.len()) {
+            Some((start, end)) => Frame::Bulk(value.slice(start..=end)),
+            None => Frame::Bulk(Bytes::new()),
+        }
+    }
+}
+
+impl SetRange {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<SetRange, ParserError> {
+        let key = parser.next_bytes()?;
+        let offset = usize::try_from(parser.next_int()?)
+            .map_err(|_| ReplyError::OutOfRange("offset is out of range".into()))?;
+        let value = parser.next_bytes()?;
+
+        if offset.saturating_add(value.len()) > MAX_STRING_LEN {
+            return Err(string_too_long().into());
+        }
+
+        Ok(SetRange { key, offset, value })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        // Writing nothing does not create the key
+        if self.value.is_empty() {
+            return match get_string(state, &self.key) {
+                Ok(value) => Frame::Integer(value.map_or(0, |value| value.len() as i64)),
+                Err(err) => err.into(),
+            };
+        }
+
+        let len = match update_string(state, self.key.clone(), |value| {
+            let end = self.offset + self.value.len();
+            if value.len() < end {
+                value.resize(end, 0);
+            }
+            value[self.offset..end].copy_from_slice(&self.value);
+            value.len()
+        }) {
+            Ok(len) => len,
+            Err(err) => return err.into(),
+        };
+        state.touch(&self.key);
+
+        Frame::Integer(len as i64)
+    }
+}
+
+impl GetDel {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<GetDel, ParserError> {
+        Ok(GetDel {
+            key: parser.next_bytes()?,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        match get_string(state, &self.key) {
+            Ok(Some(_)) => match state.remove(&self.key) {
+                Some(Value::String(value)) => Frame::Bulk(value),
+                _ => Frame::Null,
+            },
+            Ok(None) => Frame::Null,
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl GetEx {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<GetEx, ParserError> {
+        let key = parser.next_bytes()?;
+
+        let mut expiry = None;
+        let mut persist = false;
+        while parser.remaining() > 0 {
+            let option = parser.next_string()?.to_uppercase();
+            match &option[..] {
+                "PERSIST" if expiry.is_none() => persist = true,
+                "EX" | "PX" | "EXAT" | "PXAT" if expiry.is_none() && !persist => {
+                    expiry = Some(parse_expiry(parser, &option, "getex")?);
+                }
+                _ => return Err(ReplyError::Syntax.into()),
+            }
+        }
+
+        Ok(GetEx {
+            key,
+            expiry: expiry.unwrap_or(Expiry::KeepTtl),
+            persist,
+        })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let value = match get_string(state, &self.key) {
+            Ok(Some(value)) => value.clone(),
+            Ok(None) => return Frame::Null,
+            Err(err) => return err.into(),
+        };
+
+        if self.persist {
+            state.set_expires_at(&self.key, None);
+        } else if self.expiry != Expiry::KeepTtl {
+            match self.expiry.deadline() {
+                Some(when) => state.set_expires_at(&self.key, Some(when)),
+                None => return invalid_expire_time("getex").into(),
+            };
+        }
+
+        Frame::Bulk(value)
+    }
+}
+
+impl MGet {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<MGet, ParserError> {
+        let mut keys = vec![parser.next_bytes()?];
+        while parser.remaining() > 0 {
+            keys.push(parser.next_bytes()?);
+        }
+
+        Ok(MGet { keys })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        let values = self
+            .keys
+            .iter()
+            .map(|key| match state.get(key) {
+                Some(Value::String(value)) => Frame::Bulk(value.clone()),
+                _ => Frame::Null,
+            })
+            .collect();
+
+        Frame::Array(values)
+    }
+}
+
+impl MSet {
+    pub(crate) fn parse_frames(parser: &mut Parser, nx: bool) -> Result<MSet, ParserError> {
+        let mut entries = vec![(parser.next_bytes()?, parser.next_bytes()?)];
+        while parser.remaining() > 0 {
+            entries.push((parser.next_bytes()?, parser.next_bytes()?));
+        }
+
+        Ok(MSet { entries, nx })
+    }
+
+    pub(crate) fn execute(self, state: &mut State) -> Frame {
+        if self.nx && self.entries.iter().any(|(key, _)| state.contains(key)) {
+            return Frame::Integer(0);
+        }
+
+        for (key, value) in self.entries {
+            state.insert(key, Value::String(value), None);
+        }
+
+        match self.nx {
+            true => Frame::Integer(1),
+            false => Frame::Simple("OK".to_string()),
+        }
+    }
+}
+
+impl Expiry {
+    /// Converts the option into a unix time in milliseconds, or `None` if it
+    /// overflows.
+    fn deadline(self) -> Option<u64> {
+        match self {
+            Expiry::Ex(seconds) => seconds.checked_mul(1000)?.checked_add(now_ms()),
+            Expiry::Px(millis) => millis.checked_add(now_ms()),
+            Expiry::ExAt(seconds) => seconds.checked_mul(1000),
+            Expiry::PxAt(millis) => Some(millis),
+            Expiry::KeepTtl => None,
+        }
+        .filter(|when| *when <= i64::MAX as u64)
+    }
+}
+
+/// Parses the time following the `EX`, `PX`, `EXAT` or `PXAT` option of
+/// `command`.
+fn parse_expiry(parser: &mut Parser, option: &str, command: &str) -> Result<Expiry, ParserError> {
+    if parser.remaining() == 0 {
+        return Err(ReplyError::Syntax.into());
+    }
+
+    let time = parser.next_int().map_err(|_| ReplyError::NotInteger)?;
+    if time <= 0 {
+        return Err(invalid_expire_time(command).into());
+    }
+    let time = time as u64;
+
+    Ok(match option {
+        "EX" => Expiry::Ex(time),
+        "PX" => Expiry::Px(time),
+        "EXAT" => Expiry::ExAt(time),
+        _ => Expiry::PxAt(time),
+    })
+}
+
+fn invalid_expire_time(command: &str) -> ReplyError {
+    ReplyError::OutOfRange(format!("invalid expire time in '{}' command", command))
+}
+
+fn string_too_long() -> ReplyError {
+    ReplyError::OutOfRange("string exceeds maximum allowed size (proto-max-bulk-len)".into())
+}
+
+/// Returns the string stored at `key`, `None` if the key does not exist.
+pub(crate) fn get_string<'a>(
+    state: &'a mut State,
+    key: &[u8],
+) -> Result<Option<&'a Bytes>, ReplyError> {
+    match state.get(key) {
+        None => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(_) => Err(ReplyError::WrongType),
+    }
+}
+
+/// Stores the string `value` at `key`, keeping the time to live of the key.
+pub(crate) fn set_keeping_ttl(state: &mut State, key: Bytes, value: Bytes) {
+    let expires_at = state.expires_at(&key).flatten();
+    state.insert(key, Value::String(value), expires_at);
+}
+
+/// Changes the string stored at `key` in place with `f`, storing an empty
+/// one first if the key does not exist. The time to live of the key is kept.
+///
+/// The bytes are only copied if they are still shared, by a reply being
+/// written for example, so growing a string is amortized constant time.
+pub(crate) fn update_string<T>(
+    state: &mut State,
+    key: Bytes,
+    f: impl FnOnce(&mut BytesMut) -> T,
+) -> Result<T, ReplyError> {
+    match state.get_or_insert_with(key, || Value::String(Bytes::new())) {
+        Value::String(value) => {
+            let mut bytes = BytesMut::from(std::mem::take(value));
+            let result = f(&mut bytes);
+            *value = bytes.freeze();
+            Ok(result)
+        }
+        _ => Err(ReplyError::WrongType),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/string_test.rs"]
+mod string_test;
@@ -0,0 +1,283 @@
+#[cfg(test)]
+mod bitmap_test {
+    use crate::server::cmd::helper::{bulk, error, run};
+    use crate::server::db::State;
+    use crate::server::frame::Frame;
+
+    fn integers(values: &[i64]) -> Frame {
+        Frame::Array(values.iter().map(|value| Frame::Integer(*value)).collect())
+    }
+
+    #[test]
+    fn setbit_and_getbit() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["SETBIT", "key", "7", "1"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(
+            run(&mut state, &["SETBIT", "key", "7", "1"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(run(&mut state, &["GETBIT", "key", "7"]), Frame::Integer(1));
+        assert_eq!(run(&mut state, &["GETBIT", "key", "0"]), Frame::Integer(0));
+        assert_eq!(
+            run(&mut state, &["GETBIT", "key", "100"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut state, &["GET", "key"]), bulk("\u{1}"));
+
+        // Bit 0 is the most significant bit of the first byte
+        run(&mut state, &["SETBIT", "letter", "1", "1"]);
+        run(&mut state, &["SETBIT", "letter", "7", "1"]);
+        assert_eq!(run(&mut state, &["GET", "letter"]), bulk("A"));
+        assert_eq!(run(&mut state, &["STRLEN", "letter"]), Frame::Integer(1));
+    }
+
+    #[test]
+    fn setbit_errors() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["SETBIT", "key", "-1", "1"]),
+            error("bit offset is not an integer or out of range")
+        );
+        assert_eq!(
+            run(&mut state, &["SETBIT", "key", "4294967296", "1"]),
+            error("bit offset is not an integer or out of range")
+        );
+        assert_eq!(
+            run(&mut state, &["SETBIT", "key", "0", "2"]),
+            error("bit is not an integer or out of range")
+        );
+    }
+
+    #[test]
+    fn bitcount_with_ranges() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "foobar"]);
+        assert_eq!(run(&mut state, &["BITCOUNT", "key"]), Frame::Integer(26));
+        assert_eq!(
+            run(&mut state, &["BITCOUNT", "key", "0", "0"]),
+            Frame::Integer(4)
+        );
+        assert_eq!(
+            run(&mut state, &["BITCOUNT", "key", "1", "1"]),
+            Frame::Integer(6)
+        );
+        assert_eq!(
+            run(&mut state, &["BITCOUNT", "key", "1", "1", "BYTE"]),
+            Frame::Integer(6)
+        );
+        assert_eq!(
+            run(&mut state, &["BITCOUNT", "key", "5", "30", "BIT"]),
+            Frame::Integer(17)
+        );
+        assert_eq!(
+            run(&mut state, &["BITCOUNT", "key", "9", "10", "BIT"]),
+            Frame::Integer(2)
+        );
+        assert_eq!(
+            run(&mut state, &["BITCOUNT", "key", "8", "15", "BIT"]),
+            Frame::Integer(6)
+        );
+        assert_eq!(
+            run(&mut state, &["BITCOUNT", "key", "-1", "-2"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut state, &["BITCOUNT", "missing"]), Frame::Integer(0));
+        assert_eq!(
+            run(&mut state, &["BITCOUNT", "key", "0"]),
+            error("syntax error")
+        );
+    }
+
+    #[test]
+    fn bitpos_finds_the_first_bit() {
+        // 0xff 0xf0 0x00
+        let mut state = State::default();
+        for i in 0..12 {
+            run(&mut state, &["SETBIT", "key", &i.to_string(), "1"]);
+        }
+        run(&mut state, &["SETBIT", "key", "23", "0"]);
+
+        assert_eq!(run(&mut state, &["BITPOS", "key", "0"]), Frame::Integer(12));
+        assert_eq!(
+            run(&mut state, &["BITPOS", "key", "1", "1"]),
+            Frame::Integer(8)
+        );
+        assert_eq!(
+            run(&mut state, &["BITPOS", "key", "1", "2", "-1", "BYTE"]),
+            Frame::Integer(-1)
+        );
+        assert_eq!(
+            run(&mut state, &["BITPOS", "key", "1", "7", "15", "BIT"]),
+            Frame::Integer(7)
+        );
+        assert_eq!(
+            run(&mut state, &["BITPOS", "missing", "0"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(
+            run(&mut state, &["BITPOS", "missing", "1"]),
+            Frame::Integer(-1)
+        );
+        assert_eq!(
+            run(&mut state, &["BITPOS", "key", "2"]),
+            error("The bit argument must be 1 or 0.")
+        );
+    }
+
+    #[test]
+    fn bitpos_for_clear_bit_past_the_end() {
+        let mut state = State::default();
+        run(&mut state, &["SETBIT", "key", "0", "1"]);
+        for i in 1..16 {
+            run(&mut state, &["SETBIT", "key", &i.to_string(), "1"]);
+        }
+
+        // Without an end, the string is considered padded with zeros
+        assert_eq!(run(&mut state, &["BITPOS", "key", "0"]), Frame::Integer(16));
+        assert_eq!(
+            run(&mut state, &["BITPOS", "key", "0", "0", "-1"]),
+            Frame::Integer(-1)
+        );
+    }
+
+    #[test]
+    fn bitop_combines_strings() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "a", "abc"]);
+        run(&mut state, &["SET", "b", "a"]);
+
+        assert_eq!(
+            run(&mut state, &["BITOP", "AND", "dest", "a", "b"]),
+            Frame::Integer(3)
+        );
+        assert_eq!(run(&mut state, &["GET", "dest"]), bulk("a\0\0"));
+        assert_eq!(
+            run(&mut state, &["BITOP", "OR", "dest", "a", "b", "missing"]),
+            Frame::Integer(3)
+        );
+        assert_eq!(run(&mut state, &["GET", "dest"]), bulk("abc"));
+        assert_eq!(
+            run(&mut state, &["BITOP", "XOR", "dest", "a", "b"]),
+            Frame::Integer(3)
+        );
+        assert_eq!(run(&mut state, &["GET", "dest"]), bulk("\0bc"));
+
+        run(&mut state, &["BITOP", "NOT", "dest", "b"]);
+        assert_eq!(run(&mut state, &["BITCOUNT", "dest"]), Frame::Integer(5));
+
+        assert_eq!(
+            run(&mut state, &["BITOP", "AND", "dest", "missing"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "dest"]), Frame::Integer(0));
+        assert_eq!(
+            run(&mut state, &["BITOP", "NOT", "dest", "a", "b"]),
+            error("BITOP NOT must be called with a single source key.")
+        );
+    }
+
+    #[test]
+    fn bitfield_get_set_and_incrby() {
+        let mut state = State::default();
+        assert_eq!(
+            run(
+                &mut state,
+                &["BITFIELD", "key", "SET", "i8", "0", "100", "GET", "u4", "0"]
+            ),
+            integers(&[0, 6])
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["BITFIELD", "key", "INCRBY", "i8", "#0", "-120", "GET", "i8", "0"]
+            ),
+            integers(&[-20, -20])
+        );
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "key", "SET", "u16", "#1", "258"]),
+            integers(&[0])
+        );
+        assert_eq!(run(&mut state, &["STRLEN", "key"]), Frame::Integer(4));
+        assert_eq!(
+            run(&mut state, &["BITFIELD_RO", "key", "GET", "u16", "16"]),
+            integers(&[258])
+        );
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "key", "GET", "i64", "0"]),
+            integers(&[-1_441_150_772_656_996_352])
+        );
+    }
+
+    #[test]
+    fn bitfield_overflow() {
+        let mut state = State::default();
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "BITFIELD", "key", "SET", "u8", "0", "250", "INCRBY", "u8", "0", "10",
+                    "OVERFLOW", "SAT", "INCRBY", "u8", "0", "300", "OVERFLOW", "FAIL", "INCRBY",
+                    "u8", "0", "1", "GET", "u8", "0"
+                ]
+            ),
+            Frame::Array(vec![
+                Frame::Integer(0),
+                Frame::Integer(4),
+                Frame::Integer(255),
+                Frame::Null,
+                Frame::Integer(255),
+            ])
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "BITFIELD", "key", "OVERFLOW", "WRAP", "SET", "i8", "8", "200", "GET", "i8",
+                    "8"
+                ]
+            ),
+            integers(&[0, -56])
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["BITFIELD", "key", "OVERFLOW", "SAT", "INCRBY", "i8", "8", "-100"]
+            ),
+            integers(&[-128])
+        );
+    }
+
+    #[test]
+    fn bitfield_errors() {
+        let mut state = State::default();
+        let invalid_type = error(
+            "Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is.",
+        );
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "key", "GET", "u64", "0"]),
+            invalid_type
+        );
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "key", "GET", "x8", "0"]),
+            invalid_type
+        );
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "key", "OVERFLOW", "NOPE"]),
+            error("Invalid OVERFLOW type specified")
+        );
+        assert_eq!(
+            run(&mut state, &["BITFIELD_RO", "key", "SET", "u8", "0", "1"]),
+            error("BITFIELD_RO only supports the GET subcommand")
+        );
+
+        // Reading does not create the key
+        assert_eq!(
+            run(&mut state, &["BITFIELD", "key", "GET", "u8", "0"]),
+            integers(&[0])
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "key"]), Frame::Integer(0));
+    }
+}
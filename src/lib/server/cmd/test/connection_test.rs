@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod connection_test {
+    use super::super::*;
+    use crate::server::cmd::helper::{bulk, command};
+    use crate::server::cmd::Command;
+
+    fn hello(args: &[&str]) -> Result<Hello, ParserError> {
+        match Command::from_frame(command(args))? {
+            Command::Hello(hello) => Ok(hello),
+            cmd => panic!("unexpected command {:?}", cmd),
+        }
+    }
+
+    fn field(response: &Frame, name: &str) -> Frame {
+        match response {
+            Frame::Map(pairs) => pairs
+                .iter()
+                .find(|(key, _)| *key == bulk(name))
+                .map(|(_, value)| value.clone())
+                .unwrap(),
+            frame => panic!("unexpected reply {:?}", frame),
+        }
+    }
+
+    #[test]
+    fn hello_without_version_keeps_protocol() {
+        let (protocol, response) = hello(&["HELLO"]).unwrap().execute(7, Protocol::Resp3);
+        assert_eq!(protocol, Protocol::Resp3);
+        assert_eq!(field(&response, "proto"), Frame::Integer(3));
+        assert_eq!(field(&response, "id"), Frame::Integer(7));
+        assert_eq!(field(&response, "server"), bulk("redis"));
+    }
+
+    #[test]
+    fn hello_3_switches_to_resp3() {
+        let (protocol, response) = hello(&["HELLO", "3"]).unwrap().execute(1, Protocol::Resp2);
+        assert_eq!(protocol, Protocol::Resp3);
+        assert_eq!(field(&response, "proto"), Frame::Integer(3));
+    }
+
+    #[test]
+    fn hello_2_switches_back_to_resp2() {
+        let (protocol, response) = hello(&["HELLO", "2"]).unwrap().execute(1, Protocol::Resp3);
+        assert_eq!(protocol, Protocol::Resp2);
+        assert_eq!(field(&response, "proto"), Frame::Integer(2));
+    }
+
+    #[test]
+    fn hello_with_unsupported_version_returns_noproto() {
+        let (protocol, response) = hello(&["HELLO", "4"]).unwrap().execute(1, Protocol::Resp2);
+        assert_eq!(protocol, Protocol::Resp2);
+        assert_eq!(
+            response,
+            Frame::Error("NOPROTO unsupported protocol version".into())
+        );
+    }
+
+    #[test]
+    fn hello_with_invalid_version_returns_error() {
+        assert_eq!(
+            hello(&["HELLO", "three"]).unwrap_err().to_string(),
+            "Protocol version is not an integer or out of range"
+        );
+    }
+
+    #[test]
+    fn hello_accepts_auth_and_setname() {
+        let hello = hello(&["HELLO", "3", "AUTH", "default", "secret", "SETNAME", "app"]);
+        assert!(hello.is_ok());
+    }
+
+    #[test]
+    fn hello_with_unknown_option_returns_error() {
+        assert_eq!(
+            hello(&["HELLO", "3", "AUTH", "default"])
+                .unwrap_err()
+                .to_string(),
+            "Syntax error in HELLO option 'AUTH'"
+        );
+    }
+
+    #[test]
+    fn hello_reply_is_a_flat_array_in_resp2() {
+        let (_, response) = hello(&["HELLO", "2"]).unwrap().execute(1, Protocol::Resp2);
+        let bytes = response.to_bytes(Protocol::Resp2);
+        assert!(bytes.starts_with(b"*14\r\n$6\r\nserver\r\n$5\r\nredis\r\n"));
+    }
+}
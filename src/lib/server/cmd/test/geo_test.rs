@@ -0,0 +1,507 @@
+#[cfg(test)]
+mod geo_test {
+    use crate::server::cmd::helper::{bulk, bulks, error, run};
+    use crate::server::db::State;
+    use crate::server::frame::Frame;
+
+    fn sicily() -> State {
+        let mut state = State::default();
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "GEOADD",
+                    "Sicily",
+                    "13.361389",
+                    "38.115556",
+                    "Palermo",
+                    "15.087269",
+                    "37.502669",
+                    "Catania",
+                ]
+            ),
+            Frame::Integer(2)
+        );
+        state
+    }
+
+    #[test]
+    fn geoadd_stores_geohash_scores() {
+        let mut state = sicily();
+        assert_eq!(
+            run(&mut state, &["ZSCORE", "Sicily", "Palermo"]),
+            Frame::Double(3_479_099_956_230_698.0)
+        );
+
+        assert_eq!(
+            run(&mut state, &["GEOADD", "Sicily", "NX", "0", "0", "Palermo"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["GEOADD", "Sicily", "XX", "CH", "13", "38", "Palermo", "1", "1", "new"]
+            ),
+            Frame::Integer(1)
+        );
+        assert_eq!(run(&mut state, &["ZCARD", "Sicily"]), Frame::Integer(2));
+
+        assert_eq!(
+            run(
+                &mut state,
+                &["GEOADD", "missing", "XX", "13", "38", "Palermo"]
+            ),
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "missing"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn geoadd_rejects_invalid_arguments() {
+        let mut state = State::default();
+        let syntax = error("syntax error. Try GEOADD key [x1] [y1] [name1] [x2] [y2] [name2] ... ");
+        assert_eq!(run(&mut state, &["GEOADD", "key", "1", "2"]), syntax);
+        assert_eq!(
+            run(&mut state, &["GEOADD", "key", "NX", "XX", "1", "2", "a"]),
+            syntax
+        );
+        assert_eq!(
+            run(&mut state, &["GEOADD", "key", "181", "0", "a"]),
+            error("invalid longitude,latitude pair 181.000000,0.000000")
+        );
+        assert_eq!(
+            run(&mut state, &["GEOADD", "key", "one", "0", "a"]),
+            error("value is not a valid float")
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "key"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn geopos_and_geohash() {
+        let mut state = sicily();
+        assert_eq!(
+            run(&mut state, &["GEOPOS", "Sicily", "Palermo", "missing"]),
+            Frame::Array(vec![
+                bulks(&["13.36138933897018433", "38.11555639549629859"]),
+                Frame::NullArray,
+            ])
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["GEOHASH", "Sicily", "Palermo", "Catania", "missing"]
+            ),
+            Frame::Array(vec![bulk("sqc8b49rny0"), bulk("sqdtr74hyu0"), Frame::Null])
+        );
+        assert_eq!(
+            run(&mut state, &["GEOPOS", "missing", "Palermo"]),
+            Frame::Array(vec![Frame::NullArray])
+        );
+    }
+
+    #[test]
+    fn geodist_in_units() {
+        let mut state = sicily();
+        assert_eq!(
+            run(&mut state, &["GEODIST", "Sicily", "Palermo", "Catania"]),
+            bulk("166274.1516")
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["GEODIST", "Sicily", "Palermo", "Catania", "KM"]
+            ),
+            bulk("166.2742")
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["GEODIST", "Sicily", "Palermo", "Catania", "mi"]
+            ),
+            bulk("103.3182")
+        );
+        assert_eq!(
+            run(&mut state, &["GEODIST", "Sicily", "Palermo", "missing"]),
+            Frame::Null
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["GEODIST", "Sicily", "Palermo", "Catania", "yd"]
+            ),
+            error("unsupported unit provided. please use M, KM, FT, MI")
+        );
+    }
+
+    #[test]
+    fn geosearch_by_radius() {
+        let mut state = sicily();
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYRADIUS",
+                    "200",
+                    "km",
+                    "ASC"
+                ]
+            ),
+            bulks(&["Catania", "Palermo"])
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYRADIUS",
+                    "200",
+                    "km",
+                    "DESC",
+                    "WITHDIST",
+                    "WITHHASH",
+                ]
+            ),
+            Frame::Array(vec![
+                Frame::Array(vec![
+                    bulk("Palermo"),
+                    bulk("190.4424"),
+                    Frame::Integer(3_479_099_956_230_698),
+                ]),
+                Frame::Array(vec![
+                    bulk("Catania"),
+                    bulk("56.4413"),
+                    Frame::Integer(3_479_447_370_796_909),
+                ]),
+            ])
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "FROMMEMBER",
+                    "Palermo",
+                    "BYRADIUS",
+                    "100",
+                    "km"
+                ]
+            ),
+            bulks(&["Palermo"])
+        );
+    }
+
+    #[test]
+    fn geosearch_by_box_with_count() {
+        let mut state = sicily();
+        run(
+            &mut state,
+            &[
+                "GEOADD",
+                "Sicily",
+                "12.758489",
+                "38.788135",
+                "edge1",
+                "17.241510",
+                "38.788135",
+                "edge2",
+            ],
+        );
+
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYBOX",
+                    "400",
+                    "400",
+                    "km",
+                    "ASC",
+                    "WITHCOORD",
+                    "WITHDIST",
+                ]
+            ),
+            Frame::Array(vec![
+                Frame::Array(vec![
+                    bulk("Catania"),
+                    bulk("56.4413"),
+                    bulks(&["15.08726745843887329", "37.50266842333162032"]),
+                ]),
+                Frame::Array(vec![
+                    bulk("Palermo"),
+                    bulk("190.4424"),
+                    bulks(&["13.36138933897018433", "38.11555639549629859"]),
+                ]),
+                Frame::Array(vec![
+                    bulk("edge2"),
+                    bulk("279.7403"),
+                    bulks(&["17.24151045083999634", "38.78813451624225195"]),
+                ]),
+                Frame::Array(vec![
+                    bulk("edge1"),
+                    bulk("279.7405"),
+                    bulks(&["12.7584877610206604", "38.78813451624225195"]),
+                ]),
+            ])
+        );
+
+        // COUNT returns the closest members
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYBOX",
+                    "400",
+                    "400",
+                    "km",
+                    "COUNT",
+                    "2",
+                ]
+            ),
+            bulks(&["Catania", "Palermo"])
+        );
+        let Frame::Array(any) = run(
+            &mut state,
+            &[
+                "GEOSEARCH",
+                "Sicily",
+                "FROMLONLAT",
+                "15",
+                "37",
+                "BYBOX",
+                "400",
+                "400",
+                "km",
+                "COUNT",
+                "3",
+                "ANY",
+            ],
+        ) else {
+            panic!("expected an array");
+        };
+        assert_eq!(any.len(), 3);
+    }
+
+    #[test]
+    fn geosearchstore_stores_hashes_or_distances() {
+        let mut state = sicily();
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "GEOSEARCHSTORE",
+                    "dest",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYRADIUS",
+                    "200",
+                    "km",
+                ]
+            ),
+            Frame::Integer(2)
+        );
+        assert_eq!(
+            run(&mut state, &["GEOPOS", "dest", "Catania"]),
+            Frame::Array(vec![bulks(&[
+                "15.08726745843887329",
+                "37.50266842333162032"
+            ])])
+        );
+
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "GEOSEARCHSTORE",
+                    "dist",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYRADIUS",
+                    "200",
+                    "km",
+                    "STOREDIST",
+                ]
+            ),
+            Frame::Integer(2)
+        );
+        let Frame::Double(distance) = run(&mut state, &["ZSCORE", "dist", "Catania"]) else {
+            panic!("expected a double");
+        };
+        assert_eq!(format!("{:.4}", distance), "56.4413");
+
+        // An empty result deletes the destination
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "GEOSEARCHSTORE",
+                    "dest",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "0",
+                    "0",
+                    "BYRADIUS",
+                    "1",
+                    "m",
+                ]
+            ),
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "dest"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn geosearch_rejects_invalid_options() {
+        let mut state = sicily();
+        assert_eq!(
+            run(&mut state, &["GEOSEARCH", "Sicily", "BYRADIUS", "1", "km"]),
+            error("exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH")
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "FROMMEMBER",
+                    "Palermo"
+                ]
+            ),
+            error("exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH")
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["GEOSEARCH", "Sicily", "FROMLONLAT", "15", "37"]
+            ),
+            error("exactly one of BYRADIUS and BYBOX can be specified for GEOSEARCH")
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYRADIUS",
+                    "-1",
+                    "km"
+                ]
+            ),
+            error("radius cannot be negative")
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYRADIUS",
+                    "1",
+                    "km",
+                    "ANY"
+                ]
+            ),
+            error("the ANY argument requires COUNT argument")
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYRADIUS",
+                    "1",
+                    "km",
+                    "COUNT",
+                    "0",
+                ]
+            ),
+            error("COUNT must be > 0")
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "GEOSEARCHSTORE",
+                    "dest",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYRADIUS",
+                    "1",
+                    "km",
+                    "WITHDIST",
+                ]
+            ),
+            error("GEOSEARCHSTORE is not compatible with WITHDIST, WITHHASH and WITHCOORD options")
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "FROMMEMBER",
+                    "missing",
+                    "BYRADIUS",
+                    "1",
+                    "km"
+                ]
+            ),
+            error("could not decode requested zset member")
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYRADIUS",
+                    "1",
+                    "km",
+                    "STOREDIST"
+                ]
+            ),
+            error("syntax error")
+        );
+    }
+}
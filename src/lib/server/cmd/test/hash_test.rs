@@ -0,0 +1,338 @@
+#[cfg(test)]
+mod hash_test {
+    use crate::server::cmd::helper::{bulk, bulks, error, run, run_with, sorted, wrong_type};
+    use crate::server::db::State;
+    use crate::server::frame::{Frame, Protocol};
+
+    #[test]
+    fn hset_returns_number_of_added_fields() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["HSET", "user", "name", "ada", "age", "36"]),
+            Frame::Integer(2)
+        );
+        assert_eq!(
+            run(&mut state, &["HSET", "user", "age", "37", "city", "london"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(run(&mut state, &["HGET", "user", "age"]), bulk("37"));
+        assert_eq!(run(&mut state, &["HLEN", "user"]), Frame::Integer(3));
+    }
+
+    #[test]
+    fn hset_with_missing_value_returns_error() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["HSET", "user", "name", "ada", "age"]),
+            Frame::Error("ERR wrong number of arguments for 'hset' command".into())
+        );
+        assert_eq!(run(&mut state, &["HLEN", "user"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn hsetnx_only_sets_missing_fields() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["HSETNX", "user", "name", "ada"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["HSETNX", "user", "name", "grace"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut state, &["HGET", "user", "name"]), bulk("ada"));
+    }
+
+    #[test]
+    fn hget_missing_field_or_key_returns_null() {
+        let mut state = State::default();
+        assert_eq!(run(&mut state, &["HGET", "user", "name"]), Frame::Null);
+        run(&mut state, &["HSET", "user", "name", "ada"]);
+        assert_eq!(run(&mut state, &["HGET", "user", "age"]), Frame::Null);
+    }
+
+    #[test]
+    fn hmget_returns_null_for_missing_fields() {
+        let mut state = State::default();
+        run(&mut state, &["HSET", "user", "name", "ada", "age", "36"]);
+        assert_eq!(
+            run(&mut state, &["HMGET", "user", "age", "city", "name"]),
+            Frame::Array(vec![bulk("36"), Frame::Null, bulk("ada")])
+        );
+        assert_eq!(
+            run(&mut state, &["HMGET", "missing", "a", "b"]),
+            Frame::Array(vec![Frame::Null, Frame::Null])
+        );
+    }
+
+    #[test]
+    fn hdel_removes_fields_and_deletes_empty_hash() {
+        let mut state = State::default();
+        run(&mut state, &["HSET", "user", "name", "ada", "age", "36"]);
+        assert_eq!(
+            run(&mut state, &["HDEL", "user", "name", "city"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(run(&mut state, &["HDEL", "user", "age"]), Frame::Integer(1));
+        assert_eq!(run(&mut state, &["EXISTS", "user"]), Frame::Integer(0));
+        assert_eq!(run(&mut state, &["HDEL", "user", "age"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn hexists_and_hstrlen() {
+        let mut state = State::default();
+        run(&mut state, &["HSET", "user", "name", "ada"]);
+        assert_eq!(
+            run(&mut state, &["HEXISTS", "user", "name"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["HEXISTS", "user", "age"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(
+            run(&mut state, &["HSTRLEN", "user", "name"]),
+            Frame::Integer(3)
+        );
+        assert_eq!(
+            run(&mut state, &["HSTRLEN", "user", "age"]),
+            Frame::Integer(0)
+        );
+    }
+
+    #[test]
+    fn hkeys_and_hvals_return_every_field_or_value() {
+        let mut state = State::default();
+        run(&mut state, &["HSET", "user", "name", "ada", "age", "36"]);
+        assert_eq!(
+            sorted(run(&mut state, &["HKEYS", "user"])),
+            vec![bulk("age"), bulk("name")]
+        );
+        assert_eq!(
+            sorted(run(&mut state, &["HVALS", "user"])),
+            vec![bulk("36"), bulk("ada")]
+        );
+        assert_eq!(run(&mut state, &["HKEYS", "missing"]), bulks(&[]));
+    }
+
+    #[test]
+    fn hgetall_returns_a_map() {
+        let mut state = State::default();
+        run(&mut state, &["HSET", "user", "name", "ada", "age", "36"]);
+
+        let Frame::Map(mut entries) = run(&mut state, &["HGETALL", "user"]) else {
+            panic!("expected a map");
+        };
+        entries.sort_by_key(|entry| format!("{:?}", entry));
+        assert_eq!(
+            entries,
+            vec![(bulk("age"), bulk("36")), (bulk("name"), bulk("ada"))]
+        );
+        assert_eq!(run(&mut state, &["HGETALL", "missing"]), Frame::Map(vec![]));
+    }
+
+    #[test]
+    fn hincrby_creates_and_increments_fields() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["HINCRBY", "user", "visits", "5"]),
+            Frame::Integer(5)
+        );
+        assert_eq!(
+            run(&mut state, &["HINCRBY", "user", "visits", "-7"]),
+            Frame::Integer(-2)
+        );
+        assert_eq!(run(&mut state, &["HGET", "user", "visits"]), bulk("-2"));
+    }
+
+    #[test]
+    fn hincrby_errors() {
+        let mut state = State::default();
+        run(
+            &mut state,
+            &["HSET", "user", "name", "ada", "big", "9223372036854775807"],
+        );
+        assert_eq!(
+            run(&mut state, &["HINCRBY", "user", "name", "1"]),
+            Frame::Error("ERR hash value is not an integer".into())
+        );
+        assert_eq!(
+            run(&mut state, &["HINCRBY", "user", "big", "1"]),
+            Frame::Error("ERR increment or decrement would overflow".into())
+        );
+        assert_eq!(
+            run(&mut state, &["HINCRBY", "user", "big", "x"]),
+            Frame::Error("ERR value is not an integer or out of range".into())
+        );
+    }
+
+    #[test]
+    fn hincrbyfloat_returns_new_value_as_bulk() {
+        let mut state = State::default();
+        run(&mut state, &["HSET", "item", "price", "10.50"]);
+        assert_eq!(
+            run(&mut state, &["HINCRBYFLOAT", "item", "price", "0.1"]),
+            bulk("10.6")
+        );
+        assert_eq!(
+            run(&mut state, &["HINCRBYFLOAT", "item", "stock", "2e2"]),
+            bulk("200")
+        );
+        assert_eq!(run(&mut state, &["HGET", "item", "stock"]), bulk("200"));
+    }
+
+    #[test]
+    fn hincrbyfloat_errors() {
+        let mut state = State::default();
+        run(
+            &mut state,
+            &["HSET", "item", "name", "pen", "max", "1.7e308"],
+        );
+        assert_eq!(
+            run(&mut state, &["HINCRBYFLOAT", "item", "name", "1"]),
+            Frame::Error("ERR hash value is not a float".into())
+        );
+        assert_eq!(
+            run(&mut state, &["HINCRBYFLOAT", "item", "max", "1.7e308"]),
+            Frame::Error("ERR increment would produce NaN or Infinity".into())
+        );
+        assert_eq!(
+            run(&mut state, &["HINCRBYFLOAT", "item", "max", "abc"]),
+            Frame::Error("ERR value is not a valid float".into())
+        );
+        assert_eq!(
+            run(&mut state, &["HINCRBYFLOAT", "other", "max", "inf"]),
+            Frame::Error("ERR value is NaN or Infinity".into())
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "other"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn hrandfield_without_count_returns_one_field() {
+        let mut state = State::default();
+        assert_eq!(run(&mut state, &["HRANDFIELD", "user"]), Frame::Null);
+        run(&mut state, &["HSET", "user", "name", "ada"]);
+        assert_eq!(run(&mut state, &["HRANDFIELD", "user"]), bulk("name"));
+    }
+
+    #[test]
+    fn hrandfield_positive_count_returns_distinct_fields() {
+        let mut state = State::default();
+        run(&mut state, &["HSET", "user", "a", "1", "b", "2", "c", "3"]);
+        assert_eq!(
+            sorted(run(&mut state, &["HRANDFIELD", "user", "10"])),
+            vec![bulk("a"), bulk("b"), bulk("c")]
+        );
+
+        let Frame::Array(fields) = run(&mut state, &["HRANDFIELD", "user", "2"]) else {
+            panic!("expected an array");
+        };
+        assert_eq!(fields.len(), 2);
+        assert_ne!(fields[0], fields[1]);
+        assert_eq!(run(&mut state, &["HRANDFIELD", "missing", "2"]), bulks(&[]));
+    }
+
+    #[test]
+    fn hrandfield_negative_count_may_repeat_fields() {
+        let mut state = State::default();
+        run(&mut state, &["HSET", "user", "a", "1"]);
+        assert_eq!(
+            run(&mut state, &["HRANDFIELD", "user", "-3"]),
+            bulks(&["a", "a", "a"])
+        );
+        assert_eq!(
+            run(&mut state, &["HRANDFIELD", "user", "-9223372036854775807"]),
+            error("value is out of range")
+        );
+    }
+
+    #[test]
+    fn hrandfield_withvalues_depends_on_protocol() {
+        let mut state = State::default();
+        run(&mut state, &["HSET", "user", "name", "ada"]);
+        assert_eq!(
+            run(&mut state, &["HRANDFIELD", "user", "1", "WITHVALUES"]),
+            bulks(&["name", "ada"])
+        );
+        assert_eq!(
+            run_with(
+                &mut state,
+                &["HRANDFIELD", "user", "1", "WITHVALUES"],
+                Protocol::Resp3
+            ),
+            Frame::Array(vec![bulks(&["name", "ada"])])
+        );
+    }
+
+    #[test]
+    fn hrandfield_withvalues_requires_count() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["HRANDFIELD", "user", "WITHVALUES"]),
+            Frame::Error("ERR value is not an integer or out of range".into())
+        );
+        assert_eq!(
+            run(&mut state, &["HRANDFIELD", "user", "1", "VALUES"]),
+            Frame::Error("ERR syntax error".into())
+        );
+    }
+
+    #[test]
+    fn hscan_returns_matching_entries_in_one_call() {
+        let mut state = State::default();
+        run(
+            &mut state,
+            &["HSET", "session", "user:1", "a", "user:2", "b", "cart", "c"],
+        );
+
+        let Frame::Array(reply) = run(&mut state, &["HSCAN", "session", "0", "MATCH", "user:*"])
+        else {
+            panic!("expected an array");
+        };
+        assert_eq!(reply[0], bulk("0"));
+        assert_eq!(
+            sorted(reply[1].clone()),
+            vec![bulk("a"), bulk("b"), bulk("user:1"), bulk("user:2")]
+        );
+
+        assert_eq!(
+            run(
+                &mut state,
+                &["HSCAN", "session", "0", "MATCH", "c*", "NOVALUES", "COUNT", "5"]
+            ),
+            Frame::Array(vec![bulk("0"), bulks(&["cart"])])
+        );
+        assert_eq!(
+            run(&mut state, &["HSCAN", "missing", "0"]),
+            Frame::Array(vec![bulk("0"), bulks(&[])])
+        );
+    }
+
+    #[test]
+    fn hscan_rejects_invalid_arguments() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["HSCAN", "session", "x"]),
+            Frame::Error("ERR invalid cursor".into())
+        );
+        assert_eq!(
+            run(&mut state, &["HSCAN", "session", "0", "COUNT", "0"]),
+            Frame::Error("ERR syntax error".into())
+        );
+    }
+
+    #[test]
+    fn hash_commands_on_wrong_type_return_error() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(run(&mut state, &["HSET", "key", "a", "1"]), wrong_type());
+        assert_eq!(run(&mut state, &["HGET", "key", "a"]), wrong_type());
+        assert_eq!(run(&mut state, &["HGETALL", "key"]), wrong_type());
+        assert_eq!(run(&mut state, &["HINCRBY", "key", "a", "1"]), wrong_type());
+        assert_eq!(run(&mut state, &["HRANDFIELD", "key"]), wrong_type());
+
+        run(&mut state, &["HSET", "hash", "a", "1"]);
+        assert_eq!(run(&mut state, &["GET", "hash"]), wrong_type());
+        assert_eq!(run(&mut state, &["LPUSH", "hash", "a"]), wrong_type());
+    }
+}
@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod hyperloglog_test {
+    use crate::server::cmd::helper::{bulk, run, wrong_type};
+    use crate::server::db::State;
+    use crate::server::frame::Frame;
+
+    fn invalid() -> Frame {
+        Frame::Error("WRONGTYPE Key is not a valid HyperLogLog string value.".into())
+    }
+
+    #[test]
+    fn pfadd_reports_changes() {
+        let mut state = State::default();
+        assert_eq!(run(&mut state, &["PFADD", "hll"]), Frame::Integer(1));
+        assert_eq!(run(&mut state, &["PFADD", "hll"]), Frame::Integer(0));
+        assert_eq!(
+            run(&mut state, &["PFADD", "hll", "a", "b", "c"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["PFADD", "hll", "a", "b"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut state, &["PFCOUNT", "hll"]), Frame::Integer(3));
+        assert_eq!(run(&mut state, &["PFCOUNT", "missing"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn values_are_redis_strings() {
+        let mut state = State::default();
+        run(&mut state, &["PFADD", "hll", "a"]);
+        assert_eq!(
+            run(&mut state, &["GET", "hll"]),
+            Frame::Bulk(
+                b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x80\x71\xa6\x84\x4e\x57"[..]
+                    .into()
+            )
+        );
+
+        // PFCOUNT caches the cardinality in the header
+        assert_eq!(run(&mut state, &["PFCOUNT", "hll"]), Frame::Integer(1));
+        assert_eq!(
+            run(&mut state, &["GETRANGE", "hll", "8", "15"]),
+            Frame::Bulk(b"\x01\x00\x00\x00\x00\x00\x00\x00"[..].into())
+        );
+    }
+
+    #[test]
+    fn pfcount_over_several_keys_counts_the_union() {
+        let mut state = State::default();
+        run(&mut state, &["PFADD", "a", "1", "2", "3"]);
+        run(&mut state, &["PFADD", "b", "3", "4"]);
+        assert_eq!(
+            run(&mut state, &["PFCOUNT", "a", "b", "missing"]),
+            Frame::Integer(4)
+        );
+        assert_eq!(run(&mut state, &["PFCOUNT", "a"]), Frame::Integer(3));
+    }
+
+    #[test]
+    fn pfmerge_includes_the_destination() {
+        let mut state = State::default();
+        run(&mut state, &["PFADD", "a", "1", "2"]);
+        run(&mut state, &["PFADD", "b", "2", "3"]);
+        run(&mut state, &["PFADD", "dest", "4"]);
+        assert_eq!(
+            run(&mut state, &["PFMERGE", "dest", "a", "b", "missing"]),
+            Frame::Simple("OK".into())
+        );
+        assert_eq!(run(&mut state, &["PFCOUNT", "dest"]), Frame::Integer(4));
+
+        // The result is dense
+        assert_eq!(
+            run(&mut state, &["GETRANGE", "dest", "0", "4"]),
+            bulk("HYLL\u{0}")
+        );
+        assert_eq!(run(&mut state, &["STRLEN", "dest"]), Frame::Integer(12304));
+
+        assert_eq!(
+            run(&mut state, &["PFMERGE", "empty"]),
+            Frame::Simple("OK".into())
+        );
+        assert_eq!(run(&mut state, &["PFCOUNT", "empty"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn other_values_are_rejected() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "string", "value"]);
+        run(&mut state, &["RPUSH", "list", "a"]);
+        run(&mut state, &["PFADD", "hll", "a"]);
+
+        assert_eq!(run(&mut state, &["PFADD", "string", "a"]), invalid());
+        assert_eq!(run(&mut state, &["PFCOUNT", "hll", "string"]), invalid());
+        assert_eq!(run(&mut state, &["PFMERGE", "hll", "string"]), invalid());
+        assert_eq!(run(&mut state, &["PFCOUNT", "list"]), wrong_type());
+        assert_eq!(run(&mut state, &["GET", "string"]), bulk("value"));
+    }
+}
@@ -0,0 +1,265 @@
+#[cfg(test)]
+mod keys_test {
+    use crate::server::cmd::helper::run;
+    use crate::server::db::{now_ms, State};
+    use crate::server::frame::Frame;
+
+    #[test]
+    fn del_returns_number_of_removed_keys() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "a", "1"]);
+        run(&mut state, &["SET", "b", "2"]);
+        assert_eq!(run(&mut state, &["DEL", "a", "b", "c"]), Frame::Integer(2));
+        assert_eq!(run(&mut state, &["GET", "a"]), Frame::Null);
+    }
+
+    #[test]
+    fn del_same_key_twice_counts_once() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "a", "1"]);
+        assert_eq!(run(&mut state, &["DEL", "a", "a"]), Frame::Integer(1));
+    }
+
+    #[test]
+    fn del_without_keys_returns_error() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["DEL"]),
+            Frame::Error("ERR wrong number of arguments for 'del' command".into())
+        );
+    }
+
+    #[test]
+    fn exists_counts_existing_keys() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "a", "1"]);
+        assert_eq!(run(&mut state, &["EXISTS", "a"]), Frame::Integer(1));
+        assert_eq!(run(&mut state, &["EXISTS", "b"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn exists_counts_repeated_keys() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "a", "1"]);
+        assert_eq!(
+            run(&mut state, &["EXISTS", "a", "a", "b"]),
+            Frame::Integer(2)
+        );
+    }
+
+    #[test]
+    fn ttl_on_missing_key_returns_minus_two() {
+        let mut state = State::default();
+        assert_eq!(run(&mut state, &["TTL", "key"]), Frame::Integer(-2));
+        assert_eq!(run(&mut state, &["PTTL", "key"]), Frame::Integer(-2));
+    }
+
+    #[test]
+    fn ttl_on_key_without_expiry_returns_minus_one() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(run(&mut state, &["TTL", "key"]), Frame::Integer(-1));
+    }
+
+    #[test]
+    fn expire_sets_time_to_live() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(
+            run(&mut state, &["EXPIRE", "key", "100"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(run(&mut state, &["TTL", "key"]), Frame::Integer(100));
+
+        match run(&mut state, &["PTTL", "key"]) {
+            Frame::Integer(ttl) => assert!(ttl > 99_000 && ttl <= 100_000),
+            frame => panic!("unexpected reply {:?}", frame),
+        }
+    }
+
+    #[test]
+    fn expire_on_missing_key_returns_zero() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["EXPIRE", "key", "100"]),
+            Frame::Integer(0)
+        );
+    }
+
+    #[test]
+    fn pexpire_expires_key() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(run(&mut state, &["PEXPIRE", "key", "1"]), Frame::Integer(1));
+
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        assert_eq!(run(&mut state, &["GET", "key"]), Frame::Null);
+        assert_eq!(run(&mut state, &["EXISTS", "key"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn expireat_in_the_past_deletes_key() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(
+            run(&mut state, &["EXPIREAT", "key", "1"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "key"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn pexpireat_sets_absolute_deadline() {
+        let mut state = State::default();
+        let when = (now_ms() + 50_000).to_string();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(
+            run(&mut state, &["PEXPIREAT", "key", &when]),
+            Frame::Integer(1)
+        );
+        assert_eq!(run(&mut state, &["TTL", "key"]), Frame::Integer(50));
+    }
+
+    #[test]
+    fn expire_nx_only_applies_without_ttl() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(
+            run(&mut state, &["EXPIRE", "key", "100", "NX"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["EXPIRE", "key", "200", "NX"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut state, &["TTL", "key"]), Frame::Integer(100));
+    }
+
+    #[test]
+    fn expire_xx_only_applies_with_ttl() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(
+            run(&mut state, &["EXPIRE", "key", "100", "XX"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut state, &["TTL", "key"]), Frame::Integer(-1));
+    }
+
+    #[test]
+    fn expire_gt_and_lt_compare_deadlines() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(
+            run(&mut state, &["EXPIRE", "key", "100", "GT"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(
+            run(&mut state, &["EXPIRE", "key", "100", "LT"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["EXPIRE", "key", "50", "GT"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(
+            run(&mut state, &["EXPIRE", "key", "200", "GT"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(run(&mut state, &["TTL", "key"]), Frame::Integer(200));
+    }
+
+    #[test]
+    fn expire_with_incompatible_options_returns_error() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["EXPIRE", "key", "100", "NX", "XX"]),
+            Frame::Error(
+                "ERR NX and XX, GT or LT options at the same time are not compatible".into()
+            )
+        );
+        assert_eq!(
+            run(&mut state, &["EXPIRE", "key", "100", "GT", "LT"]),
+            Frame::Error("ERR GT and LT options at the same time are not compatible".into())
+        );
+    }
+
+    #[test]
+    fn expire_with_invalid_time_returns_error() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["EXPIRE", "key", "soon"]),
+            Frame::Error("ERR value is not an integer or out of range".into())
+        );
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(
+            run(&mut state, &["EXPIRE", "key", "18446744073709551"]),
+            Frame::Error("ERR invalid expire time in 'expire' command".into())
+        );
+    }
+
+    #[test]
+    fn persist_removes_time_to_live() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value", "EX", "100"]);
+        assert_eq!(run(&mut state, &["PERSIST", "key"]), Frame::Integer(1));
+        assert_eq!(run(&mut state, &["TTL", "key"]), Frame::Integer(-1));
+        assert_eq!(run(&mut state, &["PERSIST", "key"]), Frame::Integer(0));
+        assert_eq!(run(&mut state, &["PERSIST", "missing"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn expire_with_negative_time_deletes_key() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(run(&mut state, &["EXPIRE", "key", "-1"]), Frame::Integer(1));
+        assert_eq!(run(&mut state, &["EXISTS", "key"]), Frame::Integer(0));
+        assert_eq!(run(&mut state, &["TTL", "key"]), Frame::Integer(-2));
+    }
+
+    #[test]
+    fn pexpireat_with_negative_timestamp_deletes_key() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(
+            run(&mut state, &["PEXPIREAT", "key", "-1000"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(run(&mut state, &["GET", "key"]), Frame::Null);
+    }
+
+    #[test]
+    fn expire_with_overflowing_negative_time_returns_error() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(
+            run(&mut state, &["EXPIRE", "key", "-9223372036854775808"]),
+            Frame::Error("ERR invalid expire time in 'expire' command".into())
+        );
+    }
+
+    #[test]
+    fn flush_removes_every_key() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "a", "1"]);
+        run(&mut state, &["RPUSH", "b", "x"]);
+        assert_eq!(run(&mut state, &["FLUSHDB"]), Frame::Simple("OK".into()));
+        assert_eq!(run(&mut state, &["EXISTS", "a", "b"]), Frame::Integer(0));
+
+        run(&mut state, &["SET", "a", "1"]);
+        assert_eq!(
+            run(&mut state, &["FLUSHALL", "ASYNC"]),
+            Frame::Simple("OK".into())
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "a"]), Frame::Integer(0));
+        assert_eq!(
+            run(&mut state, &["FLUSHDB", "LATER"]),
+            Frame::Error("ERR syntax error".into())
+        );
+        assert_eq!(
+            run(&mut state, &["FLUSHDB", "SYNC", "ASYNC"]),
+            Frame::Error("ERR wrong number of arguments for 'flushdb' command".into())
+        );
+    }
+}
@@ -0,0 +1,418 @@
+#[cfg(test)]
+mod list_test {
+    use crate::server::cmd::helper::{bulk, bulks, run, wrong_type};
+    use crate::server::db::State;
+    use crate::server::frame::Frame;
+
+    #[test]
+    fn lpush_inserts_values_at_the_head_one_after_the_other() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["LPUSH", "list", "a", "b", "c"]),
+            Frame::Integer(3)
+        );
+        assert_eq!(
+            run(&mut state, &["LRANGE", "list", "0", "-1"]),
+            bulks(&["c", "b", "a"])
+        );
+    }
+
+    #[test]
+    fn rpush_appends_values_at_the_tail() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "list", "a", "b"]);
+        assert_eq!(run(&mut state, &["RPUSH", "list", "c"]), Frame::Integer(3));
+        assert_eq!(
+            run(&mut state, &["LRANGE", "list", "0", "-1"]),
+            bulks(&["a", "b", "c"])
+        );
+
+        run(&mut state, &["RPUSH", "list", "x", "x", "d", "x"]);
+        assert_eq!(
+            run(&mut state, &["LREM", "list", "-2", "x"]),
+            Frame::Integer(2)
+        );
+        assert_eq!(
+            run(&mut state, &["LRANGE", "list", "0", "-1"]),
+            bulks(&["a", "b", "c", "x", "d"])
+        );
+    }
+
+    #[test]
+    fn push_without_value_returns_error() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["LPUSH", "list"]),
+            Frame::Error("ERR wrong number of arguments for 'lpush' command".into())
+        );
+    }
+
+    #[test]
+    fn pop_removes_elements_from_both_ends() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "list", "a", "b", "c"]);
+        assert_eq!(run(&mut state, &["LPOP", "list"]), bulk("a"));
+        assert_eq!(run(&mut state, &["RPOP", "list"]), bulk("c"));
+        assert_eq!(run(&mut state, &["LLEN", "list"]), Frame::Integer(1));
+    }
+
+    #[test]
+    fn pop_missing_key_returns_null() {
+        let mut state = State::default();
+        assert_eq!(run(&mut state, &["LPOP", "list"]), Frame::Null);
+        assert_eq!(run(&mut state, &["LPOP", "list", "2"]), Frame::NullArray);
+    }
+
+    #[test]
+    fn pop_with_count_returns_array() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "list", "a", "b", "c"]);
+        assert_eq!(run(&mut state, &["RPOP", "list", "2"]), bulks(&["c", "b"]));
+        assert_eq!(run(&mut state, &["LPOP", "list", "0"]), bulks(&[]));
+        assert_eq!(run(&mut state, &["LPOP", "list", "5"]), bulks(&["a"]));
+    }
+
+    #[test]
+    fn pop_with_negative_count_returns_error() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["LPOP", "list", "-1"]),
+            Frame::Error("ERR value is out of range, must be positive".into())
+        );
+    }
+
+    #[test]
+    fn popping_last_element_deletes_key() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "list", "a"]);
+        run(&mut state, &["LPOP", "list"]);
+        assert_eq!(run(&mut state, &["EXISTS", "list"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn lrange_handles_negative_and_out_of_range_indexes() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "list", "a", "b", "c", "d"]);
+        assert_eq!(
+            run(&mut state, &["LRANGE", "list", "-3", "-2"]),
+            bulks(&["b", "c"])
+        );
+        assert_eq!(
+            run(&mut state, &["LRANGE", "list", "-100", "100"]),
+            bulks(&["a", "b", "c", "d"])
+        );
+        assert_eq!(run(&mut state, &["LRANGE", "list", "3", "1"]), bulks(&[]));
+        assert_eq!(run(&mut state, &["LRANGE", "list", "5", "10"]), bulks(&[]));
+        assert_eq!(
+            run(&mut state, &["LRANGE", "missing", "0", "-1"]),
+            bulks(&[])
+        );
+    }
+
+    #[test]
+    fn lrange_with_invalid_index_returns_error() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["LRANGE", "list", "a", "1"]),
+            Frame::Error("ERR value is not an integer or out of range".into())
+        );
+    }
+
+    #[test]
+    fn llen_of_missing_key_is_zero() {
+        let mut state = State::default();
+        assert_eq!(run(&mut state, &["LLEN", "list"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn lindex_returns_element_or_null() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "list", "a", "b", "c"]);
+        assert_eq!(run(&mut state, &["LINDEX", "list", "1"]), bulk("b"));
+        assert_eq!(run(&mut state, &["LINDEX", "list", "-1"]), bulk("c"));
+        assert_eq!(run(&mut state, &["LINDEX", "list", "3"]), Frame::Null);
+        assert_eq!(run(&mut state, &["LINDEX", "missing", "0"]), Frame::Null);
+    }
+
+    #[test]
+    fn lset_replaces_element() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "list", "a", "b", "c"]);
+        assert_eq!(
+            run(&mut state, &["LSET", "list", "-2", "x"]),
+            Frame::Simple("OK".into())
+        );
+        assert_eq!(
+            run(&mut state, &["LRANGE", "list", "0", "-1"]),
+            bulks(&["a", "x", "c"])
+        );
+    }
+
+    #[test]
+    fn lset_out_of_range_or_missing_key_returns_error() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["LSET", "list", "0", "x"]),
+            Frame::Error("ERR no such key".into())
+        );
+
+        run(&mut state, &["RPUSH", "list", "a"]);
+        assert_eq!(
+            run(&mut state, &["LSET", "list", "1", "x"]),
+            Frame::Error("ERR index out of range".into())
+        );
+    }
+
+    #[test]
+    fn ltrim_keeps_range() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "list", "a", "b", "c", "d"]);
+        assert_eq!(
+            run(&mut state, &["LTRIM", "list", "1", "-2"]),
+            Frame::Simple("OK".into())
+        );
+        assert_eq!(
+            run(&mut state, &["LRANGE", "list", "0", "-1"]),
+            bulks(&["b", "c"])
+        );
+    }
+
+    #[test]
+    fn ltrim_with_empty_range_deletes_key() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "list", "a", "b"]);
+        run(&mut state, &["LTRIM", "list", "5", "10"]);
+        assert_eq!(run(&mut state, &["EXISTS", "list"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn lrem_removes_from_head_tail_or_everywhere() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "list", "a", "x", "b", "x", "c", "x"]);
+        assert_eq!(
+            run(&mut state, &["LREM", "list", "1", "x"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["LRANGE", "list", "0", "-1"]),
+            bulks(&["a", "b", "x", "c", "x"])
+        );
+
+        assert_eq!(
+            run(&mut state, &["LREM", "list", "-1", "x"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["LRANGE", "list", "0", "-1"]),
+            bulks(&["a", "b", "x", "c"])
+        );
+
+        run(&mut state, &["RPUSH", "list", "x"]);
+        assert_eq!(
+            run(&mut state, &["LREM", "list", "0", "x"]),
+            Frame::Integer(2)
+        );
+        assert_eq!(
+            run(&mut state, &["LRANGE", "list", "0", "-1"]),
+            bulks(&["a", "b", "c"])
+        );
+    }
+
+    #[test]
+    fn linsert_before_and_after_pivot() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "list", "a", "c"]);
+        assert_eq!(
+            run(&mut state, &["LINSERT", "list", "BEFORE", "c", "b"]),
+            Frame::Integer(3)
+        );
+        assert_eq!(
+            run(&mut state, &["LINSERT", "list", "after", "c", "d"]),
+            Frame::Integer(4)
+        );
+        assert_eq!(
+            run(&mut state, &["LRANGE", "list", "0", "-1"]),
+            bulks(&["a", "b", "c", "d"])
+        );
+    }
+
+    #[test]
+    fn linsert_without_pivot_or_key() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["LINSERT", "list", "BEFORE", "a", "b"]),
+            Frame::Integer(0)
+        );
+
+        run(&mut state, &["RPUSH", "list", "a"]);
+        assert_eq!(
+            run(&mut state, &["LINSERT", "list", "BEFORE", "z", "b"]),
+            Frame::Integer(-1)
+        );
+        assert_eq!(
+            run(&mut state, &["LINSERT", "list", "AROUND", "a", "b"]),
+            Frame::Error("ERR syntax error".into())
+        );
+    }
+
+    #[test]
+    fn list_commands_on_string_return_wrong_type() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(run(&mut state, &["LPUSH", "key", "a"]), wrong_type());
+        assert_eq!(run(&mut state, &["LPOP", "key"]), wrong_type());
+        assert_eq!(run(&mut state, &["LRANGE", "key", "0", "-1"]), wrong_type());
+        assert_eq!(run(&mut state, &["LLEN", "key"]), wrong_type());
+        assert_eq!(run(&mut state, &["GET", "key"]), bulk("value"));
+    }
+
+    #[test]
+    fn string_commands_on_list_return_wrong_type() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "list", "a"]);
+        assert_eq!(run(&mut state, &["GET", "list"]), wrong_type());
+        assert_eq!(run(&mut state, &["SET", "list", "v", "GET"]), wrong_type());
+
+        // A plain SET overwrites values of any type
+        assert_eq!(
+            run(&mut state, &["SET", "list", "v"]),
+            Frame::Simple("OK".into())
+        );
+        assert_eq!(run(&mut state, &["GET", "list"]), bulk("v"));
+    }
+
+    #[test]
+    fn push_keeps_time_to_live() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "list", "a"]);
+        run(&mut state, &["EXPIRE", "list", "100"]);
+        run(&mut state, &["RPUSH", "list", "b"]);
+        assert_eq!(run(&mut state, &["TTL", "list"]), Frame::Integer(100));
+    }
+
+    #[test]
+    fn blpop_pops_from_first_non_empty_list() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "second", "a", "b"]);
+        assert_eq!(
+            run(&mut state, &["BLPOP", "first", "second", "0"]),
+            bulks(&["second", "a"])
+        );
+        assert_eq!(
+            run(&mut state, &["BRPOP", "first", "second", "0"]),
+            bulks(&["second", "b"])
+        );
+    }
+
+    #[test]
+    fn blpop_does_not_block_outside_of_a_connection() {
+        let mut state = State::default();
+        assert_eq!(run(&mut state, &["BLPOP", "list", "0"]), Frame::Null);
+    }
+
+    #[test]
+    fn blpop_with_invalid_timeout_returns_error() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["BLPOP", "list", "soon"]),
+            Frame::Error("ERR timeout is not a float or out of range".into())
+        );
+        assert_eq!(
+            run(&mut state, &["BLPOP", "list", "-1"]),
+            Frame::Error("ERR timeout is negative".into())
+        );
+        assert_eq!(
+            run(&mut state, &["BLPOP", "list"]),
+            Frame::Error("ERR wrong number of arguments for 'blpop' command".into())
+        );
+    }
+
+    #[test]
+    fn blpop_on_string_returns_wrong_type() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(run(&mut state, &["BLPOP", "key", "0"]), wrong_type());
+    }
+
+    #[test]
+    fn lmove_moves_element_between_lists() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "source", "a", "b", "c"]);
+        assert_eq!(
+            run(
+                &mut state,
+                &["LMOVE", "source", "destination", "RIGHT", "LEFT"]
+            ),
+            bulk("c")
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["LMOVE", "source", "destination", "left", "left"]
+            ),
+            bulk("a")
+        );
+        assert_eq!(
+            run(&mut state, &["LRANGE", "source", "0", "-1"]),
+            bulks(&["b"])
+        );
+        assert_eq!(
+            run(&mut state, &["LRANGE", "destination", "0", "-1"]),
+            bulks(&["a", "c"])
+        );
+    }
+
+    #[test]
+    fn lmove_rotates_a_list_onto_itself() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "list", "a", "b", "c"]);
+        assert_eq!(
+            run(&mut state, &["LMOVE", "list", "list", "LEFT", "RIGHT"]),
+            bulk("a")
+        );
+        assert_eq!(
+            run(&mut state, &["LRANGE", "list", "0", "-1"]),
+            bulks(&["b", "c", "a"])
+        );
+    }
+
+    #[test]
+    fn lmove_from_missing_list_returns_null() {
+        let mut state = State::default();
+        assert_eq!(
+            run(
+                &mut state,
+                &["LMOVE", "source", "destination", "LEFT", "LEFT"]
+            ),
+            Frame::Null
+        );
+        assert_eq!(
+            run(&mut state, &["EXISTS", "destination"]),
+            Frame::Integer(0)
+        );
+    }
+
+    #[test]
+    fn lmove_to_string_keeps_source_element() {
+        let mut state = State::default();
+        run(&mut state, &["RPUSH", "source", "a"]);
+        run(&mut state, &["SET", "destination", "value"]);
+        assert_eq!(
+            run(
+                &mut state,
+                &["LMOVE", "source", "destination", "LEFT", "LEFT"]
+            ),
+            wrong_type()
+        );
+        assert_eq!(run(&mut state, &["LLEN", "source"]), Frame::Integer(1));
+    }
+
+    #[test]
+    fn blmove_with_invalid_direction_returns_error() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["BLMOVE", "a", "b", "UP", "LEFT", "0"]),
+            Frame::Error("ERR syntax error".into())
+        );
+    }
+}
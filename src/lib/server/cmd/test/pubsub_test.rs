@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod pubsub_test {
+    use crate::server::cmd::helper::{command, error, run};
+    use crate::server::cmd::Command;
+    use crate::server::db::State;
+    use crate::server::frame::Frame;
+    use crate::server::pubsub::Broker;
+
+    #[test]
+    fn arguments_are_checked() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["SUBSCRIBE"]),
+            error("wrong number of arguments for 'subscribe' command")
+        );
+        assert_eq!(
+            run(&mut state, &["PUBLISH", "news"]),
+            error("wrong number of arguments for 'publish' command")
+        );
+        assert_eq!(
+            run(&mut state, &["PUBSUB", "CHANNELS", "a", "b"]),
+            error(
+                "unknown subcommand or wrong number of arguments for 'CHANNELS'. Try PUBSUB HELP."
+            )
+        );
+        assert_eq!(
+            run(&mut state, &["PUBSUB", "NUMPAT", "a"]),
+            error("unknown subcommand or wrong number of arguments for 'NUMPAT'. Try PUBSUB HELP.")
+        );
+        assert_eq!(
+            run(&mut state, &["PUBSUB", "SHARDCHANNELS", "a", "b"]),
+            error("unknown subcommand or wrong number of arguments for 'SHARDCHANNELS'. Try PUBSUB HELP.")
+        );
+        assert_eq!(
+            run(&mut state, &["SPUBLISH", "news"]),
+            error("wrong number of arguments for 'spublish' command")
+        );
+        assert_eq!(
+            run(&mut state, &["PUBSUB", "nope"]),
+            error("unknown subcommand 'nope'. Try PUBSUB HELP.")
+        );
+    }
+
+    #[test]
+    fn quit_replies_ok() {
+        let mut state = State::default();
+        assert_eq!(run(&mut state, &["QUIT"]), Frame::Simple("OK".into()));
+    }
+
+    #[test]
+    fn spublish_is_refused_for_slots_not_served() {
+        // "foo" is in slot 12182, "bar" in slot 5061
+        let broker = Broker::serving(0..8192);
+        let spublish =
+            |channel: &str| match Command::from_frame(command(&["SPUBLISH", channel, "hello"])) {
+                Ok(Command::SPublish(cmd)) => cmd.execute(&broker),
+                other => panic!("unexpected {:?}", other),
+            };
+
+        assert_eq!(
+            spublish("foo"),
+            Frame::Error("CLUSTERDOWN Hash slot not served".into())
+        );
+        assert_eq!(spublish("bar"), Frame::Integer(0));
+    }
+}
@@ -0,0 +1,311 @@
+#[cfg(test)]
+mod set_test {
+    use crate::server::cmd::helper::{bulk, error, run, sorted, wrong_type};
+    use crate::server::db::State;
+    use crate::server::frame::Frame;
+
+    #[test]
+    fn sadd_returns_number_of_new_members() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["SADD", "tags", "a", "b", "a"]),
+            Frame::Integer(2)
+        );
+        assert_eq!(
+            run(&mut state, &["SADD", "tags", "b", "c"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(run(&mut state, &["SCARD", "tags"]), Frame::Integer(3));
+        assert_eq!(
+            sorted(run(&mut state, &["SMEMBERS", "tags"])),
+            vec![bulk("a"), bulk("b"), bulk("c")]
+        );
+    }
+
+    #[test]
+    fn smembers_replies_with_a_set() {
+        let mut state = State::default();
+        run(&mut state, &["SADD", "tags", "a"]);
+        assert_eq!(
+            run(&mut state, &["SMEMBERS", "tags"]),
+            Frame::Set(vec![bulk("a")])
+        );
+        assert_eq!(
+            run(&mut state, &["SMEMBERS", "missing"]),
+            Frame::Set(vec![])
+        );
+    }
+
+    #[test]
+    fn srem_removes_members_and_deletes_empty_set() {
+        let mut state = State::default();
+        run(&mut state, &["SADD", "tags", "a", "b"]);
+        assert_eq!(
+            run(&mut state, &["SREM", "tags", "a", "x"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(run(&mut state, &["SREM", "tags", "b"]), Frame::Integer(1));
+        assert_eq!(run(&mut state, &["EXISTS", "tags"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn membership_commands() {
+        let mut state = State::default();
+        run(&mut state, &["SADD", "tags", "a", "b"]);
+        assert_eq!(
+            run(&mut state, &["SISMEMBER", "tags", "a"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["SISMEMBER", "tags", "x"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(
+            run(&mut state, &["SMISMEMBER", "tags", "b", "x", "a"]),
+            Frame::Array(vec![
+                Frame::Integer(1),
+                Frame::Integer(0),
+                Frame::Integer(1)
+            ])
+        );
+        assert_eq!(
+            run(&mut state, &["SMISMEMBER", "missing", "a"]),
+            Frame::Array(vec![Frame::Integer(0)])
+        );
+    }
+
+    #[test]
+    fn smove_moves_a_member() {
+        let mut state = State::default();
+        run(&mut state, &["SADD", "from", "a"]);
+        assert_eq!(
+            run(&mut state, &["SMOVE", "from", "to", "a"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["SMOVE", "from", "to", "a"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "from"]), Frame::Integer(0));
+        assert_eq!(
+            run(&mut state, &["SISMEMBER", "to", "a"]),
+            Frame::Integer(1)
+        );
+
+        run(&mut state, &["SET", "string", "x"]);
+        assert_eq!(
+            run(&mut state, &["SMOVE", "to", "string", "a"]),
+            wrong_type()
+        );
+        assert_eq!(
+            run(&mut state, &["SISMEMBER", "to", "a"]),
+            Frame::Integer(1)
+        );
+    }
+
+    #[test]
+    fn smove_within_the_same_set_changes_nothing() {
+        let mut state = State::default();
+        run(&mut state, &["SADD", "set", "a"]);
+        run(&mut state, &["EXPIRE", "set", "100"]);
+        assert_eq!(
+            run(&mut state, &["SMOVE", "set", "set", "a"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["SMOVE", "set", "set", "b"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(
+            run(&mut state, &["SMEMBERS", "set"]),
+            Frame::Set(vec![bulk("a")])
+        );
+        assert_eq!(run(&mut state, &["TTL", "set"]), Frame::Integer(100));
+    }
+
+    #[test]
+    fn set_algebra() {
+        let mut state = State::default();
+        run(&mut state, &["SADD", "a", "1", "2", "3"]);
+        run(&mut state, &["SADD", "b", "2", "3", "4"]);
+        run(&mut state, &["SADD", "c", "3", "5"]);
+
+        assert_eq!(
+            sorted(run(&mut state, &["SINTER", "a", "b", "c"])),
+            vec![bulk("3")]
+        );
+        assert_eq!(
+            sorted(run(&mut state, &["SUNION", "a", "b", "c"])),
+            vec![bulk("1"), bulk("2"), bulk("3"), bulk("4"), bulk("5")]
+        );
+        assert_eq!(
+            sorted(run(&mut state, &["SDIFF", "a", "b"])),
+            vec![bulk("1")]
+        );
+    }
+
+    #[test]
+    fn missing_keys_are_empty_sets() {
+        let mut state = State::default();
+        run(&mut state, &["SADD", "a", "1", "2"]);
+        assert_eq!(
+            run(&mut state, &["SINTER", "a", "missing"]),
+            Frame::Set(vec![])
+        );
+        assert_eq!(
+            sorted(run(&mut state, &["SDIFF", "a", "missing"])),
+            vec![bulk("1"), bulk("2")]
+        );
+        assert_eq!(
+            run(&mut state, &["SDIFF", "missing", "a"]),
+            Frame::Set(vec![])
+        );
+    }
+
+    #[test]
+    fn store_variants_replace_destination() {
+        let mut state = State::default();
+        run(&mut state, &["SADD", "a", "1", "2"]);
+        run(&mut state, &["SADD", "b", "2", "3"]);
+        run(&mut state, &["SET", "dest", "string"]);
+
+        assert_eq!(
+            run(&mut state, &["SUNIONSTORE", "dest", "a", "b"]),
+            Frame::Integer(3)
+        );
+        assert_eq!(
+            sorted(run(&mut state, &["SMEMBERS", "dest"])),
+            vec![bulk("1"), bulk("2"), bulk("3")]
+        );
+        assert_eq!(
+            run(&mut state, &["SINTERSTORE", "dest", "a", "b"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["SDIFFSTORE", "dest", "a", "a"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "dest"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn sintercard_counts_up_to_limit() {
+        let mut state = State::default();
+        run(&mut state, &["SADD", "a", "1", "2", "3"]);
+        run(&mut state, &["SADD", "b", "1", "2", "3", "4"]);
+        assert_eq!(
+            run(&mut state, &["SINTERCARD", "2", "a", "b"]),
+            Frame::Integer(3)
+        );
+        assert_eq!(
+            run(&mut state, &["SINTERCARD", "2", "a", "b", "LIMIT", "2"]),
+            Frame::Integer(2)
+        );
+        assert_eq!(
+            run(&mut state, &["SINTERCARD", "2", "a", "b", "LIMIT", "0"]),
+            Frame::Integer(3)
+        );
+        assert_eq!(
+            run(&mut state, &["SINTERCARD", "2", "a", "missing"]),
+            Frame::Integer(0)
+        );
+    }
+
+    #[test]
+    fn sintercard_errors() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["SINTERCARD", "0", "a"]),
+            Frame::Error("ERR numkeys should be greater than 0".into())
+        );
+        assert_eq!(
+            run(&mut state, &["SINTERCARD", "3", "a", "b"]),
+            Frame::Error("ERR Number of keys can't be greater than number of args".into())
+        );
+        assert_eq!(
+            run(&mut state, &["SINTERCARD", "1", "a", "LIMIT", "-1"]),
+            Frame::Error("ERR LIMIT can't be negative".into())
+        );
+        assert_eq!(
+            run(&mut state, &["SINTERCARD", "1", "a", "b"]),
+            Frame::Error("ERR syntax error".into())
+        );
+    }
+
+    #[test]
+    fn spop_removes_random_members() {
+        let mut state = State::default();
+        run(&mut state, &["SADD", "tags", "a", "b", "c"]);
+
+        let Frame::Bulk(popped) = run(&mut state, &["SPOP", "tags"]) else {
+            panic!("expected a bulk");
+        };
+        assert_eq!(
+            run(
+                &mut state,
+                &["SISMEMBER", "tags", std::str::from_utf8(&popped).unwrap()]
+            ),
+            Frame::Integer(0)
+        );
+
+        assert_eq!(sorted(run(&mut state, &["SPOP", "tags", "5"])).len(), 2);
+        assert_eq!(run(&mut state, &["EXISTS", "tags"]), Frame::Integer(0));
+        assert_eq!(run(&mut state, &["SPOP", "tags"]), Frame::Null);
+        assert_eq!(run(&mut state, &["SPOP", "tags", "1"]), Frame::Set(vec![]));
+    }
+
+    #[test]
+    fn spop_with_negative_count_returns_error() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["SPOP", "tags", "-1"]),
+            Frame::Error("ERR value is out of range, must be positive".into())
+        );
+    }
+
+    #[test]
+    fn srandmember_count_semantics() {
+        let mut state = State::default();
+        assert_eq!(run(&mut state, &["SRANDMEMBER", "tags"]), Frame::Null);
+        assert_eq!(
+            run(&mut state, &["SRANDMEMBER", "tags", "3"]),
+            Frame::Array(vec![])
+        );
+
+        run(&mut state, &["SADD", "tags", "a", "b"]);
+        assert_eq!(
+            sorted(run(&mut state, &["SRANDMEMBER", "tags", "5"])),
+            vec![bulk("a"), bulk("b")]
+        );
+        assert_eq!(
+            run(&mut state, &["SRANDMEMBER", "tags", "0"]),
+            Frame::Array(vec![])
+        );
+
+        let Frame::Array(picked) = run(&mut state, &["SRANDMEMBER", "tags", "-5"]) else {
+            panic!("expected an array");
+        };
+        assert_eq!(picked.len(), 5);
+        assert_eq!(run(&mut state, &["SCARD", "tags"]), Frame::Integer(2));
+        assert_eq!(
+            run(&mut state, &["SRANDMEMBER", "tags", "-9223372036854775807"]),
+            error("value is out of range")
+        );
+    }
+
+    #[test]
+    fn set_commands_on_wrong_type_return_error() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        run(&mut state, &["SADD", "set", "a"]);
+        assert_eq!(run(&mut state, &["SADD", "key", "a"]), wrong_type());
+        assert_eq!(run(&mut state, &["SMEMBERS", "key"]), wrong_type());
+        assert_eq!(run(&mut state, &["SINTER", "set", "key"]), wrong_type());
+        assert_eq!(
+            run(&mut state, &["SUNIONSTORE", "dest", "set", "key"]),
+            wrong_type()
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "dest"]), Frame::Integer(0));
+        assert_eq!(run(&mut state, &["GET", "set"]), wrong_type());
+    }
+}
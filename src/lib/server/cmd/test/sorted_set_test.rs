@@ -0,0 +1,505 @@
+#[cfg(test)]
+mod sorted_set_test {
+    use crate::server::cmd::helper::{bulk, bulks, error, run, run_with, wrong_type};
+    use crate::server::db::State;
+    use crate::server::frame::{Frame, Protocol};
+
+    /// Builds a leaderboard of players scored 1, 2, 3...
+    fn leaderboard(players: &[&str]) -> State {
+        let mut state = State::default();
+        for (i, player) in players.iter().enumerate() {
+            let score = (i + 1).to_string();
+            run(&mut state, &["ZADD", "board", &score, player]);
+        }
+        state
+    }
+
+    #[test]
+    fn zadd_returns_number_of_added_members() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["ZADD", "board", "1", "a", "2", "b"]),
+            Frame::Integer(2)
+        );
+        assert_eq!(
+            run(&mut state, &["ZADD", "board", "5", "a", "3", "c"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(run(&mut state, &["ZCARD", "board"]), Frame::Integer(3));
+        assert_eq!(
+            run(&mut state, &["ZSCORE", "board", "a"]),
+            Frame::Double(5.0)
+        );
+    }
+
+    #[test]
+    fn zadd_ch_counts_updated_members() {
+        let mut state = leaderboard(&["a", "b"]);
+        assert_eq!(
+            run(
+                &mut state,
+                &["ZADD", "board", "CH", "1", "a", "5", "b", "3", "c"]
+            ),
+            Frame::Integer(2)
+        );
+    }
+
+    #[test]
+    fn zadd_nx_and_xx() {
+        let mut state = leaderboard(&["a"]);
+        assert_eq!(
+            run(&mut state, &["ZADD", "board", "NX", "5", "a", "2", "b"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["ZSCORE", "board", "a"]),
+            Frame::Double(1.0)
+        );
+
+        assert_eq!(
+            run(
+                &mut state,
+                &["ZADD", "board", "XX", "CH", "5", "a", "3", "c"]
+            ),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["ZSCORE", "board", "a"]),
+            Frame::Double(5.0)
+        );
+        assert_eq!(run(&mut state, &["ZSCORE", "board", "c"]), Frame::Null);
+
+        assert_eq!(
+            run(&mut state, &["ZADD", "missing", "XX", "1", "a"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(
+            run(&mut state, &["ZADD", "missing", "XX", "INCR", "1", "a"]),
+            Frame::Null
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "missing"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn zadd_gt_and_lt_only_move_scores_one_way() {
+        let mut state = leaderboard(&["a", "b"]);
+        run(&mut state, &["ZADD", "board", "GT", "0", "a", "5", "b"]);
+        assert_eq!(
+            run(&mut state, &["ZSCORE", "board", "a"]),
+            Frame::Double(1.0)
+        );
+        assert_eq!(
+            run(&mut state, &["ZSCORE", "board", "b"]),
+            Frame::Double(5.0)
+        );
+
+        run(
+            &mut state,
+            &["ZADD", "board", "LT", "0", "a", "9", "b", "7", "c"],
+        );
+        assert_eq!(
+            run(&mut state, &["ZSCORE", "board", "a"]),
+            Frame::Double(0.0)
+        );
+        assert_eq!(
+            run(&mut state, &["ZSCORE", "board", "b"]),
+            Frame::Double(5.0)
+        );
+        assert_eq!(
+            run(&mut state, &["ZSCORE", "board", "c"]),
+            Frame::Double(7.0)
+        );
+    }
+
+    #[test]
+    fn zadd_incr_returns_new_score() {
+        let mut state = leaderboard(&["a"]);
+        assert_eq!(
+            run(&mut state, &["ZADD", "board", "INCR", "2.5", "a"]),
+            Frame::Double(3.5)
+        );
+        assert_eq!(
+            run(&mut state, &["ZADD", "board", "NX", "INCR", "1", "a"]),
+            Frame::Null
+        );
+        assert_eq!(
+            run(&mut state, &["ZADD", "board", "GT", "INCR", "-1", "a"]),
+            Frame::Null
+        );
+    }
+
+    #[test]
+    fn zadd_errors() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["ZADD", "board", "NX", "XX", "1", "a"]),
+            error("XX and NX options at the same time are not compatible")
+        );
+        assert_eq!(
+            run(&mut state, &["ZADD", "board", "GT", "LT", "1", "a"]),
+            error("GT, LT, and/or NX options at the same time are not compatible")
+        );
+        assert_eq!(
+            run(&mut state, &["ZADD", "board", "NX", "GT", "1", "a"]),
+            error("GT, LT, and/or NX options at the same time are not compatible")
+        );
+        assert_eq!(
+            run(&mut state, &["ZADD", "board", "INCR", "1", "a", "2", "b"]),
+            error("INCR option supports a single increment-element pair")
+        );
+        assert_eq!(
+            run(&mut state, &["ZADD", "board", "1", "a", "2"]),
+            error("syntax error")
+        );
+        assert_eq!(
+            run(&mut state, &["ZADD", "board", "abc", "a"]),
+            error("value is not a valid float")
+        );
+        assert_eq!(
+            run(&mut state, &["ZADD", "board", "nan", "a"]),
+            error("value is not a valid float")
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "board"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn zincrby_adds_to_score() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["ZINCRBY", "board", "2", "a"]),
+            Frame::Double(2.0)
+        );
+        assert_eq!(
+            run(&mut state, &["ZINCRBY", "board", "-0.5", "a"]),
+            Frame::Double(1.5)
+        );
+        run(&mut state, &["ZADD", "board", "inf", "b"]);
+        assert_eq!(
+            run(&mut state, &["ZINCRBY", "board", "-inf", "b"]),
+            error("resulting score is not a number (NaN)")
+        );
+    }
+
+    #[test]
+    fn zrange_by_rank() {
+        let mut state = leaderboard(&["a", "b", "c", "d"]);
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "board", "0", "-1"]),
+            bulks(&["a", "b", "c", "d"])
+        );
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "board", "1", "2"]),
+            bulks(&["b", "c"])
+        );
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "board", "0", "1", "REV"]),
+            bulks(&["d", "c"])
+        );
+        assert_eq!(run(&mut state, &["ZRANGE", "board", "5", "9"]), bulks(&[]));
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "missing", "0", "-1"]),
+            bulks(&[])
+        );
+    }
+
+    #[test]
+    fn zrange_by_score() {
+        let mut state = leaderboard(&["a", "b", "c", "d"]);
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "board", "2", "3", "BYSCORE"]),
+            bulks(&["b", "c"])
+        );
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "board", "(1", "+inf", "BYSCORE"]),
+            bulks(&["b", "c", "d"])
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["ZRANGE", "board", "(4", "2", "BYSCORE", "REV"]
+            ),
+            bulks(&["c", "b"])
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["ZRANGE", "board", "-inf", "inf", "BYSCORE", "LIMIT", "1", "2"]
+            ),
+            bulks(&["b", "c"])
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["ZRANGE", "board", "-inf", "inf", "BYSCORE", "LIMIT", "2", "-1"]
+            ),
+            bulks(&["c", "d"])
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["ZRANGE", "board", "+inf", "(1", "BYSCORE", "REV", "LIMIT", "1", "5"]
+            ),
+            bulks(&["c", "b"])
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["ZRANGE", "board", "-inf", "inf", "BYSCORE", "LIMIT", "9", "1"]
+            ),
+            bulks(&[])
+        );
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "board", "x", "3", "BYSCORE"]),
+            error("min or max is not a float")
+        );
+    }
+
+    #[test]
+    fn zrange_by_lex() {
+        let mut state = State::default();
+        run(
+            &mut state,
+            &[
+                "ZADD", "names", "0", "ada", "0", "bob", "0", "cy", "0", "dan",
+            ],
+        );
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "names", "[b", "(d", "BYLEX"]),
+            bulks(&["bob", "cy"])
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["ZRANGE", "names", "-", "+", "BYLEX", "LIMIT", "1", "1"]
+            ),
+            bulks(&["bob"])
+        );
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "names", "+", "[c", "BYLEX", "REV"]),
+            bulks(&["dan", "cy"])
+        );
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "names", "b", "d", "BYLEX"]),
+            error("min or max not valid string range item")
+        );
+    }
+
+    #[test]
+    fn zrange_option_errors() {
+        let mut state = State::default();
+        assert_eq!(
+            run(
+                &mut state,
+                &["ZRANGE", "board", "0", "1", "LIMIT", "0", "1"]
+            ),
+            error(
+                "syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX"
+            )
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["ZRANGE", "board", "-", "+", "BYLEX", "WITHSCORES"]
+            ),
+            error("syntax error, WITHSCORES not supported in combination with BYLEX")
+        );
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "board", "a", "1"]),
+            error("value is not an integer or out of range")
+        );
+    }
+
+    #[test]
+    fn zrange_withscores_depends_on_protocol() {
+        let mut state = leaderboard(&["a", "b"]);
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "board", "0", "-1", "WITHSCORES"]),
+            Frame::Array(vec![
+                bulk("a"),
+                Frame::Double(1.0),
+                bulk("b"),
+                Frame::Double(2.0)
+            ])
+        );
+        assert_eq!(
+            run_with(
+                &mut state,
+                &["ZRANGE", "board", "0", "-1", "WITHSCORES"],
+                Protocol::Resp3
+            ),
+            Frame::Array(vec![
+                Frame::Array(vec![bulk("a"), Frame::Double(1.0)]),
+                Frame::Array(vec![bulk("b"), Frame::Double(2.0)])
+            ])
+        );
+    }
+
+    #[test]
+    fn zrank_and_zrevrank() {
+        let mut state = leaderboard(&["a", "b", "c"]);
+        assert_eq!(run(&mut state, &["ZRANK", "board", "a"]), Frame::Integer(0));
+        assert_eq!(
+            run(&mut state, &["ZREVRANK", "board", "a"]),
+            Frame::Integer(2)
+        );
+        assert_eq!(
+            run(&mut state, &["ZRANK", "board", "b", "WITHSCORE"]),
+            Frame::Array(vec![Frame::Integer(1), Frame::Double(2.0)])
+        );
+        assert_eq!(run(&mut state, &["ZRANK", "board", "x"]), Frame::Null);
+        assert_eq!(
+            run(&mut state, &["ZRANK", "board", "x", "WITHSCORE"]),
+            Frame::NullArray
+        );
+    }
+
+    #[test]
+    fn zrem_deletes_empty_sorted_set() {
+        let mut state = leaderboard(&["a", "b"]);
+        assert_eq!(
+            run(&mut state, &["ZREM", "board", "a", "x"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(run(&mut state, &["ZREM", "board", "b"]), Frame::Integer(1));
+        assert_eq!(run(&mut state, &["EXISTS", "board"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn zcount_counts_scores_in_range() {
+        let mut state = leaderboard(&["a", "b", "c", "d"]);
+        assert_eq!(
+            run(&mut state, &["ZCOUNT", "board", "2", "3"]),
+            Frame::Integer(2)
+        );
+        assert_eq!(
+            run(&mut state, &["ZCOUNT", "board", "(1", "+inf"]),
+            Frame::Integer(3)
+        );
+        assert_eq!(
+            run(&mut state, &["ZCOUNT", "missing", "-inf", "+inf"]),
+            Frame::Integer(0)
+        );
+    }
+
+    #[test]
+    fn zpopmin_and_zpopmax() {
+        let mut state = leaderboard(&["a", "b", "c"]);
+        assert_eq!(
+            run(&mut state, &["ZPOPMIN", "board"]),
+            Frame::Array(vec![bulk("a"), Frame::Double(1.0)])
+        );
+        assert_eq!(
+            run_with(&mut state, &["ZPOPMAX", "board", "5"], Protocol::Resp3),
+            Frame::Array(vec![
+                Frame::Array(vec![bulk("c"), Frame::Double(3.0)]),
+                Frame::Array(vec![bulk("b"), Frame::Double(2.0)])
+            ])
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "board"]), Frame::Integer(0));
+        assert_eq!(run(&mut state, &["ZPOPMIN", "board"]), Frame::Array(vec![]));
+        assert_eq!(
+            run(&mut state, &["ZPOPMIN", "board", "-1"]),
+            error("value is out of range, must be positive")
+        );
+    }
+
+    #[test]
+    fn zunionstore_with_weights_and_aggregate() {
+        let mut state = State::default();
+        run(&mut state, &["ZADD", "a", "1", "x", "2", "y"]);
+        run(&mut state, &["ZADD", "b", "10", "y", "20", "z"]);
+
+        assert_eq!(
+            run(
+                &mut state,
+                &["ZUNIONSTORE", "out", "2", "a", "b", "WEIGHTS", "2", "1"]
+            ),
+            Frame::Integer(3)
+        );
+        assert_eq!(
+            run(&mut state, &["ZRANGE", "out", "0", "-1", "WITHSCORES"]),
+            Frame::Array(vec![
+                bulk("x"),
+                Frame::Double(2.0),
+                bulk("y"),
+                Frame::Double(14.0),
+                bulk("z"),
+                Frame::Double(20.0)
+            ])
+        );
+
+        run(
+            &mut state,
+            &["ZUNIONSTORE", "out", "2", "a", "b", "AGGREGATE", "MIN"],
+        );
+        assert_eq!(run(&mut state, &["ZSCORE", "out", "y"]), Frame::Double(2.0));
+    }
+
+    #[test]
+    fn zinterstore_keeps_common_members() {
+        let mut state = State::default();
+        run(&mut state, &["ZADD", "a", "1", "x", "2", "y"]);
+        run(&mut state, &["ZADD", "b", "10", "y", "20", "z"]);
+        run(&mut state, &["SADD", "set", "y", "z"]);
+
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "ZINTERSTORE",
+                    "out",
+                    "3",
+                    "a",
+                    "b",
+                    "set",
+                    "AGGREGATE",
+                    "MAX"
+                ]
+            ),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["ZSCORE", "out", "y"]),
+            Frame::Double(10.0)
+        );
+
+        assert_eq!(
+            run(&mut state, &["ZINTERSTORE", "out", "2", "a", "missing"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "out"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn zstore_errors() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["ZUNIONSTORE", "out", "0", "a"]),
+            error("at least 1 input key is needed for 'zunionstore' command")
+        );
+        assert_eq!(
+            run(&mut state, &["ZUNIONSTORE", "out", "3", "a", "b"]),
+            error("syntax error")
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["ZUNIONSTORE", "out", "1", "a", "WEIGHTS", "x"]
+            ),
+            error("weight value is not a float")
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["ZUNIONSTORE", "out", "1", "a", "AGGREGATE", "AVG"]
+            ),
+            error("syntax error")
+        );
+
+        run(&mut state, &["SET", "string", "x"]);
+        assert_eq!(
+            run(&mut state, &["ZUNIONSTORE", "out", "1", "string"]),
+            wrong_type()
+        );
+    }
+}
@@ -0,0 +1,797 @@
+#[cfg(test)]
+mod stream_test {
+    use crate::server::cmd::helper::{bulk, error, run, run_with, wrong_type};
+    use crate::server::db::State;
+    use crate::server::frame::{Frame, Protocol};
+
+    /// Builds the reply for an entry with the given fields and values.
+    fn entry(id: &str, fields: &[&str]) -> Frame {
+        Frame::Array(vec![
+            bulk(id),
+            Frame::Array(fields.iter().map(|field| bulk(field)).collect()),
+        ])
+    }
+
+    /// Builds a stream with entries `1-0`, `2-0`... holding their number.
+    fn events(count: u64) -> State {
+        let mut state = State::default();
+        for i in 1..=count {
+            let id = format!("{}-0", i);
+            run(&mut state, &["XADD", "events", &id, "n", &i.to_string()]);
+        }
+        state
+    }
+
+    fn ids(reply: Frame) -> Vec<String> {
+        let Frame::Array(entries) = reply else {
+            panic!("not an array: {:?}", reply);
+        };
+        entries
+            .into_iter()
+            .map(|entry| match entry {
+                Frame::Array(mut parts) => match parts.remove(0) {
+                    Frame::Bulk(id) => String::from_utf8(id.to_vec()).unwrap(),
+                    part => panic!("not an ID: {:?}", part),
+                },
+                entry => panic!("not an entry: {:?}", entry),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn xadd_returns_the_id() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["XADD", "events", "5-1", "a", "1", "b", "2"]),
+            bulk("5-1")
+        );
+        assert_eq!(
+            run(&mut state, &["XADD", "events", "5-*", "a", "1"]),
+            bulk("5-2")
+        );
+        assert_eq!(
+            run(&mut state, &["XADD", "events", "7", "a", "1"]),
+            bulk("7-0")
+        );
+        assert_eq!(run(&mut state, &["XLEN", "events"]), Frame::Integer(3));
+        assert_eq!(
+            run(&mut state, &["XRANGE", "events", "-", "+", "COUNT", "1"]),
+            Frame::Array(vec![entry("5-1", &["a", "1", "b", "2"])])
+        );
+    }
+
+    #[test]
+    fn xadd_generates_increasing_ids() {
+        let mut state = State::default();
+        run(&mut state, &["XADD", "events", "*", "a", "1"]);
+        run(&mut state, &["XADD", "events", "*", "a", "2"]);
+        run(&mut state, &["XADD", "events", "*", "a", "3"]);
+
+        let ids = ids(run(&mut state, &["XRANGE", "events", "-", "+"]));
+        assert_eq!(ids.len(), 3);
+        let mut sorted = ids.clone();
+        sorted.sort_by_key(|id| {
+            let (ms, seq) = id.split_once('-').unwrap();
+            (ms.parse::<u64>().unwrap(), seq.parse::<u64>().unwrap())
+        });
+        sorted.dedup();
+        assert_eq!(ids, sorted);
+    }
+
+    #[test]
+    fn xadd_rejects_ids_not_greater_than_the_top_item() {
+        let mut state = events(2);
+        let msg = "The ID specified in XADD is equal or smaller than the target stream top item";
+        assert_eq!(
+            run(&mut state, &["XADD", "events", "2-0", "a", "1"]),
+            error(msg)
+        );
+        assert_eq!(
+            run(&mut state, &["XADD", "events", "1-*", "a", "1"]),
+            error(msg)
+        );
+        assert_eq!(
+            run(&mut state, &["XADD", "other", "0-0", "a", "1"]),
+            error("The ID specified in XADD must be greater than 0-0")
+        );
+        assert_eq!(
+            run(&mut state, &["XADD", "other", "1-x", "a", "1"]),
+            error("Invalid stream ID specified as stream command argument")
+        );
+        assert_eq!(
+            run(&mut state, &["XADD", "other", "1-1", "a"]),
+            error("wrong number of arguments for 'xadd' command")
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "other"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn xadd_nomkstream() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["XADD", "events", "NOMKSTREAM", "*", "a", "1"]),
+            Frame::Null
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "events"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn xadd_trims_the_stream() {
+        let mut state = events(5);
+        run(
+            &mut state,
+            &["XADD", "events", "MAXLEN", "3", "6-0", "n", "6"],
+        );
+        assert_eq!(
+            ids(run(&mut state, &["XRANGE", "events", "-", "+"])),
+            vec!["4-0", "5-0", "6-0"]
+        );
+
+        run(
+            &mut state,
+            &["XADD", "events", "MINID", "=", "6", "7-0", "n", "7"],
+        );
+        assert_eq!(
+            ids(run(&mut state, &["XRANGE", "events", "-", "+"])),
+            vec!["6-0", "7-0"]
+        );
+
+        assert_eq!(
+            run(
+                &mut state,
+                &["XADD", "events", "MAXLEN", "1", "LIMIT", "1", "*", "n", "8"]
+            ),
+            error("syntax error, LIMIT cannot be used without the special ~ option")
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["XADD", "events", "MAXLEN", "-1", "*", "n", "8"]
+            ),
+            error("The MAXLEN argument must be >= 0.")
+        );
+    }
+
+    #[test]
+    fn xrange_bounds() {
+        let mut state = events(4);
+        assert_eq!(
+            ids(run(&mut state, &["XRANGE", "events", "2", "3"])),
+            vec!["2-0", "3-0"]
+        );
+        assert_eq!(
+            ids(run(&mut state, &["XRANGE", "events", "(2-0", "+"])),
+            vec!["3-0", "4-0"]
+        );
+        assert_eq!(
+            ids(run(&mut state, &["XRANGE", "events", "-", "(3-0"])),
+            vec!["1-0", "2-0"]
+        );
+        assert_eq!(
+            ids(run(&mut state, &["XRANGE", "events", "3", "2"])),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            ids(run(&mut state, &["XRANGE", "missing", "-", "+"])),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            run(&mut state, &["XRANGE", "events", "(-", "+"]),
+            error("Invalid stream ID specified as stream command argument")
+        );
+        assert_eq!(
+            run(&mut state, &["XRANGE", "events", "-", "(0-0"]),
+            error("invalid end ID for the interval")
+        );
+    }
+
+    #[test]
+    fn xrevrange_starts_from_the_end() {
+        let mut state = events(4);
+        assert_eq!(
+            ids(run(
+                &mut state,
+                &["XREVRANGE", "events", "+", "-", "COUNT", "2"]
+            )),
+            vec!["4-0", "3-0"]
+        );
+        assert_eq!(
+            ids(run(&mut state, &["XREVRANGE", "events", "3", "(1"])),
+            vec!["3-0", "2-0"]
+        );
+        assert_eq!(
+            ids(run(
+                &mut state,
+                &["XREVRANGE", "events", "+", "-", "COUNT", "0"]
+            )),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn xdel_and_xtrim() {
+        let mut state = events(5);
+        assert_eq!(
+            run(&mut state, &["XDEL", "events", "2-0", "3", "9-0"]),
+            Frame::Integer(2)
+        );
+        assert_eq!(run(&mut state, &["XLEN", "events"]), Frame::Integer(3));
+
+        assert_eq!(
+            run(
+                &mut state,
+                &["XTRIM", "events", "MAXLEN", "~", "0", "LIMIT", "1"]
+            ),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["XTRIM", "events", "MINID", "5"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            ids(run(&mut state, &["XRANGE", "events", "-", "+"])),
+            vec!["5-0"]
+        );
+
+        // The stream is kept once empty, and its IDs keep increasing
+        run(&mut state, &["XDEL", "events", "5-0"]);
+        assert_eq!(run(&mut state, &["XLEN", "events"]), Frame::Integer(0));
+        assert_eq!(run(&mut state, &["EXISTS", "events"]), Frame::Integer(1));
+        assert!(matches!(
+            run(&mut state, &["XADD", "events", "5-0", "n", "5"]),
+            Frame::Error(_)
+        ));
+    }
+
+    #[test]
+    fn xread_returns_entries_after_the_ids() {
+        let mut state = events(3);
+        run(&mut state, &["XADD", "other", "1-0", "n", "1"]);
+
+        assert_eq!(
+            run(
+                &mut state,
+                &["XREAD", "COUNT", "1", "STREAMS", "events", "other", "1-0", "0"]
+            ),
+            Frame::Array(vec![
+                Frame::Array(vec![
+                    bulk("events"),
+                    Frame::Array(vec![entry("2-0", &["n", "2"])])
+                ]),
+                Frame::Array(vec![
+                    bulk("other"),
+                    Frame::Array(vec![entry("1-0", &["n", "1"])])
+                ]),
+            ])
+        );
+        assert_eq!(
+            run_with(
+                &mut state,
+                &["XREAD", "STREAMS", "events", "other", "2", "1"],
+                Protocol::Resp3
+            ),
+            Frame::Map(vec![(
+                bulk("events"),
+                Frame::Array(vec![entry("3-0", &["n", "3"])])
+            )])
+        );
+    }
+
+    #[test]
+    fn xread_without_new_entries() {
+        let mut state = events(3);
+        assert_eq!(
+            run(
+                &mut state,
+                &["XREAD", "STREAMS", "events", "missing", "$", "0"]
+            ),
+            Frame::NullArray
+        );
+        assert_eq!(
+            run(&mut state, &["XREAD", "STREAMS", "events", "0", "1"]),
+            error("Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified.")
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["XREAD", "BLOCK", "-1", "STREAMS", "events", "0"]
+            ),
+            error("timeout is negative")
+        );
+    }
+
+    #[test]
+    fn stream_commands_check_the_type() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(
+            run(&mut state, &["XADD", "key", "*", "a", "1"]),
+            wrong_type()
+        );
+        assert_eq!(run(&mut state, &["XLEN", "key"]), wrong_type());
+        assert_eq!(
+            run(&mut state, &["XREAD", "STREAMS", "key", "0"]),
+            wrong_type()
+        );
+    }
+
+    /// Builds a stream of three entries with the group `workers`.
+    fn workers() -> State {
+        let mut state = events(3);
+        assert_eq!(
+            run(&mut state, &["XGROUP", "CREATE", "events", "workers", "0"]),
+            Frame::Simple("OK".into())
+        );
+        state
+    }
+
+    /// Returns the entries read from the single stream of an `XREADGROUP`
+    /// reply.
+    fn read_ids(reply: Frame) -> Vec<String> {
+        match reply {
+            Frame::Array(mut streams) if streams.len() == 1 => match streams.remove(0) {
+                Frame::Array(mut pair) => ids(pair.remove(1)),
+                stream => panic!("not a stream: {:?}", stream),
+            },
+            reply => panic!("not a single stream: {:?}", reply),
+        }
+    }
+
+    #[test]
+    fn xgroup_create() {
+        let mut state = workers();
+        assert_eq!(
+            run(&mut state, &["XGROUP", "CREATE", "events", "workers", "$"]),
+            Frame::Error("BUSYGROUP Consumer Group name already exists".into())
+        );
+        assert!(matches!(
+            run(&mut state, &["XGROUP", "CREATE", "missing", "workers", "$"]),
+            Frame::Error(msg) if msg.starts_with("ERR The XGROUP subcommand requires the key to exist")
+        ));
+        assert_eq!(
+            run(
+                &mut state,
+                &["XGROUP", "CREATE", "jobs", "workers", "$", "MKSTREAM"]
+            ),
+            Frame::Simple("OK".into())
+        );
+        assert_eq!(run(&mut state, &["XLEN", "jobs"]), Frame::Integer(0));
+        assert_eq!(
+            run(&mut state, &["XGROUP", "DESTROY", "jobs", "workers"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["XGROUP", "DESTROY", "jobs", "workers"]),
+            Frame::Integer(0)
+        );
+    }
+
+    #[test]
+    fn xreadgroup_delivers_each_entry_once() {
+        let mut state = workers();
+        assert_eq!(
+            read_ids(run(
+                &mut state,
+                &[
+                    "XREADGROUP",
+                    "GROUP",
+                    "workers",
+                    "alice",
+                    "COUNT",
+                    "2",
+                    "STREAMS",
+                    "events",
+                    ">"
+                ]
+            )),
+            vec!["1-0", "2-0"]
+        );
+        assert_eq!(
+            read_ids(run(
+                &mut state,
+                &[
+                    "XREADGROUP",
+                    "GROUP",
+                    "workers",
+                    "bob",
+                    "STREAMS",
+                    "events",
+                    ">"
+                ]
+            )),
+            vec!["3-0"]
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "XREADGROUP",
+                    "GROUP",
+                    "workers",
+                    "bob",
+                    "STREAMS",
+                    "events",
+                    ">"
+                ]
+            ),
+            Frame::NullArray
+        );
+
+        // The history of a consumer is its pending entries
+        assert_eq!(
+            read_ids(run(
+                &mut state,
+                &[
+                    "XREADGROUP",
+                    "GROUP",
+                    "workers",
+                    "alice",
+                    "STREAMS",
+                    "events",
+                    "0"
+                ]
+            )),
+            vec!["1-0", "2-0"]
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["XACK", "events", "workers", "1-0", "3-0", "9-0"]
+            ),
+            Frame::Integer(2)
+        );
+        assert_eq!(
+            read_ids(run(
+                &mut state,
+                &[
+                    "XREADGROUP",
+                    "GROUP",
+                    "workers",
+                    "bob",
+                    "STREAMS",
+                    "events",
+                    "0"
+                ]
+            )),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn xreadgroup_noack_skips_the_pending_entries_list() {
+        let mut state = workers();
+        run(
+            &mut state,
+            &[
+                "XREADGROUP",
+                "GROUP",
+                "workers",
+                "alice",
+                "NOACK",
+                "STREAMS",
+                "events",
+                ">",
+            ],
+        );
+        assert_eq!(
+            run(&mut state, &["XPENDING", "events", "workers"]),
+            Frame::Array(vec![
+                Frame::Integer(0),
+                Frame::Null,
+                Frame::Null,
+                Frame::NullArray
+            ])
+        );
+    }
+
+    #[test]
+    fn xreadgroup_requires_the_group() {
+        let mut state = events(1);
+        assert_eq!(
+            run(
+                &mut state,
+                &["XREADGROUP", "GROUP", "workers", "alice", "STREAMS", "events", ">"]
+            ),
+            Frame::Error(
+                "NOGROUP No such key 'events' or consumer group 'workers' in XREADGROUP with GROUP option"
+                    .into()
+            )
+        );
+        assert!(matches!(
+            run(
+                &mut state,
+                &["XREADGROUP", "GROUP", "workers", "alice", "STREAMS", "events", "$"]
+            ),
+            Frame::Error(msg) if msg.starts_with("ERR The $ ID is meaningless")
+        ));
+    }
+
+    #[test]
+    fn xpending_summary_and_range() {
+        let mut state = workers();
+        run(
+            &mut state,
+            &[
+                "XREADGROUP",
+                "GROUP",
+                "workers",
+                "alice",
+                "COUNT",
+                "2",
+                "STREAMS",
+                "events",
+                ">",
+            ],
+        );
+        run(
+            &mut state,
+            &[
+                "XREADGROUP",
+                "GROUP",
+                "workers",
+                "bob",
+                "STREAMS",
+                "events",
+                ">",
+            ],
+        );
+
+        assert_eq!(
+            run(&mut state, &["XPENDING", "events", "workers"]),
+            Frame::Array(vec![
+                Frame::Integer(3),
+                bulk("1-0"),
+                bulk("3-0"),
+                Frame::Array(vec![
+                    Frame::Array(vec![bulk("alice"), bulk("2")]),
+                    Frame::Array(vec![bulk("bob"), bulk("1")]),
+                ]),
+            ])
+        );
+
+        let Frame::Array(entries) = run(
+            &mut state,
+            &["XPENDING", "events", "workers", "-", "+", "10", "bob"],
+        ) else {
+            panic!("not an array");
+        };
+        assert_eq!(entries.len(), 1);
+        let Frame::Array(entry) = &entries[0] else {
+            panic!("not an entry");
+        };
+        assert_eq!(entry[0], bulk("3-0"));
+        assert_eq!(entry[1], bulk("bob"));
+        assert_eq!(entry[3], Frame::Integer(1));
+
+        assert_eq!(
+            run(
+                &mut state,
+                &["XPENDING", "events", "workers", "IDLE", "60000", "-", "+", "10"]
+            ),
+            Frame::Array(vec![])
+        );
+        assert_eq!(
+            run(&mut state, &["XPENDING", "events", "missing"]),
+            Frame::Error("NOGROUP No such key 'events' or consumer group 'missing'".into())
+        );
+    }
+
+    #[test]
+    fn xclaim_transfers_idle_entries() {
+        let mut state = workers();
+        run(
+            &mut state,
+            &[
+                "XREADGROUP",
+                "GROUP",
+                "workers",
+                "alice",
+                "STREAMS",
+                "events",
+                ">",
+            ],
+        );
+
+        // Not idle for long enough yet
+        assert_eq!(
+            run(
+                &mut state,
+                &["XCLAIM", "events", "workers", "bob", "60000", "1-0"]
+            ),
+            Frame::Array(vec![])
+        );
+
+        // Pretend alice received the entries a while ago
+        run(
+            &mut state,
+            &[
+                "XCLAIM", "events", "workers", "alice", "0", "1-0", "2-0", "IDLE", "120000",
+                "JUSTID",
+            ],
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["XCLAIM", "events", "workers", "bob", "60000", "1-0", "3-0"]
+            ),
+            Frame::Array(vec![entry("1-0", &["n", "1"])])
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "XCLAIM",
+                    "events",
+                    "workers",
+                    "bob",
+                    "0",
+                    "2-0",
+                    "RETRYCOUNT",
+                    "7",
+                    "JUSTID"
+                ]
+            ),
+            Frame::Array(vec![bulk("2-0")])
+        );
+
+        let reply = run(
+            &mut state,
+            &["XPENDING", "events", "workers", "-", "+", "10"],
+        );
+        let Frame::Array(entries) = reply else {
+            panic!("not an array");
+        };
+        let deliveries: Vec<(Frame, Frame)> = entries
+            .into_iter()
+            .map(|entry| match entry {
+                Frame::Array(entry) => (entry[1].clone(), entry[3].clone()),
+                entry => panic!("not an entry: {:?}", entry),
+            })
+            .collect();
+        assert_eq!(
+            deliveries,
+            vec![
+                (bulk("bob"), Frame::Integer(2)),
+                (bulk("bob"), Frame::Integer(7)),
+                (bulk("alice"), Frame::Integer(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn xautoclaim_scans_the_pending_entries() {
+        let mut state = workers();
+        run(
+            &mut state,
+            &[
+                "XREADGROUP",
+                "GROUP",
+                "workers",
+                "alice",
+                "STREAMS",
+                "events",
+                ">",
+            ],
+        );
+        run(
+            &mut state,
+            &[
+                "XCLAIM", "events", "workers", "alice", "0", "1-0", "2-0", "3-0", "IDLE", "120000",
+                "JUSTID",
+            ],
+        );
+        run(&mut state, &["XDEL", "events", "2-0"]);
+
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "XAUTOCLAIM",
+                    "events",
+                    "workers",
+                    "bob",
+                    "60000",
+                    "0",
+                    "COUNT",
+                    "1"
+                ]
+            ),
+            Frame::Array(vec![
+                bulk("2-0"),
+                Frame::Array(vec![entry("1-0", &["n", "1"])]),
+                Frame::Array(vec![]),
+            ])
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &[
+                    "XAUTOCLAIM",
+                    "events",
+                    "workers",
+                    "bob",
+                    "60000",
+                    "2-0",
+                    "JUSTID"
+                ]
+            ),
+            Frame::Array(vec![
+                bulk("0-0"),
+                Frame::Array(vec![bulk("3-0")]),
+                Frame::Array(vec![bulk("2-0")]),
+            ])
+        );
+        assert_eq!(
+            run(&mut state, &["XPENDING", "events", "workers"]),
+            Frame::Array(vec![
+                Frame::Integer(2),
+                bulk("1-0"),
+                bulk("3-0"),
+                Frame::Array(vec![Frame::Array(vec![bulk("bob"), bulk("2")])]),
+            ])
+        );
+    }
+
+    #[test]
+    fn xgroup_consumers() {
+        let mut state = workers();
+        assert_eq!(
+            run(
+                &mut state,
+                &["XGROUP", "CREATECONSUMER", "events", "workers", "alice"]
+            ),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["XGROUP", "CREATECONSUMER", "events", "workers", "alice"]
+            ),
+            Frame::Integer(0)
+        );
+        run(
+            &mut state,
+            &[
+                "XREADGROUP",
+                "GROUP",
+                "workers",
+                "alice",
+                "STREAMS",
+                "events",
+                ">",
+            ],
+        );
+        assert_eq!(
+            run(
+                &mut state,
+                &["XGROUP", "DELCONSUMER", "events", "workers", "alice"]
+            ),
+            Frame::Integer(3)
+        );
+
+        // Rewinding the group delivers the entries again
+        assert_eq!(
+            run(&mut state, &["XGROUP", "SETID", "events", "workers", "1"]),
+            Frame::Simple("OK".into())
+        );
+        assert_eq!(
+            read_ids(run(
+                &mut state,
+                &[
+                    "XREADGROUP",
+                    "GROUP",
+                    "workers",
+                    "bob",
+                    "STREAMS",
+                    "events",
+                    ">"
+                ]
+            )),
+            vec!["2-0", "3-0"]
+        );
+        assert_eq!(
+            run(&mut state, &["XGROUP", "SETID", "events", "missing", "$"]),
+            Frame::Error("NOGROUP No such consumer group 'missing' for key name 'events'".into())
+        );
+    }
+}
@@ -0,0 +1,414 @@
+#[cfg(test)]
+mod string_test {
+    use crate::server::cmd::helper::{bulk, error, run, wrong_type};
+    use crate::server::db::{now_ms, State};
+    use crate::server::frame::Frame;
+
+    #[test]
+    fn get_missing_key_returns_null() {
+        let mut state = State::default();
+        assert_eq!(run(&mut state, &["GET", "missing"]), Frame::Null);
+    }
+
+    #[test]
+    fn set_then_get_returns_value() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["SET", "key", "value"]),
+            Frame::Simple("OK".into())
+        );
+        assert_eq!(run(&mut state, &["GET", "key"]), bulk("value"));
+    }
+
+    #[test]
+    fn set_overwrites_previous_value() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "one"]);
+        run(&mut state, &["SET", "key", "two"]);
+        assert_eq!(run(&mut state, &["GET", "key"]), bulk("two"));
+    }
+
+    #[test]
+    fn get_with_wrong_number_of_arguments_returns_error() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["GET", "a", "b"]),
+            Frame::Error("ERR wrong number of arguments for 'get' command".into())
+        );
+    }
+
+    #[test]
+    fn set_without_value_returns_error() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["SET", "key"]),
+            Frame::Error("ERR wrong number of arguments for 'set' command".into())
+        );
+    }
+
+    #[test]
+    fn set_with_ex_sets_time_to_live() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["SET", "key", "value", "EX", "10"]),
+            Frame::Simple("OK".into())
+        );
+        assert_eq!(run(&mut state, &["TTL", "key"]), Frame::Integer(10));
+    }
+
+    #[test]
+    fn set_with_px_expires_key() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value", "px", "1"]);
+
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        assert_eq!(run(&mut state, &["GET", "key"]), Frame::Null);
+    }
+
+    #[test]
+    fn set_with_exat_and_pxat_set_absolute_deadline() {
+        let mut state = State::default();
+        let seconds = (now_ms() / 1000 + 100).to_string();
+        run(&mut state, &["SET", "a", "value", "EXAT", &seconds]);
+        match run(&mut state, &["TTL", "a"]) {
+            Frame::Integer(ttl) => assert!((99..=100).contains(&ttl)),
+            frame => panic!("unexpected reply {:?}", frame),
+        }
+
+        let millis = (now_ms() + 100_000).to_string();
+        run(&mut state, &["SET", "b", "value", "PXAT", &millis]);
+        assert_eq!(run(&mut state, &["TTL", "b"]), Frame::Integer(100));
+    }
+
+    #[test]
+    fn set_without_options_clears_time_to_live() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "one", "EX", "10"]);
+        run(&mut state, &["SET", "key", "two"]);
+        assert_eq!(run(&mut state, &["TTL", "key"]), Frame::Integer(-1));
+    }
+
+    #[test]
+    fn set_with_keepttl_retains_time_to_live() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "one", "EX", "10"]);
+        run(&mut state, &["SET", "key", "two", "KEEPTTL"]);
+        assert_eq!(run(&mut state, &["TTL", "key"]), Frame::Integer(10));
+        assert_eq!(run(&mut state, &["GET", "key"]), bulk("two"));
+    }
+
+    #[test]
+    fn set_nx_only_sets_missing_key() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["SET", "key", "one", "NX"]),
+            Frame::Simple("OK".into())
+        );
+        assert_eq!(run(&mut state, &["SET", "key", "two", "NX"]), Frame::Null);
+        assert_eq!(run(&mut state, &["GET", "key"]), bulk("one"));
+    }
+
+    #[test]
+    fn set_xx_only_sets_existing_key() {
+        let mut state = State::default();
+        assert_eq!(run(&mut state, &["SET", "key", "one", "XX"]), Frame::Null);
+        assert_eq!(run(&mut state, &["GET", "key"]), Frame::Null);
+
+        run(&mut state, &["SET", "key", "one"]);
+        assert_eq!(
+            run(&mut state, &["SET", "key", "two", "XX"]),
+            Frame::Simple("OK".into())
+        );
+        assert_eq!(run(&mut state, &["GET", "key"]), bulk("two"));
+    }
+
+    #[test]
+    fn set_get_returns_previous_value() {
+        let mut state = State::default();
+        assert_eq!(run(&mut state, &["SET", "key", "one", "GET"]), Frame::Null);
+        assert_eq!(run(&mut state, &["SET", "key", "two", "GET"]), bulk("one"));
+        assert_eq!(
+            run(&mut state, &["SET", "key", "three", "NX", "GET"]),
+            bulk("two")
+        );
+        assert_eq!(run(&mut state, &["GET", "key"]), bulk("two"));
+    }
+
+    #[test]
+    fn set_with_conflicting_options_returns_syntax_error() {
+        let mut state = State::default();
+        let syntax_error = Frame::Error("ERR syntax error".into());
+        assert_eq!(
+            run(&mut state, &["SET", "k", "v", "NX", "XX"]),
+            syntax_error
+        );
+        assert_eq!(
+            run(&mut state, &["SET", "k", "v", "EX", "1", "PX", "1"]),
+            syntax_error
+        );
+        assert_eq!(
+            run(&mut state, &["SET", "k", "v", "EX", "1", "KEEPTTL"]),
+            syntax_error
+        );
+        assert_eq!(run(&mut state, &["SET", "k", "v", "EX"]), syntax_error);
+        assert_eq!(run(&mut state, &["SET", "k", "v", "FOO"]), syntax_error);
+    }
+
+    #[test]
+    fn set_with_invalid_expire_time_returns_error() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["SET", "k", "v", "EX", "0"]),
+            Frame::Error("ERR invalid expire time in 'set' command".into())
+        );
+        assert_eq!(
+            run(&mut state, &["SET", "k", "v", "EX", "ten"]),
+            Frame::Error("ERR value is not an integer or out of range".into())
+        );
+    }
+
+    #[test]
+    fn set_with_negative_expire_time_returns_error() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["SET", "k", "v", "PX", "-100"]),
+            Frame::Error("ERR invalid expire time in 'set' command".into())
+        );
+        assert_eq!(run(&mut state, &["GET", "k"]), Frame::Null);
+    }
+
+    #[test]
+    fn incr_and_decr() {
+        let mut state = State::default();
+        assert_eq!(run(&mut state, &["INCR", "counter"]), Frame::Integer(1));
+        assert_eq!(
+            run(&mut state, &["INCRBY", "counter", "10"]),
+            Frame::Integer(11)
+        );
+        assert_eq!(run(&mut state, &["DECR", "counter"]), Frame::Integer(10));
+        assert_eq!(
+            run(&mut state, &["DECRBY", "counter", "-5"]),
+            Frame::Integer(15)
+        );
+        assert_eq!(run(&mut state, &["GET", "counter"]), bulk("15"));
+    }
+
+    #[test]
+    fn incr_keeps_time_to_live() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "counter", "1", "EX", "10"]);
+        run(&mut state, &["INCR", "counter"]);
+        assert_eq!(run(&mut state, &["TTL", "counter"]), Frame::Integer(10));
+    }
+
+    #[test]
+    fn incr_errors() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "text", "abc"]);
+        run(&mut state, &["SET", "max", &i64::MAX.to_string()]);
+        run(&mut state, &["SET", "spaced", " 1"]);
+
+        let not_integer = error("value is not an integer or out of range");
+        assert_eq!(run(&mut state, &["INCR", "text"]), not_integer);
+        assert_eq!(run(&mut state, &["INCR", "spaced"]), not_integer);
+        assert_eq!(run(&mut state, &["INCRBY", "counter", "1.5"]), not_integer);
+        assert_eq!(
+            run(&mut state, &["INCR", "max"]),
+            error("increment or decrement would overflow")
+        );
+        assert_eq!(
+            run(&mut state, &["DECRBY", "counter", &i64::MIN.to_string()]),
+            error("decrement would overflow")
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "counter"]), Frame::Integer(0));
+
+        run(&mut state, &["LPUSH", "list", "a"]);
+        assert_eq!(run(&mut state, &["INCR", "list"]), wrong_type());
+    }
+
+    #[test]
+    fn incrbyfloat() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "price", "10.50"]);
+        assert_eq!(
+            run(&mut state, &["INCRBYFLOAT", "price", "0.1"]),
+            bulk("10.6")
+        );
+        assert_eq!(
+            run(&mut state, &["INCRBYFLOAT", "price", "-5"]),
+            bulk("5.6")
+        );
+        assert_eq!(
+            run(&mut state, &["INCRBYFLOAT", "new", "5.0e3"]),
+            bulk("5000")
+        );
+        assert_eq!(
+            run(&mut state, &["INCRBYFLOAT", "other", "inf"]),
+            error("increment would produce NaN or Infinity")
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "other"]), Frame::Integer(0));
+
+        run(&mut state, &["SET", "text", "abc"]);
+        assert_eq!(
+            run(&mut state, &["INCRBYFLOAT", "text", "1"]),
+            error("value is not a valid float")
+        );
+    }
+
+    #[test]
+    fn append_and_strlen() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["APPEND", "key", "Hello"]),
+            Frame::Integer(5)
+        );
+        assert_eq!(
+            run(&mut state, &["APPEND", "key", " World"]),
+            Frame::Integer(11)
+        );
+        assert_eq!(run(&mut state, &["GET", "key"]), bulk("Hello World"));
+        assert_eq!(run(&mut state, &["STRLEN", "key"]), Frame::Integer(11));
+        assert_eq!(run(&mut state, &["STRLEN", "missing"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn writes_do_not_change_earlier_replies() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "Hello"]);
+        let reply = run(&mut state, &["GET", "key"]);
+
+        run(&mut state, &["APPEND", "key", " World"]);
+        run(&mut state, &["SETRANGE", "key", "0", "J"]);
+        assert_eq!(reply, bulk("Hello"));
+        assert_eq!(run(&mut state, &["GET", "key"]), bulk("Jello World"));
+    }
+
+    #[test]
+    fn getrange_counts_from_the_end_when_negative() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "This is a string"]);
+        assert_eq!(
+            run(&mut state, &["GETRANGE", "key", "0", "3"]),
+            bulk("This")
+        );
+        assert_eq!(
+            run(&mut state, &["GETRANGE", "key", "-3", "-1"]),
+            bulk("ing")
+        );
+        assert_eq!(
+            run(&mut state, &["GETRANGE", "key", "10", "100"]),
+            bulk("string")
+        );
+        assert_eq!(run(&mut state, &["GETRANGE", "key", "5", "3"]), bulk(""));
+        assert_eq!(
+            run(&mut state, &["GETRANGE", "missing", "0", "-1"]),
+            bulk("")
+        );
+    }
+
+    #[test]
+    fn setrange_pads_with_zero_bytes() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "Hello World"]);
+        assert_eq!(
+            run(&mut state, &["SETRANGE", "key", "6", "Redis"]),
+            Frame::Integer(11)
+        );
+        assert_eq!(run(&mut state, &["GET", "key"]), bulk("Hello Redis"));
+
+        assert_eq!(
+            run(&mut state, &["SETRANGE", "padded", "3", "ab"]),
+            Frame::Integer(5)
+        );
+        assert_eq!(run(&mut state, &["GET", "padded"]), bulk("\0\0\0ab"));
+
+        assert_eq!(
+            run(&mut state, &["SETRANGE", "missing", "3", ""]),
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "missing"]), Frame::Integer(0));
+        assert_eq!(
+            run(&mut state, &["SETRANGE", "key", "-1", "a"]),
+            error("offset is out of range")
+        );
+        assert_eq!(
+            run(&mut state, &["SETRANGE", "key", "536870911", "ab"]),
+            error("string exceeds maximum allowed size (proto-max-bulk-len)")
+        );
+    }
+
+    #[test]
+    fn getdel_removes_the_key() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(run(&mut state, &["GETDEL", "key"]), bulk("value"));
+        assert_eq!(run(&mut state, &["GETDEL", "key"]), Frame::Null);
+        assert_eq!(run(&mut state, &["EXISTS", "key"]), Frame::Integer(0));
+    }
+
+    #[test]
+    fn getex_changes_time_to_live() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "key", "value"]);
+        assert_eq!(
+            run(&mut state, &["GETEX", "key", "EX", "10"]),
+            bulk("value")
+        );
+        assert_eq!(run(&mut state, &["TTL", "key"]), Frame::Integer(10));
+        assert_eq!(run(&mut state, &["GETEX", "key"]), bulk("value"));
+        assert_eq!(run(&mut state, &["TTL", "key"]), Frame::Integer(10));
+        assert_eq!(run(&mut state, &["GETEX", "key", "PERSIST"]), bulk("value"));
+        assert_eq!(run(&mut state, &["TTL", "key"]), Frame::Integer(-1));
+
+        assert_eq!(
+            run(&mut state, &["GETEX", "key", "EX", "0"]),
+            error("invalid expire time in 'getex' command")
+        );
+        assert_eq!(
+            run(&mut state, &["GETEX", "key", "EX", "10", "PERSIST"]),
+            error("syntax error")
+        );
+        assert_eq!(
+            run(&mut state, &["GETEX", "missing", "EX", "10"]),
+            Frame::Null
+        );
+    }
+
+    #[test]
+    fn mset_and_mget() {
+        let mut state = State::default();
+        assert_eq!(
+            run(&mut state, &["MSET", "a", "1", "b", "2"]),
+            Frame::Simple("OK".into())
+        );
+        run(&mut state, &["LPUSH", "list", "x"]);
+        assert_eq!(
+            run(&mut state, &["MGET", "a", "missing", "list", "b"]),
+            Frame::Array(vec![bulk("1"), Frame::Null, Frame::Null, bulk("2")])
+        );
+        assert_eq!(
+            run(&mut state, &["MSET", "a", "1", "b"]),
+            error("wrong number of arguments for 'mset' command")
+        );
+    }
+
+    #[test]
+    fn msetnx_sets_nothing_if_a_key_exists() {
+        let mut state = State::default();
+        run(&mut state, &["SET", "b", "old"]);
+        assert_eq!(
+            run(&mut state, &["MSETNX", "a", "1", "b", "2"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(run(&mut state, &["EXISTS", "a"]), Frame::Integer(0));
+        assert_eq!(
+            run(&mut state, &["MSETNX", "a", "1", "c", "3"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run(&mut state, &["MGET", "a", "b", "c"]),
+            Frame::Array(vec![bulk("1"), bulk("old"), bulk("3")])
+        );
+    }
+}
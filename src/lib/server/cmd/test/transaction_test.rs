@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod transaction_test {
+    use super::super::*;
+    use crate::server::cmd::helper::{bulk, command};
+    use tokio::io::DuplexStream;
+
+    fn queue(transaction: &mut Transaction, args: &[&str]) -> Frame {
+        transaction.queue(Command::from_frame(command(args)).unwrap())
+    }
+
+    /// Sends `args` on `dst` the way the connection loop does, and returns
+    /// the reply.
+    fn send(db: &Db, dst: &mut Connection<DuplexStream>, args: &[&str]) -> Frame {
+        match Command::from_frame(command(args)) {
+            Ok(Command::Multi(cmd)) => cmd.apply(dst),
+            Ok(Command::Exec(cmd)) => cmd.apply(db, dst),
+            Ok(Command::Discard(cmd)) => cmd.apply(db, dst),
+            Ok(cmd) => match dst.transaction() {
+                Some(transaction) => transaction.queue(cmd),
+                None => db.with_state(|state| cmd.execute(state, dst.protocol())),
+            },
+            Err(err) => {
+                if let Some(transaction) = dst.transaction() {
+                    transaction.abort();
+                }
+                ReplyError::from(err).into()
+            }
+        }
+    }
+
+    fn connection() -> Connection<DuplexStream> {
+        Connection::new(tokio::io::duplex(4096).1)
+    }
+
+    #[test]
+    fn commands_are_queued() {
+        let mut transaction = Transaction::default();
+        assert_eq!(
+            queue(&mut transaction, &["SET", "key", "value"]),
+            Frame::Simple("QUEUED".into())
+        );
+        assert_eq!(
+            queue(&mut transaction, &["PUBLISH", "news", "hello"]),
+            Frame::Simple("QUEUED".into())
+        );
+        assert_eq!(transaction.commands.len(), 2);
+        assert!(!transaction.aborted);
+    }
+
+    #[test]
+    fn refused_commands_abort() {
+        let mut transaction = Transaction::default();
+        assert_eq!(
+            queue(&mut transaction, &["SUBSCRIBE", "news"]),
+            Frame::Error("ERR Command not allowed inside a transaction".into())
+        );
+        assert!(transaction.aborted);
+
+        let mut transaction = Transaction::default();
+        assert_eq!(
+            queue(&mut transaction, &["NOPE", "a"]),
+            Frame::Error("ERR unknown command 'NOPE', with args beginning with: 'a'".into())
+        );
+        assert!(transaction.aborted);
+        assert!(transaction.commands.is_empty());
+    }
+
+    #[tokio::test]
+    async fn queueing_errors_abort_exec() {
+        let db = Db::new();
+        let mut dst = connection();
+        let exec_abort =
+            Frame::Error("EXECABORT Transaction discarded because of previous errors.".into());
+
+        send(&db, &mut dst, &["MULTI"]);
+        send(&db, &mut dst, &["SET", "key", "value"]);
+        assert_eq!(
+            send(&db, &mut dst, &["GET"]),
+            Frame::Error("ERR wrong number of arguments for 'get' command".into())
+        );
+        assert_eq!(send(&db, &mut dst, &["EXEC"]), exec_abort);
+
+        send(&db, &mut dst, &["MULTI"]);
+        send(&db, &mut dst, &["SET", "key", "value"]);
+        send(&db, &mut dst, &["NOPE"]);
+        assert_eq!(send(&db, &mut dst, &["EXEC"]), exec_abort);
+
+        // Nothing ran, and the connection left the transaction
+        assert!(dst.transaction().is_none());
+        assert_eq!(send(&db, &mut dst, &["GET", "key"]), Frame::Null);
+    }
+
+    #[tokio::test]
+    async fn discard_drops_the_queue() {
+        let db = Db::new();
+        let mut dst = connection();
+
+        send(&db, &mut dst, &["MULTI"]);
+        send(&db, &mut dst, &["SET", "key", "value"]);
+        assert_eq!(
+            send(&db, &mut dst, &["DISCARD"]),
+            Frame::Simple("OK".into())
+        );
+        assert!(dst.transaction().is_none());
+        assert_eq!(send(&db, &mut dst, &["GET", "key"]), Frame::Null);
+        assert_eq!(
+            send(&db, &mut dst, &["DISCARD"]),
+            Frame::Error("ERR DISCARD without MULTI".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn multi_can_not_be_nested() {
+        let db = Db::new();
+        let mut dst = connection();
+
+        send(&db, &mut dst, &["MULTI"]);
+        send(&db, &mut dst, &["SET", "key", "value"]);
+        assert_eq!(
+            send(&db, &mut dst, &["MULTI"]),
+            Frame::Error("ERR MULTI calls can not be nested".into())
+        );
+
+        // The transaction goes on untouched
+        assert_eq!(
+            send(&db, &mut dst, &["EXEC"]),
+            Frame::Array(vec![Frame::Simple("OK".into())])
+        );
+    }
+
+    #[tokio::test]
+    async fn exec_replies_to_each_command() {
+        let db = Db::new();
+        let mut dst = connection();
+
+        send(&db, &mut dst, &["MULTI"]);
+        send(&db, &mut dst, &["SET", "key", "value"]);
+        send(&db, &mut dst, &["LPUSH", "key", "a"]);
+        send(&db, &mut dst, &["INCR", "key"]);
+        send(&db, &mut dst, &["GET", "key"]);
+        assert_eq!(
+            send(&db, &mut dst, &["EXEC"]),
+            Frame::Array(vec![
+                Frame::Simple("OK".into()),
+                ReplyError::WrongType.into(),
+                Frame::Error("ERR value is not an integer or out of range".into()),
+                bulk("value"),
+            ])
+        );
+    }
+}
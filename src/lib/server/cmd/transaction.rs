@@ -0,0 +1,228 @@
+use crate::error::ReplyError;
+use crate::server::cmd::Command;
+use crate::server::connection::Connection;
+use crate::server::db::Db;
+use crate::server::frame::Frame;
+use crate::server::parser::{Parser, ParserError};
+
+use bytes::Bytes;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// Starts a transaction: the commands that follow are queued, replying
+/// `QUEUED`, until `EXEC` runs them or `DISCARD` drops them.
+#[derive(Debug, Default)]
+pub(crate) struct Multi;
+
+/// Runs the commands queued since `MULTI` as a single atomic step, and
+/// replies with an array of their replies.
+///
+/// If a command could not be queued, none are run and the transaction is
+/// aborted. If a watched key changed, none are run and the reply is a null
+/// array. Either way, the keys are no longer watched.
+#[derive(Debug, Default)]
+pub(crate) struct Exec;
+
+/// Drops the commands queued since `MULTI`, and stops watching keys.
+#[derive(Debug, Default)]
+pub(crate) struct Discard;
+
+/// Watches keys for the next transaction, which is not run if any of them
+/// is modified, expires or is deleted before `EXEC`.
+#[derive(Debug)]
+pub(crate) struct Watch {
+    keys: Vec<Bytes>,
+}
+
+/// Stops watching every key.
+#[derive(Debug, Default)]
+pub(crate) struct Unwatch;
+
+/// Commands queued on a connection since `MULTI`.
+#[derive(Debug, Default)]
+pub(crate) struct Transaction {
+    commands: Vec<Command>,
+
+    /// A command could not be queued, so `EXEC` must fail
+    aborted: bool,
+}
+
+impl Transaction {
+    /// Queues `command` and returns the reply to it. Commands that can not
+    /// be part of a transaction are refused, and abort it.
+    pub(crate) fn queue(&mut self, command: Command) -> Frame {
+        match command {
+            Command::Unknown(cmd) => {
+                self.abort();
+                cmd.execute()
+            }
+            // They act on the connection, which is not available while the
+            // queued commands run
+            Command::Hello(_) | Command::Subscribe(_) | Command::Unsubscribe(_) => {
+                self.abort();
+                ReplyError::Other("Command not allowed inside a transaction".to_string()).into()
+            }
+            command => {
+                self.commands.push(command);
+                Frame::Simple("QUEUED".to_string())
+            }
+        }
+    }
+
+    /// Makes `EXEC` fail, as done when a command is refused.
+    pub(crate) fn abort(&mut self) {
+        self.aborted = true;
+    }
+}
+
+impl Multi {
+    pub(crate) fn parse_frames(_parser: &mut Parser) -> Result<Multi, ParserError> {
+        Ok(Multi)
+    }
+
+    /// Starts a transaction on `dst` and returns the reply.
+    pub(crate) fn apply<S: AsyncRead + AsyncWrite + Unpin>(self, dst: &mut Connection<S>) -> Frame {
+        match dst.transaction() {
+            Some(_) => ReplyError::Other("MULTI calls can not be nested".to_string()).into(),
+            transaction => {
+                *transaction = Some(Transaction::default());
+                Frame::Simple("OK".to_string())
+            }
+        }
+    }
+}
+
+impl Exec {
+    pub(crate) fn parse_frames(_parser: &mut Parser) -> Result<Exec, ParserError> {
+        Ok(Exec)
+    }
+
+    /// Runs the transaction of `dst` and returns the replies.
+    pub(crate) fn apply<S: AsyncRead + AsyncWrite + Unpin>(
+        self,
+        db: &Db,
+        dst: &mut Connection<S>,
+    ) -> Frame {
+        let Some(transaction) = dst.transaction().take() else {
+            return ReplyError::Other("EXEC without MULTI".to_string()).into();
+        };
+        let watched = std::mem::take(dst.watched());
+        if transaction.aborted {
+            unwatch(db, watched);
+            return ReplyError::ExecAbort.into();
+        }
+
+        // Both locks are held so no other client sees the transaction half
+        // done. Nothing locks the keyspace while holding the broker, so
+        // this can not deadlock.
+        let protocol = dst.protocol();
+        db.with_state(|state| {
+            let changed = watched
+                .iter()
+                .any(|(key, version)| state.version(key) != *version);
+            for key in watched.keys() {
+                state.unwatch(key);
+            }
+            if changed {
+                return Frame::NullArray;
+            }
+
+            let replies = db.with_broker(|broker| {
+                transaction
+                    .commands
+                    .into_iter()
+                    .map(|command| command.execute_queued(state, broker, protocol))
+                    .collect()
+            });
+            Frame::Array(replies)
+        })
+    }
+}
+
+impl Discard {
+    pub(crate) fn parse_frames(_parser: &mut Parser) -> Result<Discard, ParserError> {
+        Ok(Discard)
+    }
+
+    /// Drops the transaction of `dst` and returns the reply.
+    pub(crate) fn apply<S: AsyncRead + AsyncWrite + Unpin>(
+        self,
+        db: &Db,
+        dst: &mut Connection<S>,
+    ) -> Frame {
+        match dst.transaction().take() {
+            Some(_) => {
+                unwatch(db, std::mem::take(dst.watched()));
+                Frame::Simple("OK".to_string())
+            }
+            None => ReplyError::Other("DISCARD without MULTI".to_string()).into(),
+        }
+    }
+}
+
+impl Watch {
+    pub(crate) fn parse_frames(parser: &mut Parser) -> Result<Watch, ParserError> {
+        let mut keys = vec![parser.next_bytes()?];
+        while parser.remaining() > 0 {
+            keys.push(parser.next_bytes()?);
+        }
+
+        Ok(Watch { keys })
+    }
+
+    /// Watches the keys for `dst` and returns the reply.
+    pub(crate) fn apply<S: AsyncRead + AsyncWrite + Unpin>(
+        self,
+        db: &Db,
+        dst: &mut Connection<S>,
+    ) -> Frame {
+        if dst.transaction().is_some() {
+            return ReplyError::Other("WATCH inside MULTI is not allowed".to_string()).into();
+        }
+
+        let watched = dst.watched();
+        db.with_state(|state| {
+            for key in self.keys {
+                if let Entry::Vacant(entry) = watched.entry(key) {
+                    let version = state.watch(entry.key().clone());
+                    entry.insert(version);
+                }
+            }
+        });
+
+        Frame::Simple("OK".to_string())
+    }
+}
+
+impl Unwatch {
+    pub(crate) fn parse_frames(_parser: &mut Parser) -> Result<Unwatch, ParserError> {
+        Ok(Unwatch)
+    }
+
+    /// Stops watching the keys of `dst` and returns the reply.
+    pub(crate) fn apply<S: AsyncRead + AsyncWrite + Unpin>(
+        self,
+        db: &Db,
+        dst: &mut Connection<S>,
+    ) -> Frame {
+        unwatch(db, std::mem::take(dst.watched()));
+        Frame::Simple("OK".to_string())
+    }
+}
+
+/// Stops watching the `watched` keys of a connection.
+pub(crate) fn unwatch(db: &Db, watched: HashMap<Bytes, u64>) {
+    if !watched.is_empty() {
+        db.with_state(|state| {
+            for key in watched.keys() {
+                state.unwatch(key);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/transaction_test.rs"]
+mod transaction_test;
@@ -1,46 +1,163 @@
-use crate::server::frame::Frame;
+use crate::server::cmd::Transaction;
+use crate::server::frame::{Frame, Protocol};
+use crate::server::inline;
+use crate::server::pubsub::Subscriptions;
 use crate::Error;
 
-use bytes::{Buf, BytesMut};
-use tokio::io::{AsyncReadExt, BufWriter};
+use bytes::{Buf, Bytes, BytesMut};
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufWriter};
 use tokio::net::TcpStream;
 
+/// Reads and writes frames over a stream, a `TcpStream` unless an in-memory
+/// stream is used for testing.
 #[derive(Debug)]
-pub struct Connection {
-    /// TcpStream decorated with `BufWriter`, which provides write
+pub struct Connection<S = TcpStream> {
+    /// Stream decorated with `BufWriter`, which provides write
     /// level buffering.
-    stream: BufWriter<TcpStream>,
+    stream: BufWriter<S>,
 
     /// The buffer for reading frames
     buffer: BytesMut,
+
+    /// Unique identifier of the connection, as reported by `HELLO`
+    id: u64,
+
+    /// Protocol version negotiated with `HELLO`, which decides how replies
+    /// are encoded
+    protocol: Protocol,
+
+    /// Channels and patterns the client subscribed to, whose messages are
+    /// written to it while waiting for its next frame
+    subscriptions: Subscriptions,
+
+    /// Commands queued since `MULTI`, until `EXEC` or `DISCARD`
+    transaction: Option<Transaction>,
+
+    /// Keys watched with `WATCH`, with their version at the time
+    watched: HashMap<Bytes, u64>,
 }
 
-impl Connection {
-    pub fn new(socket: TcpStream) -> Connection {
+/// Source of connection identifiers
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Connection<S> {
+    pub fn new(socket: S) -> Connection<S> {
         Connection {
             stream: BufWriter::new(socket),
 
             buffer: BytesMut::with_capacity(4 * 1024),
+
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+
+            protocol: Protocol::default(),
+
+            subscriptions: Subscriptions::new(),
+
+            transaction: None,
+
+            watched: HashMap::new(),
         }
     }
 
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub(crate) fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+
+    pub(crate) fn set_protocol(&mut self, protocol: Protocol) {
+        self.protocol = protocol;
+    }
+
+    pub(crate) fn subscriptions(&mut self) -> &mut Subscriptions {
+        &mut self.subscriptions
+    }
+
+    /// Returns the transaction started with `MULTI`, if any.
+    pub(crate) fn transaction(&mut self) -> &mut Option<Transaction> {
+        &mut self.transaction
+    }
+
+    pub(crate) fn watched(&mut self) -> &mut HashMap<Bytes, u64> {
+        &mut self.watched
+    }
+
+    /// Returns `true` if the client subscribed to a channel or a pattern.
+    pub(crate) fn is_subscribed(&self) -> bool {
+        !self.subscriptions.is_empty()
+    }
+
+    /// Reads the next frame sent by the client. Messages published to the
+    /// subscriptions of the client are written to it in the meantime.
     pub(crate) async fn read_frame(&mut self) -> Result<Option<Frame>, Error> {
         loop {
             if let Some(frame) = self.parse_frame()? {
                 return Ok(Some(frame));
             }
 
-            if self.stream.read_buf(&mut self.buffer).await? == 0 {
-                if self.buffer.is_empty() {
-                    return Ok(None);
-                } else {
-                    return Err("connection reset by peers".into());
+            tokio::select! {
+                read = self.stream.read_buf(&mut self.buffer) => {
+                    if read? == 0 {
+                        if self.buffer.is_empty() {
+                            return Ok(None);
+                        } else {
+                            return Err(Error::ConnectionReset);
+                        }
+                    }
                 }
+                message = self.subscriptions.recv() => self.write_frame(&message).await?,
+            }
+        }
+    }
+
+    /// Waits until the client closes the connection, used while the client
+    /// is blocked. Data received in the meantime is kept for `read_frame`.
+    pub(crate) async fn closed(&mut self) {
+        loop {
+            match self.stream.read_buf(&mut self.buffer).await {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {}
             }
         }
     }
 
+    /// Writes a single `Frame` to the underlying stream.
+    ///
+    /// The frame is encoded for the protocol negotiated on this connection
+    /// into a scratch buffer first, then written through the `BufWriter` and
+    /// flushed so the client receives it right away.
+    pub(crate) async fn write_frame(&mut self, frame: &Frame) -> io::Result<()> {
+        let mut bytes = BytesMut::new();
+        frame.serialize(&mut bytes, self.protocol);
+
+        self.stream.write_all(&bytes).await?;
+
+        // Push the buffered data to the socket
+        self.stream.flush().await
+    }
+
     fn parse_frame(&mut self) -> Result<Option<Frame>, Error> {
+        loop {
+            // Like Redis, anything that does not start as a RESP array is an
+            // inline command
+            match self.buffer.first() {
+                None => return Ok(None),
+                Some(b'*') => return self.parse_resp_frame(),
+                Some(_) => match self.parse_inline_frame()? {
+                    // Empty lines are silently skipped
+                    Some(frame) if frame == Frame::new() => continue,
+                    frame => return Ok(frame),
+                },
+            }
+        }
+    }
+
+    fn parse_resp_frame(&mut self) -> Result<Option<Frame>, Error> {
         use crate::server::frame::Error;
         let mut bytes = std::io::Cursor::new(&self.buffer[..]);
         match Frame::check(&mut bytes) {
@@ -56,7 +173,22 @@ impl Connection {
                 self.buffer.advance(len);
 
                 Ok(Some(frame))
-            },
+            }
+            Err(Error::Incomplete) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn parse_inline_frame(&mut self) -> Result<Option<Frame>, Error> {
+        use crate::server::frame::Error;
+        let mut bytes = std::io::Cursor::new(&self.buffer[..]);
+        match inline::parse(&mut bytes) {
+            Ok(frame) => {
+                let len = bytes.position() as usize;
+                self.buffer.advance(len);
+
+                Ok(Some(frame))
+            }
             Err(Error::Incomplete) => Ok(None),
             Err(err) => Err(err.into()),
         }
@@ -0,0 +1,578 @@
+use crate::server::frame::Frame;
+use crate::server::pubsub::Broker;
+use crate::server::set::Set;
+use crate::server::sorted_set::SortedSet;
+use crate::server::stream::Stream;
+
+use bytes::Bytes;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{oneshot, Notify};
+
+/// Handle to the keyspace shared by every connection.
+///
+/// `Db` is cheap to clone: each clone points to the same state, so a clone is
+/// handed to every spawned connection task.
+#[derive(Debug, Clone)]
+pub(crate) struct Db {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    /// The keyspace is guarded by a std mutex: commands never hold it across
+    /// an `.await`, so an async mutex is not needed.
+    state: Mutex<State>,
+
+    /// Pub/sub subscriptions, independent from the keyspace
+    broker: Mutex<Broker>,
+
+    /// Wakes up the background task purging expired keys. It is notified
+    /// when a key gets a deadline earlier than any other one, and when the
+    /// last `Db` handle is dropped so the task can exit.
+    background_task: Arc<Notify>,
+}
+
+/// The keyspace itself. Commands are executed against it while the lock is
+/// held, so every command is atomic.
+#[derive(Debug, Default)]
+pub(crate) struct State {
+    entries: HashMap<Bytes, Entry>,
+
+    /// Keys with a time to live, ordered by deadline. Used by the background
+    /// task to find the next keys to purge without scanning the keyspace.
+    expirations: BTreeSet<(u64, Bytes)>,
+
+    /// Clients waiting for keys to be pushed to
+    blocked: BlockedClients,
+
+    /// Keys watched by clients with `WATCH`
+    watched: HashMap<Bytes, Watched>,
+}
+
+/// A key watched by clients, whose version changes every time the key is
+/// modified, expires or is deleted.
+#[derive(Debug, Default)]
+struct Watched {
+    /// Number of clients watching the key
+    clients: usize,
+
+    version: u64,
+}
+
+/// A command that can wait for other clients to act on the keys it reads,
+/// such as `BLPOP`.
+pub(crate) trait BlockingCommand: Debug + Send {
+    /// Keys the command waits on
+    fn keys(&self) -> &[Bytes];
+
+    /// Executes the command if it can be served, returning `None` if it must
+    /// keep waiting.
+    fn try_execute(&self, state: &mut State) -> Option<Frame>;
+
+    /// Reply sent when the timeout elapses before the command is served
+    fn timeout_reply(&self) -> Frame {
+        Frame::Null
+    }
+}
+
+/// Registry of the clients blocked on keys.
+#[derive(Debug, Default)]
+struct BlockedClients {
+    clients: HashMap<u64, BlockedClient>,
+
+    /// Identifiers of the clients waiting on each key, in the order they
+    /// blocked
+    by_key: HashMap<Bytes, VecDeque<u64>>,
+
+    /// Keys that were written to since clients were last served
+    ready: Vec<Bytes>,
+
+    next_id: u64,
+}
+
+#[derive(Debug)]
+struct BlockedClient {
+    command: Box<dyn BlockingCommand>,
+
+    /// Where the reply goes once the command is served
+    sender: oneshot::Sender<Frame>,
+}
+
+/// A client waiting for a blocking command to be served.
+///
+/// Dropping it, when the client disconnects for instance, unregisters the
+/// command so it is never served.
+#[derive(Debug)]
+pub(crate) struct Blocked {
+    db: Db,
+    id: u64,
+    receiver: oneshot::Receiver<Frame>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    value: Value,
+
+    /// Unix time in milliseconds after which the key no longer exists
+    expires_at: Option<u64>,
+}
+
+/// Values that can be stored under a key.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    String(Bytes),
+    List(VecDeque<Bytes>),
+    Hash(HashMap<Bytes, Bytes>),
+    Set(Set),
+    SortedSet(SortedSet),
+    Stream(Stream),
+}
+
+/// Returns the current unix time in milliseconds.
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl Db {
+    /// Creates an empty keyspace and spawns the task purging expired keys,
+    /// so it must be called from within a tokio runtime.
+    pub(crate) fn new() -> Db {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State::default()),
+            broker: Mutex::new(Broker::default()),
+            background_task: Arc::new(Notify::new()),
+        });
+
+        tokio::spawn(purge_expired_keys(
+            Arc::downgrade(&shared),
+            shared.background_task.clone(),
+        ));
+
+        Db { shared }
+    }
+
+    /// Runs `f` with exclusive access to the keyspace.
+    pub(crate) fn with_state<T>(&self, f: impl FnOnce(&mut State) -> T) -> T {
+        let mut state = self.shared.state.lock().unwrap();
+
+        let next_expiration = state.next_expiration();
+        let result = f(&mut state);
+
+        // The command may have pushed to keys blocked clients wait on
+        state.serve_blocked();
+
+        // Only wake the background task up if it now has to run sooner
+        // than it planned to.
+        if let Some(when) = state.next_expiration() {
+            if next_expiration.is_none_or(|next| when < next) {
+                self.shared.background_task.notify_one();
+            }
+        }
+
+        result
+    }
+
+    /// Runs `f` with exclusive access to the pub/sub subscriptions.
+    pub(crate) fn with_broker<T>(&self, f: impl FnOnce(&mut Broker) -> T) -> T {
+        f(&mut self.shared.broker.lock().unwrap())
+    }
+
+    /// Executes `command` right away if it can be served, otherwise blocks
+    /// the client until another one makes it possible.
+    ///
+    /// Trying and blocking happen under the same lock, so a push in between
+    /// can not be missed.
+    pub(crate) fn execute_or_block<C>(&self, command: C) -> Result<Frame, Blocked>
+    where
+        C: BlockingCommand + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+
+        self.with_state(|state| match command.try_execute(state) {
+            Some(response) => Ok(response),
+            None => Err(Blocked {
+                db: self.clone(),
+                id: state.block(Box::new(command), sender),
+                receiver,
+            }),
+        })
+    }
+}
+
+impl Blocked {
+    /// Waits for the command to be served, at most `timeout` if given.
+    ///
+    /// Returns `None` if the timeout elapsed first.
+    pub(crate) async fn wait(mut self, timeout: Option<Duration>) -> Option<Frame> {
+        let response = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, &mut self.receiver).await.ok(),
+            None => Some((&mut self.receiver).await),
+        };
+
+        if let Some(Ok(response)) = response {
+            return Some(response);
+        }
+
+        // The command may have been served right before it was unregistered
+        self.db.with_state(|state| state.unblock(self.id));
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for Blocked {
+    fn drop(&mut self) {
+        self.db.with_state(|state| state.unblock(self.id));
+    }
+}
+
+impl Shared {
+    /// Removes the expired keys and returns the deadline of the next key to
+    /// expire, if any.
+    fn purge_expired_keys(&self) -> Option<u64> {
+        let mut state = self.state.lock().unwrap();
+        state.purge_expired_keys(now_ms());
+        state.next_expiration()
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        // Let the background task notice the keyspace is gone
+        self.background_task.notify_one();
+    }
+}
+
+/// Background task purging expired keys.
+///
+/// It sleeps until the next deadline or until notified, and exits once every
+/// `Db` handle has been dropped.
+async fn purge_expired_keys(shared: Weak<Shared>, notify: Arc<Notify>) {
+    loop {
+        let next_expiration = match shared.upgrade() {
+            Some(shared) => shared.purge_expired_keys(),
+            None => return,
+        };
+
+        match next_expiration {
+            Some(when) => {
+                let delay = Duration::from_millis(when.saturating_sub(now_ms()));
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = notify.notified() => {}
+                }
+            }
+            None => notify.notified().await,
+        }
+    }
+}
+
+impl State {
+    /// Returns the value stored at `key`, if any.
+    ///
+    /// An expired key is removed on access and reported as missing.
+    pub(crate) fn get(&mut self, key: &[u8]) -> Option<&Value> {
+        self.expire_if_needed(key);
+        self.entries.get(key).map(|entry| &entry.value)
+    }
+
+    /// Returns the values stored at `keys`, like `get` does for each of
+    /// them.
+    pub(crate) fn get_all(&mut self, keys: &[Bytes]) -> Vec<Option<&Value>> {
+        for key in keys {
+            self.expire_if_needed(key);
+        }
+        keys.iter()
+            .map(|key| self.entries.get(key).map(|entry| &entry.value))
+            .collect()
+    }
+
+    /// Returns a mutable reference to the value stored at `key`, if any.
+    ///
+    /// The caller must `touch` the key once it actually modified the value.
+    pub(crate) fn get_mut(&mut self, key: &[u8]) -> Option<&mut Value> {
+        self.expire_if_needed(key);
+        self.entries.get_mut(key).map(|entry| &mut entry.value)
+    }
+
+    /// Returns a mutable reference to the value stored at `key`, storing the
+    /// value built by `default` first if the key does not exist.
+    ///
+    /// The caller must `touch` the key once it actually modified the value.
+    pub(crate) fn get_or_insert_with(
+        &mut self,
+        key: Bytes,
+        default: impl FnOnce() -> Value,
+    ) -> &mut Value {
+        self.expire_if_needed(&key);
+
+        let entry = self.entries.entry(key).or_insert_with(|| Entry {
+            value: default(),
+            expires_at: None,
+        });
+        &mut entry.value
+    }
+
+    /// Stores `value` at `key`, expiring at `expires_at` (unix time in
+    /// milliseconds) if given. Returns the previous value.
+    pub(crate) fn insert(
+        &mut self,
+        key: Bytes,
+        value: Value,
+        expires_at: Option<u64>,
+    ) -> Option<Value> {
+        self.expire_if_needed(&key);
+        self.touch(&key);
+
+        if let Some(when) = expires_at {
+            self.expirations.insert((when, key.clone()));
+        }
+
+        let previous = self
+            .entries
+            .insert(key.clone(), Entry { value, expires_at })?;
+        if let Some(when) = previous.expires_at {
+            if Some(when) != expires_at {
+                self.expirations.remove(&(when, key));
+            }
+        }
+
+        Some(previous.value)
+    }
+
+    /// Removes `key` from the keyspace, returning its value.
+    pub(crate) fn remove(&mut self, key: &[u8]) -> Option<Value> {
+        self.expire_if_needed(key);
+
+        let (key, entry) = self.entries.remove_entry(key)?;
+        self.touch(&key);
+        if let Some(when) = entry.expires_at {
+            self.expirations.remove(&(when, key));
+        }
+
+        Some(entry.value)
+    }
+
+    /// Removes every key.
+    pub(crate) fn clear(&mut self) {
+        for (key, watched) in &mut self.watched {
+            if self.entries.contains_key(key) {
+                watched.version += 1;
+            }
+        }
+
+        self.entries.clear();
+        self.expirations.clear();
+    }
+
+    /// Returns `true` if `key` exists.
+    pub(crate) fn contains(&mut self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the deadline of `key` as unix time in milliseconds.
+    ///
+    /// The outer `Option` is `None` if the key does not exist, the inner one
+    /// is `None` if the key exists but has no time to live.
+    pub(crate) fn expires_at(&mut self, key: &[u8]) -> Option<Option<u64>> {
+        self.expire_if_needed(key);
+        self.entries.get(key).map(|entry| entry.expires_at)
+    }
+
+    /// Sets or clears the deadline of `key`. A deadline in the past deletes
+    /// the key right away.
+    ///
+    /// Returns `false` if the key does not exist.
+    pub(crate) fn set_expires_at(&mut self, key: &[u8], expires_at: Option<u64>) -> bool {
+        self.expire_if_needed(key);
+
+        let Some((key, entry)) = self.entries.get_key_value(key) else {
+            return false;
+        };
+        let key = key.clone();
+
+        if let Some(when) = entry.expires_at {
+            self.expirations.remove(&(when, key.clone()));
+        }
+        self.touch(&key);
+
+        match expires_at {
+            Some(when) if when <= now_ms() => {
+                self.entries.remove(&key);
+            }
+            _ => {
+                if let Some(when) = expires_at {
+                    self.expirations.insert((when, key.clone()));
+                }
+                if let Some(entry) = self.entries.get_mut(&key) {
+                    entry.expires_at = expires_at;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Starts watching `key` for a client, and returns its current version.
+    pub(crate) fn watch(&mut self, key: Bytes) -> u64 {
+        // A key that expired before being watched has not changed since
+        self.expire_if_needed(&key);
+
+        let watched = self.watched.entry(key).or_default();
+        watched.clients += 1;
+        watched.version
+    }
+
+    /// Stops watching `key` for a client.
+    pub(crate) fn unwatch(&mut self, key: &[u8]) {
+        if let Some(watched) = self.watched.get_mut(key) {
+            watched.clients -= 1;
+            if watched.clients == 0 {
+                self.watched.remove(key);
+            }
+        }
+    }
+
+    /// Returns the version of the watched `key`, which changed if the key
+    /// was modified, expired or deleted since it was returned by `watch`.
+    pub(crate) fn version(&mut self, key: &[u8]) -> u64 {
+        // The key may have expired without being accessed since
+        self.expire_if_needed(key);
+        self.watched.get(key).map_or(0, |watched| watched.version)
+    }
+
+    /// Tells the clients blocked on `key` that it was written to. They are
+    /// served once the current command completes.
+    pub(crate) fn signal_ready(&mut self, key: &Bytes) {
+        if self.blocked.by_key.contains_key(key) {
+            self.blocked.ready.push(key.clone());
+        }
+    }
+
+    /// Registers a blocked client, whose reply is sent through `sender`.
+    fn block(&mut self, command: Box<dyn BlockingCommand>, sender: oneshot::Sender<Frame>) -> u64 {
+        let id = self.blocked.next_id;
+        self.blocked.next_id += 1;
+
+        for key in command.keys() {
+            self.blocked
+                .by_key
+                .entry(key.clone())
+                .or_default()
+                .push_back(id);
+        }
+        self.blocked
+            .clients
+            .insert(id, BlockedClient { command, sender });
+
+        id
+    }
+
+    /// Unregisters a blocked client, if it was not served yet.
+    fn unblock(&mut self, id: u64) {
+        if let Some(client) = self.blocked.clients.remove(&id) {
+            self.blocked.forget(id, client.command.keys());
+        }
+    }
+
+    /// Serves the clients blocked on the keys signaled as ready, in the
+    /// order they blocked. Serving a client may make other keys ready, which
+    /// are served in turn.
+    fn serve_blocked(&mut self) {
+        while !self.blocked.ready.is_empty() {
+            for key in std::mem::take(&mut self.blocked.ready) {
+                let Some(waiting) = self.blocked.by_key.get(&key) else {
+                    continue;
+                };
+
+                for id in waiting.clone() {
+                    // Served through another key already
+                    let Some(client) = self.blocked.clients.remove(&id) else {
+                        continue;
+                    };
+
+                    match client.command.try_execute(self) {
+                        Some(response) => {
+                            self.blocked.forget(id, client.command.keys());
+                            // The client task unregisters itself before
+                            // dropping the receiver, so it is still there
+                            let _ = client.sender.send(response);
+                        }
+                        None => {
+                            self.blocked.clients.insert(id, client);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the earliest deadline among the keys with a time to live.
+    fn next_expiration(&self) -> Option<u64> {
+        self.expirations.iter().next().map(|(when, _)| *when)
+    }
+
+    /// Removes every key whose deadline is not after `now`.
+    fn purge_expired_keys(&mut self, now: u64) {
+        while let Some((when, key)) = self.expirations.first() {
+            if *when > now {
+                break;
+            }
+
+            let key = key.clone();
+            self.expirations.pop_first();
+            self.entries.remove(&key);
+            self.touch(&key);
+        }
+    }
+
+    /// Tells the clients watching `key` that it was modified, which makes
+    /// their transaction fail. Called by the commands writing through
+    /// `get_mut` or `get_or_insert_with`, the other methods doing it
+    /// themselves.
+    pub(crate) fn touch(&mut self, key: &[u8]) {
+        if let Some(watched) = self.watched.get_mut(key) {
+            watched.version += 1;
+        }
+    }
+
+    /// Lazily removes `key` if its deadline has passed.
+    fn expire_if_needed(&mut self, key: &[u8]) {
+        let Some((key, entry)) = self.entries.get_key_value(key) else {
+            return;
+        };
+
+        if let Some(when) = entry.expires_at {
+            if when <= now_ms() {
+                let key = key.clone();
+                self.expirations.remove(&(when, key.clone()));
+                self.entries.remove(&key);
+                self.touch(&key);
+            }
+        }
+    }
+}
+
+impl BlockedClients {
+    /// Removes the client `id` from the queues of `keys`.
+    fn forget(&mut self, id: u64, keys: &[Bytes]) {
+        for key in keys {
+            if let Some(waiting) = self.by_key.get_mut(key) {
+                waiting.retain(|waiting| *waiting != id);
+                if waiting.is_empty() {
+                    self.by_key.remove(key);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/db_test.rs"]
+mod db_test;
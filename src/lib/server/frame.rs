@@ -1,17 +1,45 @@
-use bytes::{Buf, Bytes};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use core::fmt;
 use std::io::Cursor;
-use std::num::TryFromIntError;
-use std::string::FromUtf8Error;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Frame {
     Simple(String),
     Error(String),
-    Integer(u64),
+    Integer(i64),
     Bulk(Bytes),
     Array(Vec<Frame>),
     Null,
+    /// Null array, `*-1` in RESP2
+    NullArray,
+
+    /// RESP3 map, an ordered list of key/value pairs
+    Map(Vec<(Frame, Frame)>),
+    /// RESP3 unordered collection of unique elements
+    Set(Vec<Frame>),
+    /// RESP3 floating point number
+    Double(f64),
+    /// RESP3 boolean
+    Boolean(bool),
+    /// RESP3 integer of arbitrary size, kept as its decimal representation
+    BigNumber(String),
+    /// RESP3 verbatim string: a three letters format (`txt`, `mkd`, ...) and
+    /// the text itself
+    Verbatim(String, Bytes),
+    /// RESP3 attributes, auxiliary key/value pairs attached to the frame that
+    /// follows them
+    Attribute(Vec<(Frame, Frame)>, Box<Frame>),
+    /// RESP3 out of band data, such as pub/sub messages
+    Push(Vec<Frame>),
+}
+
+/// Version of the protocol spoken on a connection, negotiated with `HELLO`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Protocol {
+    /// RESP2, the default until the client switches with `HELLO 3`
+    #[default]
+    Resp2,
+    Resp3,
 }
 
 #[derive(Debug)]
@@ -19,116 +47,196 @@ pub enum Error {
     /// Not enough data is available to parse a message
     Incomplete,
 
-    /// Invalid message encoding
-    Other(crate::Error),
+    /// The first byte of a frame does not match any frame type
+    InvalidTypeByte(u8),
+
+    /// An integer frame or a length is not a valid decimal number
+    InvalidInteger,
+
+    /// A length is negative or does not fit in memory
+    InvalidLength,
+
+    /// A simple string, an error or a verbatim format is not valid UTF-8
+    InvalidUtf8,
+
+    /// The content of a frame does not match its type
+    InvalidFormat,
+
+    /// Aggregates are nested deeper than `MAX_DEPTH`
+    TooDeep,
+
+    /// An element was pushed into a frame which is not an array
+    NotAnArray,
+
+    /// An inline command is longer than `inline::MAX_INLINE_SIZE`
+    InlineTooBig,
+
+    /// A quoted argument of an inline command is not closed
+    UnbalancedQuotes,
 }
 
+/// Maximum nesting of aggregates. Clients only send flat arrays, the limit
+/// keeps a malicious frame from overflowing the stack.
+const MAX_DEPTH: usize = 128;
+
 impl Frame {
     /// Returns an empty array
     pub(crate) fn new() -> Frame {
         Frame::Array(vec![])
     }
 
-    pub(crate) fn push_bulk(&mut self, bytes: Bytes) {
+    /// Appends a bulk string to an array frame. Only the tests build frames
+    /// this way.
+    #[cfg(test)]
+    pub(crate) fn push_bulk(&mut self, bytes: Bytes) -> Result<(), Error> {
         match self {
-            Frame::Array(vec) => vec.push(Frame::Bulk(bytes)),
-            _ => panic!("Not an array frame"),
+            Frame::Array(vec) => {
+                vec.push(Frame::Bulk(bytes));
+                Ok(())
+            }
+            _ => Err(Error::NotAnArray),
         }
     }
 
-    pub(crate) fn push_int(&mut self, value: u64) {
+    /// Appends an integer to an array frame.
+    #[cfg(test)]
+    pub(crate) fn push_int(&mut self, value: i64) -> Result<(), Error> {
         match self {
-            Frame::Array(vec) => vec.push(Frame::Integer(value)),
-            _ => panic!("Not an array frame"),
+            Frame::Array(vec) => {
+                vec.push(Frame::Integer(value));
+                Ok(())
+            }
+            _ => Err(Error::NotAnArray),
         }
     }
 
+    /// Checks that a whole frame is available at the current position of
+    /// `src`, leaving the cursor right after it.
+    ///
+    /// The frame is validated the same way `parse` does, so `parse` succeeds
+    /// whenever `check` did.
     pub(crate) fn check(src: &mut Cursor<&[u8]>) -> Result<(), Error> {
-        match get_u8(src)? {
-            b'+' => {
-                get_line(src)?;
-                Ok(())
-            }
-            b'-' => {
-                get_line(src)?;
-                Ok(())
-            }
-            b':' => {
-                get_decimal(src)?;
-                Ok(())
-            }
-            b'$' => {
-                if b'-' == peek_u8(src)? {
-                    // skip '-1\r\n'
-                    skip(src, 4)
-                } else {
-                    // read the bulk string
-                    let len: usize = get_decimal(src)?.try_into()?;
-
-                    // skip that number of bytes + 2 (\r\n).
-                    skip(src, len + 2)
-                }
-            }
-            b'*' => {
-                let len = get_decimal(src)?;
-
-                for _ in 0..len {
-                    Frame::check(src)?;
-                }
-
-                Ok(())
-            }
-            val => Err(format!("protocol error; invalid frame type byte `{}`", val).into()),
-        }
+        check_frame(src, 0)
     }
 
     pub fn parse(src: &mut Cursor<&[u8]>) -> Result<Frame, Error> {
-        match get_u8(src)? {
-            b'+' => {
-               let simple_string = get_line(src)?.to_vec();
-               Ok(Frame::Simple(String::from_utf8(simple_string)?))
+        parse_frame(src, 0)
+    }
+
+    /// Encodes the frame into its RESP representation, appending the bytes to
+    /// `dst`. Arrays are encoded recursively, so nested arrays are supported.
+    ///
+    /// With `Protocol::Resp2`, RESP3 only types are downgraded to their RESP2
+    /// equivalent the same way Redis does: maps become flat arrays of keys
+    /// and values, sets and pushes become arrays, doubles, big numbers and
+    /// verbatim strings become bulks, booleans become integers and attributes
+    /// are dropped.
+    pub fn serialize(&self, dst: &mut BytesMut, protocol: Protocol) {
+        match self {
+            Frame::Simple(val) => {
+                dst.put_u8(b'+');
+                dst.put_slice(val.as_bytes());
+                dst.put_slice(b"\r\n");
             }
-            b'-' => {
-                let err = get_line(src)?.to_vec();
-                Ok(Frame::Error(String::from_utf8(err)?))
+            Frame::Error(val) => {
+                dst.put_u8(b'-');
+                dst.put_slice(val.as_bytes());
+                dst.put_slice(b"\r\n");
             }
-            b':' => {
-                let int = get_decimal(src)?;
-                Ok(Frame::Integer(int))
+            Frame::Integer(val) => {
+                dst.put_u8(b':');
+                put_decimal(dst, *val);
             }
-            b'$' => {
-                if b'-' == peek_u8(src)? {
-                    let string = get_line(src)?;
-                    if string != b"-1" {
-                        return Err(format!("protocol invalid; invalid frame format").into());
+            Frame::Bulk(val) => put_bulk(dst, val),
+            Frame::Null => match protocol {
+                Protocol::Resp2 => dst.put_slice(b"$-1\r\n"),
+                Protocol::Resp3 => dst.put_slice(b"_\r\n"),
+            },
+            Frame::NullArray => match protocol {
+                Protocol::Resp2 => dst.put_slice(b"*-1\r\n"),
+                Protocol::Resp3 => dst.put_slice(b"_\r\n"),
+            },
+            Frame::Array(val) => put_aggregate(dst, b'*', val, protocol),
+            Frame::Map(val) => match protocol {
+                Protocol::Resp2 => {
+                    dst.put_u8(b'*');
+                    put_decimal(dst, 2 * val.len() as i64);
+                    put_pairs(dst, val, protocol);
+                }
+                Protocol::Resp3 => {
+                    dst.put_u8(b'%');
+                    put_decimal(dst, val.len() as i64);
+                    put_pairs(dst, val, protocol);
+                }
+            },
+            Frame::Set(val) => match protocol {
+                Protocol::Resp2 => put_aggregate(dst, b'*', val, protocol),
+                Protocol::Resp3 => put_aggregate(dst, b'~', val, protocol),
+            },
+            Frame::Push(val) => match protocol {
+                Protocol::Resp2 => put_aggregate(dst, b'*', val, protocol),
+                Protocol::Resp3 => put_aggregate(dst, b'>', val, protocol),
+            },
+            Frame::Double(val) => {
+                let val = format_double(*val);
+                match protocol {
+                    Protocol::Resp2 => put_bulk(dst, val.as_bytes()),
+                    Protocol::Resp3 => {
+                        dst.put_u8(b',');
+                        dst.put_slice(val.as_bytes());
+                        dst.put_slice(b"\r\n");
                     }
-                    Ok(Frame::Null)
-                } else {
-                   let len: usize = get_decimal(src)?.try_into()?;
-                   let n = len + 2;
-
-                   if src.remaining() < n {
-                    return Err(Error::Incomplete);
-                   }
-
-                   let bulk =  Bytes::copy_from_slice(&src.chunk()[..len]);
-
-                   skip(src, n)?;
-
-                   Ok(Frame::Bulk(bulk))
                 }
             }
-            b'*' => {
-                let len = get_decimal(src)?.try_into()?;
-                let mut output = Vec::with_capacity(len);
-                for _ in 0..len {
-                    output.push(Frame::parse(src)?);
+            Frame::Boolean(val) => match protocol {
+                Protocol::Resp2 => {
+                    dst.put_u8(b':');
+                    put_decimal(dst, *val as i64);
                 }
-                Ok(Frame::Array(output))
+                Protocol::Resp3 => dst.put_slice(if *val { b"#t\r\n" } else { b"#f\r\n" }),
+            },
+            Frame::BigNumber(val) => match protocol {
+                Protocol::Resp2 => put_bulk(dst, val.as_bytes()),
+                Protocol::Resp3 => {
+                    dst.put_u8(b'(');
+                    dst.put_slice(val.as_bytes());
+                    dst.put_slice(b"\r\n");
+                }
+            },
+            Frame::Verbatim(format, text) => match protocol {
+                Protocol::Resp2 => put_bulk(dst, text),
+                Protocol::Resp3 => {
+                    dst.put_u8(b'=');
+                    put_decimal(dst, (format.len() + 1 + text.len()) as i64);
+                    dst.put_slice(format.as_bytes());
+                    dst.put_u8(b':');
+                    dst.put_slice(text);
+                    dst.put_slice(b"\r\n");
+                }
+            },
+            Frame::Attribute(attributes, frame) => {
+                if protocol == Protocol::Resp3 {
+                    dst.put_u8(b'|');
+                    put_decimal(dst, attributes.len() as i64);
+                    put_pairs(dst, attributes, protocol);
+                }
+                frame.serialize(dst, protocol);
             }
-            _ => unimplemented!(),
         }
     }
+
+    /// Returns the RESP representation of the frame.
+    pub fn to_bytes(&self, protocol: Protocol) -> Bytes {
+        let mut dst = BytesMut::new();
+        self.serialize(&mut dst, protocol);
+        dst.freeze()
+    }
+}
+
+impl Default for Frame {
+    fn default() -> Frame {
+        Frame::new()
+    }
 }
 
 impl PartialEq<&str> for Frame {
@@ -153,8 +261,22 @@ impl fmt::Display for Frame {
                 Ok(string) => string.fmt(fmt),
                 Err(_) => write!(fmt, "{:?}", msg),
             },
-            Frame::Null => "(nil)".fmt(fmt),
-            Frame::Array(parts) => {
+            Frame::Null | Frame::NullArray => "(nil)".fmt(fmt),
+            Frame::Double(num) => format_double(*num).fmt(fmt),
+            Frame::Boolean(val) => val.fmt(fmt),
+            Frame::BigNumber(num) => num.fmt(fmt),
+            Frame::Verbatim(_, text) => Frame::Bulk(text.clone()).fmt(fmt),
+            Frame::Attribute(_, frame) => frame.fmt(fmt),
+            Frame::Map(pairs) => {
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        write!(fmt, " ")?;
+                    }
+                    write!(fmt, "{} {}", key, value)?;
+                }
+                Ok(())
+            }
+            Frame::Array(parts) | Frame::Set(parts) | Frame::Push(parts) => {
                 for (i, part) in parts.iter().enumerate() {
                     if i > 0 {
                         // use space as the array element display separator
@@ -168,12 +290,102 @@ impl fmt::Display for Frame {
     }
 }
 
-fn peek_u8(src: &mut Cursor<&[u8]>) -> Result<u8, Error> {
-    if !src.has_remaining() {
-        return Err(Error::Incomplete);
+fn check_frame(src: &mut Cursor<&[u8]>, depth: usize) -> Result<(), Error> {
+    if depth > MAX_DEPTH {
+        return Err(Error::TooDeep);
     }
 
-    Ok(src.chunk()[0])
+    match get_u8(src)? {
+        b'+' | b'-' => {
+            get_string(src)?;
+            Ok(())
+        }
+        b':' => {
+            get_decimal(src)?;
+            Ok(())
+        }
+        b'$' => {
+            get_bulk(src)?;
+            Ok(())
+        }
+        b'*' => {
+            // `*-1\r\n` is a null array
+            let len = get_length(src)?.unwrap_or(0);
+            check_entries(src, len, depth + 1)
+        }
+        b'~' | b'>' => {
+            let len = get_count(src)?;
+            check_entries(src, len, depth + 1)
+        }
+        b'%' => check_pairs(src, depth + 1),
+        b'|' => {
+            check_pairs(src, depth + 1)?;
+
+            // attributes are followed by the frame they describe
+            check_frame(src, depth + 1)
+        }
+        b'_' => get_null(src),
+        b'#' => {
+            get_boolean(src)?;
+            Ok(())
+        }
+        b',' => {
+            get_double(src)?;
+            Ok(())
+        }
+        b'(' => {
+            get_big_number(src)?;
+            Ok(())
+        }
+        b'=' => {
+            get_verbatim(src)?;
+            Ok(())
+        }
+        byte => Err(Error::InvalidTypeByte(byte)),
+    }
+}
+
+fn parse_frame(src: &mut Cursor<&[u8]>, depth: usize) -> Result<Frame, Error> {
+    if depth > MAX_DEPTH {
+        return Err(Error::TooDeep);
+    }
+
+    match get_u8(src)? {
+        b'+' => Ok(Frame::Simple(get_string(src)?.to_string())),
+        b'-' => Ok(Frame::Error(get_string(src)?.to_string())),
+        b':' => Ok(Frame::Integer(get_decimal(src)?)),
+        b'$' => match get_bulk(src)? {
+            None => Ok(Frame::Null),
+            Some(data) => Ok(Frame::Bulk(Bytes::copy_from_slice(data))),
+        },
+        b'*' => match get_length(src)? {
+            None => Ok(Frame::NullArray),
+            Some(len) => Ok(Frame::Array(parse_entries(src, len, depth + 1)?)),
+        },
+        b'~' => Ok(Frame::Set(parse_frames(src, depth + 1)?)),
+        b'>' => Ok(Frame::Push(parse_frames(src, depth + 1)?)),
+        b'%' => Ok(Frame::Map(parse_pairs(src, depth + 1)?)),
+        b'|' => {
+            let attributes = parse_pairs(src, depth + 1)?;
+            let frame = parse_frame(src, depth + 1)?;
+            Ok(Frame::Attribute(attributes, Box::new(frame)))
+        }
+        b'_' => {
+            get_null(src)?;
+            Ok(Frame::Null)
+        }
+        b'#' => Ok(Frame::Boolean(get_boolean(src)?)),
+        b',' => Ok(Frame::Double(get_double(src)?)),
+        b'(' => Ok(Frame::BigNumber(get_big_number(src)?.to_string())),
+        b'=' => {
+            let (format, text) = get_verbatim(src)?;
+            Ok(Frame::Verbatim(
+                format.to_string(),
+                Bytes::copy_from_slice(text),
+            ))
+        }
+        byte => Err(Error::InvalidTypeByte(byte)),
+    }
 }
 
 fn get_u8(src: &mut Cursor<&[u8]>) -> Result<u8, Error> {
@@ -184,64 +396,208 @@ fn get_u8(src: &mut Cursor<&[u8]>) -> Result<u8, Error> {
     Ok(src.get_u8())
 }
 
-fn skip(src: &mut Cursor<&[u8]>, n: usize) -> Result<(), Error> {
-    if src.remaining() < n {
-        return Err(Error::Incomplete);
+fn get_decimal(src: &mut Cursor<&[u8]>) -> Result<i64, Error> {
+    use atoi::FromRadix10SignedChecked;
+
+    let line = get_line(src)?;
+
+    // the whole line must be a number, not only a prefix of it
+    match i64::from_radix_10_signed_checked(line) {
+        (Some(val), used) if used == line.len() && line.last().is_some_and(u8::is_ascii_digit) => {
+            Ok(val)
+        }
+        _ => Err(Error::InvalidInteger),
+    }
+}
+
+/// Reads the length of a bulk string or an array. A length of -1 stands for
+/// null and is returned as `None`.
+fn get_length(src: &mut Cursor<&[u8]>) -> Result<Option<usize>, Error> {
+    match get_decimal(src)? {
+        -1 => Ok(None),
+        len => len.try_into().map(Some).map_err(|_| Error::InvalidLength),
+    }
+}
+
+/// Reads the length of a RESP3 aggregate or string, which can not be null.
+fn get_count(src: &mut Cursor<&[u8]>) -> Result<usize, Error> {
+    get_decimal(src)?
+        .try_into()
+        .map_err(|_| Error::InvalidLength)
+}
+
+/// Reads a line holding a simple string or an error message.
+fn get_string<'a>(src: &mut Cursor<&'a [u8]>) -> Result<&'a str, Error> {
+    std::str::from_utf8(get_line(src)?).map_err(|_| Error::InvalidUtf8)
+}
+
+/// Reads a bulk string, `None` standing for the null bulk string.
+fn get_bulk<'a>(src: &mut Cursor<&'a [u8]>) -> Result<Option<&'a [u8]>, Error> {
+    match get_length(src)? {
+        None => Ok(None),
+        Some(len) => get_data(src, len).map(Some),
+    }
+}
+
+fn get_null(src: &mut Cursor<&[u8]>) -> Result<(), Error> {
+    if !get_line(src)?.is_empty() {
+        return Err(Error::InvalidFormat);
     }
 
-    src.advance(n);
     Ok(())
 }
 
-fn get_decimal(src: &mut Cursor<&[u8]>) -> Result<u64, Error> {
-    use atoi::atoi;
+fn get_boolean(src: &mut Cursor<&[u8]>) -> Result<bool, Error> {
+    match get_line(src)? {
+        b"t" => Ok(true),
+        b"f" => Ok(false),
+        _ => Err(Error::InvalidFormat),
+    }
+}
 
-    let line = get_line(src)?;
+fn get_double(src: &mut Cursor<&[u8]>) -> Result<f64, Error> {
+    get_string(src)?.parse().map_err(|_| Error::InvalidFormat)
+}
+
+fn get_big_number<'a>(src: &mut Cursor<&'a [u8]>) -> Result<&'a str, Error> {
+    let line = get_string(src)?;
 
-    atoi::<u64>(line).ok_or_else(|| "protocol error; invalid frame format".into())
+    let digits = line.strip_prefix('-').unwrap_or(line);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(Error::InvalidFormat);
+    }
+
+    Ok(line)
 }
 
-fn get_line<'a>(src: &mut Cursor<&'a [u8]>) -> Result<&'a [u8], Error> {
-    // Scan the bytes directly
+/// Reads a verbatim string, returning its format and its text.
+fn get_verbatim<'a>(src: &mut Cursor<&'a [u8]>) -> Result<(&'a str, &'a [u8]), Error> {
+    let len = get_count(src)?;
+    let data = get_data(src, len)?;
+
+    // the text is prefixed by its format and a colon, e.g. `txt:`
+    if len < 4 || data[3] != b':' {
+        return Err(Error::InvalidFormat);
+    }
+    let format = std::str::from_utf8(&data[..3]).map_err(|_| Error::InvalidUtf8)?;
+
+    Ok((format, &data[4..]))
+}
+
+/// Reads `len` bytes of data, which must be followed by `\r\n`.
+fn get_data<'a>(src: &mut Cursor<&'a [u8]>, len: usize) -> Result<&'a [u8], Error> {
     let start = src.position() as usize;
-    // Scan to the second to last byte
-    let end = src.get_ref().len() - 1;
+    let buf: &'a [u8] = src.get_ref();
+    let buf = buf.get(start..).unwrap_or_default();
 
-    for i in start..end {
-        if src.get_ref()[i] == b'\r' && src.get_ref()[i + 1] == b'\n' {
-            // We found a line, update the position to be *after* the \n
-            src.set_position((i + 2) as u64);
+    if buf.len() < 2 || buf.len() - 2 < len {
+        return Err(Error::Incomplete);
+    }
 
-            // Return the line
-            return Ok(&src.get_ref()[start..i]);
-        }
+    if &buf[len..len + 2] != b"\r\n" {
+        return Err(Error::InvalidFormat);
     }
 
-    Err(Error::Incomplete)
+    src.set_position((start + len + 2) as u64);
+    Ok(&buf[..len])
+}
+
+fn check_entries(src: &mut Cursor<&[u8]>, len: usize, depth: usize) -> Result<(), Error> {
+    for _ in 0..len {
+        check_frame(src, depth)?;
+    }
+
+    Ok(())
 }
 
+fn check_pairs(src: &mut Cursor<&[u8]>, depth: usize) -> Result<(), Error> {
+    let len = get_count(src)?;
 
-impl From<String> for Error {
-    fn from(src: String) -> Error {
-        Error::Other(src.into())
+    // every entry is made of a key and a value
+    for _ in 0..len {
+        check_frame(src, depth)?;
+        check_frame(src, depth)?;
     }
+
+    Ok(())
 }
 
-impl From<&str> for Error {
-    fn from(src: &str) -> Error {
-        src.to_string().into()
+fn parse_frames(src: &mut Cursor<&[u8]>, depth: usize) -> Result<Vec<Frame>, Error> {
+    let len = get_count(src)?;
+    parse_entries(src, len, depth)
+}
+
+fn parse_entries(src: &mut Cursor<&[u8]>, len: usize, depth: usize) -> Result<Vec<Frame>, Error> {
+    // the length comes from the client, do not trust it to allocate memory
+    let mut output = Vec::with_capacity(len.min(src.remaining()));
+    for _ in 0..len {
+        output.push(parse_frame(src, depth)?);
     }
+    Ok(output)
+}
+
+fn parse_pairs(src: &mut Cursor<&[u8]>, depth: usize) -> Result<Vec<(Frame, Frame)>, Error> {
+    let len = get_count(src)?;
+    let mut output = Vec::with_capacity(len.min(src.remaining()));
+    for _ in 0..len {
+        let key = parse_frame(src, depth)?;
+        let value = parse_frame(src, depth)?;
+        output.push((key, value));
+    }
+    Ok(output)
+}
+
+fn put_bulk(dst: &mut BytesMut, val: &[u8]) {
+    dst.put_u8(b'$');
+    put_decimal(dst, val.len() as i64);
+    dst.put_slice(val);
+    dst.put_slice(b"\r\n");
 }
 
-impl From<FromUtf8Error> for Error {
-    fn from(_: FromUtf8Error) -> Error {
-        "protocol error; invalid frame format".into()
+fn put_aggregate(dst: &mut BytesMut, kind: u8, val: &[Frame], protocol: Protocol) {
+    dst.put_u8(kind);
+    put_decimal(dst, val.len() as i64);
+    for entry in val {
+        entry.serialize(dst, protocol);
     }
 }
 
-impl From<TryFromIntError> for Error {
-    fn from(_: TryFromIntError) -> Error {
-        "protocol error; invalid frame format".into()
+fn put_pairs(dst: &mut BytesMut, val: &[(Frame, Frame)], protocol: Protocol) {
+    for (key, value) in val {
+        key.serialize(dst, protocol);
+        value.serialize(dst, protocol);
+    }
+}
+
+/// Formats a double the way Redis replies with it.
+pub(crate) fn format_double(val: f64) -> String {
+    if val.is_nan() {
+        "nan".to_string()
+    } else {
+        val.to_string()
+    }
+}
+
+fn put_decimal(dst: &mut BytesMut, val: i64) {
+    dst.put_slice(val.to_string().as_bytes());
+    dst.put_slice(b"\r\n");
+}
+
+fn get_line<'a>(src: &mut Cursor<&'a [u8]>) -> Result<&'a [u8], Error> {
+    let start = src.position() as usize;
+    let buf: &'a [u8] = src.get_ref();
+    let buf = buf.get(start..).unwrap_or_default();
+
+    // Scan the bytes directly
+    match buf.windows(2).position(|window| window == b"\r\n") {
+        Some(end) => {
+            // We found a line, update the position to be *after* the \n
+            src.set_position((start + end + 2) as u64);
+
+            // Return the line
+            Ok(&buf[..end])
+        }
+        None => Err(Error::Incomplete),
     }
 }
 
@@ -251,11 +607,25 @@ impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Error::Incomplete => "stream ended early".fmt(fmt),
-            Error::Other(err) => err.fmt(fmt),
+            Error::InvalidTypeByte(byte) => write!(fmt, "invalid frame type byte `{}`", byte),
+            Error::InvalidInteger => "invalid integer".fmt(fmt),
+            Error::InvalidLength => "invalid length".fmt(fmt),
+            Error::InvalidUtf8 => "invalid UTF-8 string".fmt(fmt),
+            Error::InvalidFormat => "invalid frame format".fmt(fmt),
+            Error::TooDeep => "too many nested aggregates".fmt(fmt),
+            Error::NotAnArray => "not an array frame".fmt(fmt),
+            Error::InlineTooBig => "too big inline request".fmt(fmt),
+            Error::UnbalancedQuotes => "unbalanced quotes in request".fmt(fmt),
         }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 #[path = "test/frame_test.rs"]
-mod frame_test;
\ No newline at end of file
+mod frame_test;
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/frame_prop_test.rs"]
+mod frame_prop_test;
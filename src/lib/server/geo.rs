@@ -0,0 +1,328 @@
+//! Geohashes, the positions behind the `GEO*` commands.
+//!
+//! Positions are stored in sorted sets, the score of a member being the
+//! 52 bits geohash of its longitude and latitude, as Redis does: both are
+//! scaled to 26 bits and interleaved, the latitude taking the even bits.
+//! Members close to each other thus tend to have close scores. Latitudes are
+//! limited to the range of the Web Mercator projection.
+//!
+//! Keeping only the first bits of a geohash gives a larger cell of the grid,
+//! whose members have scores in a single range. Searches only read the cell
+//! of the center and its neighbors, at the precision where a cell is about
+//! as large as the searched area.
+
+use std::ops::Range;
+
+const LONGITUDE_MIN: f64 = -180.0;
+const LONGITUDE_MAX: f64 = 180.0;
+const LATITUDE_MIN: f64 = -85.051_128_78;
+const LATITUDE_MAX: f64 = 85.051_128_78;
+
+/// Number of bits of a longitude or a latitude in a geohash
+const STEP: u32 = 26;
+
+/// Earth radius used by Redis, in meters
+const EARTH_RADIUS: f64 = 6_372_797.560_856;
+
+/// Alphabet of the textual geohashes
+const BASE32: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Length of the textual geohashes
+const BASE32_LEN: usize = 11;
+
+/// Half of the circumference of the Earth in the Web Mercator projection,
+/// in meters
+const MERCATOR_MAX: f64 = 20_037_726.37;
+
+/// Area searched around a position, in meters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Shape {
+    Radius(f64),
+    Box { width: f64, height: f64 },
+}
+
+impl Shape {
+    /// Returns the distance between `center` and `position`, both given as
+    /// `(longitude, latitude)`, if `position` is within the shape around
+    /// `center`.
+    pub(crate) fn distance_within(&self, center: (f64, f64), position: (f64, f64)) -> Option<f64> {
+        let (lon1, lat1) = center;
+        let (lon2, lat2) = position;
+
+        match *self {
+            Shape::Radius(radius) => {
+                Some(distance(lon1, lat1, lon2, lat2)).filter(|distance| *distance <= radius)
+            }
+            Shape::Box { width, height } => {
+                // The latitude distance is cheaper, so it is checked first
+                if latitude_distance(lat1, lat2) > height / 2.0
+                    || distance(lon1, lat2, lon2, lat2) > width / 2.0
+                {
+                    return None;
+                }
+                Some(distance(lon1, lat1, lon2, lat2))
+            }
+        }
+    }
+
+    /// Returns the ranges of geohashes to read to find every position within
+    /// the shape around `center`, given as `(longitude, latitude)`. They are
+    /// sorted and do not overlap.
+    pub(crate) fn search_ranges(&self, center: (f64, f64)) -> Vec<Range<u64>> {
+        let (longitude, latitude) = center;
+        let (min_lon, min_lat, max_lon, max_lat) = self.bounding_box(center);
+        let radius = match *self {
+            Shape::Radius(radius) => radius,
+            Shape::Box { width, height } => (width / 2.0).hypot(height / 2.0),
+        };
+
+        let hash = encode(longitude, latitude);
+        let mut step = estimate_step(radius, latitude);
+        let mut cell = Cell::new(hash, step);
+
+        // The neighbors may not reach the edges of the shape when the
+        // center is close to an edge of its cell
+        if step > 1
+            && (cell.lat_max + cell.height < max_lat
+                || cell.lat_min - cell.height > min_lat
+                || cell.lon_max + cell.width < max_lon
+                || cell.lon_min - cell.width > min_lon)
+        {
+            step -= 1;
+            cell = Cell::new(hash, step);
+        }
+
+        let mut ranges = vec![];
+        for lat_offset in -1..=1 {
+            for lon_offset in -1..=1 {
+                // Skip the neighbors the shape does not reach
+                let useless = step >= 2
+                    && ((lat_offset == -1 && cell.lat_min < min_lat)
+                        || (lat_offset == 1 && cell.lat_max > max_lat)
+                        || (lon_offset == -1 && cell.lon_min < min_lon)
+                        || (lon_offset == 1 && cell.lon_max > max_lon));
+                if !useless {
+                    ranges.push(cell.neighbor(lon_offset, lat_offset));
+                }
+            }
+        }
+
+        // Neighbours wrap around, so they may be the same cell
+        ranges.sort_by_key(|range| range.start);
+        ranges.dedup();
+        ranges
+    }
+
+    /// Returns the `(min_lon, min_lat, max_lon, max_lat)` box around the
+    /// shape, computed as Redis does.
+    fn bounding_box(&self, center: (f64, f64)) -> (f64, f64, f64, f64) {
+        let (longitude, latitude) = center;
+        let (width, height) = match *self {
+            Shape::Radius(radius) => (radius, radius),
+            Shape::Box { width, height } => (width / 2.0, height / 2.0),
+        };
+
+        let lat_delta = (height / EARTH_RADIUS).to_degrees();
+        let lon_delta =
+            |latitude: f64| (width / EARTH_RADIUS / latitude.to_radians().cos()).to_degrees();
+
+        // Longitudes are closer towards the poles, so the widest edge of the
+        // box is the one closest to the equator
+        let lon_delta = if latitude < 0.0 {
+            lon_delta(latitude - lat_delta)
+        } else {
+            lon_delta(latitude + lat_delta)
+        };
+
+        (
+            longitude - lon_delta,
+            latitude - lat_delta,
+            longitude + lon_delta,
+            latitude + lat_delta,
+        )
+    }
+}
+
+/// Cell of the grid holding the positions whose geohash starts with the
+/// same `2 * step` bits.
+#[derive(Debug)]
+struct Cell {
+    /// First bits of the geohashes of the cell
+    bits: u64,
+    step: u32,
+    lon_min: f64,
+    lon_max: f64,
+    lat_min: f64,
+    lat_max: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Cell {
+    /// Returns the cell of `step` bits per coordinate holding `hash`.
+    fn new(hash: u64, step: u32) -> Cell {
+        let bits = hash >> (2 * (STEP - step));
+        let width = (LONGITUDE_MAX - LONGITUDE_MIN) / (1u64 << step) as f64;
+        let height = (LATITUDE_MAX - LATITUDE_MIN) / (1u64 << step) as f64;
+        let lon_min = LONGITUDE_MIN + squash(bits >> 1) as f64 * width;
+        let lat_min = LATITUDE_MIN + squash(bits) as f64 * height;
+
+        Cell {
+            bits,
+            step,
+            lon_min,
+            lon_max: lon_min + width,
+            lat_min,
+            lat_max: lat_min + height,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the range of geohashes of the cell `lon_offset` cells east and
+    /// `lat_offset` cells north of this one, wrapping around the edges of
+    /// the grid.
+    fn neighbor(&self, lon_offset: i64, lat_offset: i64) -> Range<u64> {
+        let cells = 1i64 << self.step;
+        let longitude = (squash(self.bits >> 1) as i64 + lon_offset).rem_euclid(cells);
+        let latitude = (squash(self.bits) as i64 + lat_offset).rem_euclid(cells);
+        let bits = spread(latitude as u32) | spread(longitude as u32) << 1;
+
+        let shift = 2 * (STEP - self.step);
+        bits << shift..(bits + 1) << shift
+    }
+}
+
+/// Returns `true` if the position can be stored in a geohash.
+pub(crate) fn is_valid(longitude: f64, latitude: f64) -> bool {
+    (LONGITUDE_MIN..=LONGITUDE_MAX).contains(&longitude)
+        && (LATITUDE_MIN..=LATITUDE_MAX).contains(&latitude)
+}
+
+/// Returns the geohash of a valid position.
+pub(crate) fn encode(longitude: f64, latitude: f64) -> u64 {
+    encode_within(
+        longitude,
+        latitude,
+        (LONGITUDE_MIN, LONGITUDE_MAX),
+        (LATITUDE_MIN, LATITUDE_MAX),
+    )
+}
+
+/// Returns the `(longitude, latitude)` at the center of the area of a
+/// geohash.
+pub(crate) fn decode(hash: u64) -> (f64, f64) {
+    let latitude = squash(hash);
+    let longitude = squash(hash >> 1);
+
+    let center = |bits: u32, min: f64, max: f64| {
+        let scale = max - min;
+        let low = min + (bits as f64 / (1u64 << STEP) as f64) * scale;
+        let high = min + ((bits as f64 + 1.0) / (1u64 << STEP) as f64) * scale;
+        ((low + high) / 2.0).clamp(min, max)
+    };
+
+    (
+        center(longitude, LONGITUDE_MIN, LONGITUDE_MAX),
+        center(latitude, LATITUDE_MIN, LATITUDE_MAX),
+    )
+}
+
+/// Returns the standard textual geohash of the position stored as `hash`.
+///
+/// Standard geohashes cover latitudes from -90 to 90 degrees, so the
+/// position is encoded again, and the last character is always `0` as only
+/// 52 bits are available.
+pub(crate) fn to_base32(hash: u64) -> String {
+    let (longitude, latitude) = decode(hash);
+    let hash = encode_within(longitude, latitude, (-180.0, 180.0), (-90.0, 90.0));
+
+    (0..BASE32_LEN)
+        .map(|i| {
+            let index = match i {
+                10 => 0,
+                i => (hash >> (52 - (i + 1) * 5)) & 0x1f,
+            };
+            BASE32[index as usize] as char
+        })
+        .collect()
+}
+
+/// Returns the distance in meters between two positions, as the crow flies
+/// on a spherical Earth.
+pub(crate) fn distance(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let v = ((lon2.to_radians() - lon1.to_radians()) / 2.0).sin();
+    if v == 0.0 {
+        return latitude_distance(lat1, lat2);
+    }
+
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let u = ((lat2 - lat1) / 2.0).sin();
+    let a = u * u + lat1.cos() * lat2.cos() * v * v;
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+/// Returns the number of bits per coordinate of the cells about as large as
+/// `radius` meters around `latitude`, as Redis'
+/// `geohashEstimateStepsByRadius` does.
+fn estimate_step(radius: f64, latitude: f64) -> u32 {
+    if radius == 0.0 {
+        return STEP;
+    }
+
+    let mut step = 1;
+    let mut range = radius;
+    while range < MERCATOR_MAX {
+        range *= 2.0;
+        step += 1;
+    }
+    // Make sure the radius is included in most cases
+    step -= 2;
+
+    // Cells are narrower towards the poles
+    if latitude.abs() > 66.0 {
+        step -= 1;
+        if latitude.abs() > 80.0 {
+            step -= 1;
+        }
+    }
+
+    step.clamp(1, STEP as i32) as u32
+}
+
+/// Returns the distance in meters between two latitudes on a meridian.
+fn latitude_distance(lat1: f64, lat2: f64) -> f64 {
+    EARTH_RADIUS * (lat2.to_radians() - lat1.to_radians()).abs()
+}
+
+fn encode_within(
+    longitude: f64,
+    latitude: f64,
+    (lon_min, lon_max): (f64, f64),
+    (lat_min, lat_max): (f64, f64),
+) -> u64 {
+    let scale = (1u64 << STEP) as f64;
+    let longitude = (longitude - lon_min) / (lon_max - lon_min) * scale;
+    let latitude = (latitude - lat_min) / (lat_max - lat_min) * scale;
+
+    spread(latitude as u32) | spread(longitude as u32) << 1
+}
+
+/// Moves the bits of `value` to the even bits of the result.
+fn spread(value: u32) -> u64 {
+    (0..32).fold(0, |spread, bit| {
+        spread | ((value as u64 >> bit) & 1) << (2 * bit)
+    })
+}
+
+/// Gathers the even bits of `value`, reversing `spread`.
+fn squash(value: u64) -> u32 {
+    (0..32).fold(0, |squashed, bit| {
+        squashed | (((value >> (2 * bit)) & 1) as u32) << bit
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/geo_test.rs"]
+mod geo_test;
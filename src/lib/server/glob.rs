@@ -0,0 +1,107 @@
+//! Glob-style pattern matching, as used by `HSCAN MATCH` and the pub/sub
+//! patterns.
+//!
+//! Supports the same syntax as Redis: `*` matches any sequence, `?` any
+//! single byte, `[abc]`, `[^abc]` and `[a-z]` match sets of bytes and `\`
+//! escapes the next byte.
+
+/// Returns `true` if `string` matches `pattern`.
+///
+/// Only the last star is ever backtracked to, letting it match one more byte
+/// on a mismatch, as any match of the earlier stars can then be extended.
+/// Matching therefore takes at most `pattern.len() * string.len()` steps,
+/// however many stars the pattern has.
+pub(crate) fn matches(pattern: &[u8], string: &[u8]) -> bool {
+    let mut p = 0;
+    let mut s = 0;
+
+    // Position after the last star, and the position of the string it
+    // currently matches up to
+    let mut star = None;
+
+    loop {
+        if pattern.get(p) == Some(&b'*') {
+            p += 1;
+            star = Some((p, s));
+            continue;
+        }
+
+        if p == pattern.len() {
+            if s == string.len() {
+                return true;
+            }
+        } else if let Some(next) = string.get(s).and_then(|&byte| match_byte(pattern, p, byte)) {
+            p = next;
+            s += 1;
+            continue;
+        }
+
+        match star {
+            Some((star_p, star_s)) if star_s < string.len() => {
+                p = star_p;
+                s = star_s + 1;
+                star = Some((p, s));
+            }
+            _ => return false,
+        }
+    }
+}
+
+/// Matches `byte` against the part of `pattern` starting at `p`, which is
+/// not a star. Returns the position of the next part if it matched.
+fn match_byte(pattern: &[u8], p: usize, byte: u8) -> Option<usize> {
+    let (matched, end) = match pattern[p] {
+        b'?' => (true, p),
+        b'[' => match_class(pattern, p + 1, byte),
+        b'\\' if p + 1 < pattern.len() => (pattern[p + 1] == byte, p + 1),
+        other => (other == byte, p),
+    };
+
+    matched.then_some(end + 1)
+}
+
+/// Matches `byte` against the class starting at `start`, right after the
+/// opening `[`. Returns whether it matched and the position of the closing
+/// `]`, or of the last byte if the class is not closed.
+fn match_class(pattern: &[u8], start: usize, byte: u8) -> (bool, usize) {
+    let mut p = start;
+    let negate = pattern.get(p) == Some(&b'^');
+    if negate {
+        p += 1;
+    }
+
+    let mut matched = false;
+    loop {
+        match pattern.get(p) {
+            // An unclosed class ends with the pattern
+            None => {
+                p -= 1;
+                break;
+            }
+            Some(b']') => break,
+            Some(b'\\') if p + 1 < pattern.len() => {
+                p += 1;
+                matched |= pattern[p] == byte;
+            }
+            Some(&low) if pattern.get(p + 1) == Some(&b'-') && p + 2 < pattern.len() => {
+                let high = pattern[p + 2];
+                let (low, high) = if low <= high {
+                    (low, high)
+                } else {
+                    (high, low)
+                };
+                matched |= (low..=high).contains(&byte);
+                p += 2;
+            }
+            Some(&other) => matched |= other == byte,
+        }
+        p += 1;
+    }
+
+    (matched != negate, p)
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/glob_test.rs"]
+mod glob_test;
@@ -0,0 +1,464 @@
+//! Cardinality estimator, the value behind the `PF*` commands.
+//!
+//! A HyperLogLog is stored as a string with the same layout as Redis, so
+//! the values can be exchanged with it: a 16 bytes header followed by
+//! 16384 registers of 6 bits, either packed one after the other (dense
+//! encoding) or run-length encoded (sparse encoding). New values start
+//! sparse, which is much smaller while most registers are zero, and are
+//! turned dense once a register no longer fits the sparse encoding or the
+//! string grows too large.
+//!
+//! The header is made of the `HYLL` magic, the encoding, three unused bytes
+//! and the cardinality last computed, stored little endian. The most
+//! significant bit of its last byte is set when the cached value is stale.
+//!
+//! Adding an element changes a dense register in place. The registers are
+//! only decoded to count or merge them, or when an element changes the
+//! sparse encoding.
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+/// Number of bits of the hash used to pick a register
+const P: u32 = 14;
+
+const REGISTERS: usize = 1 << P;
+
+/// Number of bits of the hash used to count the leading zeros
+const Q: u32 = 64 - P;
+
+/// Width of a register in the dense encoding
+const REGISTER_BITS: usize = 6;
+
+const HEADER_LEN: usize = 16;
+const DENSE_LEN: usize = HEADER_LEN + (REGISTERS * REGISTER_BITS).div_ceil(8);
+
+/// Largest sparse value, larger ones are kept dense as Redis'
+/// `hll-sparse-max-bytes` does
+const SPARSE_MAX_LEN: usize = 3000;
+
+/// Largest register value the sparse encoding can hold
+const SPARSE_MAX_VALUE: u8 = 32;
+
+const MAGIC: &[u8; 4] = b"HYLL";
+const DENSE: u8 = 0;
+const SPARSE: u8 = 1;
+
+/// Sparse opcodes: `00xxxxxx` is a run of up to 64 zero registers,
+/// `01xxxxxx yyyyyyyy` a run of up to 16384 zero registers and `1vvvvvxx`
+/// a run of up to 4 registers set to a value up to 32.
+const ZERO_MAX_LEN: usize = 64;
+const XZERO: u8 = 0x40;
+const XZERO_MAX_LEN: usize = 16384;
+const VAL: u8 = 0x80;
+const VAL_MAX_LEN: usize = 4;
+
+/// `alpha` constant of the estimator for an infinite number of registers
+const ALPHA_INF: f64 = 0.721_347_520_444_481_7;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct HyperLogLog {
+    /// Register values, one per byte
+    registers: Vec<u8>,
+
+    /// Stored with the dense encoding
+    dense: bool,
+
+    /// Cardinality last computed, `None` if the registers changed since
+    cached: Option<u64>,
+}
+
+impl HyperLogLog {
+    /// Creates an empty HyperLogLog, sparse encoded.
+    pub(crate) fn new() -> HyperLogLog {
+        HyperLogLog {
+            registers: vec![0; REGISTERS],
+            dense: false,
+            cached: Some(0),
+        }
+    }
+
+    /// Decodes a HyperLogLog stored as `src`. Returns `None` if it is not a
+    /// valid one.
+    pub(crate) fn decode(src: &[u8]) -> Option<HyperLogLog> {
+        if src.len() < HEADER_LEN || &src[..4] != MAGIC {
+            return None;
+        }
+
+        let cached = cached_count(src);
+        let body = &src[HEADER_LEN..];
+        let (registers, dense) = match src[4] {
+            DENSE if src.len() == DENSE_LEN => (decode_dense(body), true),
+            SPARSE => (decode_sparse(body)?, false),
+            _ => return None,
+        };
+
+        Some(HyperLogLog {
+            registers,
+            dense,
+            cached,
+        })
+    }
+
+    /// Encodes the HyperLogLog as a string, sparse if it still can be.
+    pub(crate) fn encode(&self) -> Bytes {
+        let sparse = (!self.dense)
+            .then(|| encode_sparse(&self.registers))
+            .flatten()
+            .filter(|body| HEADER_LEN + body.len() <= SPARSE_MAX_LEN);
+
+        let mut dst = BytesMut::with_capacity(DENSE_LEN);
+        dst.put_slice(MAGIC);
+        dst.put_u8(if sparse.is_some() { SPARSE } else { DENSE });
+        dst.put_bytes(0, 3);
+        match self.cached {
+            Some(card) => dst.put_u64_le(card),
+            None => {
+                dst.put_bytes(0, 7);
+                dst.put_u8(0x80);
+            }
+        }
+
+        match sparse {
+            Some(body) => dst.put_slice(&body),
+            None => dst.put_slice(&encode_dense(&self.registers)),
+        }
+
+        dst.freeze()
+    }
+
+    /// Adds an element, returning `true` if a register changed.
+    #[cfg(test)]
+    pub(crate) fn add(&mut self, element: &[u8]) -> bool {
+        let (index, rank) = register_of(element);
+        if rank <= self.registers[index] {
+            return false;
+        }
+
+        self.set(index, rank);
+        true
+    }
+
+    /// Merges `other` in, so this one counts the union of both.
+    pub(crate) fn merge(&mut self, other: &HyperLogLog) {
+        for (index, value) in other.registers.iter().enumerate() {
+            if *value > self.registers[index] {
+                self.set(index, *value);
+            }
+        }
+    }
+
+    /// Forces the dense encoding, as Redis does for the result of `PFMERGE`.
+    pub(crate) fn make_dense(&mut self) {
+        self.dense = true;
+    }
+
+    /// Returns the estimated number of distinct elements added, from the
+    /// cache if the registers did not change since the last time.
+    pub(crate) fn count(&mut self) -> u64 {
+        if let Some(card) = self.cached {
+            return card;
+        }
+
+        let card = estimate(&self.registers);
+        self.cached = Some(card);
+        card
+    }
+
+    fn set(&mut self, index: usize, value: u8) {
+        self.registers[index] = value;
+        self.cached = None;
+
+        if value > SPARSE_MAX_VALUE {
+            self.dense = true;
+        }
+    }
+}
+
+/// Returns `true` if `src` is a valid HyperLogLog, without decoding it.
+pub(crate) fn is_valid(src: &[u8]) -> bool {
+    if src.len() < HEADER_LEN || &src[..4] != MAGIC {
+        return false;
+    }
+
+    match src[4] {
+        DENSE => src.len() == DENSE_LEN,
+        SPARSE => {
+            let len = sparse_runs(&src[HEADER_LEN..]).try_fold(0, |total, run| {
+                run.map(|(_, len)| total + len)
+                    .filter(|total| *total <= REGISTERS)
+            });
+            len == Some(REGISTERS)
+        }
+        _ => false,
+    }
+}
+
+/// Adds an element to the HyperLogLog stored as `src`, which must be valid,
+/// returning `true` if a register changed.
+pub(crate) fn add_encoded(src: &mut BytesMut, element: &[u8]) -> bool {
+    let (index, rank) = register_of(element);
+
+    if src[4] == DENSE {
+        let body = &mut src[HEADER_LEN..];
+        if rank <= dense_register(body, index) {
+            return false;
+        }
+        set_dense_register(body, index, rank);
+    } else {
+        let current = sparse_runs(&src[HEADER_LEN..])
+            .flatten()
+            .scan(0, |start, (value, len)| {
+                *start += len;
+                Some((*start, value))
+            })
+            .find(|(end, _)| index < *end)
+            .map_or(0, |(_, value)| value);
+        if rank <= current {
+            return false;
+        }
+
+        // The sparse encoding has to change
+        let Some(mut hll) = HyperLogLog::decode(src) else {
+            return false;
+        };
+        hll.set(index, rank);
+        src.clear();
+        src.extend_from_slice(&hll.encode());
+    }
+
+    // Mark the cached cardinality as stale
+    src[HEADER_LEN - 1] |= 0x80;
+    true
+}
+
+/// Returns the cardinality cached in the header of the HyperLogLog stored
+/// as `src`, `None` if it is stale.
+pub(crate) fn cached_count(src: &[u8]) -> Option<u64> {
+    let mut card = [0; 8];
+    card.copy_from_slice(&src[8..HEADER_LEN]);
+    (card[7] & 0x80 == 0).then(|| u64::from_le_bytes(card))
+}
+
+/// Caches `card` in the header of the HyperLogLog stored as `src`.
+pub(crate) fn cache_count(src: &mut [u8], card: u64) {
+    src[8..HEADER_LEN].copy_from_slice(&card.to_le_bytes());
+}
+
+/// Returns the register `element` falls in, and the value it sets it to.
+fn register_of(element: &[u8]) -> (usize, u8) {
+    let hash = murmur_hash64a(element, 0xadc8_3b19);
+    let index = (hash & (REGISTERS as u64 - 1)) as usize;
+
+    // Position of the first set bit of the remaining bits, with a bit set
+    // past them so the count is bounded
+    let rank = ((hash >> P) | (1 << Q)).trailing_zeros() as u8 + 1;
+
+    (index, rank)
+}
+
+impl Default for HyperLogLog {
+    fn default() -> HyperLogLog {
+        HyperLogLog::new()
+    }
+}
+
+/// Estimates the cardinality from the registers, with the estimator Redis
+/// uses (Otmar Ertl, "New cardinality estimation algorithms for HyperLogLog
+/// sketches").
+pub(crate) fn estimate(registers: &[u8]) -> u64 {
+    let m = REGISTERS as f64;
+
+    let mut histogram = [0u32; 64];
+    for value in registers {
+        histogram[*value as usize] += 1;
+    }
+
+    let mut z = m * tau((m - histogram[Q as usize + 1] as f64) / m);
+    for count in histogram[1..=Q as usize].iter().rev() {
+        z += *count as f64;
+        z *= 0.5;
+    }
+    z += m * sigma(histogram[0] as f64 / m);
+
+    (ALPHA_INF * m * m / z).round() as u64
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let previous = z;
+        z += x * y;
+        y += y;
+        if previous == z {
+            return z;
+        }
+    }
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let previous = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if previous == z {
+            return z / 3.0;
+        }
+    }
+}
+
+fn dense_register(body: &[u8], index: usize) -> u8 {
+    let bit = index * REGISTER_BITS;
+    let (byte, shift) = (bit / 8, bit % 8);
+    let low = body[byte] as u16 >> shift;
+    let high = body.get(byte + 1).copied().unwrap_or(0) as u16;
+    ((low | high << (8 - shift)) & 0x3f) as u8
+}
+
+fn set_dense_register(body: &mut [u8], index: usize, value: u8) {
+    let bit = index * REGISTER_BITS;
+    let (byte, shift) = (bit / 8, bit % 8);
+    let (value, mask) = (value as u16 & 0x3f, 0x3f_u16 << shift);
+    body[byte] = (body[byte] & !mask as u8) | (value << shift) as u8;
+    if let Some(next) = body.get_mut(byte + 1) {
+        *next = (*next & !(mask >> 8) as u8) | (value >> (8 - shift)) as u8;
+    }
+}
+
+fn decode_dense(body: &[u8]) -> Vec<u8> {
+    (0..REGISTERS)
+        .map(|index| dense_register(body, index))
+        .collect()
+}
+
+fn encode_dense(registers: &[u8]) -> Vec<u8> {
+    let mut body = vec![0u8; DENSE_LEN - HEADER_LEN];
+    for (index, value) in registers.iter().enumerate() {
+        set_dense_register(&mut body, index, *value);
+    }
+    body
+}
+
+/// Iterates over the sparse opcodes as runs of registers set to the same
+/// value, `(value, len)`. A truncated opcode is returned as `None`.
+fn sparse_runs(body: &[u8]) -> impl Iterator<Item = Option<(u8, usize)>> + '_ {
+    let mut bytes = body.iter();
+    std::iter::from_fn(move || {
+        let opcode = bytes.next()?;
+        Some(if opcode & VAL != 0 {
+            Some((((opcode >> 2) & 0x1f) + 1, (opcode & 0x03) as usize + 1))
+        } else if opcode & XZERO != 0 {
+            let low = bytes.next();
+            low.map(|low| (0, ((*opcode as usize & 0x3f) << 8 | *low as usize) + 1))
+        } else {
+            Some((0, *opcode as usize + 1))
+        })
+    })
+}
+
+/// Decodes the sparse opcodes, returning `None` if they do not cover every
+/// register exactly.
+fn decode_sparse(body: &[u8]) -> Option<Vec<u8>> {
+    let mut registers = Vec::with_capacity(REGISTERS);
+    for run in sparse_runs(body) {
+        let (value, len) = run?;
+        if registers.len() + len > REGISTERS {
+            return None;
+        }
+        registers.resize(registers.len() + len, value);
+    }
+
+    (registers.len() == REGISTERS).then_some(registers)
+}
+
+/// Encodes the registers as sparse opcodes, returning `None` if one of
+/// them is too large for it.
+fn encode_sparse(registers: &[u8]) -> Option<Vec<u8>> {
+    let mut body = vec![];
+
+    let mut index = 0;
+    while index < registers.len() {
+        let value = registers[index];
+        let run = registers[index..]
+            .iter()
+            .take_while(|other| **other == value)
+            .count();
+        index += run;
+
+        let mut remaining = run;
+        while remaining > 0 {
+            let len = match value {
+                0 if remaining > ZERO_MAX_LEN => {
+                    let len = remaining.min(XZERO_MAX_LEN);
+                    body.push(XZERO | ((len - 1) >> 8) as u8);
+                    body.push((len - 1) as u8);
+                    len
+                }
+                0 => {
+                    body.push((remaining - 1) as u8);
+                    remaining
+                }
+                value if value <= SPARSE_MAX_VALUE => {
+                    let len = remaining.min(VAL_MAX_LEN);
+                    body.push(VAL | (value - 1) << 2 | (len - 1) as u8);
+                    len
+                }
+                _ => return None,
+            };
+            remaining -= len;
+        }
+    }
+
+    Some(body)
+}
+
+/// MurmurHash64A by Austin Appleby, the hash function of Redis'
+/// HyperLogLog.
+fn murmur_hash64a(key: &[u8], seed: u32) -> u64 {
+    const M: u64 = 0xc6a4_a793_5bd1_e995;
+    const R: u32 = 47;
+
+    let mut h = seed as u64 ^ (key.len() as u64).wrapping_mul(M);
+
+    let mut chunks = key.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut k = u64::from_le_bytes(chunk.try_into().unwrap());
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, byte) in tail.iter().enumerate() {
+            h ^= (*byte as u64) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+
+    h
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/hyperloglog_test.rs"]
+mod hyperloglog_test;
@@ -0,0 +1,153 @@
+//! Inline commands, the human friendly protocol used when typing commands by
+//! hand through `telnet` or `nc`.
+//!
+//! A command is a single line of whitespace separated arguments. Arguments
+//! can be quoted the same way `redis-cli` does: double quotes support escape
+//! sequences (`\n`, `\t`, `\x41`, ...), single quotes only `\'`.
+
+use crate::server::frame::{Error, Frame};
+
+use bytes::{Buf, Bytes};
+use std::io::Cursor;
+
+/// Maximum size of an inline command, newline included
+pub(crate) const MAX_INLINE_SIZE: usize = 64 * 1024;
+
+/// Parses an inline command at the current position of `src`.
+///
+/// The command is returned as an array of bulks, like a client speaking RESP
+/// would have sent it. An empty line gives an empty array.
+pub(crate) fn parse(src: &mut Cursor<&[u8]>) -> Result<Frame, Error> {
+    let start = src.position() as usize;
+    let buf = &src.get_ref()[start..];
+
+    let Some(end) = buf.iter().position(|b| *b == b'\n') else {
+        if buf.len() >= MAX_INLINE_SIZE {
+            return Err(Error::InlineTooBig);
+        }
+        return Err(Error::Incomplete);
+    };
+
+    if end >= MAX_INLINE_SIZE {
+        return Err(Error::InlineTooBig);
+    }
+
+    // Tolerate clients sending a bare `\n`
+    let line = buf[..end].strip_suffix(b"\r").unwrap_or(&buf[..end]);
+    let args = split_args(line)?;
+
+    src.advance(end + 1);
+
+    Ok(Frame::Array(args.into_iter().map(Frame::Bulk).collect()))
+}
+
+/// Splits a line into arguments, handling quoted strings.
+fn split_args(line: &[u8]) -> Result<Vec<Bytes>, Error> {
+    let mut args = vec![];
+    let mut pos = 0;
+
+    loop {
+        while pos < line.len() && line[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+
+        if pos == line.len() {
+            return Ok(args);
+        }
+
+        let mut arg = vec![];
+        match line[pos] {
+            b'"' => pos = parse_double_quoted(line, pos + 1, &mut arg)?,
+            b'\'' => pos = parse_single_quoted(line, pos + 1, &mut arg)?,
+            _ => {
+                while pos < line.len() && !line[pos].is_ascii_whitespace() {
+                    arg.push(line[pos]);
+                    pos += 1;
+                }
+            }
+        }
+
+        args.push(Bytes::from(arg));
+    }
+}
+
+/// Reads a double quoted argument starting right after the opening quote and
+/// returns the position following the closing one.
+fn parse_double_quoted(line: &[u8], mut pos: usize, arg: &mut Vec<u8>) -> Result<usize, Error> {
+    loop {
+        match line.get(pos) {
+            None => return Err(Error::UnbalancedQuotes),
+            Some(b'\\') if line.get(pos + 1) == Some(&b'x') => {
+                match (line.get(pos + 2), line.get(pos + 3)) {
+                    (Some(high), Some(low))
+                        if high.is_ascii_hexdigit() && low.is_ascii_hexdigit() =>
+                    {
+                        arg.push(hex_value(*high) * 16 + hex_value(*low));
+                        pos += 4;
+                    }
+                    _ => {
+                        arg.push(b'x');
+                        pos += 2;
+                    }
+                }
+            }
+            Some(b'\\') if pos + 1 < line.len() => {
+                arg.push(match line[pos + 1] {
+                    b'n' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    b'b' => 0x08,
+                    b'a' => 0x07,
+                    other => other,
+                });
+                pos += 2;
+            }
+            Some(b'"') => return closing_quote(line, pos),
+            Some(byte) => {
+                arg.push(*byte);
+                pos += 1;
+            }
+        }
+    }
+}
+
+/// Reads a single quoted argument starting right after the opening quote and
+/// returns the position following the closing one.
+fn parse_single_quoted(line: &[u8], mut pos: usize, arg: &mut Vec<u8>) -> Result<usize, Error> {
+    loop {
+        match line.get(pos) {
+            None => return Err(Error::UnbalancedQuotes),
+            Some(b'\\') if line.get(pos + 1) == Some(&b'\'') => {
+                arg.push(b'\'');
+                pos += 2;
+            }
+            Some(b'\'') => return closing_quote(line, pos),
+            Some(byte) => {
+                arg.push(*byte);
+                pos += 1;
+            }
+        }
+    }
+}
+
+/// A closing quote must be followed by whitespace or the end of the line.
+fn closing_quote(line: &[u8], pos: usize) -> Result<usize, Error> {
+    match line.get(pos + 1) {
+        None => Ok(pos + 1),
+        Some(byte) if byte.is_ascii_whitespace() => Ok(pos + 1),
+        Some(_) => Err(Error::UnbalancedQuotes),
+    }
+}
+
+fn hex_value(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        b'a'..=b'f' => digit - b'a' + 10,
+        _ => digit - b'A' + 10,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/inline_test.rs"]
+mod inline_test;
@@ -1,3 +1,4 @@
+use crate::error::ReplyError;
 use crate::server::Frame;
 
 use bytes::Bytes;
@@ -15,15 +16,20 @@ pub(crate) enum ParserError {
     /// No more frame to be consumed
     NoMoreFrame,
 
-    /// Other errors
-    Other(crate::Error),
+    /// Frames were left over after the command was fully parsed
+    TrailingFrame,
+
+    /// The command is invalid, the error is replied to the client
+    Reply(ReplyError),
 }
 
 impl Parser {
     pub(crate) fn new(frame: Frame) -> Result<Parser, ParserError> {
         let array = match frame {
             Frame::Array(array) => array,
-            frame => return Err(format!("protocol error; expected array, got {:?}", frame).into()),
+            frame => {
+                return Err(ReplyError::Protocol(format!("expected array, got {:?}", frame)).into())
+            }
         };
 
         Ok(Parser {
@@ -40,11 +46,11 @@ impl Parser {
             Frame::Simple(s) => Ok(s),
             Frame::Bulk(data) => std::str::from_utf8(&data[..])
                 .map(|s| s.to_string())
-                .map_err(|_| "protocol error; invalid string".into()),
-            frame => Err(format!(
-                "protocol error; expected simple frame or bulk frame, got {:?}",
+                .map_err(|_| ReplyError::Syntax.into()),
+            frame => Err(ReplyError::Protocol(format!(
+                "expected simple frame or bulk frame, got {:?}",
                 frame
-            )
+            ))
             .into()),
         }
     }
@@ -53,44 +59,91 @@ impl Parser {
         match self.next()? {
             Frame::Simple(s) => Ok(Bytes::from(s.into_bytes())),
             Frame::Bulk(data) => Ok(data),
-            frame => Err(format!(
-                "protocol error; expected simple frame or bulk frame, got {:?}",
+            frame => Err(ReplyError::Protocol(format!(
+                "expected simple frame or bulk frame, got {:?}",
                 frame
-            )
+            ))
             .into()),
         }
     }
 
-    pub(crate) fn next_int(&mut self) -> Result<u64, ParserError> {
+    /// Returns the next entry as a signed integer. Simple and bulk frames
+    /// must hold nothing but the decimal representation of the number.
+    pub(crate) fn next_int(&mut self) -> Result<i64, ParserError> {
         match self.next()? {
             Frame::Integer(int) => Ok(int),
-            Frame::Simple(data) => atoi::atoi::<u64>(data.as_bytes())
-                .ok_or_else(|| "protocol error; invalid number".into()),
-            Frame::Bulk(data) => {
-                atoi::atoi::<u64>(&data).ok_or_else(|| "protocol error; invalid number".into())
+            Frame::Simple(data) => parse_int(data.as_bytes()),
+            Frame::Bulk(data) => parse_int(&data),
+            frame => {
+                Err(ReplyError::Protocol(format!("expected int frame but got {:?}", frame)).into())
             }
-            frame => Err(format!("protocol error; expected int frame but got {:?}", frame).into()),
         }
     }
 
+    /// Returns the next entry as a floating point number. Infinities are
+    /// accepted, `NaN` is not.
+    pub(crate) fn next_double(&mut self) -> Result<f64, ParserError> {
+        match self.next()? {
+            Frame::Integer(int) => Ok(int as f64),
+            Frame::Simple(data) => parse_double(data.as_bytes()),
+            Frame::Bulk(data) => parse_double(&data),
+            frame => Err(ReplyError::Protocol(format!(
+                "expected double frame but got {:?}",
+                frame
+            ))
+            .into()),
+        }
+    }
+
+    /// Returns the number of frames that have not been consumed yet.
+    pub(crate) fn remaining(&self) -> usize {
+        self.tokens.len()
+    }
+
     pub(crate) fn finish(&mut self) -> Result<(), ParserError> {
         if self.tokens.next().is_none() {
             Ok(())
         } else {
-            Err("protocol error; expected end of frame, but there was more".into())
+            Err(ParserError::TrailingFrame)
         }
     }
 }
 
-impl From<String> for ParserError {
-    fn from(src: String) -> ParserError {
-        ParserError::Other(src.into())
+pub(crate) fn parse_int(src: &[u8]) -> Result<i64, ParserError> {
+    use atoi::FromRadix10SignedChecked;
+
+    match i64::from_radix_10_signed_checked(src) {
+        (Some(int), used) if used == src.len() && src.last().is_some_and(u8::is_ascii_digit) => {
+            Ok(int)
+        }
+        _ => Err(ReplyError::NotInteger.into()),
     }
 }
 
-impl From<&str> for ParserError {
-    fn from(src: &str) -> ParserError {
-        src.to_string().into()
+/// Parses a floating point number the way Redis does: the whole input must
+/// be the number, and `NaN` is rejected.
+pub(crate) fn parse_double(src: &[u8]) -> Result<f64, ParserError> {
+    std::str::from_utf8(src)
+        .ok()
+        .and_then(|src| src.parse::<f64>().ok())
+        .filter(|double| !double.is_nan())
+        .ok_or_else(|| ReplyError::NotFloat.into())
+}
+
+impl From<ReplyError> for ParserError {
+    fn from(err: ReplyError) -> ParserError {
+        ParserError::Reply(err)
+    }
+}
+
+/// Errors not turned into a `wrong number of arguments` reply by the command
+/// are replied as is.
+impl From<ParserError> for ReplyError {
+    fn from(err: ParserError) -> ReplyError {
+        match err {
+            ParserError::Reply(err) => err,
+            err => ReplyError::Other(err.to_string()),
+        }
     }
 }
 
@@ -98,7 +151,10 @@ impl fmt::Display for ParserError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ParserError::NoMoreFrame => "protocol error; unexpected end of stream".fmt(f),
-            ParserError::Other(err) => err.fmt(f),
+            ParserError::TrailingFrame => {
+                "protocol error; expected end of frame, but there was more".fmt(f)
+            }
+            ParserError::Reply(err) => err.fmt(f),
         }
     }
 }
@@ -106,5 +162,6 @@ impl fmt::Display for ParserError {
 impl std::error::Error for ParserError {}
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 #[path = "test/parser_test.rs"]
-mod parser_test;
\ No newline at end of file
+mod parser_test;
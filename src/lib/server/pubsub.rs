@@ -0,0 +1,325 @@
+//! Publish/subscribe, the messaging behind `SUBSCRIBE` and `PUBLISH`.
+//!
+//! The `Broker` is shared by every connection and knows who listens to what:
+//! each subscribed connection registers a sender per channel or pattern,
+//! and publishing clones the message into the senders of the channel and of
+//! the patterns matching it. Each connection keeps its side of the
+//! registrations in `Subscriptions`, along with the receiver the messages
+//! are read from and written to the client.
+//!
+//! Shard channels, from `SSUBSCRIBE` and `SPUBLISH`, are kept per hash slot
+//! like in Redis Cluster, where each node only serves the shard channels of
+//! its slots. They are not matched by patterns.
+
+use crate::server::frame::Frame;
+use crate::server::glob;
+use crate::server::slot::{self, SLOTS};
+
+use bytes::Bytes;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+use tokio::sync::mpsc;
+
+/// Where the messages for a connection are sent.
+pub(crate) type Sender = mpsc::UnboundedSender<Frame>;
+
+/// Senders of the connections subscribed to each channel or pattern, by
+/// connection identifier.
+type Registry = HashMap<Bytes, HashMap<u64, Sender>>;
+
+/// What a connection subscribes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Kind {
+    /// Messages published to a channel
+    Channel,
+    /// Messages published to the channels matching a glob-style pattern
+    Pattern,
+    /// Messages published to a shard channel
+    Shard,
+}
+
+/// Registry of the subscriptions of every connection.
+#[derive(Debug)]
+pub(crate) struct Broker {
+    channels: Registry,
+    patterns: Registry,
+    /// Shard channels, by hash slot
+    shards: HashMap<u16, Registry>,
+
+    /// Hash slots served by this node
+    slots: Range<u16>,
+}
+
+/// The subscriptions of a connection, and the messages published to them.
+#[derive(Debug)]
+pub(crate) struct Subscriptions {
+    channels: BTreeSet<Bytes>,
+    patterns: BTreeSet<Bytes>,
+    shards: BTreeSet<Bytes>,
+
+    sender: Sender,
+    receiver: mpsc::UnboundedReceiver<Frame>,
+}
+
+impl Broker {
+    /// Returns a broker serving the shard channels of `slots`.
+    pub(crate) fn serving(slots: Range<u16>) -> Broker {
+        Broker {
+            channels: HashMap::new(),
+            patterns: HashMap::new(),
+            shards: HashMap::new(),
+            slots,
+        }
+    }
+
+    /// Sends `message` to the subscribers of `channel` and of the patterns
+    /// matching it. Returns the number of deliveries, a connection matching
+    /// several times receiving the message as many times.
+    pub(crate) fn publish(&self, channel: &Bytes, message: &Bytes) -> usize {
+        let mut receivers = 0;
+
+        if let Some(subscribers) = self.channels.get(channel) {
+            let frame = Frame::Push(vec![
+                Frame::Bulk(Bytes::from_static(b"message")),
+                Frame::Bulk(channel.clone()),
+                Frame::Bulk(message.clone()),
+            ]);
+            receivers += send(subscribers, &frame);
+        }
+
+        for (pattern, subscribers) in &self.patterns {
+            if glob::matches(pattern, channel) {
+                let frame = Frame::Push(vec![
+                    Frame::Bulk(Bytes::from_static(b"pmessage")),
+                    Frame::Bulk(pattern.clone()),
+                    Frame::Bulk(channel.clone()),
+                    Frame::Bulk(message.clone()),
+                ]);
+                receivers += send(subscribers, &frame);
+            }
+        }
+
+        receivers
+    }
+
+    /// Sends `message` to the subscribers of the shard channel `channel`.
+    /// Returns the number of clients that received it.
+    pub(crate) fn publish_shard(&self, channel: &Bytes, message: &Bytes) -> usize {
+        let subscribers = self
+            .shards
+            .get(&slot::hash_slot(channel))
+            .and_then(|registry| registry.get(channel));
+
+        match subscribers {
+            Some(subscribers) => {
+                let frame = Frame::Push(vec![
+                    Frame::Bulk(Bytes::from_static(b"smessage")),
+                    Frame::Bulk(channel.clone()),
+                    Frame::Bulk(message.clone()),
+                ]);
+                send(subscribers, &frame)
+            }
+            None => 0,
+        }
+    }
+
+    /// Returns `true` if this node serves the hash slot of `channel`.
+    pub(crate) fn serves(&self, channel: &[u8]) -> bool {
+        self.slots.contains(&slot::hash_slot(channel))
+    }
+
+    /// Returns the channels with at least one subscriber, only those
+    /// matching `pattern` if given.
+    pub(crate) fn channels(&self, pattern: Option<&[u8]>) -> Vec<Bytes> {
+        filter(self.channels.keys(), pattern)
+    }
+
+    /// Returns the shard channels with at least one subscriber, only those
+    /// matching `pattern` if given.
+    pub(crate) fn shard_channels(&self, pattern: Option<&[u8]>) -> Vec<Bytes> {
+        filter(self.shards.values().flat_map(HashMap::keys), pattern)
+    }
+
+    /// Returns the number of subscribers of `channel`, patterns excluded.
+    pub(crate) fn subscribers(&self, channel: &[u8]) -> usize {
+        self.channels.get(channel).map_or(0, HashMap::len)
+    }
+
+    /// Returns the number of subscribers of the shard channel `channel`.
+    pub(crate) fn shard_subscribers(&self, channel: &[u8]) -> usize {
+        self.shards
+            .get(&slot::hash_slot(channel))
+            .and_then(|registry| registry.get(channel))
+            .map_or(0, HashMap::len)
+    }
+
+    /// Returns the number of patterns subscribed to by any connection.
+    pub(crate) fn patterns(&self) -> usize {
+        self.patterns.len()
+    }
+
+    fn register(&mut self, kind: Kind, name: Bytes, id: u64, sender: Sender) {
+        let registry = match kind {
+            Kind::Channel => &mut self.channels,
+            Kind::Pattern => &mut self.patterns,
+            Kind::Shard => self.shards.entry(slot::hash_slot(&name)).or_default(),
+        };
+        registry.entry(name).or_default().insert(id, sender);
+    }
+
+    fn unregister(&mut self, kind: Kind, name: &Bytes, id: u64) {
+        let slot = slot::hash_slot(name);
+        let registry = match kind {
+            Kind::Channel => &mut self.channels,
+            Kind::Pattern => &mut self.patterns,
+            Kind::Shard => match self.shards.get_mut(&slot) {
+                Some(registry) => registry,
+                None => return,
+            },
+        };
+
+        if let Some(subscribers) = registry.get_mut(name) {
+            subscribers.remove(&id);
+            if subscribers.is_empty() {
+                registry.remove(name);
+            }
+        }
+        if kind == Kind::Shard && registry.is_empty() {
+            self.shards.remove(&slot);
+        }
+    }
+}
+
+impl Default for Broker {
+    /// A standalone node serves every hash slot.
+    fn default() -> Broker {
+        Broker::serving(0..SLOTS)
+    }
+}
+
+impl Subscriptions {
+    pub(crate) fn new() -> Subscriptions {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Subscriptions {
+            channels: BTreeSet::new(),
+            patterns: BTreeSet::new(),
+            shards: BTreeSet::new(),
+            sender,
+            receiver,
+        }
+    }
+
+    /// Returns the number of subscriptions reported when subscribing to
+    /// `kind`: the shard channels for shard channels, the channels and
+    /// patterns otherwise.
+    pub(crate) fn count(&self, kind: Kind) -> usize {
+        match kind {
+            Kind::Channel | Kind::Pattern => self.channels.len() + self.patterns.len(),
+            Kind::Shard => self.shards.len(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.channels.is_empty() && self.patterns.is_empty() && self.shards.is_empty()
+    }
+
+    /// Returns the channels or patterns subscribed to.
+    pub(crate) fn list(&self, kind: Kind) -> Vec<Bytes> {
+        self.names(kind).iter().cloned().collect()
+    }
+
+    /// Subscribes the connection `id` to a channel, a pattern or a shard
+    /// channel, doing nothing if it already is. Returns the number of
+    /// subscriptions of that kind, as counted by `count`.
+    pub(crate) fn subscribe(
+        &mut self,
+        broker: &mut Broker,
+        id: u64,
+        kind: Kind,
+        name: Bytes,
+    ) -> usize {
+        if self.names_mut(kind).insert(name.clone()) {
+            broker.register(kind, name, id, self.sender.clone());
+        }
+        self.count(kind)
+    }
+
+    /// Unsubscribes the connection `id` from a channel, a pattern or a
+    /// shard channel, doing nothing if it is not subscribed. Returns the
+    /// number of subscriptions of that kind left, as counted by `count`.
+    pub(crate) fn unsubscribe(
+        &mut self,
+        broker: &mut Broker,
+        id: u64,
+        kind: Kind,
+        name: &Bytes,
+    ) -> usize {
+        if self.names_mut(kind).remove(name) {
+            broker.unregister(kind, name, id);
+        }
+        self.count(kind)
+    }
+
+    /// Removes every subscription of the connection `id`.
+    pub(crate) fn clear(&mut self, broker: &mut Broker, id: u64) {
+        for kind in [Kind::Channel, Kind::Pattern, Kind::Shard] {
+            for name in self.list(kind) {
+                self.unsubscribe(broker, id, kind, &name);
+            }
+        }
+    }
+
+    /// Waits for the next message published to the subscriptions.
+    pub(crate) async fn recv(&mut self) -> Frame {
+        // The sender is kept alongside, so the channel is never closed
+        match self.receiver.recv().await {
+            Some(frame) => frame,
+            None => std::future::pending().await,
+        }
+    }
+
+    fn names(&self, kind: Kind) -> &BTreeSet<Bytes> {
+        match kind {
+            Kind::Channel => &self.channels,
+            Kind::Pattern => &self.patterns,
+            Kind::Shard => &self.shards,
+        }
+    }
+
+    fn names_mut(&mut self, kind: Kind) -> &mut BTreeSet<Bytes> {
+        match kind {
+            Kind::Channel => &mut self.channels,
+            Kind::Pattern => &mut self.patterns,
+            Kind::Shard => &mut self.shards,
+        }
+    }
+}
+
+impl Default for Subscriptions {
+    fn default() -> Subscriptions {
+        Subscriptions::new()
+    }
+}
+
+/// Returns the sorted `channels` matching `pattern`, all of them if none.
+fn filter<'a>(channels: impl Iterator<Item = &'a Bytes>, pattern: Option<&[u8]>) -> Vec<Bytes> {
+    let mut channels: Vec<Bytes> = channels
+        .filter(|channel| pattern.is_none_or(|pattern| glob::matches(pattern, channel)))
+        .cloned()
+        .collect();
+    channels.sort();
+    channels
+}
+
+/// Sends `frame` to every subscriber, returning how many received it.
+fn send(subscribers: &HashMap<u64, Sender>, frame: &Frame) -> usize {
+    subscribers
+        .values()
+        .filter(|sender| sender.send(frame.clone()).is_ok())
+        .count()
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/pubsub_test.rs"]
+mod pubsub_test;
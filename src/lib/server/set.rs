@@ -0,0 +1,119 @@
+//! Unordered unique members, the value behind the `S*` commands.
+//!
+//! Members are kept in a vector, with a hash map of their position in it,
+//! so a random member is picked or removed in constant time.
+
+use bytes::Bytes;
+use rand::{Rng, RngExt};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Set {
+    members: Vec<Bytes>,
+    positions: HashMap<Bytes, usize>,
+}
+
+impl Set {
+    pub(crate) fn new() -> Set {
+        Set::default()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub(crate) fn contains(&self, member: &[u8]) -> bool {
+        self.positions.contains_key(member)
+    }
+
+    /// Adds `member`, returning `true` if it was not in the set.
+    pub(crate) fn insert(&mut self, member: Bytes) -> bool {
+        match self.positions.entry(member) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                self.members.push(entry.key().clone());
+                entry.insert(self.members.len() - 1);
+                true
+            }
+        }
+    }
+
+    /// Removes `member`, returning `true` if it was in the set.
+    pub(crate) fn remove(&mut self, member: &[u8]) -> bool {
+        match self.positions.remove(member) {
+            Some(position) => {
+                self.swap_remove(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes and returns a random member, `None` if the set is empty.
+    pub(crate) fn pop(&mut self, rng: &mut impl Rng) -> Option<Bytes> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let member = self.swap_remove(rng.random_range(0..self.len()));
+        self.positions.remove(&member);
+
+        Some(member)
+    }
+
+    /// Returns the members, in no particular order.
+    pub(crate) fn members(&self) -> &[Bytes] {
+        &self.members
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Bytes> {
+        self.members.iter()
+    }
+
+    /// Removes the member at `position` of `members`, moving the last one
+    /// in its place.
+    fn swap_remove(&mut self, position: usize) -> Bytes {
+        let member = self.members.swap_remove(position);
+        if let Some(moved) = self.members.get(position) {
+            self.positions.insert(moved.clone(), position);
+        }
+
+        member
+    }
+}
+
+/// Sets are equal if they have the same members, in any order.
+impl PartialEq for Set {
+    fn eq(&self, other: &Set) -> bool {
+        self.len() == other.len() && self.iter().all(|member| other.contains(member))
+    }
+}
+
+impl<'a> IntoIterator for &'a Set {
+    type Item = &'a Bytes;
+    type IntoIter = std::slice::Iter<'a, Bytes>;
+
+    fn into_iter(self) -> std::slice::Iter<'a, Bytes> {
+        self.members.iter()
+    }
+}
+
+impl FromIterator<Bytes> for Set {
+    fn from_iter<I: IntoIterator<Item = Bytes>>(iter: I) -> Set {
+        let mut set = Set::new();
+        for member in iter {
+            set.insert(member);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/set_test.rs"]
+mod set_test;
@@ -0,0 +1,44 @@
+//! Hash slots, which split the keyspace, and the shard channels, between
+//! the nodes of a Redis Cluster.
+//!
+//! A name belongs to the slot given by the CRC16 of its hash tag, the part
+//! between the first `{` and the next `}` when not empty, or of the whole
+//! name otherwise. Names sharing a hash tag share a slot.
+
+/// Number of hash slots.
+pub(crate) const SLOTS: u16 = 16384;
+
+/// Returns the hash slot of `name`.
+pub(crate) fn hash_slot(name: &[u8]) -> u16 {
+    crc16(hash_tag(name)) % SLOTS
+}
+
+/// Returns the part of `name` that is hashed.
+fn hash_tag(name: &[u8]) -> &[u8] {
+    let Some(start) = name.iter().position(|&byte| byte == b'{') else {
+        return name;
+    };
+    match name[start + 1..].iter().position(|&byte| byte == b'}') {
+        Some(len) if len > 0 => &name[start + 1..start + 1 + len],
+        _ => name,
+    }
+}
+
+/// CRC16 as used by Redis Cluster, the XMODEM variant: polynomial 0x1021,
+/// no reflection, starting from zero.
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0, |crc, &byte| {
+        (0..8).fold(crc ^ (u16::from(byte) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/slot_test.rs"]
+mod slot_test;
@@ -0,0 +1,392 @@
+//! Members ordered by score, the value behind the `Z*` commands.
+//!
+//! Scores are kept in a hash map for constant time lookups by member, and
+//! an ordered index of `(score, member)` pairs serves the range queries.
+//! Members with the same score are ordered lexicographically.
+//!
+//! The index is a treap: a binary search tree kept balanced by random node
+//! priorities. Each node knows the size of its subtree, so positions are
+//! found in logarithmic time and ranges are read without walking the
+//! members before them.
+
+use bytes::Bytes;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::{Bound, Range};
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SortedSet {
+    scores: HashMap<Bytes, f64>,
+    index: Index,
+}
+
+/// A score with a total order, so it can be used in the index. `NaN` is
+/// never stored.
+#[derive(Debug, Clone, Copy)]
+struct Score(f64);
+
+/// The `(score, member)` pairs of a sorted set, in order.
+#[derive(Debug, Clone, Default)]
+struct Index {
+    root: Link,
+}
+
+type Link = Option<Box<Node>>;
+
+#[derive(Debug, Clone)]
+struct Node {
+    score: Score,
+    member: Bytes,
+    /// Greater than the priorities of the nodes below
+    priority: u64,
+    /// Number of nodes in the subtree rooted here
+    size: usize,
+    left: Link,
+    right: Link,
+}
+
+/// Iterates over the index from a position, see `Index::iter_from`.
+struct Iter<'a> {
+    /// Nodes still to visit, the next one last
+    stack: Vec<&'a Node>,
+    rev: bool,
+}
+
+impl SortedSet {
+    pub(crate) fn new() -> SortedSet {
+        SortedSet::default()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    /// Returns the score of `member`, if it is in the set.
+    pub(crate) fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    /// Sets the score of `member`, adding it if needed. Returns `true` if
+    /// the member was added.
+    pub(crate) fn insert(&mut self, member: Bytes, score: f64) -> bool {
+        // Adding 0 turns -0 into 0, so both are the same score
+        let score = score + 0.0;
+
+        let added = match self.scores.insert(member.clone(), score) {
+            Some(previous) => {
+                self.index.remove(Score(previous), &member);
+                false
+            }
+            None => true,
+        };
+        self.index.insert(Score(score), member);
+
+        added
+    }
+
+    /// Removes `member`, returning `true` if it was in the set.
+    pub(crate) fn remove(&mut self, member: &[u8]) -> bool {
+        match self.scores.remove(member) {
+            Some(score) => self.index.remove(Score(score), member),
+            None => false,
+        }
+    }
+
+    /// Returns the position of `member` in the set, starting from the
+    /// highest score if `rev` is set.
+    pub(crate) fn rank(&self, member: &[u8], rev: bool) -> Option<usize> {
+        let score = Score(self.score(member)?);
+        let below = self
+            .index
+            .count_while(|other, other_member| (other, other_member) < (score, member));
+
+        Some(if rev { self.len() - 1 - below } else { below })
+    }
+
+    /// Returns the number of members, from the lowest score, for which
+    /// `below` holds. Once it does not hold for a member, it must not hold
+    /// for any of the following ones.
+    pub(crate) fn count_while(&self, below: impl Fn(&[u8], f64) -> bool) -> usize {
+        self.index
+            .count_while(|score, member| below(member, score.0))
+    }
+
+    /// Iterates over the members and their score, by increasing score.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&Bytes, f64)> {
+        self.iter_from(0, false)
+    }
+
+    /// Iterates over the members and their score from the position `start`,
+    /// by increasing score, or by decreasing score if `rev` is set, `start`
+    /// then counting from the highest score.
+    pub(crate) fn iter_from(&self, start: usize, rev: bool) -> impl Iterator<Item = (&Bytes, f64)> {
+        self.index
+            .iter_from(start, rev)
+            .map(|node| (&node.member, node.score.0))
+    }
+
+    /// Returns the positions of the members whose score is within `min` and
+    /// `max`.
+    pub(crate) fn ranks_by_score(&self, min: Bound<f64>, max: Bound<f64>) -> Range<usize> {
+        // Like the scores, -0 bounds are 0
+        let min = min.map(|min| min + 0.0);
+        let max = max.map(|max| max + 0.0);
+
+        let start = self.index.count_while(|score, _| match min {
+            Bound::Included(min) => score < Score(min),
+            Bound::Excluded(min) => score <= Score(min),
+            Bound::Unbounded => false,
+        });
+        let end = self.index.count_while(|score, _| match max {
+            Bound::Included(max) => score <= Score(max),
+            Bound::Excluded(max) => score < Score(max),
+            Bound::Unbounded => true,
+        });
+
+        start..end.max(start)
+    }
+
+    /// Removes and returns the member with the lowest score, or the highest
+    /// one if `max` is set.
+    pub(crate) fn pop(&mut self, max: bool) -> Option<(Bytes, f64)> {
+        let node = if max {
+            self.index.pop_last()?
+        } else {
+            self.index.pop_first()?
+        };
+        self.scores.remove(&node.member);
+
+        Some((node.member, node.score.0))
+    }
+}
+
+/// Compares the scores only, the index is rebuilt in another shape for the
+/// same members.
+impl PartialEq for SortedSet {
+    fn eq(&self, other: &SortedSet) -> bool {
+        self.scores == other.scores
+    }
+}
+
+impl Index {
+    fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    fn insert(&mut self, score: Score, member: Bytes) {
+        let (lower, greater) = split(self.root.take(), &|other, other_member| {
+            (other, other_member) < (score, &member[..])
+        });
+        let node = Box::new(Node {
+            score,
+            member,
+            priority: rand::random(),
+            size: 1,
+            left: None,
+            right: None,
+        });
+
+        self.root = merge(merge(lower, Some(node)), greater);
+    }
+
+    /// Removes the pair, returning `true` if it was in the index.
+    fn remove(&mut self, score: Score, member: &[u8]) -> bool {
+        let (lower, rest) = split(self.root.take(), &|other, other_member| {
+            (other, other_member) < (score, member)
+        });
+        let (found, greater) = split_at(rest, 1);
+        let removed = found
+            .as_ref()
+            .is_some_and(|node| node.score == score && node.member == member);
+
+        let greater = if removed {
+            greater
+        } else {
+            merge(found, greater)
+        };
+        self.root = merge(lower, greater);
+
+        removed
+    }
+
+    fn pop_first(&mut self) -> Option<Node> {
+        let (first, rest) = split_at(self.root.take(), 1);
+        self.root = rest;
+        first.map(|node| *node)
+    }
+
+    fn pop_last(&mut self) -> Option<Node> {
+        let count = self.len().checked_sub(1)?;
+        let (rest, last) = split_at(self.root.take(), count);
+        self.root = rest;
+        last.map(|node| *node)
+    }
+
+    /// Returns the number of pairs, in order, for which `below` holds.
+    fn count_while(&self, below: impl Fn(Score, &[u8]) -> bool) -> usize {
+        let mut count = 0;
+        let mut link = &self.root;
+        while let Some(node) = link {
+            if below(node.score, &node.member) {
+                count += size(&node.left) + 1;
+                link = &node.right;
+            } else {
+                link = &node.left;
+            }
+        }
+
+        count
+    }
+
+    /// Iterates over the pairs from the position `start`, in order, or in
+    /// reverse order if `rev` is set, `start` then counting from the end.
+    fn iter_from(&self, start: usize, rev: bool) -> Iter<'_> {
+        // Stack the path to the first node, except the nodes it is after
+        let mut stack = vec![];
+        let mut start = start;
+        let mut link = &self.root;
+        while let Some(node) = link {
+            let (before, after) = node.children(rev);
+            match start.cmp(&size(before)) {
+                Ordering::Less => {
+                    stack.push(&**node);
+                    link = before;
+                }
+                Ordering::Equal => {
+                    stack.push(&**node);
+                    break;
+                }
+                Ordering::Greater => {
+                    start -= size(before) + 1;
+                    link = after;
+                }
+            }
+        }
+
+        Iter { stack, rev }
+    }
+}
+
+impl Node {
+    /// Returns the children holding the lower then the greater pairs, or
+    /// the other way around if `rev` is set.
+    fn children(&self, rev: bool) -> (&Link, &Link) {
+        if rev {
+            (&self.right, &self.left)
+        } else {
+            (&self.left, &self.right)
+        }
+    }
+
+    fn update(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<&'a Node> {
+        let node = self.stack.pop()?;
+
+        // The nodes after this one, starting with the lowest of them
+        let mut link = node.children(self.rev).1;
+        while let Some(next) = link {
+            self.stack.push(next);
+            link = next.children(self.rev).0;
+        }
+
+        Some(node)
+    }
+}
+
+fn size(link: &Link) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+/// Splits the tree at `link` into the nodes for which `below` holds and the
+/// others.
+fn split(link: Link, below: &impl Fn(Score, &[u8]) -> bool) -> (Link, Link) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+
+    if below(node.score, &node.member) {
+        let (lower, greater) = split(node.right.take(), below);
+        node.right = lower;
+        node.update();
+        (Some(node), greater)
+    } else {
+        let (lower, greater) = split(node.left.take(), below);
+        node.left = greater;
+        node.update();
+        (lower, Some(node))
+    }
+}
+
+/// Splits the tree at `link` into its first `count` nodes and the others.
+fn split_at(link: Link, count: usize) -> (Link, Link) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+
+    let left = size(&node.left);
+    if count <= left {
+        let (lower, greater) = split_at(node.left.take(), count);
+        node.left = greater;
+        node.update();
+        (lower, Some(node))
+    } else {
+        let (lower, greater) = split_at(node.right.take(), count - left - 1);
+        node.right = lower;
+        node.update();
+        (Some(node), greater)
+    }
+}
+
+/// Joins two trees, every node of `lower` being before those of `greater`.
+fn merge(lower: Link, greater: Link) -> Link {
+    match (lower, greater) {
+        (None, link) | (link, None) => link,
+        (Some(mut lower), Some(mut greater)) => {
+            if lower.priority > greater.priority {
+                lower.right = merge(lower.right.take(), Some(greater));
+                lower.update();
+                Some(lower)
+            } else {
+                greater.left = merge(Some(lower), greater.left.take());
+                greater.update();
+                Some(greater)
+            }
+        }
+    }
+}
+
+impl PartialEq for Score {
+    fn eq(&self, other: &Score) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Score) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Score) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/sorted_set_test.rs"]
+mod sorted_set_test;
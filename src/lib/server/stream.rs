@@ -0,0 +1,399 @@
+//! Append-only log of entries, the value behind the `X*` commands.
+//!
+//! Entries are kept in an ordered map keyed by their ID, which plays the
+//! role of the radix tree Redis uses: appending, range queries and trimming
+//! from the oldest entry are all logarithmic.
+//!
+//! A stream also holds its consumer groups. Each group tracks the last entry
+//! delivered to its consumers, and the entries delivered but not yet
+//! acknowledged in a pending entries list (PEL), so they can be delivered
+//! again or claimed by another consumer.
+
+use bytes::Bytes;
+use core::fmt;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// ID of a stream entry: the unix time in milliseconds it was added at and
+/// a sequence number to order the entries added within the same millisecond.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct StreamId {
+    pub(crate) ms: u64,
+    pub(crate) seq: u64,
+}
+
+/// Fields and values of an entry, in the order they were given.
+pub(crate) type Fields = Vec<(Bytes, Bytes)>;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Stream {
+    entries: BTreeMap<StreamId, Fields>,
+
+    /// ID of the last entry ever added, new entries must be greater
+    last_id: StreamId,
+
+    groups: HashMap<Bytes, ConsumerGroup>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ConsumerGroup {
+    /// ID of the last entry delivered to a consumer of the group, the next
+    /// read of new entries starts after it
+    last_delivered: StreamId,
+
+    /// Entries delivered to a consumer and not acknowledged yet
+    pending: BTreeMap<StreamId, PendingEntry>,
+
+    /// IDs of the entries pending for each consumer, a subset of the
+    /// pending entries list
+    consumers: HashMap<Bytes, BTreeSet<StreamId>>,
+}
+
+/// An entry of the pending entries list of a group.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PendingEntry {
+    /// Consumer the entry was last delivered to
+    pub(crate) consumer: Bytes,
+
+    /// Unix time in milliseconds the entry was last delivered at
+    pub(crate) delivered_at: u64,
+
+    /// Number of times the entry was delivered
+    pub(crate) deliveries: u64,
+}
+
+/// How a stream is trimmed by `XADD` and `XTRIM`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Trim {
+    /// Keep at most that many entries
+    MaxLen(usize),
+    /// Remove the entries with a lower ID
+    MinId(StreamId),
+}
+
+impl StreamId {
+    pub(crate) const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub(crate) const MAX: StreamId = StreamId {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    pub(crate) fn new(ms: u64, seq: u64) -> StreamId {
+        StreamId { ms, seq }
+    }
+
+    /// Parses `ms-seq`, or `ms` alone in which case the sequence number is
+    /// `default_seq`.
+    pub(crate) fn parse(src: &[u8], default_seq: u64) -> Option<StreamId> {
+        let src = std::str::from_utf8(src).ok()?;
+        let parse = |part: &str| {
+            if part.is_empty() || !part.bytes().all(|byte| byte.is_ascii_digit()) {
+                return None;
+            }
+            part.parse().ok()
+        };
+
+        match src.split_once('-') {
+            Some((ms, seq)) => Some(StreamId::new(parse(ms)?, parse(seq)?)),
+            None => Some(StreamId::new(parse(src)?, default_seq)),
+        }
+    }
+
+    /// Returns the smallest ID greater than this one.
+    pub(crate) fn next(self) -> Option<StreamId> {
+        match self.seq.checked_add(1) {
+            Some(seq) => Some(StreamId::new(self.ms, seq)),
+            None => Some(StreamId::new(self.ms.checked_add(1)?, 0)),
+        }
+    }
+
+    /// Returns the greatest ID lower than this one.
+    pub(crate) fn prev(self) -> Option<StreamId> {
+        match self.seq.checked_sub(1) {
+            Some(seq) => Some(StreamId::new(self.ms, seq)),
+            None => Some(StreamId::new(self.ms.checked_sub(1)?, u64::MAX)),
+        }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}-{}", self.ms, self.seq)
+    }
+}
+
+impl Stream {
+    pub(crate) fn new() -> Stream {
+        Stream::default()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns the ID of the last entry ever added, even if it was deleted
+    /// since.
+    pub(crate) fn last_id(&self) -> StreamId {
+        self.last_id
+    }
+
+    /// Returns the ID the next entry gets when added at `now`, a unix time
+    /// in milliseconds, with `ms` as its time if given. Returns `None` if
+    /// the ID would not be greater than the last one.
+    pub(crate) fn next_id(&self, ms: Option<u64>, now: u64) -> Option<StreamId> {
+        let ms = ms.unwrap_or_else(|| now.max(self.last_id.ms));
+
+        if ms == self.last_id.ms {
+            self.last_id.next()
+        } else if ms > self.last_id.ms {
+            Some(StreamId::new(ms, 0))
+        } else {
+            None
+        }
+    }
+
+    /// Appends an entry. Returns `false` if `id` is not greater than the
+    /// last ID.
+    pub(crate) fn add(&mut self, id: StreamId, fields: Fields) -> bool {
+        if id <= self.last_id {
+            return false;
+        }
+
+        self.entries.insert(id, fields);
+        self.last_id = id;
+
+        true
+    }
+
+    /// Iterates over the entries between `start` and `end`, both included,
+    /// by increasing ID.
+    pub(crate) fn range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+    ) -> impl DoubleEndedIterator<Item = (&StreamId, &Fields)> {
+        (start <= end)
+            .then(|| self.entries.range(start..=end))
+            .into_iter()
+            .flatten()
+    }
+
+    /// Returns the fields of the entry `id`, if it exists.
+    pub(crate) fn get(&self, id: &StreamId) -> Option<&Fields> {
+        self.entries.get(id)
+    }
+
+    /// Removes the entry `id`, returning `true` if it existed.
+    pub(crate) fn remove(&mut self, id: &StreamId) -> bool {
+        self.entries.remove(id).is_some()
+    }
+
+    /// Removes the oldest entries as requested by `trim`, at most `limit`
+    /// of them if given. Returns the number of removed entries.
+    pub(crate) fn trim(&mut self, trim: Trim, limit: Option<usize>) -> usize {
+        let limit = limit.unwrap_or(usize::MAX);
+
+        let mut removed = 0;
+        while removed < limit {
+            let len = self.entries.len();
+            let Some(entry) = self.entries.first_entry() else {
+                break;
+            };
+
+            let remove = match trim {
+                Trim::MaxLen(max_len) => len > max_len,
+                Trim::MinId(min_id) => *entry.key() < min_id,
+            };
+            if !remove {
+                break;
+            }
+
+            entry.remove();
+            removed += 1;
+        }
+
+        removed
+    }
+
+    /// Returns `true` if the consumer group `name` exists.
+    pub(crate) fn has_group(&self, name: &[u8]) -> bool {
+        self.groups.contains_key(name)
+    }
+
+    /// Returns the consumer group `name`, if it exists.
+    pub(crate) fn group(&mut self, name: &[u8]) -> Option<&mut ConsumerGroup> {
+        self.groups.get_mut(name)
+    }
+
+    /// Creates a consumer group delivering the entries after
+    /// `last_delivered`. Returns `false` if it already exists.
+    pub(crate) fn create_group(&mut self, name: Bytes, last_delivered: StreamId) -> bool {
+        if self.groups.contains_key(&name) {
+            return false;
+        }
+
+        self.groups.insert(
+            name,
+            ConsumerGroup {
+                last_delivered,
+                ..ConsumerGroup::default()
+            },
+        );
+
+        true
+    }
+
+    /// Removes the consumer group `name`, returning `true` if it existed.
+    pub(crate) fn destroy_group(&mut self, name: &[u8]) -> bool {
+        self.groups.remove(name).is_some()
+    }
+
+    /// Delivers to `consumer` of the group `name` at most `count` entries
+    /// it never delivered, adding them to its pending entries list unless
+    /// `no_ack` is set.
+    ///
+    /// Returns `None` if the group does not exist.
+    pub(crate) fn read_group(
+        &mut self,
+        name: &[u8],
+        consumer: &Bytes,
+        count: usize,
+        no_ack: bool,
+        now: u64,
+    ) -> Option<Vec<(StreamId, Fields)>> {
+        let group = self.groups.get_mut(name)?;
+        group.create_consumer(consumer);
+
+        let entries: Vec<(StreamId, Fields)> = match group.last_delivered.next() {
+            Some(start) => self
+                .entries
+                .range(start..)
+                .take(count)
+                .map(|(id, fields)| (*id, fields.clone()))
+                .collect(),
+            None => vec![],
+        };
+
+        for (id, _) in &entries {
+            group.last_delivered = *id;
+            if !no_ack {
+                group.deliver(*id, consumer, now, 1);
+            }
+        }
+
+        Some(entries)
+    }
+}
+
+impl ConsumerGroup {
+    pub(crate) fn last_delivered(&self) -> StreamId {
+        self.last_delivered
+    }
+
+    pub(crate) fn set_last_delivered(&mut self, id: StreamId) {
+        self.last_delivered = id;
+    }
+
+    /// Returns the pending entries list, ordered by ID.
+    pub(crate) fn pending(&self) -> &BTreeMap<StreamId, PendingEntry> {
+        &self.pending
+    }
+
+    /// Iterates over the consumers and their number of pending entries.
+    pub(crate) fn consumers(&self) -> impl Iterator<Item = (&Bytes, usize)> {
+        self.consumers
+            .iter()
+            .map(|(name, pending)| (name, pending.len()))
+    }
+
+    /// Returns the IDs of the entries pending for `consumer` that are
+    /// greater or equal to `start`, at most `count` of them.
+    pub(crate) fn consumer_pending(
+        &self,
+        consumer: &[u8],
+        start: StreamId,
+        count: usize,
+    ) -> Vec<StreamId> {
+        self.consumers
+            .get(consumer)
+            .map(|pending| pending.range(start..).take(count).copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Creates `consumer` if needed, returning `true` if it was created.
+    pub(crate) fn create_consumer(&mut self, consumer: &Bytes) -> bool {
+        if self.consumers.contains_key(consumer) {
+            return false;
+        }
+
+        self.consumers.insert(consumer.clone(), BTreeSet::new());
+        true
+    }
+
+    /// Removes `consumer` along with its pending entries. Returns the number
+    /// of entries it had pending, `None` if it did not exist.
+    pub(crate) fn remove_consumer(&mut self, consumer: &[u8]) -> Option<usize> {
+        let pending = self.consumers.remove(consumer)?;
+        for id in &pending {
+            self.pending.remove(id);
+        }
+
+        Some(pending.len())
+    }
+
+    /// Removes the entry `id` from the pending entries list, returning
+    /// `true` if it was pending.
+    pub(crate) fn ack(&mut self, id: &StreamId) -> bool {
+        let Some(entry) = self.pending.remove(id) else {
+            return false;
+        };
+        if let Some(pending) = self.consumers.get_mut(&entry.consumer) {
+            pending.remove(id);
+        }
+
+        true
+    }
+
+    /// Assigns the entry `id` to `consumer` as delivered at `delivered_at`,
+    /// adding `deliveries` to its delivery count. The entry is added to the
+    /// pending entries list if needed.
+    pub(crate) fn deliver(
+        &mut self,
+        id: StreamId,
+        consumer: &Bytes,
+        delivered_at: u64,
+        deliveries: u64,
+    ) {
+        let previous = self.pending.insert(
+            id,
+            PendingEntry {
+                consumer: consumer.clone(),
+                delivered_at,
+                deliveries,
+            },
+        );
+
+        if let Some(previous) = previous {
+            self.pending.get_mut(&id).unwrap().deliveries += previous.deliveries;
+            if let Some(pending) = self.consumers.get_mut(&previous.consumer) {
+                pending.remove(&id);
+            }
+        }
+
+        self.consumers
+            .entry(consumer.clone())
+            .or_default()
+            .insert(id);
+    }
+
+    /// Sets the delivery count of the pending entry `id`.
+    pub(crate) fn set_deliveries(&mut self, id: &StreamId, deliveries: u64) {
+        if let Some(entry) = self.pending.get_mut(id) {
+            entry.deliveries = deliveries;
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+#[path = "test/stream_test.rs"]
+mod stream_test;
@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod cmd_test {
+    use super::super::*;
+    use crate::server::cmd::helper::command;
+    use crate::server::db::State;
+    use bytes::Bytes;
+
+    #[test]
+    fn ping_without_message_returns_pong() {
+        let cmd = Command::from_frame(command(&["PING"])).unwrap();
+        assert_eq!(
+            cmd.execute(&mut State::default(), Protocol::Resp2),
+            Frame::Simple("PONG".into())
+        );
+    }
+
+    #[test]
+    fn ping_with_message_returns_message() {
+        let cmd = Command::from_frame(command(&["ping", "hello"])).unwrap();
+        assert_eq!(
+            cmd.execute(&mut State::default(), Protocol::Resp2),
+            Frame::Bulk(Bytes::from("hello"))
+        );
+    }
+
+    #[test]
+    fn ping_with_too_many_arguments_returns_error() {
+        let err = Command::from_frame(command(&["ping", "a", "b"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "wrong number of arguments for 'ping' command"
+        );
+    }
+
+    #[test]
+    fn echo_returns_message() {
+        let cmd = Command::from_frame(command(&["Echo", "hello world"])).unwrap();
+        assert_eq!(
+            cmd.execute(&mut State::default(), Protocol::Resp2),
+            Frame::Bulk(Bytes::from("hello world"))
+        );
+    }
+
+    #[test]
+    fn echo_without_message_returns_error() {
+        let err = Command::from_frame(command(&["echo"])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "wrong number of arguments for 'echo' command"
+        );
+    }
+
+    #[test]
+    fn unknown_command_returns_error_frame() {
+        let cmd = Command::from_frame(command(&["FOO", "bar", "baz"])).unwrap();
+        assert_eq!(
+            cmd.execute(&mut State::default(), Protocol::Resp2),
+            Frame::Error("ERR unknown command 'FOO', with args beginning with: 'bar' 'baz'".into())
+        );
+    }
+
+    #[test]
+    fn non_array_frame_returns_error() {
+        assert!(Command::from_frame(Frame::Simple("PING".into())).is_err());
+    }
+
+    #[test]
+    fn empty_array_frame_returns_error() {
+        assert!(Command::from_frame(Frame::Array(vec![])).is_err());
+    }
+}
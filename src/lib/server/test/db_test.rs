@@ -0,0 +1,163 @@
+#[cfg(test)]
+mod db_test {
+    use super::super::*;
+
+    fn string(value: &str) -> Value {
+        Value::String(Bytes::copy_from_slice(value.as_bytes()))
+    }
+
+    #[tokio::test]
+    async fn clones_share_the_same_keyspace() {
+        let db = Db::new();
+        let other = db.clone();
+
+        db.with_state(|state| state.insert(Bytes::from("key"), string("value"), None));
+
+        assert_eq!(
+            other.with_state(|state| state.get(b"key").cloned()),
+            Some(string("value"))
+        );
+    }
+
+    #[test]
+    fn insert_returns_previous_value() {
+        let mut state = State::default();
+        assert_eq!(state.insert(Bytes::from("key"), string("one"), None), None);
+        assert_eq!(
+            state.insert(Bytes::from("key"), string("two"), None),
+            Some(string("one"))
+        );
+    }
+
+    #[test]
+    fn remove_deletes_key() {
+        let mut state = State::default();
+        state.insert(Bytes::from("key"), string("value"), None);
+        assert!(state.contains(b"key"));
+        assert!(state.remove(b"key").is_some());
+        assert!(!state.contains(b"key"));
+        assert!(state.remove(b"key").is_none());
+    }
+
+    #[test]
+    fn expired_key_is_removed_on_access() {
+        let mut state = State::default();
+        state.insert(Bytes::from("key"), string("value"), Some(now_ms() - 1));
+
+        assert_eq!(state.get(b"key"), None);
+        assert!(state.entries.is_empty());
+        assert!(state.expirations.is_empty());
+    }
+
+    #[test]
+    fn insert_replaces_previous_deadline() {
+        let mut state = State::default();
+        let when = now_ms() + 10_000;
+        state.insert(Bytes::from("key"), string("one"), Some(when));
+        state.insert(Bytes::from("key"), string("two"), None);
+
+        assert_eq!(state.expires_at(b"key"), Some(None));
+        assert!(state.expirations.is_empty());
+    }
+
+    #[test]
+    fn set_expires_at_on_missing_key_returns_false() {
+        let mut state = State::default();
+        assert!(!state.set_expires_at(b"key", Some(now_ms() + 1000)));
+    }
+
+    #[test]
+    fn set_expires_at_in_the_past_deletes_key() {
+        let mut state = State::default();
+        state.insert(Bytes::from("key"), string("value"), None);
+
+        assert!(state.set_expires_at(b"key", Some(now_ms() - 1)));
+        assert!(!state.contains(b"key"));
+    }
+
+    #[test]
+    fn purge_expired_keys_only_removes_due_keys() {
+        let mut state = State::default();
+        let now = now_ms();
+        state.insert(Bytes::from("a"), string("a"), Some(now + 10));
+        state.insert(Bytes::from("b"), string("b"), Some(now + 20));
+        state.insert(Bytes::from("c"), string("c"), None);
+
+        state.purge_expired_keys(now + 15);
+
+        assert_eq!(state.entries.len(), 2);
+        assert!(!state.entries.contains_key(&b"a"[..]));
+        assert_eq!(state.next_expiration(), Some(now + 20));
+    }
+
+    #[tokio::test]
+    async fn background_task_purges_expired_keys() {
+        let db = Db::new();
+        db.with_state(|state| {
+            state.insert(Bytes::from("key"), string("value"), Some(now_ms() + 20))
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Inspect the raw entries so the key is not lazily expired
+        assert!(db.with_state(|state| state.entries.is_empty()));
+    }
+
+    #[test]
+    fn writes_change_watched_versions() {
+        let mut state = State::default();
+        let version = state.watch(Bytes::from("key"));
+
+        // Reading, or deleting a missing key, is not a change
+        state.get(b"key");
+        state.remove(b"key");
+        assert_eq!(state.version(b"key"), version);
+
+        state.insert(Bytes::from("key"), string("value"), None);
+        // Borrowing a value is not a change until the key is touched
+        let version = state.version(b"key");
+        assert!(state.get_mut(b"key").is_some());
+        assert_eq!(state.version(b"key"), version);
+        state.touch(b"key");
+        assert_ne!(state.version(b"key"), version);
+
+        let version = state.version(b"key");
+        state.remove(b"key");
+        assert_ne!(state.version(b"key"), version);
+    }
+
+    #[test]
+    fn expiration_changes_watched_version() {
+        let mut state = State::default();
+        state.insert(Bytes::from("key"), string("value"), Some(now_ms() + 10));
+        let version = state.watch(Bytes::from("key"));
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert_ne!(state.version(b"key"), version);
+    }
+
+    #[test]
+    fn clear_only_changes_existing_keys() {
+        let mut state = State::default();
+        state.insert(Bytes::from("a"), string("a"), None);
+        let a = state.watch(Bytes::from("a"));
+        let b = state.watch(Bytes::from("b"));
+
+        state.clear();
+        assert!(state.entries.is_empty());
+        assert_ne!(state.version(b"a"), a);
+        assert_eq!(state.version(b"b"), b);
+    }
+
+    #[test]
+    fn keys_are_watched_until_every_client_unwatches() {
+        let mut state = State::default();
+        state.watch(Bytes::from("key"));
+        state.watch(Bytes::from("key"));
+
+        state.unwatch(b"key");
+        assert!(state.watched.contains_key(&b"key"[..]));
+        state.unwatch(b"key");
+        assert!(state.watched.is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod frame_prop_test {
+    use super::super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::sample::{select, Index};
+
+    /// Frames surviving a round trip through RESP3. `NullArray` is left out
+    /// since RESP3 encodes it like `Null`, and so is NaN which is not equal
+    /// to itself.
+    fn frame() -> impl Strategy<Value = Frame> {
+        let leaf = prop_oneof![
+            "[a-zA-Z0-9 ]*".prop_map(Frame::Simple),
+            "[a-zA-Z0-9 ]*".prop_map(Frame::Error),
+            any::<i64>().prop_map(Frame::Integer),
+            vec(any::<u8>(), 0..32).prop_map(|data| Frame::Bulk(data.into())),
+            Just(Frame::Null),
+            any::<bool>().prop_map(Frame::Boolean),
+            any::<f64>()
+                .prop_filter("NaN is not equal to itself", |val| !val.is_nan())
+                .prop_map(Frame::Double),
+            "-?[1-9][0-9]{0,40}".prop_map(Frame::BigNumber),
+            ("[a-z]{3}", vec(any::<u8>(), 0..32))
+                .prop_map(|(format, text)| Frame::Verbatim(format, text.into())),
+        ];
+
+        leaf.prop_recursive(4, 64, 4, |inner| {
+            let pairs = vec((inner.clone(), inner.clone()), 0..4);
+            prop_oneof![
+                vec(inner.clone(), 0..4).prop_map(Frame::Array),
+                vec(inner.clone(), 0..4).prop_map(Frame::Set),
+                vec(inner.clone(), 0..4).prop_map(Frame::Push),
+                pairs.clone().prop_map(Frame::Map),
+                (pairs, inner).prop_map(|(attributes, frame)| {
+                    Frame::Attribute(attributes, Box::new(frame))
+                }),
+            ]
+        })
+    }
+
+    /// Bytes biased towards the ones found in RESP, so random input goes
+    /// further than the type byte.
+    fn resp_byte() -> impl Strategy<Value = u8> {
+        prop_oneof![
+            select(b"+-:$*_#,(=%~>|\r\n0123456789tfx".to_vec()),
+            any::<u8>(),
+        ]
+    }
+
+    /// `check` and `parse` must either both accept the input, consuming the
+    /// same bytes, or both reject it for the same reason.
+    fn assert_check_agrees_with_parse(input: &[u8]) -> Result<(), TestCaseError> {
+        let mut checked = Cursor::new(input);
+        let mut parsed = Cursor::new(input);
+
+        match (Frame::check(&mut checked), Frame::parse(&mut parsed)) {
+            (Ok(()), Ok(_)) => prop_assert_eq!(checked.position(), parsed.position()),
+            (Err(Error::Incomplete), Err(Error::Incomplete)) => {}
+            (Err(Error::Incomplete), Err(_)) | (Err(_), Err(Error::Incomplete)) => {
+                prop_assert!(
+                    false,
+                    "only one of check and parse found the frame incomplete"
+                )
+            }
+            (Err(_), Err(_)) => {}
+            (check, parse) => {
+                prop_assert!(false, "check returned {:?}, parse {:?}", check, parse)
+            }
+        }
+
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn serialized_frames_round_trip(frame in frame()) {
+            let bytes = frame.to_bytes(Protocol::Resp3);
+
+            let mut cursor = Cursor::new(&bytes[..]);
+            prop_assert!(Frame::check(&mut cursor).is_ok());
+            prop_assert_eq!(cursor.position() as usize, bytes.len());
+
+            let mut cursor = Cursor::new(&bytes[..]);
+            prop_assert_eq!(Frame::parse(&mut cursor).unwrap(), frame);
+        }
+
+        #[test]
+        fn check_agrees_with_parse_on_random_bytes(input in vec(resp_byte(), 0..128)) {
+            assert_check_agrees_with_parse(&input)?;
+        }
+
+        #[test]
+        fn truncated_frames_are_incomplete(frame in frame(), cut in any::<Index>()) {
+            let bytes = frame.to_bytes(Protocol::Resp3);
+            let input = &bytes[..cut.index(bytes.len())];
+
+            assert_check_agrees_with_parse(input)?;
+            prop_assert!(matches!(
+                Frame::check(&mut Cursor::new(input)),
+                Err(Error::Incomplete)
+            ));
+        }
+
+        #[test]
+        fn check_agrees_with_parse_on_corrupted_frames(
+            frame in frame(),
+            at in any::<Index>(),
+            byte in resp_byte(),
+        ) {
+            let mut bytes = frame.to_bytes(Protocol::Resp3).to_vec();
+            let at = at.index(bytes.len());
+            bytes[at] = byte;
+
+            assert_check_agrees_with_parse(&bytes)?;
+        }
+    }
+}
@@ -11,14 +11,14 @@ mod frame_test {
     #[test]
     fn push_integer_into_frame() {
         let mut frame = Frame::new();
-        frame.push_int(42);
+        frame.push_int(42).unwrap();
         assert_eq!(frame, Frame::Array(vec![Frame::Integer(42)]));
     }
 
     #[test]
     fn push_bytes_into_frame() {
         let mut frame = Frame::new();
-        frame.push_bulk(Bytes::from("HelloWorld!"));
+        frame.push_bulk(Bytes::from("HelloWorld!")).unwrap();
         assert_eq!(
             frame,
             Frame::Array(vec![Frame::Bulk(Bytes::from("HelloWorld!"))])
@@ -188,4 +188,540 @@ mod frame_test {
         let mut frame = Cursor::new(bytes.as_bytes());
         assert!(Frame::parse(&mut frame).is_err());
     }
+
+    fn round_trip(frame: Frame) {
+        let bytes = frame.to_bytes(Protocol::Resp2);
+        let mut cursor = Cursor::new(&bytes[..]);
+        assert!(Frame::check(&mut cursor).is_ok());
+        assert_eq!(cursor.position() as usize, bytes.len());
+
+        cursor.set_position(0);
+        assert_eq!(Frame::parse(&mut cursor).unwrap(), frame);
+    }
+
+    #[test]
+    fn serialize_simple_frame() {
+        let frame = Frame::Simple("OK".into());
+        assert_eq!(frame.to_bytes(Protocol::Resp2), Bytes::from("+OK\r\n"));
+    }
+
+    #[test]
+    fn serialize_error_frame() {
+        let frame = Frame::Error("ERR unknown command".into());
+        assert_eq!(
+            frame.to_bytes(Protocol::Resp2),
+            Bytes::from("-ERR unknown command\r\n")
+        );
+    }
+
+    #[test]
+    fn serialize_integer_frame() {
+        let frame = Frame::Integer(1000);
+        assert_eq!(frame.to_bytes(Protocol::Resp2), Bytes::from(":1000\r\n"));
+    }
+
+    #[test]
+    fn serialize_bulk_frame() {
+        let frame = Frame::Bulk(Bytes::from("hello world"));
+        assert_eq!(
+            frame.to_bytes(Protocol::Resp2),
+            Bytes::from("$11\r\nhello world\r\n")
+        );
+    }
+
+    #[test]
+    fn serialize_empty_bulk_frame() {
+        let frame = Frame::Bulk(Bytes::new());
+        assert_eq!(frame.to_bytes(Protocol::Resp2), Bytes::from("$0\r\n\r\n"));
+    }
+
+    #[test]
+    fn serialize_null_frame() {
+        assert_eq!(
+            Frame::Null.to_bytes(Protocol::Resp2),
+            Bytes::from("$-1\r\n")
+        );
+    }
+
+    #[test]
+    fn serialize_empty_array_frame() {
+        assert_eq!(
+            Frame::new().to_bytes(Protocol::Resp2),
+            Bytes::from("*0\r\n")
+        );
+    }
+
+    #[test]
+    fn serialize_nested_array_frame() {
+        let frame = Frame::Array(vec![
+            Frame::Integer(1),
+            Frame::Array(vec![Frame::Bulk(Bytes::from("a")), Frame::Null]),
+        ]);
+        assert_eq!(
+            frame.to_bytes(Protocol::Resp2),
+            Bytes::from("*2\r\n:1\r\n*2\r\n$1\r\na\r\n$-1\r\n")
+        );
+    }
+
+    #[test]
+    fn serialize_appends_to_buffer() {
+        let mut bytes = BytesMut::from("+OK\r\n");
+        Frame::Integer(7).serialize(&mut bytes, Protocol::Resp2);
+        assert_eq!(&bytes[..], b"+OK\r\n:7\r\n");
+    }
+
+    #[test]
+    fn round_trip_simple_frame() {
+        round_trip(Frame::Simple("hello world".into()));
+    }
+
+    #[test]
+    fn round_trip_error_frame() {
+        round_trip(Frame::Error("Error message".into()));
+    }
+
+    #[test]
+    fn round_trip_integer_frame() {
+        round_trip(Frame::Integer(i64::MAX));
+    }
+
+    #[test]
+    fn round_trip_bulk_frame() {
+        round_trip(Frame::Bulk(Bytes::from("hello\r\nworld")));
+    }
+
+    #[test]
+    fn round_trip_binary_bulk_frame() {
+        round_trip(Frame::Bulk(Bytes::from(vec![0x00, 0xff, b'\r', b'\n'])));
+    }
+
+    #[test]
+    fn round_trip_null_frame() {
+        round_trip(Frame::Null);
+    }
+
+    #[test]
+    fn round_trip_nested_array_frame() {
+        round_trip(Frame::Array(vec![
+            Frame::Simple("OK".into()),
+            Frame::Error("ERR".into()),
+            Frame::Integer(42),
+            Frame::Bulk(Bytes::from("bulk")),
+            Frame::Null,
+            Frame::Array(vec![Frame::new(), Frame::Array(vec![Frame::Integer(0)])]),
+        ]));
+    }
+
+    #[test]
+    fn parse_then_serialize_returns_same_bytes() {
+        let bytes = "*3\r\n$3\r\nset\r\n$3\r\nkey\r\n$5\r\nvalue\r\n";
+        let mut cursor = Cursor::new(bytes.as_bytes());
+        let frame = Frame::parse(&mut cursor).unwrap();
+        assert_eq!(frame.to_bytes(Protocol::Resp2), Bytes::from(bytes));
+    }
+
+    fn parse_str(bytes: &str) -> Frame {
+        let mut cursor = Cursor::new(bytes.as_bytes());
+        assert!(Frame::check(&mut cursor).is_ok());
+        assert_eq!(cursor.position() as usize, bytes.len());
+
+        cursor.set_position(0);
+        Frame::parse(&mut cursor).unwrap()
+    }
+
+    fn round_trip_resp3(frame: Frame) {
+        let bytes = frame.to_bytes(Protocol::Resp3);
+        let mut cursor = Cursor::new(&bytes[..]);
+        assert!(Frame::check(&mut cursor).is_ok());
+        assert_eq!(cursor.position() as usize, bytes.len());
+
+        cursor.set_position(0);
+        assert_eq!(Frame::parse(&mut cursor).unwrap(), frame);
+    }
+
+    #[test]
+    fn parse_resp3_null_frame() {
+        assert_eq!(parse_str("_\r\n"), Frame::Null);
+    }
+
+    #[test]
+    fn parse_boolean_frames() {
+        assert_eq!(parse_str("#t\r\n"), Frame::Boolean(true));
+        assert_eq!(parse_str("#f\r\n"), Frame::Boolean(false));
+    }
+
+    #[test]
+    fn parse_invalid_boolean_frame() {
+        let mut frame = Cursor::new(&b"#x\r\n"[..]);
+        assert!(Frame::parse(&mut frame).is_err());
+    }
+
+    #[test]
+    fn parse_double_frames() {
+        assert_eq!(parse_str(",1.23\r\n"), Frame::Double(1.23));
+        assert_eq!(parse_str(",-10\r\n"), Frame::Double(-10.0));
+        assert_eq!(parse_str(",inf\r\n"), Frame::Double(f64::INFINITY));
+        assert_eq!(parse_str(",-inf\r\n"), Frame::Double(f64::NEG_INFINITY));
+        assert!(matches!(parse_str(",nan\r\n"), Frame::Double(val) if val.is_nan()));
+    }
+
+    #[test]
+    fn parse_big_number_frame() {
+        assert_eq!(
+            parse_str("(3492890328409238509324850943850943825024385\r\n"),
+            Frame::BigNumber("3492890328409238509324850943850943825024385".into())
+        );
+        assert_eq!(parse_str("(-12\r\n"), Frame::BigNumber("-12".into()));
+    }
+
+    #[test]
+    fn parse_invalid_big_number_frame() {
+        let mut frame = Cursor::new(&b"(12a\r\n"[..]);
+        assert!(Frame::parse(&mut frame).is_err());
+    }
+
+    #[test]
+    fn parse_verbatim_frame() {
+        assert_eq!(
+            parse_str("=15\r\ntxt:Some string\r\n"),
+            Frame::Verbatim("txt".into(), Bytes::from("Some string"))
+        );
+    }
+
+    #[test]
+    fn parse_invalid_verbatim_frame() {
+        let mut frame = Cursor::new(&b"=5\r\ntxt-a\r\n"[..]);
+        assert!(Frame::parse(&mut frame).is_err());
+    }
+
+    #[test]
+    fn parse_map_frame() {
+        assert_eq!(
+            parse_str("%2\r\n+first\r\n:1\r\n+second\r\n:2\r\n"),
+            Frame::Map(vec![
+                (Frame::Simple("first".into()), Frame::Integer(1)),
+                (Frame::Simple("second".into()), Frame::Integer(2)),
+            ])
+        );
+    }
+
+    #[test]
+    fn check_incomplete_map_frame() {
+        let mut frame = Cursor::new(&b"%2\r\n+first\r\n:1\r\n+second\r\n"[..]);
+        assert!(matches!(Frame::check(&mut frame), Err(Error::Incomplete)));
+    }
+
+    #[test]
+    fn parse_set_frame() {
+        assert_eq!(
+            parse_str("~2\r\n+a\r\n+b\r\n"),
+            Frame::Set(vec![Frame::Simple("a".into()), Frame::Simple("b".into())])
+        );
+    }
+
+    #[test]
+    fn parse_push_frame() {
+        assert_eq!(
+            parse_str(">2\r\n+message\r\n$5\r\nhello\r\n"),
+            Frame::Push(vec![
+                Frame::Simple("message".into()),
+                Frame::Bulk(Bytes::from("hello"))
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_attribute_frame() {
+        assert_eq!(
+            parse_str("|1\r\n+ttl\r\n:3600\r\n*2\r\n:1\r\n:2\r\n"),
+            Frame::Attribute(
+                vec![(Frame::Simple("ttl".into()), Frame::Integer(3600))],
+                Box::new(Frame::Array(vec![Frame::Integer(1), Frame::Integer(2)]))
+            )
+        );
+    }
+
+    #[test]
+    fn check_attribute_without_frame_is_incomplete() {
+        let mut frame = Cursor::new(&b"|1\r\n+ttl\r\n:3600\r\n"[..]);
+        assert!(matches!(Frame::check(&mut frame), Err(Error::Incomplete)));
+    }
+
+    #[test]
+    fn serialize_resp3_frames() {
+        assert_eq!(Frame::Null.to_bytes(Protocol::Resp3), Bytes::from("_\r\n"));
+        assert_eq!(
+            Frame::Double(1.5).to_bytes(Protocol::Resp3),
+            Bytes::from(",1.5\r\n")
+        );
+        assert_eq!(
+            Frame::Double(f64::NAN).to_bytes(Protocol::Resp3),
+            Bytes::from(",nan\r\n")
+        );
+        assert_eq!(
+            Frame::Boolean(true).to_bytes(Protocol::Resp3),
+            Bytes::from("#t\r\n")
+        );
+        assert_eq!(
+            Frame::Verbatim("txt".into(), Bytes::from("hi")).to_bytes(Protocol::Resp3),
+            Bytes::from("=6\r\ntxt:hi\r\n")
+        );
+        assert_eq!(
+            Frame::Map(vec![(Frame::Bulk(Bytes::from("a")), Frame::Integer(1))])
+                .to_bytes(Protocol::Resp3),
+            Bytes::from("%1\r\n$1\r\na\r\n:1\r\n")
+        );
+    }
+
+    #[test]
+    fn serialize_downgrades_resp3_frames_for_resp2() {
+        assert_eq!(
+            Frame::Map(vec![(Frame::Bulk(Bytes::from("a")), Frame::Integer(1))])
+                .to_bytes(Protocol::Resp2),
+            Bytes::from("*2\r\n$1\r\na\r\n:1\r\n")
+        );
+        assert_eq!(
+            Frame::Set(vec![Frame::Integer(1)]).to_bytes(Protocol::Resp2),
+            Bytes::from("*1\r\n:1\r\n")
+        );
+        assert_eq!(
+            Frame::Push(vec![Frame::Integer(1)]).to_bytes(Protocol::Resp2),
+            Bytes::from("*1\r\n:1\r\n")
+        );
+        assert_eq!(
+            Frame::Double(3.0).to_bytes(Protocol::Resp2),
+            Bytes::from("$1\r\n3\r\n")
+        );
+        assert_eq!(
+            Frame::Boolean(false).to_bytes(Protocol::Resp2),
+            Bytes::from(":0\r\n")
+        );
+        assert_eq!(
+            Frame::BigNumber("123".into()).to_bytes(Protocol::Resp2),
+            Bytes::from("$3\r\n123\r\n")
+        );
+        assert_eq!(
+            Frame::Verbatim("txt".into(), Bytes::from("hi")).to_bytes(Protocol::Resp2),
+            Bytes::from("$2\r\nhi\r\n")
+        );
+        assert_eq!(
+            Frame::Attribute(
+                vec![(Frame::Simple("ttl".into()), Frame::Integer(1))],
+                Box::new(Frame::Integer(2))
+            )
+            .to_bytes(Protocol::Resp2),
+            Bytes::from(":2\r\n")
+        );
+    }
+
+    #[test]
+    fn round_trip_resp3_frames() {
+        round_trip_resp3(Frame::Null);
+        round_trip_resp3(Frame::Boolean(false));
+        round_trip_resp3(Frame::Double(-0.25));
+        round_trip_resp3(Frame::Double(f64::INFINITY));
+        round_trip_resp3(Frame::BigNumber("-123456789012345678901234567890".into()));
+        round_trip_resp3(Frame::Verbatim("mkd".into(), Bytes::from("# title\r\n")));
+        round_trip_resp3(Frame::Set(vec![Frame::Bulk(Bytes::from("a"))]));
+        round_trip_resp3(Frame::Push(vec![
+            Frame::Bulk(Bytes::from("message")),
+            Frame::Array(vec![Frame::Null]),
+        ]));
+        round_trip_resp3(Frame::Map(vec![(
+            Frame::Bulk(Bytes::from("nested")),
+            Frame::Map(vec![(Frame::Integer(1), Frame::Set(vec![]))]),
+        )]));
+        round_trip_resp3(Frame::Attribute(
+            vec![(Frame::Simple("key".into()), Frame::Boolean(true))],
+            Box::new(Frame::Bulk(Bytes::from("value"))),
+        ));
+    }
+
+    #[test]
+    fn check_negative_integer_frame() {
+        let mut frame = Cursor::new(&b":-5\r\n"[..]);
+        assert!(Frame::check(&mut frame).is_ok());
+    }
+
+    #[test]
+    fn parse_negative_integer_frame() {
+        let mut frame = Cursor::new(&b":-5\r\n"[..]);
+        assert_eq!(Frame::parse(&mut frame).unwrap(), Frame::Integer(-5));
+    }
+
+    #[test]
+    fn parse_i64_min_integer_frame() {
+        let mut frame = Cursor::new(&b":-9223372036854775808\r\n"[..]);
+        assert_eq!(Frame::parse(&mut frame).unwrap(), Frame::Integer(i64::MIN));
+    }
+
+    #[test]
+    fn parse_integer_frame_with_trailing_characters() {
+        let mut frame = Cursor::new(&b":12a\r\n"[..]);
+        assert!(Frame::check(&mut frame).is_err());
+
+        let mut frame = Cursor::new(&b":12a\r\n"[..]);
+        assert!(Frame::parse(&mut frame).is_err());
+    }
+
+    #[test]
+    fn check_null_array_frame() {
+        let mut frame = Cursor::new(&b"*-1\r\n"[..]);
+        assert!(Frame::check(&mut frame).is_ok());
+        assert_eq!(frame.position(), 5);
+    }
+
+    #[test]
+    fn parse_null_array_frame() {
+        let mut frame = Cursor::new(&b"*-1\r\n"[..]);
+        assert_eq!(Frame::parse(&mut frame).unwrap(), Frame::NullArray);
+    }
+
+    #[test]
+    fn check_null_array_inside_array() {
+        let mut frame = Cursor::new(&b"*2\r\n*-1\r\n:1\r\n"[..]);
+        assert!(Frame::check(&mut frame).is_ok());
+
+        frame.set_position(0);
+        assert_eq!(
+            Frame::parse(&mut frame).unwrap(),
+            Frame::Array(vec![Frame::NullArray, Frame::Integer(1)])
+        );
+    }
+
+    #[test]
+    fn check_invalid_negative_bulk_length() {
+        let mut frame = Cursor::new(&b"$-2\r\n"[..]);
+        assert!(Frame::check(&mut frame).is_err());
+
+        let mut frame = Cursor::new(&b"$-2\r\n"[..]);
+        assert!(Frame::parse(&mut frame).is_err());
+    }
+
+    #[test]
+    fn check_invalid_negative_array_length() {
+        let mut frame = Cursor::new(&b"*-2\r\n"[..]);
+        assert!(Frame::check(&mut frame).is_err());
+
+        let mut frame = Cursor::new(&b"*-2\r\n"[..]);
+        assert!(Frame::parse(&mut frame).is_err());
+    }
+
+    #[test]
+    fn check_negative_set_length() {
+        let mut frame = Cursor::new(&b"~-1\r\n"[..]);
+        assert!(Frame::check(&mut frame).is_err());
+
+        let mut frame = Cursor::new(&b"~-1\r\n"[..]);
+        assert!(Frame::parse(&mut frame).is_err());
+    }
+
+    #[test]
+    fn check_incomplete_null_bulk_frame() {
+        let mut frame = Cursor::new(&b"$-1\r"[..]);
+        assert!(matches!(Frame::check(&mut frame), Err(Error::Incomplete)));
+    }
+
+    #[test]
+    fn serialize_negative_integer_frame() {
+        assert_eq!(
+            Frame::Integer(-2).to_bytes(Protocol::Resp2),
+            Bytes::from(":-2\r\n")
+        );
+    }
+
+    #[test]
+    fn serialize_null_array_frame() {
+        assert_eq!(
+            Frame::NullArray.to_bytes(Protocol::Resp2),
+            Bytes::from("*-1\r\n")
+        );
+        assert_eq!(
+            Frame::NullArray.to_bytes(Protocol::Resp3),
+            Bytes::from("_\r\n")
+        );
+    }
+
+    #[test]
+    fn round_trip_negative_integer_frames() {
+        round_trip(Frame::Integer(-1));
+        round_trip(Frame::Integer(i64::MIN));
+    }
+
+    #[test]
+    fn round_trip_null_array_frame() {
+        round_trip(Frame::NullArray);
+    }
+
+    #[test]
+    fn parse_integer_frame_with_lone_minus_sign() {
+        let mut frame = Cursor::new(&b":-\r\n"[..]);
+        assert!(Frame::parse(&mut frame).is_err());
+    }
+
+    #[test]
+    fn push_into_non_array_frame_is_an_error() {
+        let mut frame = Frame::Null;
+        assert!(matches!(frame.push_int(42), Err(Error::NotAnArray)));
+        assert!(matches!(
+            frame.push_bulk(Bytes::from("a")),
+            Err(Error::NotAnArray)
+        ));
+        assert_eq!(frame, Frame::Null);
+    }
+
+    #[test]
+    fn parse_unknown_type_byte() {
+        let mut frame = Cursor::new(&b"?3\r\n"[..]);
+        assert!(matches!(
+            Frame::check(&mut frame),
+            Err(Error::InvalidTypeByte(b'?'))
+        ));
+
+        let mut frame = Cursor::new(&b"?3\r\n"[..]);
+        assert!(matches!(
+            Frame::parse(&mut frame),
+            Err(Error::InvalidTypeByte(b'?'))
+        ));
+    }
+
+    #[test]
+    fn check_bulk_frame_without_terminator() {
+        let mut frame = Cursor::new(&b"$5\r\nhelloXX"[..]);
+        assert!(matches!(
+            Frame::check(&mut frame),
+            Err(Error::InvalidFormat)
+        ));
+    }
+
+    #[test]
+    fn check_simple_frame_with_invalid_utf8() {
+        let mut frame = Cursor::new(&b"+\xff\r\n"[..]);
+        assert!(matches!(Frame::check(&mut frame), Err(Error::InvalidUtf8)));
+    }
+
+    #[test]
+    fn check_invalid_boolean_frame() {
+        let mut frame = Cursor::new(&b"#x\r\n"[..]);
+        assert!(matches!(
+            Frame::check(&mut frame),
+            Err(Error::InvalidFormat)
+        ));
+    }
+
+    #[test]
+    fn parse_huge_array_length_is_incomplete() {
+        let mut frame = Cursor::new(&b"*9223372036854775807\r\n:1\r\n"[..]);
+        assert!(matches!(Frame::parse(&mut frame), Err(Error::Incomplete)));
+    }
+
+    #[test]
+    fn check_deeply_nested_frames() {
+        let bytes = "*1\r\n".repeat(100_000);
+        let mut frame = Cursor::new(bytes.as_bytes());
+        assert!(matches!(Frame::check(&mut frame), Err(Error::TooDeep)));
+
+        let mut frame = Cursor::new(bytes.as_bytes());
+        assert!(matches!(Frame::parse(&mut frame), Err(Error::TooDeep)));
+    }
 }
@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod geo_test {
+    use super::super::*;
+
+    const PALERMO: (f64, f64) = (13.361389, 38.115556);
+    const CATANIA: (f64, f64) = (15.087269, 37.502669);
+
+    #[test]
+    fn encode_interleaves_the_coordinates() {
+        assert_eq!(encode(PALERMO.0, PALERMO.1), 3_479_099_956_230_698);
+        assert_eq!(encode(CATANIA.0, CATANIA.1), 3_479_447_370_796_909);
+        assert_eq!(encode(LONGITUDE_MIN, LATITUDE_MIN), 0);
+    }
+
+    #[test]
+    fn decode_returns_the_center_of_the_area() {
+        let (longitude, latitude) = decode(3_479_099_956_230_698);
+        assert_eq!(format!("{:.17}", longitude), "13.36138933897018433");
+        assert_eq!(format!("{:.17}", latitude), "38.11555639549629859");
+
+        let (longitude, latitude) = decode(encode(LONGITUDE_MAX, LATITUDE_MAX));
+        assert!(longitude <= LONGITUDE_MAX && latitude <= LATITUDE_MAX);
+    }
+
+    #[test]
+    fn base32_is_a_standard_geohash() {
+        assert_eq!(to_base32(3_479_099_956_230_698), "sqc8b49rny0");
+        assert_eq!(to_base32(3_479_447_370_796_909), "sqdtr74hyu0");
+    }
+
+    #[test]
+    fn valid_positions() {
+        assert!(is_valid(PALERMO.0, PALERMO.1));
+        assert!(is_valid(-180.0, 85.05112878));
+        assert!(!is_valid(180.1, 0.0));
+        assert!(!is_valid(0.0, 85.06));
+    }
+
+    #[test]
+    fn distance_between_positions() {
+        let palermo = decode(encode(PALERMO.0, PALERMO.1));
+        let catania = decode(encode(CATANIA.0, CATANIA.1));
+        let distance = distance(palermo.0, palermo.1, catania.0, catania.1);
+        assert_eq!(format!("{:.4}", distance), "166274.1516");
+
+        // Same longitude
+        assert_eq!(
+            super::super::distance(10.0, 0.0, 10.0, 1.0),
+            latitude_distance(0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn shapes_contain_close_positions() {
+        let center = (15.0, 37.0);
+        let radius = Shape::Radius(200_000.0);
+        assert!(radius.distance_within(center, CATANIA).is_some());
+        assert!(radius.distance_within(center, PALERMO).is_some());
+        assert!(Shape::Radius(100_000.0)
+            .distance_within(center, PALERMO)
+            .is_none());
+
+        let square = Shape::Box {
+            width: 400_000.0,
+            height: 400_000.0,
+        };
+        let distance = square.distance_within(center, PALERMO).unwrap();
+        assert_eq!(format!("{:.4}", distance / 1000.0), "190.4424");
+        let narrow = Shape::Box {
+            width: 100_000.0,
+            height: 400_000.0,
+        };
+        assert!(narrow.distance_within(center, PALERMO).is_none());
+    }
+
+    #[test]
+    fn search_ranges_cover_the_shape() {
+        let shapes = [
+            Shape::Radius(100.0),
+            Shape::Radius(20_000.0),
+            Shape::Radius(500_000.0),
+            Shape::Box {
+                width: 30_000.0,
+                height: 5_000.0,
+            },
+        ];
+        let centers = [PALERMO, (0.0, 0.0), (179.99, -12.0), (-45.0, 75.0)];
+
+        for shape in shapes {
+            for center in centers {
+                let ranges = shape.search_ranges(center);
+                assert!(ranges.windows(2).all(|pair| pair[0].end <= pair[1].start));
+
+                // Every position of a grid around the center within the
+                // shape is in one of the ranges
+                let (min_lon, min_lat, max_lon, max_lat) = shape.bounding_box(center);
+                for i in 0..=40 {
+                    for j in 0..=40 {
+                        let longitude = min_lon + (max_lon - min_lon) * i as f64 / 40.0;
+                        let latitude = min_lat + (max_lat - min_lat) * j as f64 / 40.0;
+                        let longitude = (longitude + 540.0) % 360.0 - 180.0;
+                        if !is_valid(longitude, latitude) {
+                            continue;
+                        }
+
+                        let hash = encode(longitude, latitude);
+                        let position = decode(hash);
+                        if shape.distance_within(center, position).is_some() {
+                            assert!(
+                                ranges.iter().any(|range| range.contains(&hash)),
+                                "{:?} around {:?} misses {:?}",
+                                shape,
+                                center,
+                                position
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn search_ranges_shrink_with_the_shape() {
+        let len = |shape: Shape| -> u64 {
+            let ranges = shape.search_ranges(PALERMO);
+            ranges.iter().map(|range| range.end - range.start).sum()
+        };
+        assert!(len(Shape::Radius(1_000.0)) < len(Shape::Radius(100_000.0)));
+        assert!(len(Shape::Radius(100_000.0)) < 1 << 42);
+    }
+}
@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod glob_test {
+    use super::super::*;
+
+    fn glob(pattern: &str, string: &str) -> bool {
+        matches(pattern.as_bytes(), string.as_bytes())
+    }
+
+    #[test]
+    fn literal_pattern() {
+        assert!(glob("hello", "hello"));
+        assert!(!glob("hello", "hell"));
+        assert!(!glob("hell", "hello"));
+        assert!(glob("", ""));
+    }
+
+    #[test]
+    fn star_matches_any_sequence() {
+        assert!(glob("*", ""));
+        assert!(glob("h*o", "hello"));
+        assert!(glob("h*o", "ho"));
+        assert!(glob("news.*", "news.tech"));
+        assert!(!glob("news.*", "sport.tech"));
+        assert!(glob("a**b", "axxb"));
+        assert!(glob("*b*", "abc"));
+        assert!(glob("*a*b", "xaxxab"));
+        assert!(!glob("*a*b", "xaxxa"));
+    }
+
+    #[test]
+    fn many_stars_do_not_backtrack_exponentially() {
+        let string = "a".repeat(10_000);
+        assert!(!glob("*a*a*a*a*a*a*a*a*a*a*a*a*b", &string));
+        assert!(glob("*a*a*a*a*a*a*a*a*a*a*a*a*", &string));
+    }
+
+    #[test]
+    fn question_mark_matches_one_byte() {
+        assert!(glob("h?llo", "hello"));
+        assert!(!glob("h?llo", "hllo"));
+        assert!(!glob("?", ""));
+    }
+
+    #[test]
+    fn classes() {
+        assert!(glob("h[ae]llo", "hallo"));
+        assert!(!glob("h[ae]llo", "hillo"));
+        assert!(glob("h[^e]llo", "hallo"));
+        assert!(!glob("h[^e]llo", "hello"));
+        assert!(glob("h[a-b]llo", "hbllo"));
+        assert!(glob("h[b-a]llo", "hallo"));
+        assert!(!glob("h[a-b]llo", "hcllo"));
+    }
+
+    #[test]
+    fn escaped_bytes_are_literal() {
+        assert!(glob("h\\*llo", "h*llo"));
+        assert!(!glob("h\\*llo", "hello"));
+        assert!(glob("[\\]]", "]"));
+    }
+
+    #[test]
+    fn unclosed_class_ends_with_pattern() {
+        assert!(glob("h[ab", "ha"));
+        assert!(!glob("h[ab", "hc"));
+    }
+}
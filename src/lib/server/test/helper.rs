@@ -0,0 +1,62 @@
+//! Helpers shared by the command tests.
+
+use crate::error::ReplyError;
+use crate::server::cmd::Command;
+use crate::server::db::State;
+use crate::server::frame::{Frame, Protocol};
+
+use bytes::Bytes;
+
+/// Builds the `Frame::Array` a client would send for `args`.
+pub(crate) fn command(args: &[&str]) -> Frame {
+    Frame::Array(
+        args.iter()
+            .map(|arg| Frame::Bulk(Bytes::copy_from_slice(arg.as_bytes())))
+            .collect(),
+    )
+}
+
+/// Parses `args` as a command and executes it against `state`, as sent on a
+/// RESP2 connection.
+pub(crate) fn run(state: &mut State, args: &[&str]) -> Frame {
+    run_with(state, args, Protocol::Resp2)
+}
+
+/// Parses `args` as a command and executes it against `state`, as sent on a
+/// connection speaking `protocol`.
+pub(crate) fn run_with(state: &mut State, args: &[&str], protocol: Protocol) -> Frame {
+    match Command::from_frame(command(args)) {
+        Ok(cmd) => cmd.execute(state, protocol),
+        Err(err) => ReplyError::from(err).into(),
+    }
+}
+
+/// Shorthand for a bulk frame holding `value`.
+pub(crate) fn bulk(value: &str) -> Frame {
+    Frame::Bulk(Bytes::copy_from_slice(value.as_bytes()))
+}
+
+/// Shorthand for an array of bulk frames holding `values`.
+pub(crate) fn bulks(values: &[&str]) -> Frame {
+    Frame::Array(values.iter().map(|value| bulk(value)).collect())
+}
+
+/// Returns the entries of a set or array reply, sorted for replies with no
+/// order.
+pub(crate) fn sorted(frame: Frame) -> Vec<Frame> {
+    let (Frame::Set(mut values) | Frame::Array(mut values)) = frame else {
+        panic!("expected a set or an array, got {:?}", frame);
+    };
+    values.sort_by_key(|value| format!("{:?}", value));
+    values
+}
+
+/// Shorthand for an `ERR` error frame with `msg`.
+pub(crate) fn error(msg: &str) -> Frame {
+    Frame::Error(format!("ERR {}", msg))
+}
+
+/// The error replied to commands run against a key of another type.
+pub(crate) fn wrong_type() -> Frame {
+    ReplyError::WrongType.into()
+}
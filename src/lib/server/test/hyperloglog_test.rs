@@ -0,0 +1,158 @@
+#[cfg(test)]
+mod hyperloglog_test {
+    use super::super::*;
+
+    #[test]
+    fn murmur_hash_matches_the_reference() {
+        let seed = 0xadc8_3b19;
+        assert_eq!(murmur_hash64a(b"", seed), 0xd8df_ea65_85bc_9732);
+        assert_eq!(murmur_hash64a(b"a", seed), 0x53d2_470a_9b43_b1a7);
+        assert_eq!(murmur_hash64a(b"hello", seed), 0x0f65_6f01_eecf_e400);
+        assert_eq!(murmur_hash64a(b"hello world!", seed), 0x0fc4_4401_1f57_220c);
+    }
+
+    #[test]
+    fn new_is_sparse_with_a_valid_cache() {
+        let hll = HyperLogLog::new();
+        assert_eq!(
+            &hll.encode()[..],
+            b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x7f\xff"
+        );
+        assert_eq!(HyperLogLog::decode(&hll.encode()), Some(hll));
+    }
+
+    #[test]
+    fn add_sets_a_register_and_invalidates_the_cache() {
+        let mut hll = HyperLogLog::new();
+        assert!(hll.add(b"a"));
+        assert!(!hll.add(b"a"));
+
+        // "a" sets register 12711 to 2
+        assert_eq!(
+            &hll.encode()[..],
+            b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x80\x71\xa6\x84\x4e\x57"
+        );
+
+        assert_eq!(hll.count(), 1);
+        assert_eq!(&hll.encode()[8..16], b"\x01\x00\x00\x00\x00\x00\x00\x00");
+    }
+
+    #[test]
+    fn adding_in_place_matches_adding_to_the_registers() {
+        for dense in [false, true] {
+            let mut hll = HyperLogLog::new();
+            if dense {
+                hll.make_dense();
+            }
+
+            // Sparse values turn dense as they grow
+            let mut src = BytesMut::from(&hll.encode()[..]);
+            for i in 0..5000 {
+                let element = format!("element:{}", i);
+                assert_eq!(
+                    add_encoded(&mut src, element.as_bytes()),
+                    hll.add(element.as_bytes())
+                );
+            }
+
+            assert!(is_valid(&src));
+            assert_eq!(src[4], DENSE);
+            assert_eq!(cached_count(&src), None);
+            assert_eq!(HyperLogLog::decode(&src).unwrap().registers, hll.registers);
+        }
+    }
+
+    #[test]
+    fn dense_and_sparse_decode_to_the_same_registers() {
+        let mut hll = HyperLogLog::new();
+        for i in 0..100 {
+            hll.add(format!("element:{}", i).as_bytes());
+        }
+
+        let mut dense = hll.clone();
+        dense.make_dense();
+        let encoded = dense.encode();
+        assert_eq!(encoded.len(), DENSE_LEN);
+        assert_eq!(encoded[4], DENSE);
+
+        let decoded = HyperLogLog::decode(&encoded).unwrap();
+        assert_eq!(decoded.registers, hll.registers);
+        assert_eq!(HyperLogLog::decode(&hll.encode()).unwrap(), hll);
+    }
+
+    #[test]
+    fn large_sparse_values_turn_dense() {
+        let mut hll = HyperLogLog::new();
+        for i in 0..5000 {
+            hll.add(format!("element:{}", i).as_bytes());
+        }
+        assert_eq!(hll.encode()[4], DENSE);
+
+        // Registers above 32 do not fit the sparse encoding
+        let mut hll = HyperLogLog::new();
+        hll.set(0, 33);
+        assert_eq!(hll.encode()[4], DENSE);
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        assert!(!is_valid(
+            b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x7f"
+        ));
+        assert_eq!(HyperLogLog::decode(b""), None);
+        assert_eq!(
+            HyperLogLog::decode(b"HYLX\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x7f\xff"),
+            None
+        );
+        // Unknown encoding
+        assert_eq!(
+            HyperLogLog::decode(b"HYLL\x02\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x7f\xff"),
+            None
+        );
+        // Dense of the wrong size
+        assert_eq!(
+            HyperLogLog::decode(b"HYLL\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x7f\xff"),
+            None
+        );
+        // Sparse not covering every register
+        assert_eq!(
+            HyperLogLog::decode(b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x7f\xfe"),
+            None
+        );
+        assert_eq!(
+            HyperLogLog::decode(
+                b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x7f\xff\x00"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn count_is_close_to_the_cardinality() {
+        let mut hll = HyperLogLog::new();
+        for i in 0..10 {
+            hll.add(format!("element:{}", i).as_bytes());
+        }
+        assert_eq!(hll.count(), 10);
+
+        for i in 0..100_000 {
+            hll.add(format!("element:{}", i).as_bytes());
+        }
+        let count = hll.count() as f64;
+        assert!((count - 100_000.0).abs() / 100_000.0 < 0.02, "{}", count);
+    }
+
+    #[test]
+    fn merge_counts_the_union() {
+        let mut left = HyperLogLog::new();
+        let mut right = HyperLogLog::new();
+        for i in 0..1000 {
+            left.add(format!("element:{}", i).as_bytes());
+            right.add(format!("element:{}", i + 500).as_bytes());
+        }
+
+        left.merge(&right);
+        let count = left.count() as f64;
+        assert!((count - 1500.0).abs() / 1500.0 < 0.02, "{}", count);
+    }
+}
@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod inline_test {
+    use super::super::*;
+
+    fn parse_str(line: &str) -> Result<Frame, Error> {
+        let mut src = Cursor::new(line.as_bytes());
+        parse(&mut src)
+    }
+
+    fn args(args: &[&[u8]]) -> Frame {
+        Frame::Array(
+            args.iter()
+                .map(|arg| Frame::Bulk(Bytes::copy_from_slice(arg)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn parse_single_word() {
+        assert_eq!(parse_str("PING\r\n").unwrap(), args(&[b"PING"]));
+    }
+
+    #[test]
+    fn parse_words_separated_by_whitespace() {
+        assert_eq!(
+            parse_str("  SET \t a   b \r\n").unwrap(),
+            args(&[b"SET", b"a", b"b"])
+        );
+    }
+
+    #[test]
+    fn parse_line_ending_with_bare_newline() {
+        assert_eq!(parse_str("GET a\n").unwrap(), args(&[b"GET", b"a"]));
+    }
+
+    #[test]
+    fn parse_empty_line_returns_empty_array() {
+        assert_eq!(parse_str("\r\n").unwrap(), Frame::new());
+        assert_eq!(parse_str("   \n").unwrap(), Frame::new());
+    }
+
+    #[test]
+    fn parse_advances_past_the_line() {
+        let mut src = Cursor::new(&b"PING\r\nECHO a\r\n"[..]);
+        assert_eq!(parse(&mut src).unwrap(), args(&[b"PING"]));
+        assert_eq!(src.position(), 6);
+        assert_eq!(parse(&mut src).unwrap(), args(&[b"ECHO", b"a"]));
+        assert_eq!(src.position(), 14);
+    }
+
+    #[test]
+    fn parse_without_newline_is_incomplete() {
+        assert!(matches!(parse_str("SET a b"), Err(Error::Incomplete)));
+    }
+
+    #[test]
+    fn parse_double_quoted_argument() {
+        assert_eq!(
+            parse_str("SET key \"hello world\"\r\n").unwrap(),
+            args(&[b"SET", b"key", b"hello world"])
+        );
+    }
+
+    #[test]
+    fn parse_double_quoted_escapes() {
+        assert_eq!(
+            parse_str("ECHO \"a\\nb\\tc\\\"d\\\\e\\x41\\x4a\"\r\n").unwrap(),
+            args(&[b"ECHO", b"a\nb\tc\"d\\eAJ"])
+        );
+    }
+
+    #[test]
+    fn parse_invalid_hex_escape_is_kept_verbatim() {
+        assert_eq!(
+            parse_str("ECHO \"\\xZZ\"\r\n").unwrap(),
+            args(&[b"ECHO", b"xZZ"])
+        );
+    }
+
+    #[test]
+    fn parse_single_quoted_argument() {
+        assert_eq!(
+            parse_str("ECHO 'it\\'s \"raw\"\\n'\r\n").unwrap(),
+            args(&[b"ECHO", b"it's \"raw\"\\n"])
+        );
+    }
+
+    #[test]
+    fn parse_empty_quoted_argument() {
+        assert_eq!(
+            parse_str("SET key \"\"\r\n").unwrap(),
+            args(&[b"SET", b"key", b""])
+        );
+    }
+
+    #[test]
+    fn parse_unbalanced_quotes_returns_error() {
+        assert!(matches!(
+            parse_str("ECHO \"abc\r\n"),
+            Err(Error::UnbalancedQuotes)
+        ));
+        assert!(matches!(
+            parse_str("ECHO 'abc\r\n"),
+            Err(Error::UnbalancedQuotes)
+        ));
+    }
+
+    #[test]
+    fn parse_closing_quote_followed_by_character_returns_error() {
+        assert!(matches!(
+            parse_str("ECHO \"abc\"def\r\n"),
+            Err(Error::UnbalancedQuotes)
+        ));
+    }
+
+    #[test]
+    fn parse_too_big_request_returns_error() {
+        let line = "a".repeat(MAX_INLINE_SIZE);
+        assert!(matches!(parse_str(&line), Err(Error::InlineTooBig)));
+
+        let line = format!("{}\r\n", "a".repeat(MAX_INLINE_SIZE));
+        assert!(matches!(parse_str(&line), Err(Error::InlineTooBig)));
+    }
+
+    #[test]
+    fn parse_request_just_below_the_limit() {
+        let line = format!("{}\n", "a".repeat(MAX_INLINE_SIZE - 1));
+        assert!(parse_str(&line).is_ok());
+    }
+}
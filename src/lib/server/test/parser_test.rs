@@ -68,31 +68,31 @@ mod parser_test {
     }
 
     #[test]
-    fn next_int_returns_u64_if_integer_frame() {
+    fn next_int_returns_i64_if_integer_frame() {
         let mut parser = Parser::new(Frame::Array(vec![Frame::Integer(42)])).unwrap();
         assert_eq!(parser.next_int().unwrap(), 42);
     }
 
     #[test]
-    fn next_int_returns_u64_if_simple_frame_can_be_converted_into_u64() {
+    fn next_int_returns_i64_if_simple_frame_can_be_converted_into_i64() {
         let mut parser = Parser::new(Frame::Array(vec![Frame::Simple("42".into())])).unwrap();
         assert_eq!(parser.next_int().unwrap(), 42);
     }
 
     #[test]
-    fn next_int_returns_error_if_simple_frame_can_not_be_converted_into_u64() {
+    fn next_int_returns_error_if_simple_frame_can_not_be_converted_into_i64() {
         let mut parser = Parser::new(Frame::Array(vec![Frame::Simple("Hello".into())])).unwrap();
         assert!(parser.next_int().is_err());
     }
 
     #[test]
-    fn next_int_returns_u64_if_bulk_frame_can_be_converted_into_u64() {
+    fn next_int_returns_i64_if_bulk_frame_can_be_converted_into_i64() {
         let mut parser = Parser::new(Frame::Array(vec![Frame::Bulk(Bytes::from("42"))])).unwrap();
         assert_eq!(parser.next_int().unwrap(), 42);
     }
 
     #[test]
-    fn next_int_returns_error_if_bulk_frame_can_not_be_converted_into_u64() {
+    fn next_int_returns_error_if_bulk_frame_can_not_be_converted_into_i64() {
         let mut parser =
             Parser::new(Frame::Array(vec![Frame::Bulk(Bytes::from("Hello"))])).unwrap();
         assert!(parser.next_int().is_err());
@@ -106,8 +106,7 @@ mod parser_test {
 
     #[test]
     fn next_bytes_returns_bytes_if_simple_frame() {
-        let mut parser =
-            Parser::new(Frame::Array(vec![Frame::Simple("Hello".into())]).into()).unwrap();
+        let mut parser = Parser::new(Frame::Array(vec![Frame::Simple("Hello".into())])).unwrap();
         assert_eq!(parser.next_bytes().unwrap(), Bytes::from("Hello"));
     }
 
@@ -135,4 +134,104 @@ mod parser_test {
         let mut parser = Parser::new(Frame::Array(vec![Frame::Null])).unwrap();
         assert!(parser.finish().is_err());
     }
+
+    #[test]
+    fn remaining_returns_number_of_unconsumed_frames() {
+        let mut parser = Parser::new(Frame::Array(vec![
+            Frame::Bulk(Bytes::from("get")),
+            Frame::Bulk(Bytes::from("key")),
+        ]))
+        .unwrap();
+        assert_eq!(parser.remaining(), 2);
+        parser.next_bytes().unwrap();
+        assert_eq!(parser.remaining(), 1);
+        parser.next_bytes().unwrap();
+        assert_eq!(parser.remaining(), 0);
+    }
+
+    #[test]
+    fn next_int_returns_negative_integer_frame() {
+        let mut parser = Parser::new(Frame::Array(vec![Frame::Integer(-5)])).unwrap();
+        assert_eq!(parser.next_int().unwrap(), -5);
+    }
+
+    #[test]
+    fn next_int_returns_negative_number_from_bulk_frame() {
+        let mut parser = Parser::new(Frame::Array(vec![Frame::Bulk(Bytes::from("-42"))])).unwrap();
+        assert_eq!(parser.next_int().unwrap(), -42);
+    }
+
+    #[test]
+    fn next_int_returns_negative_number_from_simple_frame() {
+        let mut parser = Parser::new(Frame::Array(vec![Frame::Simple("-1".into())])).unwrap();
+        assert_eq!(parser.next_int().unwrap(), -1);
+    }
+
+    #[test]
+    fn next_int_returns_i64_min() {
+        let mut parser = Parser::new(Frame::Array(vec![Frame::Bulk(Bytes::from(
+            "-9223372036854775808",
+        ))]))
+        .unwrap();
+        assert_eq!(parser.next_int().unwrap(), i64::MIN);
+    }
+
+    #[test]
+    fn next_int_returns_error_on_overflow() {
+        let mut parser = Parser::new(Frame::Array(vec![Frame::Bulk(Bytes::from(
+            "9223372036854775808",
+        ))]))
+        .unwrap();
+        assert!(parser.next_int().is_err());
+    }
+
+    #[test]
+    fn next_int_returns_error_on_trailing_characters() {
+        let mut parser =
+            Parser::new(Frame::Array(vec![Frame::Bulk(Bytes::from("12abc"))])).unwrap();
+        assert!(parser.next_int().is_err());
+    }
+
+    #[test]
+    fn next_int_returns_error_on_lone_minus_sign() {
+        let mut parser = Parser::new(Frame::Array(vec![Frame::Bulk(Bytes::from("-"))])).unwrap();
+        assert!(parser.next_int().is_err());
+    }
+
+    #[test]
+    fn next_int_returns_error_on_empty_bulk() {
+        let mut parser = Parser::new(Frame::Array(vec![Frame::Bulk(Bytes::new())])).unwrap();
+        assert!(parser.next_int().is_err());
+    }
+
+    #[test]
+    fn next_double_parses_decimal_and_exponent_notations() {
+        let mut parser = Parser::new(Frame::Array(vec![
+            Frame::Bulk(Bytes::from("1.5")),
+            Frame::Bulk(Bytes::from("-2e3")),
+            Frame::Bulk(Bytes::from("+inf")),
+            Frame::Integer(7),
+        ]))
+        .unwrap();
+        assert_eq!(parser.next_double().unwrap(), 1.5);
+        assert_eq!(parser.next_double().unwrap(), -2000.0);
+        assert_eq!(parser.next_double().unwrap(), f64::INFINITY);
+        assert_eq!(parser.next_double().unwrap(), 7.0);
+    }
+
+    #[test]
+    fn next_double_returns_error_on_nan_and_garbage() {
+        let mut parser = Parser::new(Frame::Array(vec![
+            Frame::Bulk(Bytes::from("nan")),
+            Frame::Bulk(Bytes::from("1.5x")),
+            Frame::Bulk(Bytes::new()),
+        ]))
+        .unwrap();
+        for _ in 0..3 {
+            assert!(matches!(
+                parser.next_double(),
+                Err(ParserError::Reply(ReplyError::NotFloat))
+            ));
+        }
+    }
 }
@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod pubsub_test {
+    use super::super::*;
+    use crate::server::cmd::helper::bulk;
+
+    fn name(value: &str) -> Bytes {
+        Bytes::copy_from_slice(value.as_bytes())
+    }
+
+    #[test]
+    fn subscriptions_are_counted_once() {
+        let mut broker = Broker::default();
+        let mut subscriptions = Subscriptions::new();
+
+        assert_eq!(
+            subscriptions.subscribe(&mut broker, 1, Kind::Channel, name("news")),
+            1
+        );
+        assert_eq!(
+            subscriptions.subscribe(&mut broker, 1, Kind::Channel, name("news")),
+            1
+        );
+        assert_eq!(
+            subscriptions.subscribe(&mut broker, 1, Kind::Pattern, name("n*")),
+            2
+        );
+        assert_eq!(broker.subscribers(b"news"), 1);
+        assert_eq!(broker.patterns(), 1);
+
+        assert_eq!(
+            subscriptions.unsubscribe(&mut broker, 1, Kind::Channel, &name("other")),
+            2
+        );
+        assert_eq!(
+            subscriptions.unsubscribe(&mut broker, 1, Kind::Channel, &name("news")),
+            1
+        );
+        assert_eq!(broker.subscribers(b"news"), 0);
+        assert!(broker.channels(None).is_empty());
+    }
+
+    #[tokio::test]
+    async fn publish_reaches_channels_and_patterns() {
+        let mut broker = Broker::default();
+        let mut alice = Subscriptions::new();
+        let mut bob = Subscriptions::new();
+        alice.subscribe(&mut broker, 1, Kind::Channel, name("news"));
+        alice.subscribe(&mut broker, 1, Kind::Pattern, name("n*"));
+        bob.subscribe(&mut broker, 2, Kind::Pattern, name("sport"));
+
+        assert_eq!(broker.publish(&name("news"), &name("hello")), 2);
+        assert_eq!(broker.publish(&name("weather"), &name("sunny")), 0);
+
+        assert_eq!(
+            alice.recv().await,
+            Frame::Push(vec![bulk("message"), bulk("news"), bulk("hello")])
+        );
+        assert_eq!(
+            alice.recv().await,
+            Frame::Push(vec![
+                bulk("pmessage"),
+                bulk("n*"),
+                bulk("news"),
+                bulk("hello"),
+            ])
+        );
+    }
+
+    #[test]
+    fn channels_can_be_filtered() {
+        let mut broker = Broker::default();
+        let mut subscriptions = Subscriptions::new();
+        for channel in ["news.sport", "news.weather", "chat"] {
+            subscriptions.subscribe(&mut broker, 1, Kind::Channel, name(channel));
+        }
+
+        assert_eq!(
+            broker.channels(Some(b"news.*")),
+            vec![name("news.sport"), name("news.weather")]
+        );
+        assert_eq!(broker.channels(None).len(), 3);
+
+        subscriptions.clear(&mut broker, 1);
+        assert!(subscriptions.is_empty());
+        assert!(broker.channels(None).is_empty());
+    }
+
+    #[tokio::test]
+    async fn shard_channels_are_kept_apart() {
+        let mut broker = Broker::default();
+        let mut subscriptions = Subscriptions::new();
+
+        assert_eq!(
+            subscriptions.subscribe(&mut broker, 1, Kind::Channel, name("news")),
+            1
+        );
+        assert_eq!(
+            subscriptions.subscribe(&mut broker, 1, Kind::Shard, name("{user}.a")),
+            1
+        );
+        assert_eq!(
+            subscriptions.subscribe(&mut broker, 1, Kind::Shard, name("{user}.b")),
+            2
+        );
+        subscriptions.subscribe(&mut broker, 1, Kind::Pattern, name("*"));
+
+        assert_eq!(broker.shard_subscribers(b"{user}.a"), 1);
+        assert_eq!(broker.subscribers(b"{user}.a"), 0);
+        assert_eq!(
+            broker.shard_channels(None),
+            vec![name("{user}.a"), name("{user}.b")]
+        );
+        assert_eq!(broker.channels(None), vec![name("news")]);
+
+        // Patterns do not match shard channels
+        assert_eq!(broker.publish_shard(&name("{user}.a"), &name("hi")), 1);
+        assert_eq!(broker.publish_shard(&name("news"), &name("hi")), 0);
+        assert_eq!(
+            subscriptions.recv().await,
+            Frame::Push(vec![bulk("smessage"), bulk("{user}.a"), bulk("hi")])
+        );
+
+        assert_eq!(
+            subscriptions.unsubscribe(&mut broker, 1, Kind::Shard, &name("{user}.a")),
+            1
+        );
+        subscriptions.clear(&mut broker, 1);
+        assert!(subscriptions.is_empty());
+        assert!(broker.shard_channels(None).is_empty());
+    }
+
+    #[test]
+    fn brokers_serve_their_slots() {
+        assert!(Broker::default().serves(b"foo"));
+
+        // "foo" is in slot 12182, "bar" in slot 5061
+        let broker = Broker::serving(0..8192);
+        assert!(!broker.serves(b"foo"));
+        assert!(broker.serves(b"bar"));
+    }
+}
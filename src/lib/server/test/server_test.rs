@@ -0,0 +1,755 @@
+#[cfg(test)]
+mod server_test {
+    use super::super::*;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+    use tokio::task::JoinHandle;
+
+    /// Serves an in-memory connection and returns the client side of it.
+    fn serve() -> (DuplexStream, JoinHandle<Result<(), Error>>) {
+        let (client, server) = tokio::io::duplex(4096);
+        let handle = tokio::spawn(handle_connection(Db::new(), Connection::new(server)));
+        (client, handle)
+    }
+
+    /// Connects a new client to `db`.
+    fn connect(db: &Db) -> DuplexStream {
+        let (client, server) = tokio::io::duplex(4096);
+        tokio::spawn(handle_connection(db.clone(), Connection::new(server)));
+        client
+    }
+
+    /// Reads the next reply sent to `client`.
+    async fn read_reply(client: &mut DuplexStream) -> String {
+        let mut response = vec![0; 4096];
+        let len = client.read(&mut response).await.unwrap();
+        String::from_utf8(response[..len].to_vec()).unwrap()
+    }
+
+    /// Sends an inline command and returns its reply.
+    async fn request(client: &mut DuplexStream, command: &str) -> String {
+        send(client, command).await;
+        read_reply(client).await
+    }
+
+    /// Sends an inline command, then lets the server handle it.
+    async fn send(client: &mut DuplexStream, command: &str) {
+        client
+            .write_all(format!("{}\r\n", command).as_bytes())
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    /// Returns `true` if `client` receives nothing for a while.
+    async fn is_blocked(client: &mut DuplexStream) -> bool {
+        let mut response = vec![0; 4096];
+        tokio::time::timeout(Duration::from_secs(60), client.read(&mut response))
+            .await
+            .is_err()
+    }
+
+    /// Reads from `client` until the peer closes the stream.
+    async fn read_to_end(client: &mut DuplexStream) -> String {
+        let mut response = vec![];
+        client.read_to_end(&mut response).await.unwrap();
+        String::from_utf8(response).unwrap()
+    }
+
+    #[tokio::test]
+    async fn replies_to_commands_until_client_disconnects() {
+        let (mut client, handle) = serve();
+
+        client
+            .write_all(b"*1\r\n$4\r\nPING\r\nECHO hello\r\n")
+            .await
+            .unwrap();
+        client.shutdown().await.unwrap();
+
+        assert_eq!(read_to_end(&mut client).await, "+PONG\r\n$5\r\nhello\r\n");
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn empty_array_is_ignored() {
+        let (mut client, handle) = serve();
+
+        client.write_all(b"*0\r\nPING\r\n").await.unwrap();
+        client.shutdown().await.unwrap();
+
+        assert_eq!(read_to_end(&mut client).await, "+PONG\r\n");
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn command_error_keeps_connection_open() {
+        let (mut client, handle) = serve();
+
+        client.write_all(b"GET\r\nPING\r\n").await.unwrap();
+        client.shutdown().await.unwrap();
+
+        assert_eq!(
+            read_to_end(&mut client).await,
+            "-ERR wrong number of arguments for 'get' command\r\n+PONG\r\n"
+        );
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn invalid_length_closes_connection() {
+        let (mut client, handle) = serve();
+
+        client.write_all(b"*1\r\n$x\r\nPING\r\n").await.unwrap();
+
+        let response = read_to_end(&mut client).await;
+        assert!(response.starts_with("-ERR Protocol error: "));
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn non_bulk_argument_closes_connection() {
+        let (mut client, handle) = serve();
+
+        client.write_all(b"*1\r\n:5\r\nPING\r\n").await.unwrap();
+
+        let response = read_to_end(&mut client).await;
+        assert!(response.starts_with("-ERR Protocol error: "));
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn unbalanced_quotes_close_connection() {
+        let (mut client, handle) = serve();
+
+        client.write_all(b"ECHO \"hello\r\nPING\r\n").await.unwrap();
+
+        assert_eq!(
+            read_to_end(&mut client).await,
+            "-ERR Protocol error: unbalanced quotes in request\r\n"
+        );
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn truncated_frame_is_an_error() {
+        let (mut client, handle) = serve();
+
+        client.write_all(b"*1\r\n$4\r\nPI").await.unwrap();
+        client.shutdown().await.unwrap();
+
+        assert_eq!(read_to_end(&mut client).await, "");
+        assert!(handle.await.unwrap().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn blpop_waits_until_another_client_pushes() {
+        let db = Db::new();
+        let mut worker = connect(&db);
+        let mut producer = connect(&db);
+
+        send(&mut worker, "BLPOP jobs 0").await;
+        assert!(is_blocked(&mut worker).await);
+
+        assert_eq!(request(&mut producer, "RPUSH jobs a").await, ":1\r\n");
+        assert_eq!(
+            read_reply(&mut worker).await,
+            "*2\r\n$4\r\njobs\r\n$1\r\na\r\n"
+        );
+        assert_eq!(request(&mut producer, "LLEN jobs").await, ":0\r\n");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn blocked_clients_are_served_in_fifo_order() {
+        let db = Db::new();
+        let mut first = connect(&db);
+        let mut second = connect(&db);
+        let mut producer = connect(&db);
+
+        send(&mut first, "BRPOP jobs 0").await;
+        send(&mut second, "BRPOP jobs 0").await;
+
+        request(&mut producer, "RPUSH jobs a").await;
+        assert_eq!(
+            read_reply(&mut first).await,
+            "*2\r\n$4\r\njobs\r\n$1\r\na\r\n"
+        );
+        assert!(is_blocked(&mut second).await);
+
+        request(&mut producer, "RPUSH jobs b").await;
+        assert_eq!(
+            read_reply(&mut second).await,
+            "*2\r\n$4\r\njobs\r\n$1\r\nb\r\n"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn single_push_serves_several_blocked_clients() {
+        let db = Db::new();
+        let mut first = connect(&db);
+        let mut second = connect(&db);
+        let mut producer = connect(&db);
+
+        send(&mut first, "BLPOP jobs other 0").await;
+        send(&mut second, "BLPOP other jobs 0").await;
+
+        assert_eq!(request(&mut producer, "RPUSH jobs a b").await, ":2\r\n");
+        assert_eq!(
+            read_reply(&mut first).await,
+            "*2\r\n$4\r\njobs\r\n$1\r\na\r\n"
+        );
+        assert_eq!(
+            read_reply(&mut second).await,
+            "*2\r\n$4\r\njobs\r\n$1\r\nb\r\n"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn blpop_replies_null_on_timeout() {
+        let db = Db::new();
+        let mut worker = connect(&db);
+        let mut producer = connect(&db);
+
+        send(&mut worker, "BLPOP jobs 1.5").await;
+        assert_eq!(read_reply(&mut worker).await, "$-1\r\n");
+
+        // The timed out client does not consume later pushes
+        request(&mut producer, "RPUSH jobs a").await;
+        assert_eq!(request(&mut producer, "LLEN jobs").await, ":1\r\n");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn disconnected_client_is_not_served() {
+        let db = Db::new();
+        let mut worker = connect(&db);
+        let mut producer = connect(&db);
+
+        send(&mut worker, "BLPOP jobs 0").await;
+        drop(worker);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        request(&mut producer, "RPUSH jobs a").await;
+        assert_eq!(request(&mut producer, "LLEN jobs").await, ":1\r\n");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn blmove_waits_for_source_list() {
+        let db = Db::new();
+        let mut worker = connect(&db);
+        let mut producer = connect(&db);
+
+        send(&mut worker, "BLMOVE jobs processing LEFT RIGHT 0").await;
+        assert!(is_blocked(&mut worker).await);
+
+        request(&mut producer, "RPUSH jobs a").await;
+        assert_eq!(read_reply(&mut worker).await, "$1\r\na\r\n");
+        assert_eq!(
+            request(&mut producer, "LRANGE processing 0 -1").await,
+            "*1\r\n$1\r\na\r\n"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn blmove_wakes_clients_blocked_on_destination() {
+        let db = Db::new();
+        let mut mover = connect(&db);
+        let mut worker = connect(&db);
+        let mut producer = connect(&db);
+
+        send(&mut mover, "BLMOVE jobs processing LEFT RIGHT 0").await;
+        send(&mut worker, "BLPOP processing 0").await;
+
+        request(&mut producer, "RPUSH jobs a").await;
+        assert_eq!(read_reply(&mut mover).await, "$1\r\na\r\n");
+        assert_eq!(
+            read_reply(&mut worker).await,
+            "*2\r\n$10\r\nprocessing\r\n$1\r\na\r\n"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hgetall_reply_depends_on_protocol() {
+        let db = Db::new();
+        let mut client = connect(&db);
+
+        request(&mut client, "HSET user name ada").await;
+        assert_eq!(
+            request(&mut client, "HGETALL user").await,
+            "*2\r\n$4\r\nname\r\n$3\r\nada\r\n"
+        );
+
+        request(&mut client, "HELLO 3").await;
+        assert_eq!(
+            request(&mut client, "HGETALL user").await,
+            "%1\r\n$4\r\nname\r\n$3\r\nada\r\n"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn xread_block_waits_for_new_entries() {
+        let db = Db::new();
+        let mut reader = connect(&db);
+        let mut producer = connect(&db);
+
+        request(&mut producer, "XADD events 1-1 kind old").await;
+        send(&mut reader, "XREAD BLOCK 0 STREAMS events $").await;
+        assert!(is_blocked(&mut reader).await);
+
+        assert_eq!(
+            request(&mut producer, "XADD events 2-1 kind new").await,
+            "$3\r\n2-1\r\n"
+        );
+        assert_eq!(
+            read_reply(&mut reader).await,
+            "*1\r\n*2\r\n$6\r\nevents\r\n*1\r\n*2\r\n$3\r\n2-1\r\n*2\r\n$4\r\nkind\r\n$3\r\nnew\r\n"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn xread_block_replies_null_array_on_timeout() {
+        let db = Db::new();
+        let mut reader = connect(&db);
+
+        send(&mut reader, "XREAD BLOCK 1500 STREAMS events 0").await;
+        assert_eq!(read_reply(&mut reader).await, "*-1\r\n");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn xreadgroup_block_waits_for_new_entries() {
+        let db = Db::new();
+        let mut worker = connect(&db);
+        let mut producer = connect(&db);
+
+        request(&mut producer, "XGROUP CREATE jobs workers $ MKSTREAM").await;
+        send(
+            &mut worker,
+            "XREADGROUP GROUP workers alice BLOCK 0 STREAMS jobs >",
+        )
+        .await;
+        assert!(is_blocked(&mut worker).await);
+
+        request(&mut producer, "XADD jobs 1-1 task a").await;
+        assert_eq!(
+            read_reply(&mut worker).await,
+            "*1\r\n*2\r\n$4\r\njobs\r\n*1\r\n*2\r\n$3\r\n1-1\r\n*2\r\n$4\r\ntask\r\n$1\r\na\r\n"
+        );
+        assert_eq!(
+            request(&mut producer, "XACK jobs workers 1-1").await,
+            ":1\r\n"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn published_messages_reach_subscribers() {
+        let db = Db::new();
+        let mut subscriber = connect(&db);
+        let mut publisher = connect(&db);
+
+        assert_eq!(
+            request(&mut subscriber, "SUBSCRIBE news").await,
+            "*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n"
+        );
+        assert_eq!(
+            request(&mut subscriber, "PSUBSCRIBE n*").await,
+            "*3\r\n$10\r\npsubscribe\r\n$2\r\nn*\r\n:2\r\n"
+        );
+
+        assert_eq!(
+            request(&mut publisher, "PUBLISH news hello").await,
+            ":2\r\n"
+        );
+        assert_eq!(
+            read_reply(&mut subscriber).await,
+            "*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n\
+             *4\r\n$8\r\npmessage\r\n$2\r\nn*\r\n$4\r\nnews\r\n$5\r\nhello\r\n"
+        );
+        assert_eq!(
+            request(&mut publisher, "PUBLISH sport goal").await,
+            ":0\r\n"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn subscribed_resp2_client_is_restricted() {
+        let db = Db::new();
+        let mut client = connect(&db);
+
+        request(&mut client, "SUBSCRIBE news").await;
+        assert_eq!(
+            request(&mut client, "GET key").await,
+            "-ERR Can't execute 'get': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING / QUIT / RESET are allowed in this context\r\n"
+        );
+        assert_eq!(
+            request(&mut client, "PING").await,
+            "*2\r\n$4\r\npong\r\n$0\r\n\r\n"
+        );
+
+        assert_eq!(
+            request(&mut client, "UNSUBSCRIBE").await,
+            "*3\r\n$11\r\nunsubscribe\r\n$4\r\nnews\r\n:0\r\n"
+        );
+        assert_eq!(request(&mut client, "PING").await, "+PONG\r\n");
+        assert_eq!(
+            request(&mut client, "UNSUBSCRIBE").await,
+            "*3\r\n$11\r\nunsubscribe\r\n$-1\r\n:0\r\n"
+        );
+    }
+
+    #[test]
+    fn subscribed_clients_only_manage_subscriptions() {
+        use crate::server::cmd::helper::command;
+
+        for name in [
+            "subscribe",
+            "PSUBSCRIBE",
+            "sSubscribe",
+            "punsubscribe",
+            "PING",
+            "reset",
+        ] {
+            assert_eq!(forbidden_when_subscribed(&command(&[name])), None);
+        }
+        for name in ["GET", "xsubscribe", "PSPING"] {
+            assert_eq!(
+                forbidden_when_subscribed(&command(&[name])),
+                Some(name.to_lowercase())
+            );
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn subscribed_resp3_client_receives_pushes() {
+        let db = Db::new();
+        let mut subscriber = connect(&db);
+        let mut publisher = connect(&db);
+
+        request(&mut subscriber, "HELLO 3").await;
+        assert_eq!(
+            request(&mut subscriber, "SUBSCRIBE news").await,
+            ">3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n"
+        );
+        assert_eq!(request(&mut subscriber, "SET key value").await, "+OK\r\n");
+
+        request(&mut publisher, "PUBLISH news hello").await;
+        assert_eq!(
+            read_reply(&mut subscriber).await,
+            ">3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pubsub_reports_subscriptions() {
+        let db = Db::new();
+        let mut alice = connect(&db);
+        let mut bob = connect(&db);
+        let mut client = connect(&db);
+
+        request(&mut alice, "SUBSCRIBE news.sport chat").await;
+        request(&mut bob, "SUBSCRIBE news.sport").await;
+        request(&mut bob, "PSUBSCRIBE news.*").await;
+
+        assert_eq!(
+            request(&mut client, "PUBSUB CHANNELS news.*").await,
+            "*1\r\n$10\r\nnews.sport\r\n"
+        );
+        assert_eq!(
+            request(&mut client, "PUBSUB NUMSUB news.sport other").await,
+            "*4\r\n$10\r\nnews.sport\r\n:2\r\n$5\r\nother\r\n:0\r\n"
+        );
+        assert_eq!(request(&mut client, "PUBSUB NUMPAT").await, ":1\r\n");
+
+        drop(bob);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(
+            request(&mut client, "PUBSUB NUMSUB news.sport").await,
+            "*2\r\n$10\r\nnews.sport\r\n:1\r\n"
+        );
+        assert_eq!(request(&mut client, "PUBSUB NUMPAT").await, ":0\r\n");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reset_leaves_subscriber_mode() {
+        let db = Db::new();
+        let mut client = connect(&db);
+
+        request(&mut client, "SUBSCRIBE news").await;
+        assert_eq!(request(&mut client, "RESET").await, "+RESET\r\n");
+        assert_eq!(request(&mut client, "GET key").await, "$-1\r\n");
+        assert_eq!(request(&mut client, "PUBLISH news hello").await, ":0\r\n");
+    }
+
+    #[tokio::test]
+    async fn quit_closes_connection() {
+        let (mut client, handle) = serve();
+
+        client.write_all(b"QUIT\r\nPING\r\n").await.unwrap();
+
+        assert_eq!(read_to_end(&mut client).await, "+OK\r\n");
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shard_messages_reach_shard_subscribers() {
+        let db = Db::new();
+        let mut subscriber = connect(&db);
+        let mut publisher = connect(&db);
+
+        request(&mut subscriber, "SUBSCRIBE news").await;
+        assert_eq!(
+            request(&mut subscriber, "SSUBSCRIBE orders").await,
+            "*3\r\n$10\r\nssubscribe\r\n$6\r\norders\r\n:1\r\n"
+        );
+        assert_eq!(
+            request(&mut publisher, "PUBSUB SHARDCHANNELS").await,
+            "*1\r\n$6\r\norders\r\n"
+        );
+        assert_eq!(
+            request(&mut publisher, "PUBSUB SHARDNUMSUB orders news").await,
+            "*4\r\n$6\r\norders\r\n:1\r\n$4\r\nnews\r\n:0\r\n"
+        );
+
+        assert_eq!(
+            request(&mut publisher, "PUBLISH orders new").await,
+            ":0\r\n"
+        );
+        assert_eq!(
+            request(&mut publisher, "SPUBLISH orders new").await,
+            ":1\r\n"
+        );
+        assert_eq!(
+            read_reply(&mut subscriber).await,
+            "*3\r\n$8\r\nsmessage\r\n$6\r\norders\r\n$3\r\nnew\r\n"
+        );
+
+        assert_eq!(
+            request(&mut subscriber, "SUNSUBSCRIBE").await,
+            "*3\r\n$12\r\nsunsubscribe\r\n$6\r\norders\r\n:0\r\n"
+        );
+        assert_eq!(
+            request(&mut subscriber, "GET key").await,
+            "-ERR Can't execute 'get': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING / QUIT / RESET are allowed in this context\r\n"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn exec_runs_queued_commands() {
+        let db = Db::new();
+        let mut client = connect(&db);
+        let mut other = connect(&db);
+
+        assert_eq!(request(&mut client, "MULTI").await, "+OK\r\n");
+        assert_eq!(request(&mut client, "SET key 1").await, "+QUEUED\r\n");
+        assert_eq!(request(&mut client, "INCR key").await, "+QUEUED\r\n");
+        assert_eq!(request(&mut client, "LPUSH key a").await, "+QUEUED\r\n");
+        assert_eq!(
+            request(&mut client, "MULTI").await,
+            "-ERR MULTI calls can not be nested\r\n"
+        );
+
+        // Nothing runs before EXEC
+        assert_eq!(request(&mut other, "GET key").await, "$-1\r\n");
+
+        // A command failing at runtime does not stop the others
+        assert_eq!(
+            request(&mut client, "EXEC").await,
+            "*3\r\n+OK\r\n:2\r\n-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+        );
+        assert_eq!(request(&mut client, "GET key").await, "$1\r\n2\r\n");
+        assert_eq!(
+            request(&mut client, "EXEC").await,
+            "-ERR EXEC without MULTI\r\n"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn queueing_error_aborts_transaction() {
+        let db = Db::new();
+        let mut client = connect(&db);
+
+        request(&mut client, "MULTI").await;
+        request(&mut client, "SET key 1").await;
+        assert_eq!(
+            request(&mut client, "GET").await,
+            "-ERR wrong number of arguments for 'get' command\r\n"
+        );
+        assert_eq!(
+            request(&mut client, "EXEC").await,
+            "-EXECABORT Transaction discarded because of previous errors.\r\n"
+        );
+        assert_eq!(request(&mut client, "GET key").await, "$-1\r\n");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn discard_drops_queued_commands() {
+        let db = Db::new();
+        let mut client = connect(&db);
+
+        assert_eq!(
+            request(&mut client, "DISCARD").await,
+            "-ERR DISCARD without MULTI\r\n"
+        );
+        request(&mut client, "MULTI").await;
+        request(&mut client, "SET key 1").await;
+        assert_eq!(request(&mut client, "DISCARD").await, "+OK\r\n");
+        assert_eq!(request(&mut client, "GET key").await, "$-1\r\n");
+
+        request(&mut client, "MULTI").await;
+        request(&mut client, "SET key 1").await;
+        assert_eq!(request(&mut client, "RESET").await, "+RESET\r\n");
+        assert_eq!(
+            request(&mut client, "EXEC").await,
+            "-ERR EXEC without MULTI\r\n"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn exec_publishes_and_does_not_block() {
+        let db = Db::new();
+        let mut client = connect(&db);
+        let mut subscriber = connect(&db);
+
+        request(&mut subscriber, "SUBSCRIBE news").await;
+        request(&mut client, "MULTI").await;
+        request(&mut client, "BLPOP list 0").await;
+        request(&mut client, "PUBLISH news hello").await;
+        assert_eq!(request(&mut client, "EXEC").await, "*2\r\n$-1\r\n:1\r\n");
+        assert_eq!(
+            read_reply(&mut subscriber).await,
+            "*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn exec_fails_when_watched_key_changes() {
+        let db = Db::new();
+        let mut client = connect(&db);
+        let mut other = connect(&db);
+
+        request(&mut client, "SET balance 10").await;
+        assert_eq!(request(&mut client, "WATCH balance").await, "+OK\r\n");
+        assert_eq!(request(&mut client, "GET balance").await, "$2\r\n10\r\n");
+        request(&mut other, "INCRBY balance 5").await;
+
+        request(&mut client, "MULTI").await;
+        request(&mut client, "SET balance 9").await;
+        assert_eq!(request(&mut client, "EXEC").await, "*-1\r\n");
+        assert_eq!(request(&mut client, "GET balance").await, "$2\r\n15\r\n");
+
+        // EXEC unwatched the key
+        request(&mut client, "MULTI").await;
+        request(&mut client, "SET balance 14").await;
+        request(&mut other, "SET balance 0").await;
+        assert_eq!(request(&mut client, "EXEC").await, "*1\r\n+OK\r\n");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn exec_runs_when_watched_keys_are_unchanged() {
+        let db = Db::new();
+        let mut client = connect(&db);
+        let mut other = connect(&db);
+
+        request(&mut client, "RPUSH list a").await;
+        request(&mut client, "WATCH list missing").await;
+        request(&mut other, "LRANGE list 0 -1").await;
+        request(&mut other, "DEL missing").await;
+
+        request(&mut client, "MULTI").await;
+        assert_eq!(
+            request(&mut client, "WATCH list").await,
+            "-ERR WATCH inside MULTI is not allowed\r\n"
+        );
+        request(&mut client, "RPUSH list b").await;
+        assert_eq!(request(&mut client, "EXEC").await, "*1\r\n:2\r\n");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn no_op_writes_keep_watched_keys_unchanged() {
+        let db = Db::new();
+        let mut client = connect(&db);
+        let mut other = connect(&db);
+
+        request(&mut client, "HSET hash field value").await;
+        request(&mut client, "SADD set member").await;
+        request(&mut client, "RPUSH list a b").await;
+        request(&mut client, "ZADD zset 1 member").await;
+        request(&mut client, "XADD stream 1-1 field value").await;
+        request(&mut client, "XGROUP CREATE stream group $").await;
+        request(&mut client, "SET string value").await;
+        request(
+            &mut client,
+            "WATCH hash set list zset stream string missing",
+        )
+        .await;
+
+        for command in [
+            "HDEL hash missing",
+            "SREM set missing",
+            "SMOVE set other missing",
+            "SPOP set 0",
+            "LREM list 0 missing",
+            "LTRIM list 0 -1",
+            "ZREM zset missing",
+            "ZADD zset XX 2 missing",
+            "ZADD missing XX 1 member",
+            "GEOADD missing XX 13 38 member",
+            "XDEL stream 2-1",
+            "XACK stream group 1-1",
+            "XREADGROUP GROUP group consumer STREAMS stream >",
+            "LPUSH string a",
+            "HSET string field value",
+            "SADD string member",
+            "ZADD string 1 member",
+            "XADD string * field value",
+        ] {
+            request(&mut other, command).await;
+        }
+
+        request(&mut client, "MULTI").await;
+        request(&mut client, "GET string").await;
+        assert_eq!(request(&mut client, "EXEC").await, "*1\r\n$5\r\nvalue\r\n");
+
+        // Real modifications still abort the transaction
+        request(&mut client, "WATCH hash").await;
+        request(&mut other, "HDEL hash field").await;
+        request(&mut client, "MULTI").await;
+        assert_eq!(request(&mut client, "EXEC").await, "*-1\r\n");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn deleting_or_flushing_watched_keys_fails_exec() {
+        let db = Db::new();
+        let mut client = connect(&db);
+        let mut other = connect(&db);
+
+        request(&mut client, "SET key value").await;
+        request(&mut client, "WATCH key").await;
+        request(&mut other, "DEL key").await;
+        request(&mut client, "MULTI").await;
+        assert_eq!(request(&mut client, "EXEC").await, "*-1\r\n");
+
+        request(&mut client, "SET key value").await;
+        request(&mut client, "WATCH key").await;
+        request(&mut other, "FLUSHALL").await;
+        request(&mut client, "MULTI").await;
+        assert_eq!(request(&mut client, "EXEC").await, "*-1\r\n");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unwatch_forgets_watched_keys() {
+        let db = Db::new();
+        let mut client = connect(&db);
+        let mut other = connect(&db);
+
+        request(&mut client, "WATCH key").await;
+        assert_eq!(request(&mut client, "UNWATCH").await, "+OK\r\n");
+        request(&mut other, "SET key value").await;
+        request(&mut client, "MULTI").await;
+        assert_eq!(request(&mut client, "EXEC").await, "*0\r\n");
+
+        request(&mut client, "WATCH key").await;
+        request(&mut client, "MULTI").await;
+        assert_eq!(request(&mut client, "DISCARD").await, "+OK\r\n");
+        request(&mut other, "SET key other").await;
+        request(&mut client, "MULTI").await;
+        assert_eq!(request(&mut client, "EXEC").await, "*0\r\n");
+    }
+}
@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod set_test {
+    use super::super::*;
+
+    fn set(members: &[&'static str]) -> Set {
+        members.iter().map(|member| Bytes::from(*member)).collect()
+    }
+
+    #[test]
+    fn members_are_unique() {
+        let mut set = set(&["a", "b", "a"]);
+        assert_eq!(set.len(), 2);
+        assert!(!set.insert(Bytes::from("b")));
+        assert!(set.insert(Bytes::from("c")));
+        assert!(set.contains(b"c"));
+        assert_eq!(set, self::set(&["c", "b", "a"]));
+    }
+
+    #[test]
+    fn removing_keeps_positions_consistent() {
+        let mut set = set(&["a", "b", "c", "d"]);
+        assert!(set.remove(b"a"));
+        assert!(!set.remove(b"a"));
+        assert!(set.remove(b"c"));
+        assert_eq!(set, self::set(&["b", "d"]));
+        assert!(set.remove(b"d"));
+        assert!(set.remove(b"b"));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn pop_removes_every_member_once() {
+        let mut set = set(&["a", "b", "c"]);
+        let mut rng = rand::rng();
+        let mut popped: Vec<Bytes> = std::iter::from_fn(|| set.pop(&mut rng)).collect();
+        popped.sort();
+        assert_eq!(popped, vec!["a", "b", "c"]);
+        assert!(set.is_empty());
+        assert_eq!(set.members().len(), 0);
+    }
+}
@@ -0,0 +1,32 @@
+#[cfg(test)]
+mod slot_test {
+    use super::super::*;
+
+    #[test]
+    fn crc16_matches_xmodem() {
+        assert_eq!(crc16(b""), 0);
+        assert_eq!(crc16(b"123456789"), 0x31c3);
+    }
+
+    #[test]
+    fn slots_match_redis() {
+        assert_eq!(hash_slot(b"foo"), 12182);
+        assert_eq!(hash_slot(b"bar"), 5061);
+        assert_eq!(hash_slot(b"hello"), 866);
+        assert!(hash_slot(b"\xff\xff\xff") < SLOTS);
+    }
+
+    #[test]
+    fn hash_tags_share_slots() {
+        assert_eq!(
+            hash_slot(b"{user1000}.following"),
+            hash_slot(b"{user1000}.followers")
+        );
+        assert_eq!(hash_slot(b"foo{user1000}bar"), hash_slot(b"user1000"));
+        assert_eq!(hash_slot(b"{user}{other}"), hash_slot(b"user"));
+
+        // Empty or unclosed tags hash the whole name
+        assert_eq!(hash_slot(b"{}user"), crc16(b"{}user") % SLOTS);
+        assert_eq!(hash_slot(b"{user"), crc16(b"{user") % SLOTS);
+    }
+}
@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod sorted_set_test {
+    use super::super::*;
+
+    fn set(entries: &[(&'static str, f64)]) -> SortedSet {
+        let mut set = SortedSet::new();
+        for (member, score) in entries {
+            set.insert(Bytes::from(*member), *score);
+        }
+        set
+    }
+
+    fn members<'a>(entries: impl Iterator<Item = (&'a Bytes, f64)>) -> Vec<&'a [u8]> {
+        entries.map(|(member, _)| &member[..]).collect()
+    }
+
+    fn by_score(set: &SortedSet, min: Bound<f64>, max: Bound<f64>) -> Vec<&[u8]> {
+        let ranks = set.ranks_by_score(min, max);
+        members(set.iter_from(ranks.start, false).take(ranks.len()))
+    }
+
+    #[test]
+    fn members_are_ordered_by_score_then_lexicographically() {
+        let set = set(&[("c", 1.0), ("b", 2.0), ("a", 1.0), ("d", -1.0)]);
+        assert_eq!(members(set.iter()), vec![b"d", b"a", b"c", b"b"]);
+    }
+
+    #[test]
+    fn insert_updates_score() {
+        let mut set = set(&[("a", 1.0), ("b", 2.0)]);
+        assert!(!set.insert(Bytes::from("a"), 3.0));
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.score(b"a"), Some(3.0));
+        assert_eq!(members(set.iter()), vec![b"b", b"a"]);
+    }
+
+    #[test]
+    fn remove_and_pop() {
+        let mut set = set(&[("a", 1.0), ("b", 2.0), ("c", 3.0)]);
+        assert!(set.remove(b"b"));
+        assert!(!set.remove(b"b"));
+        assert_eq!(set.pop(true), Some((Bytes::from("c"), 3.0)));
+        assert_eq!(set.pop(false), Some((Bytes::from("a"), 1.0)));
+        assert!(set.is_empty());
+        assert_eq!(set.pop(false), None);
+    }
+
+    #[test]
+    fn rank_counts_from_either_end() {
+        let set = set(&[("a", 1.0), ("b", 2.0), ("c", 3.0)]);
+        assert_eq!(set.rank(b"a", false), Some(0));
+        assert_eq!(set.rank(b"c", false), Some(2));
+        assert_eq!(set.rank(b"c", true), Some(0));
+        assert_eq!(set.rank(b"x", false), None);
+    }
+
+    #[test]
+    fn range_by_score_bounds() {
+        let set = set(&[("a", 1.0), ("b", 2.0), ("c", 2.0), ("d", 3.0)]);
+        assert_eq!(
+            by_score(&set, Bound::Included(2.0), Bound::Included(2.0)),
+            vec![b"b", b"c"]
+        );
+        assert_eq!(
+            by_score(&set, Bound::Excluded(1.0), Bound::Excluded(3.0)),
+            vec![b"b", b"c"]
+        );
+        assert_eq!(
+            by_score(&set, Bound::Excluded(2.0), Bound::Unbounded),
+            vec![b"d"]
+        );
+        assert!(by_score(&set, Bound::Included(3.0), Bound::Included(1.0)).is_empty());
+        assert!(by_score(&set, Bound::Excluded(2.0), Bound::Excluded(2.0)).is_empty());
+    }
+
+    #[test]
+    fn range_by_score_with_infinities() {
+        let set = set(&[("a", f64::NEG_INFINITY), ("b", 0.0), ("c", f64::INFINITY)]);
+        assert_eq!(
+            by_score(
+                &set,
+                Bound::Included(f64::NEG_INFINITY),
+                Bound::Included(f64::INFINITY)
+            ),
+            vec![b"a", b"b", b"c"]
+        );
+        assert_eq!(
+            by_score(
+                &set,
+                Bound::Excluded(f64::NEG_INFINITY),
+                Bound::Excluded(f64::INFINITY)
+            ),
+            vec![b"b"]
+        );
+        assert!(by_score(&set, Bound::Excluded(f64::INFINITY), Bound::Unbounded).is_empty());
+    }
+
+    #[test]
+    fn negative_zero_is_zero() {
+        let set = set(&[("a", -0.0)]);
+        assert_eq!(
+            by_score(&set, Bound::Included(0.0), Bound::Included(0.0)),
+            vec![b"a"]
+        );
+        assert_eq!(
+            by_score(&set, Bound::Included(-1.0), Bound::Included(-0.0)),
+            vec![b"a"]
+        );
+        assert_eq!(set.score(b"a").unwrap().to_string(), "0");
+    }
+
+    #[test]
+    fn positions_follow_updates() {
+        // Enough members for the index to be several levels deep
+        let mut set = SortedSet::new();
+        let mut expected = vec![];
+        for i in 0..500u32 {
+            let member = Bytes::from(format!("m{}", i));
+            let score = f64::from(i * 7 % 101);
+            set.insert(member.clone(), score);
+            expected.push((score, member));
+        }
+        for i in (0..500).step_by(3) {
+            assert!(set.remove(format!("m{}", i).as_bytes()));
+        }
+        expected.retain(|(_, member)| set.score(member).is_some());
+        expected.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+
+        for (rank, (_, member)) in expected.iter().enumerate() {
+            assert_eq!(set.rank(member, false), Some(rank));
+            assert_eq!(set.rank(member, true), Some(expected.len() - 1 - rank));
+        }
+        let from = |start, rev| members(set.iter_from(start, rev).take(3));
+        assert_eq!(
+            from(100, false),
+            expected[100..103]
+                .iter()
+                .map(|(_, member)| &member[..])
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            from(0, true),
+            expected[expected.len() - 3..]
+                .iter()
+                .rev()
+                .map(|(_, member)| &member[..])
+                .collect::<Vec<_>>()
+        );
+        assert!(from(expected.len(), false).is_empty());
+        assert_eq!(
+            set.ranks_by_score(Bound::Included(10.0), Bound::Excluded(20.0))
+                .len(),
+            expected
+                .iter()
+                .filter(|(score, _)| (10.0..20.0).contains(score))
+                .count()
+        );
+    }
+}
@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod stream_test {
+    use super::super::*;
+
+    fn fields() -> Fields {
+        vec![(Bytes::from("field"), Bytes::from("value"))]
+    }
+
+    fn stream(ids: &[(u64, u64)]) -> Stream {
+        let mut stream = Stream::new();
+        for (ms, seq) in ids {
+            assert!(stream.add(StreamId::new(*ms, *seq), fields()));
+        }
+        stream
+    }
+
+    fn ids<'a>(entries: impl Iterator<Item = (&'a StreamId, &'a Fields)>) -> Vec<String> {
+        entries.map(|(id, _)| id.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_id() {
+        assert_eq!(StreamId::parse(b"5-3", 0), Some(StreamId::new(5, 3)));
+        assert_eq!(StreamId::parse(b"5", 0), Some(StreamId::new(5, 0)));
+        assert_eq!(
+            StreamId::parse(b"5", u64::MAX),
+            Some(StreamId::new(5, u64::MAX))
+        );
+        assert_eq!(StreamId::parse(b"5-", 0), None);
+        assert_eq!(StreamId::parse(b"-3", 0), None);
+        assert_eq!(StreamId::parse(b"5-+3", 0), None);
+        assert_eq!(StreamId::parse(b"a-3", 0), None);
+        assert_eq!(StreamId::parse(b"18446744073709551616", 0), None);
+    }
+
+    #[test]
+    fn next_and_prev_carry_over_the_time() {
+        assert_eq!(StreamId::new(1, u64::MAX).next(), Some(StreamId::new(2, 0)));
+        assert_eq!(StreamId::new(2, 0).prev(), Some(StreamId::new(1, u64::MAX)));
+        assert_eq!(StreamId::MAX.next(), None);
+        assert_eq!(StreamId::MIN.prev(), None);
+    }
+
+    #[test]
+    fn next_id_is_greater_than_the_last_one() {
+        let stream = stream(&[(100, 4)]);
+        assert_eq!(stream.next_id(None, 200), Some(StreamId::new(200, 0)));
+        // The clock went backward
+        assert_eq!(stream.next_id(None, 50), Some(StreamId::new(100, 5)));
+        assert_eq!(stream.next_id(Some(100), 200), Some(StreamId::new(100, 5)));
+        assert_eq!(stream.next_id(Some(99), 200), None);
+    }
+
+    #[test]
+    fn add_rejects_ids_not_greater_than_the_last_one() {
+        let mut stream = stream(&[(1, 1)]);
+        assert!(!stream.add(StreamId::new(1, 1), fields()));
+        assert!(!stream.add(StreamId::new(0, 5), fields()));
+
+        // Deleting the last entry does not allow reusing its ID
+        assert!(stream.remove(&StreamId::new(1, 1)));
+        assert!(!stream.add(StreamId::new(1, 1), fields()));
+        assert_eq!(stream.last_id(), StreamId::new(1, 1));
+        assert_eq!(stream.len(), 0);
+    }
+
+    #[test]
+    fn range_is_inclusive() {
+        let stream = stream(&[(1, 0), (2, 0), (2, 1), (3, 0)]);
+        assert_eq!(
+            ids(stream.range(StreamId::new(2, 0), StreamId::new(3, 0))),
+            vec!["2-0", "2-1", "3-0"]
+        );
+        assert_eq!(
+            ids(stream.range(StreamId::MIN, StreamId::MAX).rev()),
+            vec!["3-0", "2-1", "2-0", "1-0"]
+        );
+        assert!(ids(stream.range(StreamId::new(3, 0), StreamId::new(1, 0))).is_empty());
+    }
+
+    #[test]
+    fn trim_by_length_and_id() {
+        let mut stream = stream(&[(1, 0), (2, 0), (3, 0), (4, 0), (5, 0)]);
+        assert_eq!(stream.trim(Trim::MaxLen(3), Some(1)), 1);
+        assert_eq!(stream.trim(Trim::MaxLen(3), None), 1);
+        assert_eq!(stream.trim(Trim::MaxLen(3), None), 0);
+        assert_eq!(stream.trim(Trim::MinId(StreamId::new(5, 0)), None), 2);
+        assert_eq!(ids(stream.range(StreamId::MIN, StreamId::MAX)), vec!["5-0"]);
+    }
+
+    #[test]
+    fn read_group_delivers_new_entries_once() {
+        let mut stream = stream(&[(1, 0), (2, 0), (3, 0)]);
+        let alice = Bytes::from("alice");
+        let bob = Bytes::from("bob");
+        assert!(stream.create_group(Bytes::from("workers"), StreamId::MIN));
+        assert!(!stream.create_group(Bytes::from("workers"), StreamId::MIN));
+
+        let read = stream
+            .read_group(b"workers", &alice, 2, false, 100)
+            .unwrap();
+        assert_eq!(read.len(), 2);
+        let read = stream.read_group(b"workers", &bob, 2, false, 100).unwrap();
+        assert_eq!(read[0].0, StreamId::new(3, 0));
+        assert!(stream.read_group(b"missing", &bob, 2, false, 100).is_none());
+
+        let group = stream.group(b"workers").unwrap();
+        assert_eq!(group.last_delivered(), StreamId::new(3, 0));
+        assert_eq!(group.pending().len(), 3);
+        assert_eq!(
+            group.consumer_pending(b"alice", StreamId::MIN, 10),
+            vec![StreamId::new(1, 0), StreamId::new(2, 0)]
+        );
+    }
+
+    #[test]
+    fn ack_and_deliver_update_the_pending_entries() {
+        let mut stream = stream(&[(1, 0), (2, 0)]);
+        let alice = Bytes::from("alice");
+        let bob = Bytes::from("bob");
+        stream.create_group(Bytes::from("workers"), StreamId::MIN);
+        stream.read_group(b"workers", &alice, 10, false, 100);
+
+        let group = stream.group(b"workers").unwrap();
+        assert!(group.ack(&StreamId::new(1, 0)));
+        assert!(!group.ack(&StreamId::new(1, 0)));
+
+        // Claiming moves the entry to the other consumer
+        group.deliver(StreamId::new(2, 0), &bob, 200, 1);
+        let entry = &group.pending()[&StreamId::new(2, 0)];
+        assert_eq!(&entry.consumer[..], b"bob");
+        assert_eq!(entry.delivered_at, 200);
+        assert_eq!(entry.deliveries, 2);
+        assert!(group
+            .consumer_pending(b"alice", StreamId::MIN, 10)
+            .is_empty());
+
+        assert_eq!(group.remove_consumer(b"bob"), Some(1));
+        assert!(group.pending().is_empty());
+        assert!(stream.destroy_group(b"workers"));
+        assert!(stream.group(b"workers").is_none());
+    }
+}
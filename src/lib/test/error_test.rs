@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod error_test {
+    use super::super::*;
+
+    fn reply(err: ReplyError) -> Frame {
+        err.into()
+    }
+
+    #[test]
+    fn generic_errors_start_with_err() {
+        assert_eq!(
+            reply(ReplyError::Syntax),
+            Frame::Error("ERR syntax error".into())
+        );
+        assert_eq!(
+            reply(ReplyError::NotInteger),
+            Frame::Error("ERR value is not an integer or out of range".into())
+        );
+        assert_eq!(
+            reply(ReplyError::WrongArity("get".into())),
+            Frame::Error("ERR wrong number of arguments for 'get' command".into())
+        );
+        assert_eq!(
+            reply(ReplyError::OutOfRange("offset is out of range".into())),
+            Frame::Error("ERR offset is out of range".into())
+        );
+    }
+
+    #[test]
+    fn protocol_error_reply() {
+        assert_eq!(
+            reply(ReplyError::Protocol("invalid bulk length".into())),
+            Frame::Error("ERR Protocol error: invalid bulk length".into())
+        );
+    }
+
+    #[test]
+    fn unknown_command_reply_quotes_arguments() {
+        let err = ReplyError::UnknownCommand {
+            name: "foo".into(),
+            args: vec!["a".into(), "b".into()],
+        };
+        assert_eq!(
+            reply(err),
+            Frame::Error("ERR unknown command 'foo', with args beginning with: 'a' 'b'".into())
+        );
+    }
+
+    #[test]
+    fn specific_errors_have_their_own_code() {
+        assert_eq!(
+            reply(ReplyError::WrongType),
+            Frame::Error(
+                "WRONGTYPE Operation against a key holding the wrong kind of value".into()
+            )
+        );
+        assert_eq!(
+            reply(ReplyError::NoAuth),
+            Frame::Error("NOAUTH Authentication required.".into())
+        );
+        assert_eq!(
+            reply(ReplyError::ExecAbort),
+            Frame::Error("EXECABORT Transaction discarded because of previous errors.".into())
+        );
+        assert_eq!(
+            reply(ReplyError::NoProto),
+            Frame::Error("NOPROTO unsupported protocol version".into())
+        );
+        assert_eq!(
+            reply(ReplyError::BusyGroup),
+            Frame::Error("BUSYGROUP Consumer Group name already exists".into())
+        );
+        assert_eq!(
+            reply(ReplyError::InvalidHyperLogLog),
+            Frame::Error("WRONGTYPE Key is not a valid HyperLogLog string value.".into())
+        );
+        assert_eq!(
+            reply(ReplyError::SlotNotServed),
+            Frame::Error("CLUSTERDOWN Hash slot not served".into())
+        );
+    }
+
+    #[test]
+    fn frame_errors_are_protocol_errors() {
+        let err = Error::from(frame::Error::InvalidTypeByte(b'?'));
+        assert!(matches!(err, Error::Protocol(_)));
+        assert_eq!(
+            err.to_string(),
+            "protocol error: invalid frame type byte `63`"
+        );
+    }
+}